@@ -1,13 +1,20 @@
 //! A module to convert file extension to reader
 
 use crate::errors::Error;
+use crate::formula::Expr;
+#[cfg(feature = "encryption")]
+use crate::open_workbook_with_password;
 use crate::vba::VbaProject;
 use crate::{
-    open_workbook, open_workbook_from_rs, Data, Metadata, Ods, Range, Reader, Xls, Xlsb, Xlsx,
+    open_workbook, open_workbook_from_rs, AutoFilter, Comment, Data, DataValidation,
+    DataWithFormat, Dimensions, FormulaInfo, Hyperlink, Metadata, Ods, Range, Reader, RichText,
+    RowColumnProperties, Style, StyleId, Table, WorksheetRows, Xls, Xlsb, Xlsx,
 };
 use std::borrow::Cow;
 use std::fs::File;
 use std::io::BufReader;
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::path::Path;
 
 /// A wrapper over all sheets when the file type is not known at static time
@@ -53,6 +60,47 @@ where
     })
 }
 
+/// Opens a password protected workbook and define the file type at runtime.
+///
+/// Whenever possible use the statically known `open_workbook_with_password` function instead
+#[cfg(feature = "encryption")]
+pub fn open_workbook_auto_with_password<P>(
+    path: P,
+    password: &str,
+) -> Result<Sheets<Cursor<Vec<u8>>>, Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    Ok(match path.extension().and_then(|e| e.to_str()) {
+        Some("xls") | Some("xla") => {
+            Sheets::Xls(open_workbook_with_password(path, password).map_err(Error::Xls)?)
+        }
+        Some("xlsx") | Some("xlsm") | Some("xlam") => {
+            Sheets::Xlsx(open_workbook_with_password(path, password).map_err(Error::Xlsx)?)
+        }
+        Some("xlsb") => {
+            Sheets::Xlsb(open_workbook_with_password(path, password).map_err(Error::Xlsb)?)
+        }
+        Some("ods") => {
+            Sheets::Ods(open_workbook_with_password(path, password).map_err(Error::Ods)?)
+        }
+        _ => {
+            if let Ok(ret) = open_workbook_with_password::<Xls<_>, _>(path, password) {
+                return Ok(Sheets::Xls(ret));
+            } else if let Ok(ret) = open_workbook_with_password::<Xlsx<_>, _>(path, password) {
+                return Ok(Sheets::Xlsx(ret));
+            } else if let Ok(ret) = open_workbook_with_password::<Xlsb<_>, _>(path, password) {
+                return Ok(Sheets::Xlsb(ret));
+            } else if let Ok(ret) = open_workbook_with_password::<Ods<_>, _>(path, password) {
+                return Ok(Sheets::Ods(ret));
+            } else {
+                return Err(Error::Msg("Cannot detect file format"));
+            };
+        }
+    })
+}
+
 /// Opens a workbook from the given bytes.
 ///
 /// Whenever possible use the statically known `open_workbook_from_rs` function instead
@@ -73,6 +121,66 @@ where
     };
 }
 
+impl<RS> Sheets<RS> {
+    /// Returns the formulas of [`Reader::worksheet_formula`] in Excel A1 syntax for all formats
+    ///
+    /// Only changes the OpenDocument formulas, the other formats already use this syntax.
+    /// See [`Ods::with_excel_formulas`].
+    pub fn with_excel_formulas(&mut self, excel: bool) -> &mut Self {
+        if let Sheets::Ods(ref mut e) = *self {
+            e.with_excel_formulas(excel);
+        }
+        self
+    }
+}
+
+impl<RS> Sheets<RS>
+where
+    RS: std::io::Read + std::io::Seek,
+{
+    /// Load the tables of the workbook
+    ///
+    /// Must be called before any other table method. Xls files have no tables.
+    pub fn load_tables(&mut self) -> Result<(), Error> {
+        match *self {
+            Sheets::Xls(_) => Ok(()),
+            Sheets::Xlsx(ref mut e) => e.load_tables().map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.load_tables().map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.load_tables().map_err(Error::Ods),
+        }
+    }
+
+    /// Get the names of all the tables
+    pub fn table_names(&self) -> Vec<&String> {
+        match *self {
+            Sheets::Xls(_) => Vec::new(),
+            Sheets::Xlsx(ref e) => e.table_names(),
+            Sheets::Xlsb(ref e) => e.table_names(),
+            Sheets::Ods(ref e) => e.table_names(),
+        }
+    }
+
+    /// Get the names of all the tables in a sheet
+    pub fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        match *self {
+            Sheets::Xls(_) => Vec::new(),
+            Sheets::Xlsx(ref e) => e.table_names_in_sheet(sheet_name),
+            Sheets::Xlsb(ref e) => e.table_names_in_sheet(sheet_name),
+            Sheets::Ods(ref e) => e.table_names_in_sheet(sheet_name),
+        }
+    }
+
+    /// Get the table by name
+    pub fn table_by_name(&mut self, table_name: &str) -> Result<Table<Data>, Error> {
+        match *self {
+            Sheets::Xls(_) => Err(Error::Msg("Tables are not supported by xls files")),
+            Sheets::Xlsx(ref mut e) => e.table_by_name(table_name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.table_by_name(table_name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.table_by_name(table_name).map_err(Error::Ods),
+        }
+    }
+}
+
 impl<RS> Reader<RS> for Sheets<RS>
 where
     RS: std::io::Read + std::io::Seek,
@@ -124,6 +232,152 @@ where
         }
     }
 
+    /// Read worksheet formulas parsed as expression trees
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<Expr>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_formula_expr(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_formula_expr(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_formula_expr(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_formula_expr(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read worksheet formulas along with their kind
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_formula_info(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_formula_info(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_formula_info(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_formula_info(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read worksheet data along with the number format code of each cell
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_range_with_format(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_range_with_format(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_range_with_format(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_range_with_format(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the style of each cell
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_styles(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_styles(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_styles(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_styles(name).map_err(Error::Ods),
+        }
+    }
+
+    fn styles(&self) -> &[Style] {
+        match *self {
+            Sheets::Xls(ref e) => e.styles(),
+            Sheets::Xlsx(ref e) => e.styles(),
+            Sheets::Xlsb(ref e) => e.styles(),
+            Sheets::Ods(ref e) => e.styles(),
+        }
+    }
+
+    /// Read the comments of a worksheet
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_comments(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_comments(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_comments(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_comments(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the hyperlinks of a worksheet
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_hyperlinks(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the rich text strings of a worksheet
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<RichText>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_rich_text(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Read the merged cells of a worksheet
+    fn merged_cells(&mut self, name: &str) -> Result<Vec<Dimensions>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.merged_cells(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.merged_cells(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.merged_cells(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.merged_cells(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_row_column_properties(
+        &mut self,
+        name: &str,
+    ) -> Result<RowColumnProperties, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_row_column_properties(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_row_column_properties(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_row_column_properties(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_row_column_properties(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_auto_filter(&mut self, name: &str) -> Result<Option<AutoFilter>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_auto_filter(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_auto_filter(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_auto_filter(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_auto_filter(name).map_err(Error::Ods),
+        }
+    }
+
+    fn worksheet_data_validations(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<DataValidation>, Self::Error> {
+        match *self {
+            Sheets::Xls(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xls),
+            Sheets::Xlsx(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xlsx),
+            Sheets::Xlsb(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Xlsb),
+            Sheets::Ods(ref mut e) => e.worksheet_data_validations(name).map_err(Error::Ods),
+        }
+    }
+
+    /// Iterate over the rows of a worksheet
+    fn worksheet_rows<'a>(&'a mut self, name: &str) -> Result<WorksheetRows<'a, Error>, Error>
+    where
+        Error: 'a,
+    {
+        Ok(match *self {
+            Sheets::Xls(ref mut e) => {
+                Box::new(e.worksheet_rows(name)?.map(|r| r.map_err(Error::Xls)))
+            }
+            Sheets::Xlsx(ref mut e) => {
+                Box::new(e.worksheet_rows(name)?.map(|r| r.map_err(Error::Xlsx)))
+            }
+            Sheets::Xlsb(ref mut e) => {
+                Box::new(e.worksheet_rows(name)?.map(|r| r.map_err(Error::Xlsb)))
+            }
+            Sheets::Ods(ref mut e) => {
+                Box::new(e.worksheet_rows(name)?.map(|r| r.map_err(Error::Ods)))
+            }
+        })
+    }
+
     fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
         match *self {
             Sheets::Xls(ref mut e) => e.worksheets(),
@@ -0,0 +1,296 @@
+//! BIFF8 stream encryption (MS-XLS 2.2.10, MS-OFFCRYPTO 2.3.6 and 2.3.7)
+//!
+//! Only record data is encrypted, record headers and a few records are kept
+//! in clear text.
+
+use rc4::cipher::{consts::U256, KeyInit, StreamCipher};
+use rc4::Rc4;
+
+use super::{utf16le, CryptoError, HashAlgorithm};
+use crate::utils::{read_u16, read_usize};
+
+/// Size of a RC4 re-keying block
+const BLOCK_LEN: usize = 1024;
+
+/// Decrypts, in place, the Workbook stream if it starts with a FilePass record
+///
+/// Returns `false` if the stream is not encrypted.
+pub(crate) fn decrypt_workbook(stream: &mut [u8], password: &str) -> Result<bool, CryptoError> {
+    let mut pos = 0;
+    let file_pass = loop {
+        if pos + 4 > stream.len() {
+            return Ok(false);
+        }
+        let typ = read_u16(&stream[pos..]);
+        let len = read_u16(&stream[pos + 2..]) as usize;
+        let data = stream
+            .get(pos + 4..pos + 4 + len)
+            .ok_or(CryptoError::Invalid("record length"))?;
+        pos += 4 + len;
+        match typ {
+            0x002F => break data.to_vec(),
+            // EOF: end of the workbook globals
+            0x000A => return Ok(false),
+            _ => (),
+        }
+    };
+    if file_pass.len() < 6 {
+        return Err(CryptoError::Invalid("FilePass"));
+    }
+    let mut cipher = match (read_u16(&file_pass), read_u16(&file_pass[2..])) {
+        (0, _) => Cipher::Xor(xor_key(&file_pass[2..], password)?),
+        (1, 1) => Cipher::Rc4(Box::new(Rc4Stream::new_rc4(&file_pass[6..], password)?)),
+        (1, 2..=4) => Cipher::Rc4(Box::new(Rc4Stream::new_cryptoapi(
+            &file_pass[6..],
+            password,
+        )?)),
+        (typ, version) => {
+            return Err(CryptoError::Unsupported(format!(
+                "FilePass type {typ} version {version}"
+            )))
+        }
+    };
+    while pos + 4 <= stream.len() {
+        let typ = read_u16(&stream[pos..]);
+        let len = read_u16(&stream[pos + 2..]) as usize;
+        let start = pos + 4;
+        let end = (start + len).min(stream.len());
+        pos = start + len;
+        match typ {
+            // BOF, FilePass, UsrExcl, FileLock, InterfaceHdr, RRDInfo, RRDHead
+            0x0809 | 0x002F | 0x0194 | 0x0195 | 0x00E1 | 0x0196 | 0x0138 => continue,
+            // BoundSheet8: lbPlyPos is not encrypted
+            0x0085 if end > start + 4 => cipher.decrypt(stream, start + 4, end, len),
+            0x0085 => continue,
+            _ => cipher.decrypt(stream, start, end, len),
+        }
+    }
+    Ok(true)
+}
+
+enum Cipher {
+    Xor([u8; 16]),
+    Rc4(Box<Rc4Stream>),
+}
+
+impl Cipher {
+    /// Decrypts `stream[start..end]`, part of a record of `len` bytes
+    fn decrypt(&mut self, stream: &mut [u8], start: usize, end: usize, len: usize) {
+        match self {
+            Cipher::Xor(key) => {
+                for (pos, b) in stream[start..end].iter_mut().enumerate() {
+                    *b = b.rotate_left(3) ^ key[(start + pos + len) % 16];
+                }
+            }
+            Cipher::Rc4(rc4) => rc4.decrypt(start, &mut stream[start..end]),
+        }
+    }
+}
+
+/// An RC4 key stream, re-keyed every 1024 bytes of the Workbook stream
+struct Rc4Stream {
+    /// Derives the key of a given block
+    key: Box<dyn Fn(u32) -> Vec<u8>>,
+    rc4: Rc4<U256>,
+    /// Block of the current key
+    block: usize,
+    /// Position of the key stream in the Workbook stream
+    pos: usize,
+}
+
+impl Rc4Stream {
+    fn new(key: Box<dyn Fn(u32) -> Vec<u8>>) -> Self {
+        let rc4 = rc4(&key(0));
+        Rc4Stream {
+            key,
+            rc4,
+            block: 0,
+            pos: 0,
+        }
+    }
+
+    /// RC4 encryption, version 1.1 (2.3.6.2)
+    fn new_rc4(data: &[u8], password: &str) -> Result<Self, CryptoError> {
+        if data.len() < 48 {
+            return Err(CryptoError::Invalid("FilePass"));
+        }
+        let md5 = HashAlgorithm::Md5;
+        let salt = &data[..16];
+        let h0 = md5.digest(&[&utf16le(password)]);
+        let buf = [&h0[..5], salt].concat().repeat(16);
+        let h1 = md5.digest(&[&buf]);
+        let h1 = h1[..5].to_vec();
+        let stream = Rc4Stream::new(Box::new(move |block| {
+            md5.digest(&[&h1, &block.to_le_bytes()])
+        }));
+        stream.verify(md5, &data[16..32], &data[32..48])
+    }
+
+    /// RC4 CryptoAPI encryption (2.3.5.2)
+    fn new_cryptoapi(data: &[u8], password: &str) -> Result<Self, CryptoError> {
+        if data.len() < 8 {
+            return Err(CryptoError::Invalid("FilePass"));
+        }
+        let header_len = read_usize(&data[4..]);
+        let header = data
+            .get(8..8 + header_len)
+            .filter(|h| h.len() >= 20)
+            .ok_or(CryptoError::Invalid("EncryptionHeader"))?;
+        let key_len = match read_usize(&header[16..]) {
+            0 => 5,
+            bits => bits / 8,
+        };
+        let verifier = &data[8 + header_len..];
+        let salt_len = verifier.get(..4).map_or(0, read_usize);
+        if verifier.len() < 4 + salt_len + 16 + 4 + 20 {
+            return Err(CryptoError::Invalid("EncryptionVerifier"));
+        }
+        let sha1 = HashAlgorithm::Sha1;
+        let h0 = sha1.digest(&[&verifier[4..4 + salt_len], &utf16le(password)]);
+        let stream = Rc4Stream::new(Box::new(move |block| {
+            let mut key = sha1.digest(&[&h0, &block.to_le_bytes()]);
+            key.truncate(key_len);
+            // 40 bits keys are padded to 128 bits
+            if key_len == 5 {
+                key.resize(16, 0);
+            }
+            key
+        }));
+        let v = &verifier[4 + salt_len..];
+        stream.verify(sha1, &v[..16], &v[20..40])
+    }
+
+    /// Checks the password against the encrypted verifier and its hash
+    fn verify(
+        self,
+        hash: HashAlgorithm,
+        verifier: &[u8],
+        verifier_hash: &[u8],
+    ) -> Result<Self, CryptoError> {
+        let mut v = [verifier, verifier_hash].concat();
+        rc4(&(self.key)(0)).apply_keystream(&mut v);
+        let (verifier, expected) = v.split_at(16);
+        if hash.digest(&[verifier]) != expected {
+            return Err(CryptoError::InvalidPassword);
+        }
+        Ok(self)
+    }
+
+    /// Decrypts `data`, located at `pos` in the Workbook stream
+    fn decrypt(&mut self, mut pos: usize, mut data: &mut [u8]) {
+        while !data.is_empty() {
+            let block = pos / BLOCK_LEN;
+            if block != self.block || pos < self.pos {
+                self.rc4 = rc4(&(self.key)(block as u32));
+                self.block = block;
+                self.pos = block * BLOCK_LEN;
+            }
+            self.rc4.apply_keystream(&mut vec![0; pos - self.pos]);
+            let len = data.len().min((block + 1) * BLOCK_LEN - pos);
+            let (chunk, rest) = data.split_at_mut(len);
+            self.rc4.apply_keystream(chunk);
+            pos += len;
+            self.pos = pos;
+            data = rest;
+        }
+    }
+}
+
+/// RC4 cipher of a key of any length
+///
+/// The key schedule reads 256 key bytes, cycling through the key: repeating the key up
+/// to 256 bytes gives the same cipher.
+fn rc4(key: &[u8]) -> Rc4<U256> {
+    let key = key.iter().cycle().take(256).copied().collect::<Vec<_>>();
+    Rc4::new_from_slice(&key).expect("256 bytes key")
+}
+
+/// XOR obfuscation, method 1 (2.3.7.2 and 2.3.7.3)
+fn xor_key(data: &[u8], password: &str) -> Result<[u8; 16], CryptoError> {
+    const INITIAL_CODE: [u16; 15] = [
+        0xE1F0, 0x1D0F, 0xCC9C, 0x84C0, 0x110C, 0x0E10, 0xF1CE, 0x313E, 0x1872, 0xE139, 0xD40F,
+        0x84F9, 0x280C, 0xA96A, 0x4EC3,
+    ];
+    // first column of the XorMatrix, next columns being derived by
+    // multiplication in GF(2^16) (polynomial 0x1021)
+    const XOR_MATRIX_ROWS: [u16; 15] = [
+        0xAEFC, 0x7B61, 0x4563, 0x0375, 0xD849, 0x6F45, 0xEB23, 0x47D3, 0xB861, 0x45A0, 0xAA51,
+        0x76B4, 0x3730, 0x3331, 0x1021,
+    ];
+    const PAD: [u8; 15] = [
+        0xBB, 0xFF, 0xFF, 0xBA, 0xFF, 0xFF, 0xB9, 0x80, 0x00, 0xBE, 0x0F, 0x00, 0xBF, 0x0F, 0x00,
+    ];
+
+    // single byte per character, high byte if the low byte is zero
+    let password: Vec<u8> = password
+        .encode_utf16()
+        .take(15)
+        .map(|c| match c as u8 {
+            0 => (c >> 8) as u8,
+            b => b,
+        })
+        .collect();
+    if password.is_empty() {
+        return Err(CryptoError::InvalidPassword);
+    }
+
+    let matrix: Vec<u16> = XOR_MATRIX_ROWS
+        .iter()
+        .flat_map(|&row| {
+            (0..7).scan(row, |v, _| {
+                let current = *v;
+                *v = if *v & 0x8000 != 0 {
+                    (*v << 1) ^ 0x1021
+                } else {
+                    *v << 1
+                };
+                Some(current)
+            })
+        })
+        .collect();
+    let mut key = INITIAL_CODE[password.len() - 1];
+    let mut element = matrix.len();
+    for &c in password.iter().rev() {
+        for bit in 0..7 {
+            element -= 1;
+            if c & (0x40 >> bit) != 0 {
+                key ^= matrix[element];
+            }
+        }
+    }
+
+    let mut verifier = 0u16;
+    for &c in password.iter().rev().chain(&[password.len() as u8]) {
+        verifier = (((verifier >> 14) & 1) | ((verifier << 1) & 0x7FFF)) ^ c as u16;
+    }
+    if read_u16(data) != key || read_u16(&data[2..]) != verifier ^ 0xCE4B {
+        return Err(CryptoError::InvalidPassword);
+    }
+
+    let mut array = [0; 16];
+    array[..password.len()].copy_from_slice(&password);
+    array[password.len()..].copy_from_slice(&PAD[..16 - password.len()]);
+    let key = key.to_le_bytes();
+    for (i, b) in array.iter_mut().enumerate() {
+        *b = (*b ^ key[i & 1]).rotate_left(2);
+    }
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rc4_key_length() {
+        let mut data = *b"Plaintext";
+        rc4(b"Key").apply_keystream(&mut data);
+        assert_eq!(data, [0xBB, 0xF3, 0x16, 0xE8, 0xD9, 0x40, 0xAF, 0x0A, 0xD3]);
+        let mut data = *b"Attack at dawn";
+        rc4(b"Secret").apply_keystream(&mut data);
+        assert_eq!(
+            data,
+            [0x45, 0xA0, 0x1F, 0x64, 0x5F, 0xC3, 0x5B, 0x38, 0x35, 0x52, 0x54, 0x4B, 0x9B, 0xF5]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Decryption of password protected workbooks
+//!
+//! - ECMA-376 Agile and Standard encryption (xlsx, xlsb), MS-OFFCRYPTO
+//! - BIFF8 RC4, RC4 CryptoAPI and XOR obfuscation (xls), MS-XLS 2.2.10
+//! - OpenDocument manifest based AES and Blowfish encryption (ods), ODF 1.2 3.4
+//!
+//! Without the `encryption` feature, only `CryptoError` is defined, so that the readers
+//! errors are the same with or without the feature.
+
+#[cfg(feature = "encryption")]
+use aes::cipher::{
+    Block, BlockCipher, BlockDecrypt, BlockDecryptMut, InnerIvInit, InvalidLength, KeyInit,
+};
+#[cfg(feature = "encryption")]
+use sha2::Digest;
+
+#[cfg(feature = "encryption")]
+pub(crate) mod biff;
+#[cfg(feature = "encryption")]
+pub(crate) mod odf;
+#[cfg(feature = "encryption")]
+pub(crate) mod ooxml;
+
+/// An error raised while decrypting a workbook
+#[derive(Debug)]
+pub enum CryptoError {
+    /// Io error
+    Io(std::io::Error),
+    /// Cfb error
+    Cfb(crate::cfb::CfbError),
+    /// Xml error
+    Xml(quick_xml::Error),
+    /// Zip error
+    Zip(zip::result::ZipError),
+    /// Password does not match the encryption verifier
+    InvalidPassword,
+    /// Encryption algorithm not supported
+    Unsupported(String),
+    /// Malformed encryption data
+    Invalid(&'static str),
+}
+
+from_err!(std::io::Error, CryptoError, Io);
+from_err!(crate::cfb::CfbError, CryptoError, Cfb);
+from_err!(quick_xml::Error, CryptoError, Xml);
+from_err!(zip::result::ZipError, CryptoError, Zip);
+
+impl std::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::Io(e) => write!(f, "I/O error: {e}"),
+            CryptoError::Cfb(e) => write!(f, "Cfb error: {e}"),
+            CryptoError::Xml(e) => write!(f, "Xml error: {e}"),
+            CryptoError::Zip(e) => write!(f, "Zip error: {e}"),
+            CryptoError::InvalidPassword => write!(f, "Invalid password"),
+            CryptoError::Unsupported(e) => write!(f, "Unsupported encryption: {e}"),
+            CryptoError::Invalid(e) => write!(f, "Invalid encryption data: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CryptoError::Io(e) => Some(e),
+            CryptoError::Cfb(e) => Some(e),
+            CryptoError::Xml(e) => Some(e),
+            CryptoError::Zip(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Hash algorithms referenced by encryption descriptors
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[cfg(feature = "encryption")]
+impl HashAlgorithm {
+    /// Parses an algorithm name, as found in Agile descriptors or ODF manifests
+    pub fn from_name(name: &str) -> Result<HashAlgorithm, CryptoError> {
+        match name.to_ascii_uppercase().replace('-', "").as_str() {
+            "MD5" => Ok(HashAlgorithm::Md5),
+            "SHA1" => Ok(HashAlgorithm::Sha1),
+            "SHA256" => Ok(HashAlgorithm::Sha256),
+            "SHA384" => Ok(HashAlgorithm::Sha384),
+            "SHA512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(CryptoError::Unsupported(format!("hash algorithm {name}"))),
+        }
+    }
+
+    /// Hashes the concatenation of `parts`
+    pub fn digest(self, parts: &[&[u8]]) -> Vec<u8> {
+        fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        match self {
+            HashAlgorithm::Md5 => digest::<md5::Md5>(parts),
+            HashAlgorithm::Sha1 => digest::<sha1::Sha1>(parts),
+            HashAlgorithm::Sha256 => digest::<sha2::Sha256>(parts),
+            HashAlgorithm::Sha384 => digest::<sha2::Sha384>(parts),
+            HashAlgorithm::Sha512 => digest::<sha2::Sha512>(parts),
+        }
+    }
+}
+
+/// Decrypts `data` with AES, in CBC mode if there is an `iv` or else in ECB mode
+///
+/// The key size selects AES-128, AES-192 or AES-256. Any trailing partial block is
+/// left as is.
+#[cfg(feature = "encryption")]
+pub(crate) fn aes_decrypt(
+    key: &[u8],
+    iv: Option<&[u8]>,
+    data: &mut [u8],
+) -> Result<(), InvalidLength> {
+    fn decrypt<C>(key: &[u8], iv: Option<&[u8]>, data: &mut [u8]) -> Result<(), InvalidLength>
+    where
+        C: BlockCipher + BlockDecrypt + KeyInit,
+    {
+        let cipher = C::new_from_slice(key)?;
+        let blocks = data
+            .chunks_exact_mut(C::block_size())
+            .map(Block::<C>::from_mut_slice);
+        match iv {
+            Some(iv) => {
+                let mut cbc = cbc::Decryptor::inner_iv_slice_init(cipher, iv)?;
+                blocks.for_each(|b| cbc.decrypt_block_mut(b));
+            }
+            None => blocks.for_each(|b| cipher.decrypt_block(b)),
+        }
+        Ok(())
+    }
+    match key.len() {
+        16 => decrypt::<aes::Aes128>(key, iv, data),
+        24 => decrypt::<aes::Aes192>(key, iv, data),
+        _ => decrypt::<aes::Aes256>(key, iv, data),
+    }
+}
+
+/// Decodes standard base64, ignoring whitespaces
+#[cfg(feature = "encryption")]
+pub(crate) fn base64_decode(s: &str) -> Result<Vec<u8>, CryptoError> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return Err(CryptoError::Invalid("base64")),
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Encodes a password as UTF-16LE, as expected by MS-OFFCRYPTO
+#[cfg(feature = "encryption")]
+fn utf16le(password: &str) -> Vec<u8> {
+    password.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        assert_eq!(base64_decode("").unwrap(), b"");
+        assert_eq!(base64_decode("Zm9vYg==").unwrap(), b"foob");
+        assert_eq!(base64_decode("Zm9v\nYmFy").unwrap(), b"foobar");
+        assert!(base64_decode("Zm9v*").is_err());
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn aes() {
+        // FIPS-197 appendix C, ECB
+        let plain = unhex("00112233445566778899aabbccddeeff");
+        for (key, cipher) in [
+            (
+                "000102030405060708090a0b0c0d0e0f",
+                "69c4e0d86a7b0430d8cdb78070b4c55a",
+            ),
+            (
+                "000102030405060708090a0b0c0d0e0f1011121314151617",
+                "dda97ca4864cdfe06eaf70a0ec0d7191",
+            ),
+            (
+                "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                "8ea2b7ca516745bfeafc49904b496089",
+            ),
+        ] {
+            let mut block = unhex(cipher);
+            aes_decrypt(&unhex(key), None, &mut block).unwrap();
+            assert_eq!(block, plain);
+        }
+
+        // SP 800-38A F.2.2, CBC, with a trailing partial block
+        let mut data = unhex("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2ff");
+        let key = unhex("2b7e151628aed2a6abf7158809cf4f3c");
+        let iv = unhex("000102030405060708090a0b0c0d0e0f");
+        aes_decrypt(&key, Some(&iv), &mut data).unwrap();
+        assert_eq!(
+            data,
+            unhex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51ff")
+        );
+
+        assert!(aes_decrypt(&key[..15], None, &mut data).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! OpenDocument package encryption (ODF 1.2 part 3, 3.4)
+//!
+//! Each encrypted entry is deflated then encrypted, its parameters being
+//! described in `META-INF/manifest.xml`.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+use flate2::read::DeflateDecoder;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader as XmlReader, Writer as XmlWriter};
+use zip::read::ZipArchive;
+use zip::write::{SimpleFileOptions, ZipWriter};
+use zip::CompressionMethod;
+
+use aes::cipher::KeyIvInit;
+use blowfish::Blowfish;
+use cfb_mode::BufDecryptor;
+use sha1::Sha1;
+
+use super::{aes_decrypt, base64_decode, CryptoError, HashAlgorithm};
+
+const MANIFEST: &str = "META-INF/manifest.xml";
+
+/// Encryption parameters of a manifest file entry
+#[derive(Default)]
+struct EncryptionData {
+    checksum_type: String,
+    checksum: Vec<u8>,
+    algorithm: String,
+    iv: Vec<u8>,
+    start_key: Option<String>,
+    derivation: String,
+    key_size: Option<usize>,
+    iterations: u32,
+    salt: Vec<u8>,
+}
+
+impl EncryptionData {
+    fn read(&mut self, e: &BytesStart<'_>, reader: &XmlReader<&[u8]>) -> Result<(), CryptoError> {
+        for a in e.attributes() {
+            let a = a.map_err(quick_xml::Error::InvalidAttr)?;
+            let value = a.decode_and_unescape_value(reader)?;
+            match (e.local_name().as_ref(), a.key.local_name().as_ref()) {
+                (b"encryption-data", b"checksum-type") => self.checksum_type = value.into(),
+                (b"encryption-data", b"checksum") => self.checksum = base64_decode(&value)?,
+                (b"algorithm", b"algorithm-name") => self.algorithm = value.into(),
+                (b"algorithm", b"initialisation-vector") => self.iv = base64_decode(&value)?,
+                (b"start-key-generation", b"start-key-generation-name") => {
+                    self.start_key = Some(value.into())
+                }
+                (b"key-derivation", b"key-derivation-name") => self.derivation = value.into(),
+                (b"key-derivation", b"key-size") => {
+                    self.key_size = Some(
+                        value
+                            .parse()
+                            .map_err(|_| CryptoError::Invalid("key-size"))?,
+                    )
+                }
+                (b"key-derivation", b"iteration-count") => {
+                    self.iterations = value
+                        .parse()
+                        .map_err(|_| CryptoError::Invalid("iteration-count"))?
+                }
+                (b"key-derivation", b"salt") => self.salt = base64_decode(&value)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypts and inflates an entry
+    fn decrypt(&self, mut data: Vec<u8>, password: &str) -> Result<Vec<u8>, CryptoError> {
+        if self.derivation != "PBKDF2" {
+            return Err(CryptoError::Unsupported(format!(
+                "key derivation {}",
+                self.derivation
+            )));
+        }
+        let start_key = match self.start_key.as_deref() {
+            None => HashAlgorithm::Sha1,
+            Some(name) => HashAlgorithm::from_name(name.rsplit('#').next().unwrap_or(name))?,
+        };
+        let start_key = start_key.digest(&[password.as_bytes()]);
+        let mut key = vec![0; self.key_size.unwrap_or(16)];
+        pbkdf2::pbkdf2_hmac::<Sha1>(&start_key, &self.salt, self.iterations, &mut key);
+
+        let candidates = match self.algorithm.rsplit('#').next() {
+            Some("aes128-cbc" | "aes192-cbc" | "aes256-cbc") => {
+                if self.iv.len() != 16 {
+                    return Err(CryptoError::Invalid("initialisation-vector"));
+                }
+                aes_decrypt(&key, Some(&self.iv), &mut data)
+                    .map_err(|_| CryptoError::Invalid("key-size"))?;
+                let padded = data.clone();
+                // W3C padding, the last byte being the padding length
+                let pad = data.last().map_or(0, |&b| b as usize);
+                if pad == 0 || pad > 16 || pad > data.len() {
+                    return Err(CryptoError::InvalidPassword);
+                }
+                data.truncate(data.len() - pad);
+                vec![padded]
+            }
+            Some("Blowfish CFB") => {
+                if self.iv.len() != 8 {
+                    return Err(CryptoError::Invalid("initialisation-vector"));
+                }
+                BufDecryptor::<Blowfish>::new_from_slices(&key, &self.iv)
+                    .map_err(|_| CryptoError::Invalid("key-size"))?
+                    .decrypt(&mut data);
+                Vec::new()
+            }
+            _ => {
+                return Err(CryptoError::Unsupported(format!(
+                    "algorithm {}",
+                    self.algorithm
+                )))
+            }
+        };
+
+        // checksum of the (at most) first 1024 decrypted bytes, the padding
+        // being included or not depending on the producer
+        let checksum = self.checksum_type.rsplit(['#', '/']).next().unwrap_or("");
+        let (hash, len) = match checksum.to_ascii_lowercase().as_str() {
+            // "SHA1/1K"
+            "1k" => (HashAlgorithm::Sha1, 1024),
+            "sha256-1k" => (HashAlgorithm::Sha256, 1024),
+            c => (HashAlgorithm::from_name(c)?, usize::MAX),
+        };
+        let valid = std::iter::once(&data)
+            .chain(&candidates)
+            .any(|d| hash.digest(&[&d[..len.min(d.len())]]) == self.checksum);
+        if !valid {
+            return Err(CryptoError::InvalidPassword);
+        }
+
+        let mut inflated = Vec::new();
+        DeflateDecoder::new(&data[..]).read_to_end(&mut inflated)?;
+        Ok(inflated)
+    }
+}
+
+/// Parses the manifest, returning the encryption data of each encrypted entry
+/// and the manifest stripped of these encryption data
+fn read_manifest(
+    manifest: &[u8],
+) -> Result<(HashMap<String, EncryptionData>, Vec<u8>), CryptoError> {
+    let mut reader = XmlReader::from_reader(manifest);
+    let mut writer = XmlWriter::new(Vec::new());
+    let mut entries = HashMap::new();
+    let mut path = String::new();
+    let mut current: Option<EncryptionData> = None;
+    let mut buf = Vec::new();
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e)
+                if e.local_name().as_ref() == b"file-entry" =>
+            {
+                for a in e.attributes() {
+                    let a = a.map_err(quick_xml::Error::InvalidAttr)?;
+                    if a.key.local_name().as_ref() == b"full-path" {
+                        path = a.decode_and_unescape_value(&reader)?.into();
+                    }
+                }
+            }
+            Event::Start(ref e) if e.local_name().as_ref() == b"encryption-data" => {
+                let mut data = EncryptionData::default();
+                data.read(e, &reader)?;
+                current = Some(data);
+                buf.clear();
+                continue;
+            }
+            Event::End(ref e) if e.local_name().as_ref() == b"encryption-data" => {
+                if let Some(data) = current.take() {
+                    entries.insert(std::mem::take(&mut path), data);
+                }
+                buf.clear();
+                continue;
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        match current {
+            Some(ref mut data) => {
+                if let Event::Start(ref e) | Event::Empty(ref e) = event {
+                    data.read(e, &reader)?;
+                }
+            }
+            None => writer.write_event(event)?,
+        }
+        buf.clear();
+    }
+    Ok((entries, writer.into_inner()))
+}
+
+/// Entries read by `Ods`
+fn is_read(name: &str) -> bool {
+    matches!(name, "content.xml" | "styles.xml") || name.starts_with("Pictures")
+}
+
+/// Decrypts an encrypted OpenDocument package and returns an equivalent,
+/// unencrypted, zip archive
+///
+/// Packages without encrypted entries are returned unchanged. Encrypted entries
+/// which are never read (meta data, settings, thumbnails) are left out.
+pub(crate) fn decrypt(data: Vec<u8>, password: &str) -> Result<Vec<u8>, CryptoError> {
+    let mut zip = ZipArchive::new(Cursor::new(&data))?;
+    let mut manifest = Vec::new();
+    if let Ok(mut f) = zip.by_name(MANIFEST) {
+        f.read_to_end(&mut manifest)?;
+    }
+    let (entries, manifest) = read_manifest(&manifest)?;
+    if entries.is_empty() {
+        drop(zip);
+        return Ok(data);
+    }
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        if file.is_dir() {
+            writer.add_directory(name, options)?;
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let content = match entries.get(&name) {
+            Some(encryption) if is_read(&name) => encryption.decrypt(content, password)?,
+            // each entry needs its own (slow) key derivation
+            Some(_) => continue,
+            None if name == MANIFEST => manifest.clone(),
+            None => content,
+        };
+        writer.start_file(name, options)?;
+        writer.write_all(&content)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
@@ -0,0 +1,290 @@
+//! ECMA-376 document encryption (MS-OFFCRYPTO 2.3.4), used by xlsx and xlsb
+//!
+//! The encrypted zip package is stored in the `EncryptedPackage` stream of a
+//! compound file, next to an `EncryptionInfo` stream describing the keys.
+
+use std::io::Cursor;
+
+use quick_xml::events::Event;
+use quick_xml::Reader as XmlReader;
+
+use super::{aes_decrypt, utf16le, CryptoError, HashAlgorithm};
+use crate::cfb::Cfb;
+use crate::utils::{read_u16, read_u32, read_usize};
+
+const SEGMENT_LEN: usize = 4096;
+/// Maximum number of password hashing rounds of the Agile encryption (2.3.4.10)
+const MAX_SPIN_COUNT: u32 = 10_000_000;
+
+/// Decrypts an encrypted OOXML package and returns the inner zip archive
+///
+/// Files which are not compound files are returned unchanged.
+pub(crate) fn decrypt(data: Vec<u8>, password: &str) -> Result<Vec<u8>, CryptoError> {
+    if !data.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        return Ok(data);
+    }
+    let mut reader = Cursor::new(&data);
+    let mut cfb = Cfb::new(&mut reader, data.len())?;
+    let info = cfb.get_stream("EncryptionInfo", &mut reader)?;
+    let package = cfb.get_stream("EncryptedPackage", &mut reader)?;
+    if info.len() < 8 || package.len() < 8 {
+        return Err(CryptoError::Invalid("EncryptionInfo"));
+    }
+    match (read_u16(&info), read_u16(&info[2..])) {
+        (4, 4) => decrypt_agile(&info[8..], &package, password),
+        (2..=4, 2) => decrypt_standard(&info[8..], &package, password),
+        (major, minor) => Err(CryptoError::Unsupported(format!(
+            "EncryptionInfo version {major}.{minor}"
+        ))),
+    }
+}
+
+/// Size of the decrypted package, and the encrypted bytes
+fn split_package(package: &[u8]) -> (usize, &[u8]) {
+    (read_usize(package).min(package.len() - 8), &package[8..])
+}
+
+/// Resizes a key or a salt, padding with 0x36 (2.3.4.11)
+fn fit(mut bytes: Vec<u8>, len: usize) -> Vec<u8> {
+    bytes.resize(len, 0x36);
+    bytes
+}
+
+/// Agile key encryptor or key data parameters
+#[derive(Default)]
+struct AgileParams {
+    salt: Vec<u8>,
+    hash: Option<HashAlgorithm>,
+    key_bits: usize,
+    spin_count: u32,
+    verifier_input: Vec<u8>,
+    verifier_hash: Vec<u8>,
+    key_value: Vec<u8>,
+}
+
+impl AgileParams {
+    fn from_attributes(
+        e: &quick_xml::events::BytesStart<'_>,
+        reader: &XmlReader<&[u8]>,
+    ) -> Result<Self, CryptoError> {
+        let mut params = AgileParams::default();
+        for a in e.attributes() {
+            let a = a.map_err(quick_xml::Error::InvalidAttr)?;
+            let value = a.decode_and_unescape_value(reader)?;
+            let parse = |v: &str| v.parse::<u32>().map_err(|_| CryptoError::Invalid("number"));
+            match a.key.local_name().as_ref() {
+                b"saltValue" => params.salt = super::base64_decode(&value)?,
+                b"hashAlgorithm" => params.hash = Some(HashAlgorithm::from_name(&value)?),
+                b"keyBits" => params.key_bits = parse(&value)? as usize,
+                b"spinCount" => params.spin_count = parse(&value)?,
+                b"encryptedVerifierHashInput" => {
+                    params.verifier_input = super::base64_decode(&value)?
+                }
+                b"encryptedVerifierHashValue" => {
+                    params.verifier_hash = super::base64_decode(&value)?
+                }
+                b"encryptedKeyValue" => params.key_value = super::base64_decode(&value)?,
+                b"cipherAlgorithm" if value != "AES" => {
+                    return Err(CryptoError::Unsupported(format!("cipher {value}")))
+                }
+                b"cipherChaining" if value != "ChainingModeCBC" => {
+                    return Err(CryptoError::Unsupported(format!("chaining {value}")))
+                }
+                _ => (),
+            }
+        }
+        Ok(params)
+    }
+
+    fn hash(&self) -> Result<HashAlgorithm, CryptoError> {
+        self.hash
+            .ok_or(CryptoError::Invalid("missing hashAlgorithm"))
+    }
+
+    /// Decrypts `data` with AES-CBC, the salt being used as IV
+    fn decrypt(&self, key: &[u8], iv: Vec<u8>, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let mut data = data.to_vec();
+        aes_decrypt(key, Some(&fit(iv, 16)), &mut data)
+            .map_err(|_| CryptoError::Invalid("keyBits"))?;
+        Ok(data)
+    }
+}
+
+/// Agile encryption (2.3.4.10)
+fn decrypt_agile(xml: &[u8], package: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    let mut reader = XmlReader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut key_data = None;
+    let mut encryptor = None;
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) => match e.local_name().as_ref() {
+                b"keyData" => key_data = Some(AgileParams::from_attributes(e, &reader)?),
+                b"encryptedKey" if encryptor.is_none() => {
+                    let params = AgileParams::from_attributes(e, &reader)?;
+                    // skip certificate key encryptors
+                    if params.spin_count > 0 || !params.verifier_input.is_empty() {
+                        encryptor = Some(params);
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    let key_data = key_data.ok_or(CryptoError::Invalid("missing keyData"))?;
+    let encryptor = encryptor.ok_or(CryptoError::Unsupported("no password key".into()))?;
+
+    // password key derivation (2.3.4.11)
+    if encryptor.spin_count > MAX_SPIN_COUNT {
+        return Err(CryptoError::Invalid("spinCount"));
+    }
+    let hash = encryptor.hash()?;
+    let mut h = hash.digest(&[&encryptor.salt, &utf16le(password)]);
+    for i in 0..encryptor.spin_count {
+        h = hash.digest(&[&i.to_le_bytes(), &h]);
+    }
+    let block_key = |block: &[u8]| fit(hash.digest(&[&h, block]), encryptor.key_bits / 8);
+
+    let input = encryptor.decrypt(
+        &block_key(&[0xfe, 0xa7, 0xd2, 0x76, 0x3b, 0x4b, 0x9e, 0x79]),
+        encryptor.salt.clone(),
+        &encryptor.verifier_input,
+    )?;
+    let expected = encryptor.decrypt(
+        &block_key(&[0xd7, 0xaa, 0x0f, 0x6d, 0x30, 0x61, 0x34, 0x4e]),
+        encryptor.salt.clone(),
+        &encryptor.verifier_hash,
+    )?;
+    let salt_len = encryptor.salt.len().min(input.len());
+    let actual = hash.digest(&[&input[..salt_len]]);
+    if expected.get(..actual.len()) != Some(&actual[..]) {
+        return Err(CryptoError::InvalidPassword);
+    }
+    let mut key = encryptor.decrypt(
+        &block_key(&[0x14, 0x6e, 0x0b, 0xe7, 0xab, 0xac, 0xd0, 0xd6]),
+        encryptor.salt.clone(),
+        &encryptor.key_value,
+    )?;
+    key.truncate(key_data.key_bits / 8);
+
+    // package, by segments of 4096 bytes (2.3.4.15)
+    let data_hash = key_data.hash()?;
+    let (size, encrypted) = split_package(package);
+    let mut out = Vec::with_capacity(encrypted.len());
+    for (i, segment) in encrypted.chunks(SEGMENT_LEN).enumerate() {
+        let iv = data_hash.digest(&[&key_data.salt, &(i as u32).to_le_bytes()]);
+        out.extend(key_data.decrypt(&key, iv, segment)?);
+    }
+    out.truncate(size);
+    Ok(out)
+}
+
+/// Standard encryption, AES only (2.3.4.5)
+fn decrypt_standard(info: &[u8], package: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    let header_len = read_usize(info);
+    if header_len < 32 || info.len() < 4 + header_len + 4 {
+        return Err(CryptoError::Invalid("EncryptionHeader"));
+    }
+    let header = &info[4..4 + header_len];
+    let alg_id = read_u32(&header[8..]);
+    if !matches!(alg_id, 0x660E..=0x6610) {
+        return Err(CryptoError::Unsupported(format!("AlgID 0x{alg_id:X}")));
+    }
+    let key_len = read_usize(&header[16..]) / 8;
+    if !matches!(key_len, 16 | 24 | 32) {
+        return Err(CryptoError::Invalid("KeySize"));
+    }
+
+    // EncryptionVerifier
+    let verifier = &info[4 + header_len..];
+    let salt_len = read_usize(verifier);
+    if verifier.len() < 4 + salt_len + 16 + 4 + 32 {
+        return Err(CryptoError::Invalid("EncryptionVerifier"));
+    }
+    let salt = &verifier[4..4 + salt_len];
+    let mut encrypted_verifier = verifier[4 + salt_len..20 + salt_len].to_vec();
+    let mut encrypted_hash = verifier[24 + salt_len..56 + salt_len].to_vec();
+
+    // key derivation (2.3.4.7)
+    let sha1 = HashAlgorithm::Sha1;
+    let mut h = sha1.digest(&[salt, &utf16le(password)]);
+    for i in 0..50_000u32 {
+        h = sha1.digest(&[&i.to_le_bytes(), &h]);
+    }
+    let h = sha1.digest(&[&h, &0u32.to_le_bytes()]);
+    let derive = |pad: u8| {
+        let mut buf = [pad; 64];
+        buf.iter_mut().zip(&h).for_each(|(b, h)| *b ^= h);
+        sha1.digest(&[&buf])
+    };
+    let key = [derive(0x36), derive(0x5C)].concat();
+    let decrypt = |data: &mut [u8]| {
+        aes_decrypt(&key[..key_len], None, data).map_err(|_| CryptoError::Invalid("KeySize"))
+    };
+
+    // password verification (2.3.4.9)
+    decrypt(&mut encrypted_verifier)?;
+    decrypt(&mut encrypted_hash)?;
+    if sha1.digest(&[&encrypted_verifier]) != encrypted_hash[..20] {
+        return Err(CryptoError::InvalidPassword);
+    }
+
+    let (size, encrypted) = split_package(package);
+    let mut out = encrypted.to_vec();
+    decrypt(&mut out)?;
+    out.truncate(size);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Standard EncryptionInfo stream, after its version and flags
+    fn standard_info(key_bits: u32) -> Vec<u8> {
+        let mut header = vec![0; 32];
+        header[8..12].copy_from_slice(&0x660Eu32.to_le_bytes());
+        header[16..20].copy_from_slice(&key_bits.to_le_bytes());
+        let mut info = 32u32.to_le_bytes().to_vec();
+        info.extend(header);
+        // salt size, salt, verifier, verifier hash size and verifier hash
+        info.extend(16u32.to_le_bytes());
+        info.extend([0; 16 + 16 + 4 + 32]);
+        info
+    }
+
+    #[test]
+    fn standard_key_size() {
+        let package = [0; 8];
+        assert!(matches!(
+            decrypt_standard(&standard_info(4096), &package, "pass"),
+            Err(CryptoError::Invalid("KeySize"))
+        ));
+        assert!(matches!(
+            decrypt_standard(&standard_info(40), &package, "pass"),
+            Err(CryptoError::Invalid("KeySize"))
+        ));
+        assert!(matches!(
+            decrypt_standard(&standard_info(128), &package, "pass"),
+            Err(CryptoError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn agile_spin_count() {
+        let xml = br#"<encryption>
+            <keyData keyBits="128" hashAlgorithm="SHA512" saltValue="AAAAAAAAAAAAAAAAAAAAAA=="/>
+            <keyEncryptors><keyEncryptor><encryptedKey spinCount="4294967295" keyBits="128"
+                hashAlgorithm="SHA512" saltValue="AAAAAAAAAAAAAAAAAAAAAA=="
+                encryptedVerifierHashInput="AAAAAAAAAAAAAAAAAAAAAA=="/>
+            </keyEncryptor></keyEncryptors>
+        </encryption>"#;
+        assert!(matches!(
+            decrypt_agile(xml, &[0; 8], "pass"),
+            Err(CryptoError::Invalid("spinCount"))
+        ));
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::sync::Arc;
 #[cfg(feature = "dates")]
 use std::sync::OnceLock;
 
@@ -6,6 +7,7 @@ use serde::de::Visitor;
 use serde::{self, Deserialize};
 
 use super::CellErrorType;
+use crate::formats::{iso_datetime_to_serial, iso_time_to_serial, NumberFormat};
 
 #[cfg(feature = "dates")]
 static EXCEL_EPOCH: OnceLock<chrono::NaiveDateTime> = OnceLock::new();
@@ -219,6 +221,45 @@ impl fmt::Display for Data {
     }
 }
 
+impl Data {
+    /// Renders the value as displayed by a spreadsheet application for the
+    /// given number format code (e.g. `#,##0.00`, `0%` or `dd/mm/yyyy`)
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::Data;
+    ///
+    /// assert_eq!(Data::Float(0.1 + 0.2).to_display_string("General"), "0.3");
+    /// assert_eq!(Data::Float(-1234.5).to_display_string("#,##0.00"), "-1,234.50");
+    /// assert_eq!(Data::Float(0.25).to_display_string("0%"), "25%");
+    /// assert_eq!(Data::Float(45000.).to_display_string("yyyy-mm-dd"), "2023-03-15");
+    /// ```
+    pub fn to_display_string(&self, format: &str) -> String {
+        self.to_display_string_with(&NumberFormat::parse(format))
+    }
+
+    pub(crate) fn to_display_string_with(&self, format: &NumberFormat) -> String {
+        match self {
+            Data::Int(v) => format.format_f64(*v as f64, false),
+            Data::Float(v) => format.format_f64(*v, false),
+            Data::DateTime(v) => format.format_f64(v.value, v.is_1904),
+            Data::String(s) => format.format_str(s),
+            Data::Bool(true) => "TRUE".to_string(),
+            Data::Bool(false) => "FALSE".to_string(),
+            Data::Error(e) => e.to_string(),
+            Data::DateTimeIso(s) => match iso_datetime_to_serial(s) {
+                Some(v) if format.is_date() => format.format_f64(v, false),
+                _ => s.clone(),
+            },
+            Data::DurationIso(s) => match iso_time_to_serial(s) {
+                Some(v) if format.is_date() => format.format_f64(v, false),
+                _ => s.clone(),
+            },
+            Data::Empty => String::new(),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Data {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Data, D::Error>
@@ -330,6 +371,30 @@ where
     }
 }
 
+/// A cell value along with the number format code of its style
+///
+/// The format code is shared between all cells having the same style.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DataWithFormat {
+    /// The cell value
+    pub value: Data,
+    /// The number format code (e.g. `0.00%` or `"$"#,##0`), if the cell has a style
+    pub format: Option<Arc<str>>,
+}
+
+impl DataWithFormat {
+    /// Creates a new `DataWithFormat`
+    pub fn new(value: Data, format: Option<Arc<str>>) -> Self {
+        DataWithFormat { value, format }
+    }
+
+    /// Renders the value with its own number format (`General` if none)
+    pub fn to_display_string(&self) -> String {
+        self.value
+            .to_display_string(self.format.as_deref().unwrap_or("General"))
+    }
+}
+
 /// An enum to represent all different data types that can appear as
 /// a value in a worksheet cell
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -653,6 +718,38 @@ impl<'a> From<DataRef<'a>> for Data {
     }
 }
 
+impl From<Data> for DataRef<'_> {
+    fn from(value: Data) -> Self {
+        match value {
+            Data::Int(v) => DataRef::Int(v),
+            Data::Float(v) => DataRef::Float(v),
+            Data::String(v) => DataRef::String(v),
+            Data::Bool(v) => DataRef::Bool(v),
+            Data::DateTime(v) => DataRef::DateTime(v),
+            Data::DateTimeIso(v) => DataRef::DateTimeIso(v),
+            Data::DurationIso(v) => DataRef::DurationIso(v),
+            Data::Error(v) => DataRef::Error(v),
+            Data::Empty => DataRef::Empty,
+        }
+    }
+}
+
+impl<'a> From<&'a Data> for DataRef<'a> {
+    fn from(value: &'a Data) -> Self {
+        match value {
+            Data::Int(v) => DataRef::Int(*v),
+            Data::Float(v) => DataRef::Float(*v),
+            Data::String(v) => DataRef::SharedString(v),
+            Data::Bool(v) => DataRef::Bool(*v),
+            Data::DateTime(v) => DataRef::DateTime(*v),
+            Data::DateTimeIso(v) => DataRef::DateTimeIso(v.clone()),
+            Data::DurationIso(v) => DataRef::DurationIso(v.clone()),
+            Data::Error(v) => DataRef::Error(v.clone()),
+            Data::Empty => DataRef::Empty,
+        }
+    }
+}
+
 /// Excel datetime type. Possible: date, time, datetime, duration.
 /// At this time we can only determine datetime (date and time are datetime too) and duration.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -851,4 +948,23 @@ mod tests {
         assert_eq!(DataRef::Bool(true).as_f64(), Some(1.0));
         assert_eq!(DataRef::Bool(false).as_f64(), Some(0.0));
     }
+
+    #[test]
+    fn test_display_small_numbers() {
+        let cases = [
+            (0.0001, ["0.0001", "0.00", "0.0"]),
+            (0.004, ["0.004", "0.00", "0.0"]),
+            (-0.0001, ["-0.0001", "0.00", "0.0"]),
+            (0.04, ["0.04", "0.04", "0.0"]),
+        ];
+        for (value, expected) in cases {
+            for (format, expected) in ["General", "0.00", "0.0"].into_iter().zip(expected) {
+                assert_eq!(
+                    Data::Float(value).to_display_string(format),
+                    expected,
+                    "{value} {format}"
+                );
+            }
+        }
+    }
 }
@@ -1,10 +1,11 @@
 use serde::de::value::BorrowedStrDeserializer;
 use serde::de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
 use serde::{self, forward_to_deserialize_any, Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::{fmt, slice, str};
 
-use super::{CellErrorType, CellType, Data, Range, Rows};
+use super::{CellErrorType, CellType, Data, DataRef, Dimensions, Error, Range, Rows};
 
 /// A cell deserialization specific error enum
 #[derive(Debug)]
@@ -85,12 +86,15 @@ pub enum Headers<'h, H> {
 #[derive(Clone)]
 pub struct RangeDeserializerBuilder<'h, H> {
     headers: Headers<'h, H>,
+    /// Merged cells whose value is copied to all their cells
+    merged: Vec<Dimensions>,
 }
 
 impl Default for RangeDeserializerBuilder<'static, &'static str> {
     fn default() -> Self {
         RangeDeserializerBuilder {
             headers: Headers::All,
+            merged: Vec::new(),
         }
     }
 }
@@ -170,9 +174,46 @@ impl<'h, H: AsRef<str> + Clone + 'h> RangeDeserializerBuilder<'h, H> {
     pub fn with_headers(headers: &'h [H]) -> Self {
         RangeDeserializerBuilder {
             headers: Headers::Custom(headers),
+            merged: Vec::new(),
         }
     }
 
+    /// Read the cells of merged areas as the value of their top left cell
+    ///
+    /// Only the top left cell of merged cells holds a value, the others are empty, which
+    /// prevents matching headers spanning several columns or reading grouped row labels.
+    /// The areas are usually those of [`Reader::merged_cells`](crate::Reader::merged_cells).
+    ///
+    /// Only the cells of the range are read: merged areas ending past the last used cell
+    /// are fully read after extending the range with [`Range::fill_merged`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use calamine::{Data, Dimensions, Range, RangeDeserializerBuilder};
+    /// # fn main() -> Result<(), calamine::DeError> {
+    /// let mut range = Range::new((0, 0), (2, 1));
+    /// range.set_value((0, 0), Data::from("group"));
+    /// range.set_value((0, 1), Data::from("value"));
+    /// range.set_value((1, 0), Data::from("a"));
+    /// range.set_value((1, 1), Data::from(1.0));
+    /// range.set_value((2, 1), Data::from(2.0));
+    ///
+    /// // "a" spans two rows
+    /// let merged = [Dimensions::new((1, 0), (2, 0))];
+    /// let rows = RangeDeserializerBuilder::new()
+    ///     .fill_merged(&merged)
+    ///     .from_range(&range)?
+    ///     .collect::<Result<Vec<(String, f64)>, _>>()?;
+    /// assert_eq!(rows, [("a".to_string(), 1.0), ("a".to_string(), 2.0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fill_merged(&mut self, merged: &[Dimensions]) -> &mut Self {
+        self.merged = merged.to_vec();
+        self
+    }
+
     /// Build a `RangeDeserializer` from this configuration.
     ///
     /// # Example
@@ -206,6 +247,42 @@ impl<'h, H: AsRef<str> + Clone + 'h> RangeDeserializerBuilder<'h, H> {
     {
         RangeDeserializer::new(self, range)
     }
+
+    /// Build a `RowsDeserializer` from this configuration, deserializing rows as they are read
+    ///
+    /// Unlike [`RangeDeserializerBuilder::from_range`], the worksheet is never fully loaded
+    /// in memory. Columns are counted from the first non-empty cell of the first row and
+    /// empty rows are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use calamine::{open_workbook, Error, Xlsx, Reader, RangeDeserializerBuilder};
+    /// fn main() -> Result<(), Error> {
+    ///     let path = format!("{}/tests/temperature.xlsx", env!("CARGO_MANIFEST_DIR"));
+    ///     let mut workbook: Xlsx<_> = open_workbook(path)?;
+    ///     let rows = workbook.worksheet_rows("Sheet1")?;
+    ///     let mut iter = RangeDeserializerBuilder::new().from_rows(rows)?;
+    ///
+    ///     if let Some(result) = iter.next() {
+    ///         let (label, value): (String, f64) = result?;
+    ///         assert_eq!(label, "celsius");
+    ///         assert_eq!(value, 22.2222);
+    ///
+    ///         Ok(())
+    ///     } else {
+    ///         Err(From::from("expected at least one record but got none"))
+    ///     }
+    /// }
+    /// ```
+    pub fn from_rows<'a, I, E, D>(&self, rows: I) -> Result<RowsDeserializer<I::IntoIter, D>, Error>
+    where
+        I: IntoIterator<Item = Result<(u32, Vec<DataRef<'a>>), E>>,
+        E: Into<Error>,
+        D: DeserializeOwned,
+    {
+        RowsDeserializer::new(self, rows.into_iter())
+    }
 }
 
 impl<'h> RangeDeserializerBuilder<'h, &str> {
@@ -320,6 +397,12 @@ where
     column_indexes: Vec<usize>,
     headers: Option<Vec<String>>,
     rows: Rows<'cell, T>,
+    /// All the cells of the range, to read merged cells from their top left cell
+    cells: &'cell [T],
+    /// Index in `cells` of the top left cell of each merged cell
+    merged: HashMap<usize, usize>,
+    /// Index of the next row in the range
+    next_row: usize,
     current_pos: (u32, u32),
     end_pos: (u32, u32),
     _priv: PhantomData<D>,
@@ -335,6 +418,7 @@ where
         range: &'cell Range<T>,
     ) -> Result<Self, DeError> {
         let mut rows = range.rows();
+        let merged = merged_cells_sources(range, &builder.merged);
 
         let mut current_pos = range.start().unwrap_or((0, 0));
         let end_pos = range.end().unwrap_or((0, 0));
@@ -345,7 +429,8 @@ where
                 if let Some(row) = rows.next() {
                     let all_indexes = (0..row.len()).collect::<Vec<_>>();
                     let all_headers = {
-                        let de = RowDeserializer::new(&all_indexes, None, row, current_pos);
+                        let indexes = merged_indexes(&all_indexes, 0, &merged);
+                        let de = RowDeserializer::new(&indexes, None, &range.inner, current_pos);
                         current_pos.0 += 1;
                         Deserialize::deserialize(de)?
                     };
@@ -357,7 +442,8 @@ where
             Headers::Custom(headers) => {
                 if let Some(row) = rows.next() {
                     let all_indexes = (0..row.len()).collect::<Vec<_>>();
-                    let de = RowDeserializer::new(&all_indexes, None, row, current_pos);
+                    let indexes = merged_indexes(&all_indexes, 0, &merged);
+                    let de = RowDeserializer::new(&indexes, None, &range.inner, current_pos);
                     current_pos.0 += 1;
                     let all_headers: Vec<String> = Deserialize::deserialize(de)?;
                     let custom_indexes = headers
@@ -377,10 +463,15 @@ where
             }
         };
 
+        // the header row is already read
+        let next_row = usize::from(headers.is_some());
         Ok(RangeDeserializer {
             column_indexes,
             headers,
             rows,
+            cells: &range.inner,
+            merged,
+            next_row,
             current_pos,
             end_pos,
             _priv: PhantomData,
@@ -400,15 +491,26 @@ where
             ref column_indexes,
             ref headers,
             ref mut rows,
+            cells,
+            ref merged,
+            ref mut next_row,
             mut current_pos,
             ..
         } = *self;
 
         if let Some(row) = rows.next() {
             current_pos.0 += 1;
+            let offset = *next_row * row.len();
+            *next_row += 1;
             let headers = headers.as_ref().map(|h| &**h);
-            let de = RowDeserializer::new(column_indexes, headers, row, current_pos);
-            Some(Deserialize::deserialize(de))
+            if merged.is_empty() {
+                let de = RowDeserializer::new(column_indexes, headers, row, current_pos);
+                Some(Deserialize::deserialize(de))
+            } else {
+                let indexes = merged_indexes(column_indexes, offset, merged);
+                let de = RowDeserializer::new(&indexes, headers, cells, current_pos);
+                Some(Deserialize::deserialize(de))
+            }
         } else {
             None
         }
@@ -421,6 +523,199 @@ where
     }
 }
 
+/// Maps the index of each merged cell of `range` to the index of its top left cell
+fn merged_cells_sources<T: CellType>(
+    range: &Range<T>,
+    merged: &[Dimensions],
+) -> HashMap<usize, usize> {
+    let mut sources = HashMap::new();
+    let (Some(start), Some(end)) = (range.start(), range.end()) else {
+        return sources;
+    };
+    let width = range.width();
+    let index = |row: u32, col: u32| (row - start.0) as usize * width + (col - start.1) as usize;
+    for area in merged {
+        if range.get_value(area.start).is_none() {
+            continue;
+        }
+        let anchor = index(area.start.0, area.start.1);
+        for row in area.start.0..=area.end.0.min(end.0) {
+            for col in area.start.1..=area.end.1.min(end.1) {
+                sources.insert(index(row, col), anchor);
+            }
+        }
+    }
+    sources
+}
+
+/// Indexes in the range cells of the columns of the row starting at `offset`, merged cells
+/// being read from their top left cell
+fn merged_indexes(
+    column_indexes: &[usize],
+    offset: usize,
+    merged: &HashMap<usize, usize>,
+) -> Vec<usize> {
+    column_indexes
+        .iter()
+        .map(|i| *merged.get(&(offset + i)).unwrap_or(&(offset + i)))
+        .collect()
+}
+
+/// Copies the value of the top left cell of merged cells to the other cells of a row
+///
+/// `anchors` holds the value of the top left cell of each merged area, read on its first row.
+fn fill_merged_row(merged: &[Dimensions], anchors: &mut [Data], row: u32, cells: &mut Vec<Data>) {
+    for (area, anchor) in merged.iter().zip(anchors.iter_mut()) {
+        if row < area.start.0 || row > area.end.0 {
+            continue;
+        }
+        let (first, last) = (area.start.1 as usize, area.end.1 as usize);
+        if row == area.start.0 {
+            *anchor = cells.get(first).cloned().unwrap_or(Data::Empty);
+        }
+        if *anchor == Data::Empty {
+            continue;
+        }
+        if cells.len() <= last {
+            cells.resize(last + 1, Data::Empty);
+        }
+        for cell in &mut cells[first..=last] {
+            *cell = anchor.clone();
+        }
+    }
+}
+
+/// A configured deserializer over streamed worksheet rows.
+///
+/// See [`RangeDeserializerBuilder::from_rows`].
+pub struct RowsDeserializer<I, D> {
+    rows: I,
+    /// Column of the first cell, as rows are indexed by absolute column
+    first_col: usize,
+    /// Columns to deserialize, `None` to deserialize every cell of each row
+    column_indexes: Option<Vec<usize>>,
+    headers: Option<Vec<String>>,
+    /// First row, when it is not a header row
+    pending: Option<(u32, Vec<Data>)>,
+    /// Merged cells whose value is copied to all their cells
+    merged: Vec<Dimensions>,
+    /// Value of the top left cell of each merged area
+    anchors: Vec<Data>,
+    _priv: PhantomData<D>,
+}
+
+impl<'a, I, E, D> RowsDeserializer<I, D>
+where
+    I: Iterator<Item = Result<(u32, Vec<DataRef<'a>>), E>>,
+    E: Into<Error>,
+    D: DeserializeOwned,
+{
+    fn new<'h, H: AsRef<str> + Clone + 'h>(
+        builder: &RangeDeserializerBuilder<'h, H>,
+        mut rows: I,
+    ) -> Result<Self, Error> {
+        let (row, cells) = match rows.next() {
+            Some(row) => row.map_err(Into::into)?,
+            None => {
+                return Ok(RowsDeserializer {
+                    rows,
+                    first_col: 0,
+                    column_indexes: None,
+                    headers: None,
+                    pending: None,
+                    merged: Vec::new(),
+                    anchors: Vec::new(),
+                    _priv: PhantomData,
+                })
+            }
+        };
+        let first_col = cells.iter().position(|c| *c != DataRef::Empty).unwrap_or(0);
+        let mut cells: Vec<Data> = cells.into_iter().map(Data::from).collect();
+        let merged = builder.merged.clone();
+        let mut anchors = vec![Data::Empty; merged.len()];
+        fill_merged_row(&merged, &mut anchors, row, &mut cells);
+        let cells = cells.split_off(first_col);
+        let pos = (row, first_col as u32);
+        let all_indexes = (0..cells.len()).collect::<Vec<_>>();
+
+        let (column_indexes, headers, pending) = match builder.headers {
+            Headers::None => (None, None, Some((row, cells))),
+            Headers::All => {
+                let de = RowDeserializer::new(&all_indexes, None, &cells, pos);
+                let all_headers: Vec<String> = Deserialize::deserialize(de)?;
+                (Some(all_indexes), Some(all_headers), None)
+            }
+            Headers::Custom(headers) => {
+                let de = RowDeserializer::new(&all_indexes, None, &cells, pos);
+                let all_headers: Vec<String> = Deserialize::deserialize(de)?;
+                let custom_indexes = headers
+                    .iter()
+                    .map(|h| h.as_ref().trim())
+                    .map(|h| {
+                        all_headers
+                            .iter()
+                            .position(|header| header.trim() == h)
+                            .ok_or_else(|| DeError::HeaderNotFound(h.to_owned()))
+                    })
+                    .collect::<Result<Vec<_>, DeError>>()?;
+                (Some(custom_indexes), Some(all_headers), None)
+            }
+        };
+
+        Ok(RowsDeserializer {
+            rows,
+            first_col,
+            column_indexes,
+            headers,
+            pending,
+            merged,
+            anchors,
+            _priv: PhantomData,
+        })
+    }
+}
+
+impl<'a, I, E, D> Iterator for RowsDeserializer<I, D>
+where
+    I: Iterator<Item = Result<(u32, Vec<DataRef<'a>>), E>>,
+    E: Into<Error>,
+    D: DeserializeOwned,
+{
+    type Item = Result<D, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (row, mut cells) = match self.pending.take() {
+            Some(row) => row,
+            None => match self.rows.next()? {
+                Ok((row, cells)) => {
+                    let mut cells: Vec<Data> = cells.into_iter().map(Data::from).collect();
+                    fill_merged_row(&self.merged, &mut self.anchors, row, &mut cells);
+                    let first_col = self.first_col.min(cells.len());
+                    (row, cells.split_off(first_col))
+                }
+                Err(e) => return Some(Err(e.into())),
+            },
+        };
+        let all_indexes;
+        let column_indexes = match self.column_indexes {
+            Some(ref indexes) => indexes,
+            None => {
+                all_indexes = (0..cells.len()).collect::<Vec<_>>();
+                &all_indexes
+            }
+        };
+        // rows are only as long as their last non-empty cell
+        let width = column_indexes.iter().max().map_or(0, |i| i + 1);
+        let width = width.max(self.headers.as_ref().map_or(0, |h| h.len()));
+        if cells.len() < width {
+            cells.resize(width, Data::Empty);
+        }
+        let pos = (row, self.first_col as u32);
+        let de = RowDeserializer::new(column_indexes, self.headers.as_deref(), &cells, pos);
+        Some(Deserialize::deserialize(de).map_err(Error::De))
+    }
+}
+
 struct RowDeserializer<'header, 'cell, T> {
     cells: &'cell [T],
     headers: Option<&'header [String]>,
@@ -832,4 +1127,82 @@ mod tests {
             Content::Foo
         );
     }
+
+    #[test]
+    fn test_from_rows() {
+        use crate::{DataRef, Error, RangeDeserializerBuilder};
+
+        #[derive(Debug, serde_derive::Deserialize, PartialEq)]
+        struct Record {
+            label: Option<String>,
+            value: f64,
+        }
+
+        // rows starting at column B, with a missing cell
+        let rows = || {
+            vec![
+                Ok::<_, Error>((
+                    2,
+                    vec![
+                        DataRef::Empty,
+                        DataRef::SharedString("label"),
+                        DataRef::SharedString("value"),
+                    ],
+                )),
+                Ok((
+                    3,
+                    vec![
+                        DataRef::Empty,
+                        DataRef::SharedString("a"),
+                        DataRef::Float(1.5),
+                    ],
+                )),
+                Ok((5, vec![DataRef::Empty, DataRef::Empty, DataRef::Int(2)])),
+            ]
+        };
+
+        let records = RangeDeserializerBuilder::with_deserialize_headers::<Record>()
+            .from_rows(rows())
+            .unwrap()
+            .collect::<Result<Vec<Record>, _>>()
+            .unwrap();
+        let expected = [
+            Record {
+                label: Some("a".to_string()),
+                value: 1.5,
+            },
+            Record {
+                label: None,
+                value: 2.,
+            },
+        ];
+        assert_eq!(records, expected);
+
+        let tuples = RangeDeserializerBuilder::new()
+            .has_headers(false)
+            .from_rows(rows())
+            .unwrap()
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .unwrap();
+        assert_eq!(tuples[0], ("label".to_string(), "value".to_string()));
+        assert_eq!(tuples.len(), 3);
+
+        let missing = RangeDeserializerBuilder::with_headers(&["missing"])
+            .from_rows::<_, _, Vec<String>>(rows())
+            .err();
+        assert!(matches!(
+            missing,
+            Some(Error::De(super::DeError::HeaderNotFound(_)))
+        ));
+
+        // reader errors are forwarded
+        let mut failing = rows();
+        failing.push(Err(Error::Msg("broken")));
+        let mut iter = RangeDeserializerBuilder::new()
+            .from_rows::<_, _, (String, f64)>(failing)
+            .unwrap();
+        assert_eq!(iter.by_ref().take(2).count(), 2);
+        assert!(matches!(iter.next(), Some(Err(Error::Msg("broken")))));
+        assert!(iter.next().is_none());
+    }
 }
@@ -0,0 +1,510 @@
+//! Formula dependencies
+//!
+//! Maps each formula cell to the cells and ranges it reads, to trace the inputs of a cell
+//! or the cells impacted by an input.
+//!
+//! # Examples
+//! ```
+//! use calamine::{open_workbook_auto, DependencyGraph};
+//!
+//! # let path = format!("{}/tests/eval.xlsx", env!("CARGO_MANIFEST_DIR"));
+//! let mut workbook = open_workbook_auto(path).unwrap();
+//! let graph = DependencyGraph::new(&mut workbook).unwrap();
+//! // the total, in B4, reads the amounts B2:B3 and the `Rate` defined name
+//! for area in graph.precedents("Sheet1", (3, 1)) {
+//!     println!("{}!{:?}:{:?}", area.sheet, area.start, area.end);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::formula::{parse_defined_name, Expr, Reference};
+use crate::{Range, Reader, SheetType};
+
+/// Last row of a worksheet, for whole columns references
+const MAX_ROW: u32 = 1_048_575;
+/// Last column of a worksheet, for whole rows references
+const MAX_COL: u32 = 16_383;
+
+/// A cell of a workbook
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SheetCell {
+    /// Sheet name
+    pub sheet: String,
+    /// Position (row, column)
+    pub pos: (u32, u32),
+}
+
+/// A rectangular area of a worksheet
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SheetArea {
+    /// Sheet name
+    pub sheet: String,
+    /// Top left corner (row, column)
+    pub start: (u32, u32),
+    /// Bottom right corner (row, column), whole columns and rows ending with the worksheet
+    pub end: (u32, u32),
+}
+
+impl SheetArea {
+    /// Does the area contain the cell at `pos` of `sheet`
+    pub fn contains(&self, sheet: &str, pos: (u32, u32)) -> bool {
+        self.sheet == sheet
+            && (self.start.0..=self.end.0).contains(&pos.0)
+            && (self.start.1..=self.end.1).contains(&pos.1)
+    }
+}
+
+/// The cells read by each formula of a workbook
+///
+/// References through defined names are resolved to the cells of the name, and 3-D
+/// references to one area per sheet. References computed by functions, like `OFFSET`
+/// or `INDIRECT`, cannot be followed: only their arguments are precedents.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    /// Worksheet names, in workbook order
+    sheets: Vec<String>,
+    /// Direct precedents of each formula cell
+    precedents: BTreeMap<SheetCell, Vec<SheetArea>>,
+    /// Formula cells reading each single cell precedent
+    cell_dependents: HashMap<SheetCell, Vec<SheetCell>>,
+    /// Formula cells reading each multi-cell area precedent
+    area_dependents: BTreeMap<SheetArea, Vec<SheetCell>>,
+}
+
+impl DependencyGraph {
+    /// Reads the formulas and defined names of all the worksheets of a workbook
+    pub fn new<RS, R>(workbook: &mut R) -> Result<Self, R::Error>
+    where
+        RS: Read + Seek,
+        R: Reader<RS>,
+    {
+        let names = workbook
+            .sheets_metadata()
+            .iter()
+            .filter(|s| s.typ == SheetType::WorkSheet)
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        let mut sheets = Vec::with_capacity(names.len());
+        for name in names {
+            let formulas = workbook.worksheet_formula_expr(&name)?;
+            sheets.push((name, formulas));
+        }
+        Ok(DependencyGraph::from_sheets(
+            sheets,
+            workbook.defined_names(),
+        ))
+    }
+
+    fn from_sheets(sheets: Vec<(String, Range<Expr>)>, names: &[(String, String)]) -> Self {
+        let names = names
+            .iter()
+            .filter_map(|(name, formula)| {
+                Some((name.to_uppercase(), parse_defined_name(formula).ok()?))
+            })
+            .collect::<HashMap<_, _>>();
+        let sheet_names = sheets
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let mut precedents = BTreeMap::new();
+        for (sheet, formulas) in &sheets {
+            let mut collector = Collector {
+                sheets: &sheet_names,
+                names: &names,
+                pending_names: HashSet::new(),
+            };
+            let start = formulas.start().unwrap_or_default();
+            for (row, col, expr) in formulas.used_cells() {
+                let mut areas = Vec::new();
+                collector.collect(sheet, expr, &mut areas);
+                // keep the first occurrence order, without duplicates
+                let mut seen = HashSet::new();
+                areas.retain(|a| seen.insert(a.clone()));
+                let cell = SheetCell {
+                    sheet: sheet.clone(),
+                    pos: (start.0 + row as u32, start.1 + col as u32),
+                };
+                precedents.insert(cell, areas);
+            }
+        }
+        // reverse index, each list sorted as iterating over the sorted formula cells
+        let mut cell_dependents: HashMap<_, Vec<_>> = HashMap::new();
+        let mut area_dependents: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (cell, areas) in &precedents {
+            for area in areas {
+                if area.start == area.end {
+                    let precedent = SheetCell {
+                        sheet: area.sheet.clone(),
+                        pos: area.start,
+                    };
+                    cell_dependents
+                        .entry(precedent)
+                        .or_default()
+                        .push(cell.clone());
+                } else {
+                    area_dependents
+                        .entry(area.clone())
+                        .or_default()
+                        .push(cell.clone());
+                }
+            }
+        }
+        DependencyGraph {
+            sheets: sheet_names,
+            precedents,
+            cell_dependents,
+            area_dependents,
+        }
+    }
+
+    /// All the formula cells, sorted by sheet name and position
+    pub fn formula_cells(&self) -> impl Iterator<Item = &SheetCell> {
+        self.precedents.keys()
+    }
+
+    /// Cells and ranges read directly by the formula at `pos` of `sheet`
+    ///
+    /// Returns an empty slice if the cell has no formula.
+    pub fn precedents(&self, sheet: &str, pos: (u32, u32)) -> &[SheetArea] {
+        let cell = SheetCell {
+            sheet: self.sheet_name(sheet).to_string(),
+            pos,
+        };
+        self.precedents.get(&cell).map_or(&[], |p| p.as_slice())
+    }
+
+    /// Formula cells reading directly the cell at `pos` of `sheet`
+    pub fn dependents(&self, sheet: &str, pos: (u32, u32)) -> Vec<&SheetCell> {
+        let sheet = self.sheet_name(sheet);
+        let cell = SheetCell {
+            sheet: sheet.to_string(),
+            pos,
+        };
+        // areas are sorted by sheet then top left corner: skip the ones starting below `pos`
+        let first = SheetArea {
+            sheet: sheet.to_string(),
+            start: (0, 0),
+            end: (0, 0),
+        };
+        let last = SheetArea {
+            sheet: sheet.to_string(),
+            start: pos,
+            end: (MAX_ROW, MAX_COL),
+        };
+        let areas = self
+            .area_dependents
+            .range(first..=last)
+            .filter(|(area, _)| area.contains(sheet, pos))
+            .flat_map(|(_, cells)| cells);
+        self.cell_dependents
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .chain(areas)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Cells and ranges read by the formula at `pos` of `sheet`, directly or through
+    /// other formulas
+    pub fn all_precedents(&self, sheet: &str, pos: (u32, u32)) -> Vec<&SheetArea> {
+        let mut areas = Vec::new();
+        let mut seen = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(self.sheet_name(sheet), pos)];
+        while let Some((sheet, pos)) = stack.pop() {
+            if !visited.insert((sheet, pos)) {
+                continue;
+            }
+            for area in self.precedents(sheet, pos) {
+                if seen.insert(area) {
+                    areas.push(area);
+                    stack.extend(self.formulas_in(area).map(|c| (c.sheet.as_str(), c.pos)));
+                }
+            }
+        }
+        areas
+    }
+
+    /// Formula cells reading the cell at `pos` of `sheet`, directly or through other formulas
+    pub fn all_dependents(&self, sheet: &str, pos: (u32, u32)) -> Vec<&SheetCell> {
+        let mut dependents = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(self.sheet_name(sheet), pos)];
+        while let Some((sheet, pos)) = stack.pop() {
+            for cell in self.dependents(sheet, pos) {
+                if visited.insert(cell) {
+                    dependents.push(cell);
+                    stack.push((&cell.sheet, cell.pos));
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Formula cells sorted so that each formula comes after the formulas it reads
+    ///
+    /// Returns as error the cells which cannot be ordered, on circular references or
+    /// reading them, if any.
+    pub fn topological_order(&self) -> Result<Vec<&SheetCell>, Vec<&SheetCell>> {
+        // formula precedents of each formula
+        let edges = self
+            .precedents
+            .iter()
+            .map(|(cell, areas)| {
+                let precedents = areas
+                    .iter()
+                    .flat_map(|a| self.formulas_in(a))
+                    .collect::<BTreeSet<_>>();
+                (cell, precedents)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut dependents: BTreeMap<&SheetCell, Vec<&SheetCell>> = BTreeMap::new();
+        let mut in_degrees = BTreeMap::new();
+        for (cell, precedents) in &edges {
+            in_degrees.insert(*cell, precedents.len());
+            for p in precedents {
+                dependents.entry(*p).or_default().push(*cell);
+            }
+        }
+        let mut ready = in_degrees
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(c, _)| *c)
+            .collect::<Vec<_>>();
+        ready.reverse();
+        let mut order = Vec::with_capacity(edges.len());
+        while let Some(cell) = ready.pop() {
+            order.push(cell);
+            for d in dependents.get(cell).into_iter().flatten() {
+                let degree = in_degrees.get_mut(d).expect("formula cell");
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(*d);
+                }
+            }
+        }
+        if order.len() == edges.len() {
+            Ok(order)
+        } else {
+            Err(in_degrees
+                .into_iter()
+                .filter(|(_, d)| *d > 0)
+                .map(|(c, _)| c)
+                .collect())
+        }
+    }
+
+    /// Formula cells within an area
+    fn formulas_in<'a>(&'a self, area: &'a SheetArea) -> impl Iterator<Item = &'a SheetCell> {
+        let first = SheetCell {
+            sheet: area.sheet.clone(),
+            pos: area.start,
+        };
+        let last = SheetCell {
+            sheet: area.sheet.clone(),
+            pos: area.end,
+        };
+        self.precedents
+            .range(first..=last)
+            .map(|(cell, _)| cell)
+            .filter(move |cell| (area.start.1..=area.end.1).contains(&cell.pos.1))
+    }
+
+    fn sheet_name<'a>(&'a self, name: &'a str) -> &'a str {
+        sheet_name(&self.sheets, name)
+    }
+}
+
+/// Worksheet name as written in the workbook, sheet names being case insensitive
+fn sheet_name<'a>(sheets: &'a [String], name: &'a str) -> &'a str {
+    sheets
+        .iter()
+        .find(|s| s.eq_ignore_ascii_case(name))
+        .map_or(name, |s| s.as_str())
+}
+
+/// Collects the references of a formula
+struct Collector<'a> {
+    /// Worksheet names, in workbook order
+    sheets: &'a [String],
+    names: &'a HashMap<String, Expr>,
+    /// Names being resolved, to stop on circular names
+    pending_names: HashSet<String>,
+}
+
+impl Collector<'_> {
+    fn collect(&mut self, sheet: &str, expr: &Expr, areas: &mut Vec<SheetArea>) {
+        match expr {
+            Expr::Reference(r) => self.reference(sheet, r, areas),
+            Expr::Name(name) => {
+                let key = name.to_uppercase();
+                if let Some(e) = self.names.get(&key) {
+                    if self.pending_names.insert(key.clone()) {
+                        self.collect(sheet, e, areas);
+                        self.pending_names.remove(&key);
+                    }
+                }
+            }
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    self.collect(sheet, arg, areas);
+                }
+            }
+            Expr::Unary(_, e) => self.collect(sheet, e, areas),
+            Expr::Binary(_, l, r) => {
+                self.collect(sheet, l, areas);
+                self.collect(sheet, r, areas);
+            }
+            Expr::Array(rows) => {
+                for e in rows.iter().flatten() {
+                    self.collect(sheet, e, areas);
+                }
+            }
+            Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Error(_) | Expr::Missing => {}
+        }
+    }
+
+    fn reference(&self, sheet: &str, r: &Reference, areas: &mut Vec<SheetArea>) {
+        let first = sheet_name(self.sheets, r.sheet.as_deref().unwrap_or(sheet));
+        let sheets = match &r.last_sheet {
+            Some(last) => {
+                let last = sheet_name(self.sheets, last);
+                let sheets = self.sheets;
+                match (
+                    sheets.iter().position(|s| s == first),
+                    sheets.iter().position(|s| s == last),
+                ) {
+                    (Some(i), Some(j)) => sheets[i.min(j)..=i.max(j)].to_vec(),
+                    // unknown sheets, likely in an external workbook
+                    _ => return,
+                }
+            }
+            None => vec![first.to_string()],
+        };
+        let start = (r.start.row.unwrap_or(0), r.start.col.unwrap_or(0));
+        let end = (r.end.row.unwrap_or(MAX_ROW), r.end.col.unwrap_or(MAX_COL));
+        for sheet in sheets {
+            areas.push(SheetArea {
+                sheet,
+                start: (start.0.min(end.0), start.1.min(end.1)),
+                end: (start.0.max(end.0), start.1.max(end.1)),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::parse_formula;
+
+    fn sheet(name: &str, formulas: &[((u32, u32), &str)]) -> (String, Range<Expr>) {
+        let mut range = Range::new((0, 0), (9, 9));
+        for (pos, formula) in formulas {
+            range.set_value(*pos, parse_formula(formula).unwrap());
+        }
+        (name.to_string(), range)
+    }
+
+    fn area(sheet: &str, start: (u32, u32), end: (u32, u32)) -> SheetArea {
+        SheetArea {
+            sheet: sheet.to_string(),
+            start,
+            end,
+        }
+    }
+
+    fn cell(sheet: &str, pos: (u32, u32)) -> SheetCell {
+        SheetCell {
+            sheet: sheet.to_string(),
+            pos,
+        }
+    }
+
+    #[test]
+    fn precedents_and_dependents() {
+        let graph = DependencyGraph::from_sheets(
+            vec![
+                sheet("S1", &[((0, 1), "A1*2+Total"), ((1, 1), "SUM(s3!A:A,B1)")]),
+                sheet("S2", &[((0, 0), "SUM(S1:S3!C1)")]),
+                sheet("S3", &[((0, 0), "S1!B1")]),
+            ],
+            &[
+                ("Total".to_string(), "S2!$A$1".to_string()),
+                ("Loop".to_string(), "Loop+1".to_string()),
+            ],
+        );
+        assert_eq!(
+            graph.precedents("S1", (0, 1)),
+            [area("S1", (0, 0), (0, 0)), area("S2", (0, 0), (0, 0))]
+        );
+        assert_eq!(
+            graph.precedents("s1", (1, 1)),
+            [area("S3", (0, 0), (MAX_ROW, 0)), area("S1", (0, 1), (0, 1))]
+        );
+        assert_eq!(
+            graph.precedents("S2", (0, 0)),
+            [
+                area("S1", (0, 2), (0, 2)),
+                area("S2", (0, 2), (0, 2)),
+                area("S3", (0, 2), (0, 2))
+            ]
+        );
+        assert!(graph.precedents("S1", (5, 5)).is_empty());
+
+        assert_eq!(
+            graph.dependents("S1", (0, 1)),
+            [&cell("S1", (1, 1)), &cell("S3", (0, 0))]
+        );
+        assert_eq!(graph.dependents("S3", (7, 0)), [&cell("S1", (1, 1))]);
+        assert_eq!(graph.dependents("S1", (0, 2)), [&cell("S2", (0, 0))]);
+        assert!(graph.dependents("S3", (7, 1)).is_empty());
+        assert_eq!(graph.all_dependents("S2", (0, 0)).len(), 3);
+        assert_eq!(graph.all_precedents("S3", (0, 0)).len(), 6);
+    }
+
+    #[test]
+    fn topological_order() {
+        let graph = DependencyGraph::from_sheets(
+            vec![
+                sheet("S1", &[((0, 0), "S2!A1+1"), ((0, 1), "A1+A3")]),
+                sheet("S2", &[((0, 0), "42")]),
+            ],
+            &[],
+        );
+        assert_eq!(
+            graph.topological_order().unwrap(),
+            [
+                &cell("S2", (0, 0)),
+                &cell("S1", (0, 0)),
+                &cell("S1", (0, 1))
+            ]
+        );
+
+        let graph = DependencyGraph::from_sheets(
+            vec![sheet(
+                "S1",
+                &[
+                    ((0, 0), "B1"),
+                    ((0, 1), "C1"),
+                    ((0, 2), "A1"),
+                    ((1, 0), "A1"),
+                ],
+            )],
+            &[],
+        );
+        assert_eq!(
+            graph.topological_order().unwrap_err(),
+            [
+                &cell("S1", (0, 0)),
+                &cell("S1", (0, 1)),
+                &cell("S1", (0, 2)),
+                &cell("S1", (1, 0))
+            ]
+        );
+    }
+}
@@ -0,0 +1,785 @@
+//! Built-in worksheet functions
+
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    compare, is_number, number, to_bool, to_number, to_text, type_rank, Context, Evaluator, Value,
+};
+use crate::formula::Expr;
+use crate::{CellErrorType, Data, ExcelDateTime, ExcelDateTimeType};
+
+/// Serial number of 1970-01-01
+const UNIX_EPOCH_SERIAL: f64 = 25569.;
+
+/// Calls a function, unknown functions evaluating to `#NAME?`
+pub(super) fn call(ev: &mut Evaluator, ctx: Context, name: &str, args: &[Expr]) -> Value {
+    let mut f = Function { ev, ctx, args };
+    let res = match name {
+        "SUM" => f.numbers().map(|n| number(n.iter().fold(0., |a, b| a + b))),
+        "PRODUCT" => f.numbers().map(|n| number(n.iter().product())),
+        "AVERAGE" => f.numbers().map(|n| {
+            if n.is_empty() {
+                Data::Error(CellErrorType::Div0)
+            } else {
+                number(n.iter().sum::<f64>() / n.len() as f64)
+            }
+        }),
+        "MIN" => f
+            .numbers()
+            .map(|n| number(n.into_iter().reduce(f64::min).unwrap_or(0.))),
+        "MAX" => f
+            .numbers()
+            .map(|n| number(n.into_iter().reduce(f64::max).unwrap_or(0.))),
+        "COUNT" => f.count(|d, from_ref| {
+            if from_ref {
+                is_number(d)
+            } else {
+                !matches!(d, Data::Error(_)) && to_number(d).is_ok()
+            }
+        }),
+        "COUNTA" => f.count(|d, _| d != &Data::Empty),
+        "IF" => return f.if_(),
+        "IFERROR" => return f.if_error(|_| true),
+        "IFNA" => return f.if_error(|e| e == &CellErrorType::NA),
+        "AND" => f.logical(|b| b.iter().all(|b| *b)),
+        "OR" => f.logical(|b| b.iter().any(|b| *b)),
+        "NOT" => f
+            .arity(1, 1)
+            .and_then(|_| Ok(Data::Bool(!to_bool(&f.scalar(0))?))),
+        "TRUE" => f.arity(0, 0).map(|_| Data::Bool(true)),
+        "FALSE" => f.arity(0, 0).map(|_| Data::Bool(false)),
+        "NA" => f.arity(0, 0).map(|_| Data::Error(CellErrorType::NA)),
+        "ISBLANK" => f.arity(1, 1).map(|_| {
+            let v = f.arg(0);
+            let is_ref = matches!(v, Value::Ref(_));
+            Data::Bool(is_ref && f.ev.scalar(f.ctx, v) == Data::Empty)
+        }),
+        "ISNUMBER" => f.is(is_number),
+        "ISTEXT" => f.is(|d| matches!(d, Data::String(_))),
+        "ISERROR" => f.is(|d| matches!(d, Data::Error(_))),
+        "ISNA" => f.is(|d| d == &Data::Error(CellErrorType::NA)),
+        "ABS" => f.math(|n| Ok(n.abs())),
+        "INT" => f.math(|n| Ok(n.floor())),
+        "SQRT" => f.math(|n| {
+            if n < 0. {
+                Err(CellErrorType::Num)
+            } else {
+                Ok(n.sqrt())
+            }
+        }),
+        "MOD" => f.math2(|a, b| {
+            if b == 0. {
+                Err(CellErrorType::Div0)
+            } else {
+                Ok(a - b * (a / b).floor())
+            }
+        }),
+        "POWER" => f.math2(|a, b| Ok(a.powf(b))),
+        "ROUND" => f.round(f64::round),
+        "ROUNDUP" => f.round(|n| n.abs().ceil().copysign(n)),
+        "ROUNDDOWN" => f.round(f64::trunc),
+        "VLOOKUP" => return f.lookup(true),
+        "HLOOKUP" => return f.lookup(false),
+        "MATCH" => f.match_(),
+        "INDEX" => return f.index(),
+        "CONCAT" => f.concat(true),
+        "CONCATENATE" => f.concat(false),
+        "LEN" => f
+            .arity(1, 1)
+            .and_then(|_| Ok(Data::Float(f.text(0)?.chars().count() as f64))),
+        "LEFT" => f.substring(|s, n| s.chars().take(n).collect()),
+        "RIGHT" => f.substring(|s, n| {
+            let len = s.chars().count();
+            s.chars().skip(len.saturating_sub(n)).collect()
+        }),
+        "MID" => f.mid(),
+        "UPPER" => f
+            .arity(1, 1)
+            .and_then(|_| Ok(Data::String(f.text(0)?.to_uppercase()))),
+        "LOWER" => f
+            .arity(1, 1)
+            .and_then(|_| Ok(Data::String(f.text(0)?.to_lowercase()))),
+        "TRIM" => f.arity(1, 1).and_then(|_| {
+            let s = f.text(0)?;
+            Ok(Data::String(
+                s.split(' ')
+                    .filter(|w| !w.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ))
+        }),
+        "DATE" => f.arity(3, 3).and_then(|_| {
+            let y = f.number(0)?.trunc() as i64;
+            let m = f.number(1)?.trunc() as i64;
+            let d = f.number(2)?.trunc() as i64;
+            let y = if (0..1900).contains(&y) { y + 1900 } else { y };
+            if !(1900..10000).contains(&y) {
+                return Err(CellErrorType::Num);
+            }
+            date(ymd_to_serial(y, m, d)?)
+        }),
+        "TODAY" => f.arity(0, 0).and_then(|_| date(now().floor())),
+        "NOW" => f.arity(0, 0).and_then(|_| date(now())),
+        "YEAR" => f.date_part(|(y, _, _)| y as f64),
+        "MONTH" => f.date_part(|(_, m, _)| m as f64),
+        "DAY" => f.date_part(|(_, _, d)| d as f64),
+        "WEEKDAY" => f.arity(1, 2).and_then(|_| {
+            let serial = f.number(0)?.floor();
+            if serial < 0. {
+                return Err(CellErrorType::Num);
+            }
+            let typ = if f.args.len() > 1 { f.number(1)? } else { 1. };
+            let (offset, base) = match typ as i64 {
+                1 => (1., 1.),
+                2 => (2., 1.),
+                3 => (2., 0.),
+                _ => return Err(CellErrorType::Num),
+            };
+            Ok(Data::Float((serial - offset).rem_euclid(7.) + base))
+        }),
+        "EDATE" => f.months(|y, m, d| {
+            let d = d.min(days_in_month(y, m));
+            ymd_to_serial(y, m as i64, d as i64)
+        }),
+        "EOMONTH" => f.months(|y, m, _| ymd_to_serial(y, m as i64, days_in_month(y, m) as i64)),
+        "DAYS" => f
+            .arity(2, 2)
+            .and_then(|_| Ok(Data::Float(f.number(0)?.floor() - f.number(1)?.floor()))),
+        _ => Err(CellErrorType::Name),
+    };
+    Value::Data(res.unwrap_or_else(Data::Error))
+}
+
+/// A function call being evaluated
+struct Function<'a> {
+    ev: &'a mut Evaluator,
+    ctx: Context,
+    args: &'a [Expr],
+}
+
+impl<'a> Function<'a> {
+    /// Checks the number of arguments
+    fn arity(&self, min: usize, max: usize) -> Result<(), CellErrorType> {
+        if (min..=max).contains(&self.args.len()) {
+            Ok(())
+        } else {
+            Err(CellErrorType::Value)
+        }
+    }
+
+    fn arg(&mut self, i: usize) -> Value {
+        match self.args.get(i) {
+            Some(e) => self.ev.eval(self.ctx, e),
+            None => Value::Data(Data::Empty),
+        }
+    }
+
+    fn scalar(&mut self, i: usize) -> Data {
+        let v = self.arg(i);
+        self.ev.scalar(self.ctx, v)
+    }
+
+    fn number(&mut self, i: usize) -> Result<f64, CellErrorType> {
+        to_number(&self.scalar(i))
+    }
+
+    fn text(&mut self, i: usize) -> Result<String, CellErrorType> {
+        match self.scalar(i) {
+            Data::Error(e) => Err(e),
+            d => Ok(to_text(&d)),
+        }
+    }
+
+    /// The numbers of all arguments, like `SUM`
+    ///
+    /// Referenced cells which are not numbers are ignored, other arguments are coerced.
+    fn numbers(&mut self) -> Result<Vec<f64>, CellErrorType> {
+        let mut numbers = Vec::new();
+        for i in 0..self.args.len() {
+            let v = self.arg(i);
+            let (cells, from_ref) = self.ev.flatten(&v);
+            for d in cells {
+                match d {
+                    Data::Error(e) => return Err(e),
+                    d if from_ref && !is_number(&d) => (),
+                    d => numbers.push(to_number(&d)?),
+                }
+            }
+        }
+        Ok(numbers)
+    }
+
+    /// Counts the cells matching `f`, like `COUNT`
+    fn count(&mut self, f: impl Fn(&Data, bool) -> bool) -> Result<Data, CellErrorType> {
+        let mut count = 0;
+        for i in 0..self.args.len() {
+            if self.args[i] == Expr::Missing {
+                continue;
+            }
+            let v = self.arg(i);
+            let (cells, from_ref) = self.ev.flatten(&v);
+            count += cells.iter().filter(|d| f(d, from_ref)).count();
+        }
+        Ok(Data::Float(count as f64))
+    }
+
+    /// The booleans of all arguments, like `AND`
+    fn logical(&mut self, f: impl Fn(&[bool]) -> bool) -> Result<Data, CellErrorType> {
+        let mut bools = Vec::new();
+        for i in 0..self.args.len() {
+            let v = self.arg(i);
+            let (cells, from_ref) = self.ev.flatten(&v);
+            for d in cells {
+                match d {
+                    Data::Error(e) => return Err(e),
+                    Data::Bool(b) => bools.push(b),
+                    d if from_ref && !is_number(&d) => (),
+                    d => bools.push(to_bool(&d)?),
+                }
+            }
+        }
+        if bools.is_empty() {
+            return Err(CellErrorType::Value);
+        }
+        Ok(Data::Bool(f(&bools)))
+    }
+
+    fn is(&mut self, f: impl Fn(&Data) -> bool) -> Result<Data, CellErrorType> {
+        self.arity(1, 1)?;
+        Ok(Data::Bool(f(&self.scalar(0))))
+    }
+
+    fn math(
+        &mut self,
+        f: impl Fn(f64) -> Result<f64, CellErrorType>,
+    ) -> Result<Data, CellErrorType> {
+        self.arity(1, 1)?;
+        Ok(number(f(self.number(0)?)?))
+    }
+
+    fn math2(
+        &mut self,
+        f: impl Fn(f64, f64) -> Result<f64, CellErrorType>,
+    ) -> Result<Data, CellErrorType> {
+        self.arity(2, 2)?;
+        let a = self.number(0)?;
+        Ok(number(f(a, self.number(1)?)?))
+    }
+
+    /// Rounds to a number of digits with `f`, like `ROUND`
+    fn round(&mut self, f: impl Fn(f64) -> f64) -> Result<Data, CellErrorType> {
+        self.arity(2, 2)?;
+        let n = self.number(0)?;
+        let factor = 10f64.powi(self.number(1)?.trunc() as i32);
+        // round to 15 significant digits first, like Excel, so that 2.345 * 100 is 234.5
+        let scaled = format!("{:.14e}", n * factor).parse().unwrap_or(n * factor);
+        Ok(number(f(scaled) / factor))
+    }
+
+    fn if_(&mut self) -> Value {
+        if self.arity(2, 3).is_err() {
+            return Value::Data(Data::Error(CellErrorType::Value));
+        }
+        let cond = match to_bool(&self.scalar(0)) {
+            Ok(c) => c,
+            Err(e) => return Value::Data(Data::Error(e)),
+        };
+        match (cond, self.args.get(2)) {
+            (true, _) => self.branch(1),
+            (false, Some(_)) => self.branch(2),
+            (false, None) => Value::Data(Data::Bool(false)),
+        }
+    }
+
+    /// Evaluates a result argument, an omitted one being 0
+    fn branch(&mut self, i: usize) -> Value {
+        match self.args[i] {
+            Expr::Missing => Value::Data(Data::Float(0.)),
+            _ => self.arg(i),
+        }
+    }
+
+    fn if_error(&mut self, catch: impl Fn(&CellErrorType) -> bool) -> Value {
+        if self.arity(2, 2).is_err() {
+            return Value::Data(Data::Error(CellErrorType::Value));
+        }
+        match self.scalar(0) {
+            Data::Error(e) if catch(&e) => self.branch(1),
+            d => Value::Data(d),
+        }
+    }
+
+    /// Finds the position of `needle` in the first `len` cells of a row or column
+    ///
+    /// `match_type` is `MATCH` one: 1 for the largest value lower or equal, 0 for an exact
+    /// match and -1 for the smallest value greater or equal, in sorted data.
+    fn find(
+        &mut self,
+        haystack: &Value,
+        len: u32,
+        pos: impl Fn(u32) -> (u32, u32),
+        needle: &Data,
+        match_type: i8,
+    ) -> Option<u32> {
+        let mut found = None;
+        for i in 0..len {
+            let (row, col) = pos(i);
+            let d = self.ev.item(haystack, row, col);
+            if d == Data::Empty || type_rank(&d) != type_rank(needle) {
+                continue;
+            }
+            if match_type == 0 {
+                if let (Data::String(pattern), Data::String(text)) = (needle, &d) {
+                    if wildcard_match(pattern, text) {
+                        return Some(i);
+                    }
+                    continue;
+                }
+            }
+            match (match_type, compare(&d, needle)) {
+                (0, Ordering::Equal) => return Some(i),
+                (0, _) => (),
+                (1, Ordering::Greater) | (-1, Ordering::Less) => break,
+                _ => found = Some(i),
+            }
+        }
+        found
+    }
+
+    /// `VLOOKUP` or, if not `vertical`, `HLOOKUP`
+    fn lookup(&mut self, vertical: bool) -> Value {
+        let res = (|| {
+            self.arity(3, 4)?;
+            let needle = match self.scalar(0) {
+                Data::Error(e) => return Err(e),
+                d => d,
+            };
+            let table = self.arg(1);
+            let index = self.number(2)?.trunc();
+            let approximate = match self.args.get(3) {
+                Some(Expr::Missing) | None => true,
+                Some(_) => to_bool(&self.scalar(3))?,
+            };
+            let (rows, cols) = self.ev.size(&table);
+            let (used_rows, used_cols) = self.used_size(&table);
+            let (len, width) = if vertical {
+                (used_rows, cols)
+            } else {
+                (used_cols, rows)
+            };
+            if index < 1. {
+                return Err(CellErrorType::Value);
+            }
+            if index > width as f64 {
+                return Err(CellErrorType::Ref);
+            }
+            let index = index as u32 - 1;
+            let pos = |i| if vertical { (i, 0) } else { (0, i) };
+            let found = self
+                .find(&table, len, pos, &needle, i8::from(approximate))
+                .ok_or(CellErrorType::NA)?;
+            let (row, col) = if vertical {
+                (found, index)
+            } else {
+                (index, found)
+            };
+            Ok(self.ev.item(&table, row, col))
+        })();
+        Value::Data(res.unwrap_or_else(Data::Error))
+    }
+
+    fn match_(&mut self) -> Result<Data, CellErrorType> {
+        self.arity(2, 3)?;
+        let needle = match self.scalar(0) {
+            Data::Error(e) => return Err(e),
+            d => d,
+        };
+        let haystack = self.arg(1);
+        let match_type = match self.args.get(2) {
+            Some(Expr::Missing) | None => 1,
+            Some(_) => match self.number(2)? {
+                n if n > 0. => 1,
+                n if n < 0. => -1,
+                _ => 0,
+            },
+        };
+        let (rows, cols) = self.ev.size(&haystack);
+        let (used_rows, used_cols) = self.used_size(&haystack);
+        let found = if cols == 1 {
+            self.find(&haystack, used_rows, |i| (i, 0), &needle, match_type)
+        } else if rows == 1 {
+            self.find(&haystack, used_cols, |i| (0, i), &needle, match_type)
+        } else {
+            None
+        };
+        found
+            .map(|i| Data::Float(i as f64 + 1.))
+            .ok_or(CellErrorType::NA)
+    }
+
+    /// Number of used rows and columns of a value, within its sheet used range
+    fn used_size(&self, value: &Value) -> (u32, u32) {
+        match value {
+            Value::Ref(area) => {
+                let end = self.ev.used_end(area);
+                (
+                    (end.0 + 1).saturating_sub(area.start.0),
+                    (end.1 + 1).saturating_sub(area.start.1),
+                )
+            }
+            v => self.ev.size(v),
+        }
+    }
+
+    fn index(&mut self) -> Value {
+        let res = (|| {
+            self.arity(2, 3)?;
+            let value = self.arg(0);
+            let (rows, cols) = self.ev.size(&value);
+            let mut row = self.number(1)?.trunc();
+            let mut col = match self.args.get(2) {
+                Some(Expr::Missing) | None => f64::from(u8::from(cols == 1)),
+                Some(_) => self.number(2)?.trunc(),
+            };
+            // a single row is indexed by column
+            if rows == 1 && self.args.len() == 2 {
+                (row, col) = (1., row);
+            }
+            if row < 0. || col < 0. {
+                return Err(CellErrorType::Value);
+            }
+            if row > rows as f64 || col > cols as f64 {
+                return Err(CellErrorType::Ref);
+            }
+            let (row, col) = (row as u32, col as u32);
+            match value {
+                Value::Ref(mut area) => {
+                    if row > 0 {
+                        area.start.0 += row - 1;
+                        area.end.0 = area.start.0;
+                    }
+                    if col > 0 {
+                        area.start.1 += col - 1;
+                        area.end.1 = area.start.1;
+                    }
+                    Ok(Value::Ref(area))
+                }
+                v => Ok(Value::Data(self.ev.item(
+                    &v,
+                    row.saturating_sub(1),
+                    col.saturating_sub(1),
+                ))),
+            }
+        })();
+        res.unwrap_or_else(|e| Value::Data(Data::Error(e)))
+    }
+
+    /// Concatenates the arguments, flattening ranges if `flatten`
+    fn concat(&mut self, flatten: bool) -> Result<Data, CellErrorType> {
+        let mut s = String::new();
+        for i in 0..self.args.len() {
+            let cells = if flatten {
+                let v = self.arg(i);
+                self.ev.flatten(&v).0
+            } else {
+                vec![self.scalar(i)]
+            };
+            for d in cells {
+                match d {
+                    Data::Error(e) => return Err(e),
+                    d => s.push_str(&to_text(&d)),
+                }
+            }
+        }
+        Ok(Data::String(s))
+    }
+
+    /// `LEFT` and `RIGHT`, the number of characters defaulting to 1
+    fn substring(&mut self, f: impl Fn(&str, usize) -> String) -> Result<Data, CellErrorType> {
+        self.arity(1, 2)?;
+        let s = self.text(0)?;
+        let n = if self.args.len() > 1 {
+            self.number(1)?.trunc()
+        } else {
+            1.
+        };
+        if n < 0. {
+            return Err(CellErrorType::Value);
+        }
+        Ok(Data::String(f(&s, n as usize)))
+    }
+
+    fn mid(&mut self) -> Result<Data, CellErrorType> {
+        self.arity(3, 3)?;
+        let s = self.text(0)?;
+        let start = self.number(1)?.trunc();
+        let len = self.number(2)?.trunc();
+        if start < 1. || len < 0. {
+            return Err(CellErrorType::Value);
+        }
+        Ok(Data::String(
+            s.chars()
+                .skip(start as usize - 1)
+                .take(len as usize)
+                .collect(),
+        ))
+    }
+
+    fn date_part(&mut self, f: impl Fn((i64, u32, u32)) -> f64) -> Result<Data, CellErrorType> {
+        self.arity(1, 1)?;
+        Ok(Data::Float(f(serial_to_ymd(self.number(0)?)?)))
+    }
+
+    /// `EDATE` and `EOMONTH`: `f` gets the shifted year and month, and the start day
+    fn months(
+        &mut self,
+        f: impl Fn(i64, u32, u32) -> Result<f64, CellErrorType>,
+    ) -> Result<Data, CellErrorType> {
+        self.arity(2, 2)?;
+        let (y, m, d) = serial_to_ymd(self.number(0)?)?;
+        let months = y * 12 + m as i64 - 1 + self.number(1)?.trunc() as i64;
+        date(f(
+            months.div_euclid(12),
+            months.rem_euclid(12) as u32 + 1,
+            d,
+        )?)
+    }
+}
+
+/// A date result
+fn date(serial: f64) -> Result<Data, CellErrorType> {
+    if serial < 0. {
+        return Err(CellErrorType::Num);
+    }
+    Ok(Data::DateTime(ExcelDateTime::new(
+        serial,
+        ExcelDateTimeType::DateTime,
+        false,
+    )))
+}
+
+/// Current serial date time, in UTC
+fn now() -> f64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0., |d| d.as_secs_f64());
+    UNIX_EPOCH_SERIAL + secs / 86_400.
+}
+
+/// Days since 1970-01-01 of a proleptic gregorian date
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = m as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic gregorian date of a number of days since 1970-01-01
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    let (ny, nm) = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+    (days_from_civil(ny, nm, 1) - days_from_civil(y, m, 1)) as u32
+}
+
+/// Case insensitive text comparison where `*` matches any characters, `?` any single
+/// character and `~` escapes the next character, like exact lookups do
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // pattern and text positions to resume from after the last `*`
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                star = Some((p, t));
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some('~') if p + 1 < pattern.len() && pattern[p + 1] == text[t] => {
+                p += 2;
+                t += 1;
+                continue;
+            }
+            Some(&c) if c != '~' && c == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => (),
+        }
+        match star {
+            Some((sp, st)) => {
+                p = sp;
+                t = st + 1;
+                star = Some((sp, st + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Serial number of a date, months and days out of their range overflowing like `DATE`
+///
+/// Excel wrongly considers 1900 as a leap year, shifting the dates before March 1900.
+fn ymd_to_serial(y: i64, m: i64, d: i64) -> Result<f64, CellErrorType> {
+    let months = y * 12 + m - 1;
+    let first = days_from_civil(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1)
+        - days_from_civil(1899, 12, 30);
+    // days overflow from the first of the month, so that 1900-02-29 exists
+    let first = if first < 61 { first - 1 } else { first };
+    match first + d - 1 {
+        d if d < 1 => Err(CellErrorType::Num),
+        d => Ok(d as f64),
+    }
+}
+
+/// Date of a serial number, with the fictitious 1900-02-29
+fn serial_to_ymd(serial: f64) -> Result<(i64, u32, u32), CellErrorType> {
+    let days = serial.floor() as i64;
+    match days {
+        d if d < 0 => Err(CellErrorType::Num),
+        0 => Ok((1900, 1, 0)),
+        60 => Ok((1900, 2, 29)),
+        d if d < 60 => Ok(civil_from_days(days_from_civil(1899, 12, 31) + d)),
+        d => Ok(civil_from_days(days_from_civil(1899, 12, 30) + d)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::sheet;
+    use super::*;
+
+    #[test]
+    fn serial_dates() {
+        assert_eq!(ymd_to_serial(1900, 1, 1), Ok(1.));
+        assert_eq!(ymd_to_serial(1900, 3, 1), Ok(61.));
+        assert_eq!(ymd_to_serial(1900, 2, 29), Ok(60.));
+        assert_eq!(ymd_to_serial(1900, 3, 0), Ok(60.));
+        assert_eq!(ymd_to_serial(1900, 2, 30), Ok(61.));
+        assert_eq!(ymd_to_serial(2023, 15, 0), Ok(45351.));
+        assert_eq!(serial_to_ymd(60.), Ok((1900, 2, 29)));
+        assert_eq!(serial_to_ymd(45351.5), Ok((2024, 2, 29)));
+        assert_eq!(days_in_month(2023, 2), 28);
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("a*c", "ABBC"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a?c", "abc"));
+        assert!(!wildcard_match("a?c", "ac"));
+        assert!(wildcard_match("~*b", "*B"));
+        assert!(!wildcard_match("~*b", "ab"));
+        assert!(wildcard_match("a~?", "a?"));
+    }
+
+    #[test]
+    fn functions() {
+        let mut ev = sheet(&[
+            ((0, 0), "a"),
+            ((0, 1), "1"),
+            ((1, 0), "b"),
+            ((1, 1), "2"),
+            ((2, 0), "c"),
+            ((2, 1), "=B2*2"),
+            ((3, 1), "x"),
+        ]);
+        let mut eval = |f| ev.evaluate("Sheet1", f).unwrap();
+        assert_eq!(eval("SUM(B1:B4, 10, TRUE)"), Data::Float(18.));
+        assert_eq!(eval("SUM(A1:A3)").to_string(), "0");
+        assert_eq!(eval("AVERAGE(B:B)"), Data::Float(7. / 3.));
+        assert_eq!(eval("COUNT(A1:B4)"), Data::Float(3.));
+        assert_eq!(eval("COUNTA(A1:B4)"), Data::Float(7.));
+        assert_eq!(eval("MAX(B1:B3)-MIN(B1:B3)"), Data::Float(3.));
+        assert_eq!(
+            eval("IF(B1>1, \"big\", \"small\")"),
+            Data::String("small".to_string())
+        );
+        assert_eq!(eval("IFERROR(1/0, -1)"), Data::Float(-1.));
+        assert_eq!(eval("AND(B1:B2, NOT(FALSE))"), Data::Bool(true));
+        assert_eq!(eval("VLOOKUP(\"B\", A1:B3, 2, FALSE)"), Data::Float(2.));
+        assert_eq!(eval("VLOOKUP(\"bb\", A1:B3, 2)"), Data::Float(2.));
+        assert_eq!(
+            eval("VLOOKUP(\"z\", A1:B3, 3, FALSE)"),
+            Data::Error(CellErrorType::Ref)
+        );
+        assert_eq!(
+            eval("HLOOKUP(\"b\", A2:B2, 1, FALSE)"),
+            Data::String("b".to_string())
+        );
+        assert_eq!(eval("MATCH(4, B1:B3, 0)"), Data::Float(3.));
+        assert_eq!(eval("MATCH(\"?\", A1:A3, 0)"), Data::Float(1.));
+        assert_eq!(eval("MATCH(\"*c\", A1:A3, 0)"), Data::Float(3.));
+        assert_eq!(eval("VLOOKUP(\"B*\", A1:B3, 2, FALSE)"), Data::Float(2.));
+        assert_eq!(
+            eval("MATCH(\"~*\", A1:A3, 0)"),
+            Data::Error(CellErrorType::NA)
+        );
+        assert_eq!(
+            eval("MATCH(\"d\", A1:A3, 0)"),
+            Data::Error(CellErrorType::NA)
+        );
+        assert_eq!(eval("INDEX(A1:B3, 3, 2)"), Data::Float(4.));
+        assert_eq!(eval("SUM(INDEX(A1:B3, 0, 2))"), Data::Float(7.));
+        assert_eq!(
+            eval("CONCAT(A1:A3, 1)&CONCATENATE(\"-\", B1)"),
+            Data::String("abc1-1".to_string())
+        );
+        assert_eq!(
+            eval("MID(\"calamine\", 3, 3)&LEFT(\"ab\")&RIGHT(\"ab\", 5)"),
+            Data::String("lamaab".to_string())
+        );
+        assert_eq!(eval("TRIM(\"  a   b \")"), Data::String("a b".to_string()));
+        assert_eq!(eval("LEN(1/3)"), Data::Float(17.));
+        assert_eq!(
+            eval("CONCAT(0.1+0.2, \"|\", 2/3)"),
+            Data::String("0.3|0.666666666666667".to_string())
+        );
+        assert_eq!(
+            eval("ROUND(2.345, 2)+ROUNDUP(-1.1, 0)"),
+            Data::Float(2.35 - 2.)
+        );
+        assert_eq!(eval("MOD(-3, 2)"), Data::Float(1.));
+        assert_eq!(
+            eval("YEAR(DATE(2023, 13, 1))*100+MONTH(EOMONTH(DATE(2024,1,31),1))"),
+            Data::Float(202402.)
+        );
+        assert_eq!(eval("DAY(EDATE(DATE(2024,1,31),1))"), Data::Float(29.));
+        assert_eq!(eval("WEEKDAY(DATE(2024,1,1))"), Data::Float(2.));
+        assert_eq!(
+            eval("DAYS(DATE(2024,3,1), DATE(2024,2,1))"),
+            Data::Float(29.)
+        );
+        assert_eq!(
+            eval("DATE(2024,1,1)"),
+            Data::DateTime(ExcelDateTime::new(
+                45292.,
+                ExcelDateTimeType::DateTime,
+                false
+            ))
+        );
+    }
+}
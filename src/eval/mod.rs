@@ -0,0 +1,719 @@
+//! Formula evaluation
+//!
+//! Computes the value of formula cells stored without their cached result, as written by
+//! most spreadsheet generators.
+//!
+//! # Examples
+//! ```
+//! use calamine::{open_workbook_auto, Data, Evaluator};
+//!
+//! # let path = format!("{}/tests/eval.xlsx", env!("CARGO_MANIFEST_DIR"));
+//! let mut workbook = open_workbook_auto(path).unwrap();
+//! let mut evaluator = Evaluator::new(&mut workbook).unwrap();
+//! let range = evaluator.worksheet_range("Sheet1").unwrap();
+//! assert_eq!(range.get_value((3, 1)), Some(&Data::Float(60.)));
+//! ```
+
+mod functions;
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::formats::format_general_text;
+use crate::formula::{
+    parse_defined_name, parse_formula, BinaryOp, CellRef, Expr, Reference, UnaryOp,
+};
+use crate::{CellErrorType, Data, Range, Reader, SheetType};
+
+/// Evaluates the formulas of a workbook
+///
+/// Cell values already present in the workbook are kept, only the formula cells
+/// without a value are evaluated, on demand, and memoized. References to other sheets
+/// and to defined names are supported.
+///
+/// Unknown functions evaluate to `#NAME?` and circular references to `#REF!`.
+/// Dates follow the 1900 date system.
+pub struct Evaluator {
+    sheets: Vec<EvalSheet>,
+    /// Defined names by upper case name
+    names: HashMap<String, String>,
+    /// Evaluated cells, by (sheet, row, col)
+    cache: HashMap<(usize, u32, u32), Data>,
+    /// Cells and names being evaluated, to detect cycles
+    pending: HashSet<(usize, u32, u32)>,
+    pending_names: HashSet<String>,
+}
+
+struct EvalSheet {
+    name: String,
+    values: Range<Data>,
+    formulas: Range<Expr>,
+    /// Last used (row, col), of values or formulas
+    end: (u32, u32),
+}
+
+/// An evaluated expression
+#[derive(Debug, Clone)]
+enum Value {
+    Data(Data),
+    Ref(Area),
+    /// Union or 3-D reference
+    Refs(Vec<Area>),
+    Array(Vec<Vec<Data>>),
+}
+
+/// A rectangular reference on a sheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Area {
+    sheet: usize,
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+impl Area {
+    fn size(&self) -> (u32, u32) {
+        (self.end.0 - self.start.0 + 1, self.end.1 - self.start.1 + 1)
+    }
+}
+
+/// Where an expression is evaluated
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    sheet: usize,
+    /// Formula cell, if any, for implicit intersections
+    pos: Option<(u32, u32)>,
+}
+
+impl Evaluator {
+    /// Reads the values and formulas of all the worksheets of a workbook
+    pub fn new<RS, R>(workbook: &mut R) -> Result<Self, R::Error>
+    where
+        RS: Read + Seek,
+        R: Reader<RS>,
+    {
+        let names = workbook
+            .sheets_metadata()
+            .iter()
+            .filter(|s| s.typ == SheetType::WorkSheet)
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        let mut sheets = Vec::with_capacity(names.len());
+        for name in names {
+            let values = workbook.worksheet_range(&name)?;
+            let formulas = workbook.worksheet_formula_expr(&name)?;
+            sheets.push((name, values, formulas));
+        }
+        Ok(Evaluator::from_sheets(
+            sheets,
+            workbook.defined_names().to_vec(),
+        ))
+    }
+
+    fn from_sheets(
+        sheets: Vec<(String, Range<Data>, Range<Expr>)>,
+        names: Vec<(String, String)>,
+    ) -> Self {
+        let sheets = sheets
+            .into_iter()
+            .map(|(name, values, formulas)| {
+                let end = [values.end(), formulas.end()]
+                    .into_iter()
+                    .flatten()
+                    .fold((0, 0), |(r, c), (r2, c2)| (r.max(r2), c.max(c2)));
+                EvalSheet {
+                    name,
+                    values,
+                    formulas,
+                    end,
+                }
+            })
+            .collect();
+        Evaluator {
+            sheets,
+            names: names
+                .into_iter()
+                .map(|(name, formula)| (name.to_uppercase(), formula))
+                .collect(),
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            pending_names: HashSet::new(),
+        }
+    }
+
+    /// Gets the values of a worksheet, with the missing formula values evaluated
+    ///
+    /// Returns `None` if there is no such worksheet.
+    pub fn worksheet_range(&mut self, name: &str) -> Option<Range<Data>> {
+        let sheet = self.sheet_index(name)?;
+        let s = &self.sheets[sheet];
+        let (start, end) = match (s.values.start(), s.formulas.start()) {
+            (None, None) => return Some(Range::empty()),
+            (Some(v), Some(f)) => ((v.0.min(f.0), v.1.min(f.1)), s.end),
+            (Some(start), None) | (None, Some(start)) => (start, s.end),
+        };
+        let formulas = s
+            .formulas
+            .used_cells()
+            .map(|(r, c, _)| {
+                let (r0, c0) = s.formulas.start().unwrap_or_default();
+                (r0 + r as u32, c0 + c as u32)
+            })
+            .collect::<Vec<_>>();
+        let mut range = Range::new(start, end);
+        for (r, c, v) in s.values.used_cells() {
+            let (r0, c0) = s.values.start().unwrap_or_default();
+            range.set_value((r0 + r as u32, c0 + c as u32), v.clone());
+        }
+        for pos in formulas {
+            let value = self.cell(sheet, pos);
+            range.set_value(pos, value);
+        }
+        Some(range)
+    }
+
+    /// Evaluates a formula as if it were in a cell of the `sheet` worksheet
+    ///
+    /// Returns `None` if there is no such worksheet.
+    pub fn evaluate(&mut self, sheet: &str, formula: &str) -> Option<Data> {
+        let ctx = Context {
+            sheet: self.sheet_index(sheet)?,
+            pos: None,
+        };
+        let value = match parse_formula(formula) {
+            Ok(expr) => self.evaluate_expr(ctx, &expr),
+            Err(_) => Data::Error(CellErrorType::Name),
+        };
+        Some(value)
+    }
+
+    fn sheet_index(&self, name: &str) -> Option<usize> {
+        self.sheets
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    fn evaluate_expr(&mut self, ctx: Context, expr: &Expr) -> Data {
+        let value = self.eval(ctx, expr);
+        match self.scalar(ctx, value) {
+            // a reference to an empty cell is 0
+            Data::Empty => Data::Float(0.),
+            v => v,
+        }
+    }
+
+    /// Gets the value of a cell, evaluating its formula if it has no value
+    fn cell(&mut self, sheet: usize, pos: (u32, u32)) -> Data {
+        let s = &self.sheets[sheet];
+        match s.values.get_value(pos) {
+            Some(Data::Empty) | None => (),
+            Some(v) => return v.clone(),
+        }
+        let formula = match s.formulas.get_value(pos) {
+            Some(Expr::Missing) | None => return Data::Empty,
+            Some(f) => f.clone(),
+        };
+        let key = (sheet, pos.0, pos.1);
+        if let Some(v) = self.cache.get(&key) {
+            return v.clone();
+        }
+        if !self.pending.insert(key) {
+            return Data::Error(CellErrorType::Ref);
+        }
+        let ctx = Context {
+            sheet,
+            pos: Some(pos),
+        };
+        let value = self.evaluate_expr(ctx, &formula);
+        self.pending.remove(&key);
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    fn eval(&mut self, ctx: Context, expr: &Expr) -> Value {
+        match expr {
+            Expr::Number(n) => Value::Data(Data::Float(*n)),
+            Expr::String(s) => Value::Data(Data::String(s.clone())),
+            Expr::Bool(b) => Value::Data(Data::Bool(*b)),
+            Expr::Error(e) => Value::Data(Data::Error(e.clone())),
+            Expr::Missing => Value::Data(Data::Empty),
+            Expr::Reference(r) => match (self.area(ctx, r), &r.last_sheet) {
+                (Some(area), None) => Value::Ref(area),
+                (Some(area), Some(last)) => match self.sheet_index(last) {
+                    Some(last) => Value::Refs(
+                        (area.sheet.min(last)..=area.sheet.max(last))
+                            .map(|sheet| self.area_on(sheet, r))
+                            .collect(),
+                    ),
+                    None => Value::Data(Data::Error(CellErrorType::Ref)),
+                },
+                (None, _) => Value::Data(Data::Error(CellErrorType::Ref)),
+            },
+            Expr::Name(name) => self.name(ctx, name),
+            Expr::Function { name, args } => functions::call(self, ctx, name, args),
+            Expr::Array(rows) => Value::Array(
+                rows.iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|e| {
+                                let v = self.eval(ctx, e);
+                                self.scalar(ctx, v)
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Expr::Unary(op, e) => {
+                let v = self.eval(ctx, e);
+                self.map(v, |d| {
+                    let n = match to_number(&d) {
+                        Ok(n) => n,
+                        Err(e) => return Data::Error(e),
+                    };
+                    match op {
+                        UnaryOp::Plus => d,
+                        UnaryOp::Minus => Data::Float(-n),
+                        UnaryOp::Percent => Data::Float(n / 100.),
+                    }
+                })
+            }
+            Expr::Binary(BinaryOp::Range, l, r) => match (self.eval(ctx, l), self.eval(ctx, r)) {
+                (Value::Ref(a), Value::Ref(b)) if a.sheet == b.sheet => Value::Ref(Area {
+                    sheet: a.sheet,
+                    start: (a.start.0.min(b.start.0), a.start.1.min(b.start.1)),
+                    end: (a.end.0.max(b.end.0), a.end.1.max(b.end.1)),
+                }),
+                _ => Value::Data(Data::Error(CellErrorType::Value)),
+            },
+            Expr::Binary(BinaryOp::Intersect, l, r) => {
+                match (self.eval(ctx, l), self.eval(ctx, r)) {
+                    (Value::Ref(a), Value::Ref(b)) if a.sheet == b.sheet => {
+                        let start = (a.start.0.max(b.start.0), a.start.1.max(b.start.1));
+                        let end = (a.end.0.min(b.end.0), a.end.1.min(b.end.1));
+                        if start.0 <= end.0 && start.1 <= end.1 {
+                            Value::Ref(Area {
+                                sheet: a.sheet,
+                                start,
+                                end,
+                            })
+                        } else {
+                            Value::Data(Data::Error(CellErrorType::Null))
+                        }
+                    }
+                    _ => Value::Data(Data::Error(CellErrorType::Value)),
+                }
+            }
+            Expr::Binary(BinaryOp::Union, l, r) => {
+                let mut areas = Vec::new();
+                for v in [self.eval(ctx, l), self.eval(ctx, r)] {
+                    match v {
+                        Value::Ref(area) => areas.push(area),
+                        Value::Refs(a) => areas.extend(a),
+                        Value::Data(Data::Error(e)) => return Value::Data(Data::Error(e)),
+                        _ => return Value::Data(Data::Error(CellErrorType::Value)),
+                    }
+                }
+                Value::Refs(areas)
+            }
+            Expr::Binary(op, l, r) => {
+                let l = self.eval(ctx, l);
+                let r = self.eval(ctx, r);
+                self.binary(ctx, *op, l, r)
+            }
+        }
+    }
+
+    /// Resolves a reference, whole columns and rows ending with the sheet used range
+    fn area(&self, ctx: Context, r: &Reference) -> Option<Area> {
+        let sheet = match &r.sheet {
+            Some(name) => self.sheet_index(name)?,
+            None => ctx.sheet,
+        };
+        Some(self.area_on(sheet, r))
+    }
+
+    fn area_on(&self, sheet: usize, r: &Reference) -> Area {
+        let end = self.sheets[sheet].end;
+        let corner = |c: &CellRef, default: (u32, u32)| {
+            (c.row.unwrap_or(default.0), c.col.unwrap_or(default.1))
+        };
+        let start = corner(&r.start, (0, 0));
+        let end = corner(&r.end, end);
+        Area {
+            sheet,
+            start: (start.0.min(end.0), start.1.min(end.1)),
+            end: (start.0.max(end.0), start.1.max(end.1)),
+        }
+    }
+
+    fn name(&mut self, ctx: Context, name: &str) -> Value {
+        let key = name.to_uppercase();
+        let formula = match self.names.get(&key) {
+            Some(f) => f.clone(),
+            None => return Value::Data(Data::Error(CellErrorType::Name)),
+        };
+        if !self.pending_names.insert(key.clone()) {
+            return Value::Data(Data::Error(CellErrorType::Ref));
+        }
+        let value = match parse_defined_name(&formula) {
+            Ok(expr) => self.eval(ctx, &expr),
+            Err(_) => Value::Data(Data::Error(CellErrorType::Name)),
+        };
+        self.pending_names.remove(&key);
+        value
+    }
+
+    /// Reduces a value to a single cell value
+    ///
+    /// Multi-cells references use the implicit intersection with the formula cell.
+    fn scalar(&mut self, ctx: Context, value: Value) -> Data {
+        match value {
+            Value::Data(d) => d,
+            Value::Array(rows) => rows
+                .into_iter()
+                .next()
+                .and_then(|r| r.into_iter().next())
+                .unwrap_or(Data::Empty),
+            Value::Ref(area) if area.start == area.end => self.cell(area.sheet, area.start),
+            Value::Ref(area) => {
+                let pos = ctx.pos.filter(|_| area.sheet == ctx.sheet);
+                match pos {
+                    Some((row, _)) if area.start.1 == area.end.1 && area.size().0 > 1 => {
+                        if (area.start.0..=area.end.0).contains(&row) {
+                            self.cell(area.sheet, (row, area.start.1))
+                        } else {
+                            Data::Error(CellErrorType::Value)
+                        }
+                    }
+                    Some((_, col)) if area.start.0 == area.end.0 => {
+                        if (area.start.1..=area.end.1).contains(&col) {
+                            self.cell(area.sheet, (area.start.0, col))
+                        } else {
+                            Data::Error(CellErrorType::Value)
+                        }
+                    }
+                    _ => Data::Error(CellErrorType::Value),
+                }
+            }
+            Value::Refs(_) => Data::Error(CellErrorType::Value),
+        }
+    }
+
+    /// Materializes a value as rows of cells
+    ///
+    /// References are truncated to the used range of their sheet.
+    fn matrix(&mut self, value: &Value) -> Vec<Vec<Data>> {
+        match value {
+            Value::Data(d) => vec![vec![d.clone()]],
+            Value::Array(rows) => rows.clone(),
+            Value::Ref(area) => {
+                let end = self.used_end(area);
+                (area.start.0..=end.0)
+                    .map(|r| {
+                        (area.start.1..=end.1)
+                            .map(|c| self.cell(area.sheet, (r, c)))
+                            .collect()
+                    })
+                    .collect()
+            }
+            Value::Refs(areas) => areas
+                .iter()
+                .flat_map(|area| self.matrix(&Value::Ref(*area)))
+                .collect(),
+        }
+    }
+
+    /// All the cells of a value, row by row, and whether they come from a reference
+    fn flatten(&mut self, value: &Value) -> (Vec<Data>, bool) {
+        let from_ref = !matches!(value, Value::Data(_));
+        (self.matrix(value).into_iter().flatten().collect(), from_ref)
+    }
+
+    /// Last cell of an area within the used range of its sheet
+    fn used_end(&self, area: &Area) -> (u32, u32) {
+        let end = self.sheets[area.sheet].end;
+        (area.end.0.min(end.0), area.end.1.min(end.1))
+    }
+
+    /// Number of rows and columns of a value
+    fn size(&self, value: &Value) -> (u32, u32) {
+        match value {
+            Value::Data(_) => (1, 1),
+            Value::Array(rows) => (
+                rows.len() as u32,
+                rows.first().map_or(0, |r| r.len()) as u32,
+            ),
+            Value::Ref(area) => area.size(),
+            Value::Refs(_) => (1, 1),
+        }
+    }
+
+    /// Gets the cell at a relative position of a value
+    fn item(&mut self, value: &Value, row: u32, col: u32) -> Data {
+        match value {
+            Value::Data(d) => d.clone(),
+            Value::Array(rows) => rows
+                .get(row as usize)
+                .and_then(|r| r.get(col as usize))
+                .cloned()
+                .unwrap_or(Data::Empty),
+            Value::Ref(area) => self.cell(area.sheet, (area.start.0 + row, area.start.1 + col)),
+            Value::Refs(_) => Data::Error(CellErrorType::Value),
+        }
+    }
+
+    /// Applies `f` to a scalar value, or to each cell of a multi-cells value
+    fn map(&mut self, value: Value, mut f: impl FnMut(Data) -> Data) -> Value {
+        match value {
+            Value::Ref(area) if area.start == area.end => {
+                let d = self.cell(area.sheet, area.start);
+                Value::Data(f(d))
+            }
+            Value::Data(d) => Value::Data(f(d)),
+            v @ (Value::Ref(_) | Value::Refs(_) | Value::Array(_)) => Value::Array(
+                self.matrix(&v)
+                    .into_iter()
+                    .map(|r| r.into_iter().map(&mut f).collect())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Evaluates a binary operation, element-wise on multi-cells values
+    fn binary(&mut self, ctx: Context, op: BinaryOp, l: Value, r: Value) -> Value {
+        let is_multi = |v: &Value, ev: &Self| ev.size(v) != (1, 1);
+        if !is_multi(&l, self) && !is_multi(&r, self) {
+            let l = self.scalar(ctx, l);
+            let r = self.scalar(ctx, r);
+            return Value::Data(binary_op(op, l, r));
+        }
+        let (lr, lc) = self.size(&l);
+        let (rr, rc) = self.size(&r);
+        let (rows, cols) = (lr.max(rr), lc.max(rc));
+        let lm = self.matrix(&l);
+        let rm = self.matrix(&r);
+        // single rows and columns are broadcast
+        let get = |m: &[Vec<Data>], size: (u32, u32), i: u32, j: u32| {
+            let i = if size.0 == 1 { 0 } else { i };
+            let j = if size.1 == 1 { 0 } else { j };
+            if i >= size.0 || j >= size.1 {
+                return Data::Error(CellErrorType::NA);
+            }
+            m.get(i as usize)
+                .and_then(|r| r.get(j as usize))
+                .cloned()
+                .unwrap_or(Data::Empty)
+        };
+        Value::Array(
+            (0..rows)
+                .map(|i| {
+                    (0..cols)
+                        .map(|j| binary_op(op, get(&lm, (lr, lc), i, j), get(&rm, (rr, rc), i, j)))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+fn binary_op(op: BinaryOp, l: Data, r: Data) -> Data {
+    if let Data::Error(e) = l {
+        return Data::Error(e);
+    }
+    if let Data::Error(e) = r {
+        return Data::Error(e);
+    }
+    let cmp = |ord: fn(Ordering) -> bool| Data::Bool(ord(compare(&l, &r)));
+    match op {
+        BinaryOp::Concat => Data::String(to_text(&l) + &to_text(&r)),
+        BinaryOp::Eq => cmp(|o| o == Ordering::Equal),
+        BinaryOp::Ne => cmp(|o| o != Ordering::Equal),
+        BinaryOp::Lt => cmp(|o| o == Ordering::Less),
+        BinaryOp::Le => cmp(|o| o != Ordering::Greater),
+        BinaryOp::Gt => cmp(|o| o == Ordering::Greater),
+        BinaryOp::Ge => cmp(|o| o != Ordering::Less),
+        _ => {
+            let (a, b) = match (to_number(&l), to_number(&r)) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(e), _) | (_, Err(e)) => return Data::Error(e),
+            };
+            let n = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div if b == 0. => return Data::Error(CellErrorType::Div0),
+                BinaryOp::Div => a / b,
+                BinaryOp::Pow => a.powf(b),
+                _ => unreachable!(),
+            };
+            number(n)
+        }
+    }
+}
+
+/// A number result, `#NUM!` if it is not finite
+fn number(n: f64) -> Data {
+    if n.is_finite() {
+        Data::Float(n)
+    } else {
+        Data::Error(CellErrorType::Num)
+    }
+}
+
+/// Coerces a cell value to a number, like the arithmetic operators do
+fn to_number(d: &Data) -> Result<f64, CellErrorType> {
+    match d {
+        Data::Int(i) => Ok(*i as f64),
+        Data::Float(f) => Ok(*f),
+        Data::Bool(b) => Ok(if *b { 1. } else { 0. }),
+        Data::DateTime(dt) => Ok(dt.as_f64()),
+        Data::Empty => Ok(0.),
+        Data::String(s) => s.trim().parse().map_err(|_| CellErrorType::Value),
+        Data::Error(e) => Err(e.clone()),
+        Data::DateTimeIso(_) | Data::DurationIso(_) => Err(CellErrorType::Value),
+    }
+}
+
+/// Is the value stored as a number (including dates)
+fn is_number(d: &Data) -> bool {
+    matches!(d, Data::Int(_) | Data::Float(_) | Data::DateTime(_))
+}
+
+/// Converts a cell value to text, like the `&` operator does
+fn to_text(d: &Data) -> String {
+    match d {
+        Data::Empty => String::new(),
+        Data::String(s) => s.clone(),
+        Data::Bool(true) => "TRUE".to_string(),
+        Data::Bool(false) => "FALSE".to_string(),
+        Data::Float(f) => format_general_text(*f),
+        Data::DateTime(dt) => format_general_text(dt.as_f64()),
+        d => d.to_string(),
+    }
+}
+
+/// Coerces a cell value to a boolean, like `IF` does
+fn to_bool(d: &Data) -> Result<bool, CellErrorType> {
+    match d {
+        Data::Bool(b) => Ok(*b),
+        Data::String(s) if s.eq_ignore_ascii_case("TRUE") => Ok(true),
+        Data::String(s) if s.eq_ignore_ascii_case("FALSE") => Ok(false),
+        d => to_number(d).map(|n| n != 0.),
+    }
+}
+
+/// Sort order of the value types: numbers, text, booleans then errors
+fn type_rank(d: &Data) -> u8 {
+    match d {
+        Data::String(_) | Data::DateTimeIso(_) | Data::DurationIso(_) => 1,
+        Data::Bool(_) => 2,
+        Data::Error(_) => 3,
+        _ => 0,
+    }
+}
+
+/// Compares two values: numbers < text < booleans, text being case insensitive
+///
+/// Empty cells compare as the empty value of the other side type.
+fn compare(l: &Data, r: &Data) -> Ordering {
+    let rank = type_rank;
+    let empty_as = |other: &Data| match rank(other) {
+        1 => Data::String(String::new()),
+        2 => Data::Bool(false),
+        _ => Data::Float(0.),
+    };
+    let (l, r) = match (l, r) {
+        (Data::Empty, r) => (empty_as(r), r.clone()),
+        (l, Data::Empty) => (l.clone(), empty_as(l)),
+        (l, r) => (l.clone(), r.clone()),
+    };
+    match rank(&l).cmp(&rank(&r)) {
+        Ordering::Equal => (),
+        o => return o,
+    }
+    match (&l, &r) {
+        (Data::Bool(a), Data::Bool(b)) => a.cmp(b),
+        (l, r) if rank(l) == 1 => to_text(l).to_lowercase().cmp(&to_text(r).to_lowercase()),
+        (l, r) => {
+            let a = to_number(l).unwrap_or(0.);
+            let b = to_number(r).unwrap_or(0.);
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An evaluator for a single sheet, whose cells are given as formulas or values
+    pub(super) fn sheet(cells: &[((u32, u32), &str)]) -> Evaluator {
+        let mut values = Vec::new();
+        let mut formulas = Vec::new();
+        for &(pos, s) in cells {
+            match s.strip_prefix('=') {
+                Some(f) => formulas.push(crate::Cell::new(pos, parse_formula(f).unwrap())),
+                None => values.push(crate::Cell::new(
+                    pos,
+                    s.parse().map_or(Data::String(s.to_string()), Data::Float),
+                )),
+            }
+        }
+        Evaluator::from_sheets(
+            vec![(
+                "Sheet1".to_string(),
+                Range::from_sparse(values),
+                Range::from_sparse(formulas),
+            )],
+            vec![("Rate".to_string(), "Sheet1!$B$1".to_string())],
+        )
+    }
+
+    #[test]
+    fn operators() {
+        let mut ev = sheet(&[((0, 0), "2"), ((0, 1), "0.5"), ((1, 0), "=A1*10")]);
+        let mut eval = |f| ev.evaluate("Sheet1", f).unwrap();
+        assert_eq!(eval("1+2*3"), Data::Float(7.));
+        assert_eq!(eval("-A1^2"), Data::Float(4.));
+        assert_eq!(eval("A2/Rate"), Data::Float(40.));
+        assert_eq!(eval("50%&\"x\""), Data::String("0.5x".to_string()));
+        assert_eq!(eval("\"a\"&(0.1+0.2)"), Data::String("a0.3".to_string()));
+        assert_eq!(
+            eval("1/3&\"\""),
+            Data::String("0.333333333333333".to_string())
+        );
+        assert_eq!(
+            eval("2^60&\"\""),
+            Data::String("1.15292150460685E+18".to_string())
+        );
+        assert_eq!(eval("\"abc\"=\"ABC\""), Data::Bool(true));
+        assert_eq!(eval("\"a\">1"), Data::Bool(true));
+        assert_eq!(eval("C9=0"), Data::Bool(true));
+        assert_eq!(eval("1/0"), Data::Error(CellErrorType::Div0));
+        assert_eq!(eval("\"a\"+1"), Data::Error(CellErrorType::Value));
+        assert_eq!(eval("SUM(A1:A2*2)"), Data::Float(44.));
+        assert_eq!(eval("Missing"), Data::Error(CellErrorType::Name));
+        assert_eq!(eval("NOPE(1)"), Data::Error(CellErrorType::Name));
+    }
+
+    #[test]
+    fn worksheet_range() {
+        let mut ev = sheet(&[
+            ((0, 0), "=B1"),
+            ((0, 1), "=A1+1"),
+            ((2, 0), "5"),
+            ((2, 2), "=A1:A3"),
+            ((3, 3), "=C3*2"),
+        ]);
+        let range = ev.worksheet_range("Sheet1").unwrap();
+        assert_eq!(range.start(), Some((0, 0)));
+        assert_eq!(range.end(), Some((3, 3)));
+        assert_eq!(
+            range.get_value((0, 1)),
+            Some(&Data::Error(CellErrorType::Ref))
+        );
+        // implicit intersection of A1:A3 with row 3
+        assert_eq!(range.get_value((2, 2)), Some(&Data::Float(5.)));
+        assert_eq!(range.get_value((3, 3)), Some(&Data::Float(10.)));
+    }
+}
@@ -66,7 +66,7 @@ pub fn builtin_format_by_id(id: &[u8]) -> CellFormat {
         b"22" |
         // mm:ss
         b"45" |
-        // mmss.0
+        // mm:ss.0
         b"47" => CellFormat::DateTime,
         // [h]:mm:ss
         b"46" => CellFormat::TimeDelta,
@@ -85,6 +85,43 @@ pub fn builtin_format_by_code(code: u16) -> CellFormat {
     }
 }
 
+/// Get the format code of a builtin number format id
+///
+/// Ids which depend on the locale (e.g. 5 to 8 or 27 to 36) are not known.
+pub fn builtin_format_code(id: u16) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "mm-dd-yy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
 // convert i64 to date, if format == Date
 pub fn format_excel_i64(value: i64, format: Option<&CellFormat>, is_1904: bool) -> Data {
     match format {
@@ -129,6 +166,1132 @@ pub fn format_excel_f64(value: f64, format: Option<&CellFormat>, is_1904: bool)
     format_excel_f64_ref(value, format, is_1904).into()
 }
 
+/// A parsed excel number format code (e.g. `#,##0.00;[Red]-#,##0.00`)
+///
+/// The format is made of up to 4 sections (positive, negative, zero and text)
+/// and can be used to render a cell value exactly as a spreadsheet application
+/// would display it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct NumberFormat {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Section {
+    tokens: Vec<Token>,
+    condition: Option<(Comparison, f64)>,
+    is_date: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn matches(self, value: f64, operand: f64) -> bool {
+        match self {
+            Comparison::Lt => value < operand,
+            Comparison::Le => value <= operand,
+            Comparison::Gt => value > operand,
+            Comparison::Ge => value >= operand,
+            Comparison::Eq => value == operand,
+            Comparison::Ne => value != operand,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimeUnit {
+    Hour,
+    Minute,
+    Second,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    General,
+    /// `@`
+    Text,
+    /// `0`, `#` or `?`
+    Digit(char),
+    Point,
+    Comma,
+    Percent,
+    /// `E+` (true) or `E-` (false)
+    Exponent(bool),
+    Slash,
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    SubSecond(usize),
+    Elapsed(TimeUnit, usize),
+    AmPm(String, String),
+}
+
+impl NumberFormat {
+    /// Parses a format code
+    pub(crate) fn parse(code: &str) -> NumberFormat {
+        let sections = split_sections(code)
+            .into_iter()
+            .map(|s| parse_section(&s))
+            .collect();
+        NumberFormat { sections }
+    }
+
+    /// Renders a number (or a date serial) with this format
+    pub(crate) fn format_f64(&self, value: f64, is_1904: bool) -> String {
+        if !value.is_finite() {
+            return "#NUM!".to_string();
+        }
+        let (numbers, _) = self.split_text_section();
+        let Some((section, drop_sign)) = select_section(numbers, value) else {
+            return format_general(value);
+        };
+        let value = if drop_sign { value.abs() } else { value };
+        if section.is_date {
+            format_date(&section.tokens, value, is_1904)
+        } else {
+            format_numeric(&section.tokens, value)
+        }
+    }
+
+    /// Checks whether numbers are rendered as dates or times
+    pub(crate) fn is_date(&self) -> bool {
+        self.sections.first().is_some_and(|s| s.is_date)
+    }
+
+    /// Renders a text value with this format
+    pub(crate) fn format_str(&self, value: &str) -> String {
+        let section = match self.split_text_section() {
+            (_, Some(section)) => section,
+            (numbers, None) if numbers.len() == 1 && numbers[0].tokens.contains(&Token::Text) => {
+                &numbers[0]
+            }
+            _ => return value.to_string(),
+        };
+        let mut out = String::new();
+        for token in &section.tokens {
+            match token {
+                Token::Text => out.push_str(value),
+                Token::Literal(s) => out.push_str(s),
+                _ => (),
+            }
+        }
+        out
+    }
+
+    /// Splits the number sections from the (optional) text section
+    fn split_text_section(&self) -> (&[Section], Option<&Section>) {
+        match self.sections.len() {
+            0 => (&[], None),
+            1 => (&self.sections, None),
+            n if n >= 4 => (&self.sections[..3], Some(&self.sections[3])),
+            n if self.sections[n - 1].tokens.contains(&Token::Text) => {
+                (&self.sections[..n - 1], Some(&self.sections[n - 1]))
+            }
+            _ => (&self.sections, None),
+        }
+    }
+}
+
+/// Selects the section used to render `value` and whether its sign must be dropped
+fn select_section(sections: &[Section], value: f64) -> Option<(&Section, bool)> {
+    if sections.iter().any(|s| s.condition.is_some()) {
+        let idx = sections
+            .iter()
+            .position(|s| matches!(s.condition, Some((op, v)) if op.matches(value, v)))
+            .or_else(|| sections.iter().position(|s| s.condition.is_none()))?;
+        return Some((&sections[idx], idx == 1));
+    }
+    match sections.len() {
+        0 => None,
+        1 => Some((&sections[0], false)),
+        2 if value < 0. => Some((&sections[1], true)),
+        2 => Some((&sections[0], false)),
+        _ if value < 0. => Some((&sections[1], true)),
+        _ if value == 0. => Some((&sections[2], false)),
+        _ => Some((&sections[0], false)),
+    }
+}
+
+/// Splits the format code on `;`, ignoring quoted, escaped or bracketed ones
+fn split_sections(code: &str) -> Vec<String> {
+    let mut sections = Vec::new();
+    let mut current = String::new();
+    let mut chars = code.chars();
+    let mut in_quote = false;
+    let mut in_bracket = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '_' | '*' if !in_quote && !in_bracket => {
+                current.push(c);
+                if let Some(n) = chars.next() {
+                    current.push(n);
+                }
+                continue;
+            }
+            '"' if !in_bracket => in_quote = !in_quote,
+            '[' if !in_quote => in_bracket = true,
+            ']' if !in_quote => in_bracket = false,
+            ';' if !in_quote && !in_bracket => {
+                sections.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    sections.push(current);
+    sections
+}
+
+fn starts_with_ignore_case(chars: &[char], pat: &str) -> bool {
+    chars.len() >= pat.len()
+        && chars
+            .iter()
+            .zip(pat.chars())
+            .all(|(c, p)| c.eq_ignore_ascii_case(&p))
+}
+
+/// Checks whether a section contains date or time tokens
+fn section_is_date(chars: &[char]) -> bool {
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' | '_' | '*' => i += 1,
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+            }
+            '[' => {
+                let start = i + 1;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                if elapsed_unit(&chars[start..i]).is_some() {
+                    return true;
+                }
+            }
+            _ if starts_with_ignore_case(&chars[i..], "general") => i += 6,
+            _ if starts_with_ignore_case(&chars[i..], "am/pm")
+                || starts_with_ignore_case(&chars[i..], "a/p") =>
+            {
+                return true
+            }
+            'y' | 'm' | 'd' | 'h' | 's' | 'Y' | 'M' | 'D' | 'H' | 'S' => return true,
+            _ => (),
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Parses the content of a `[h]`, `[mm]` or `[ss]` bracket
+fn elapsed_unit(content: &[char]) -> Option<TimeUnit> {
+    let first = content.first()?.to_ascii_lowercase();
+    if !content.iter().all(|c| c.to_ascii_lowercase() == first) {
+        return None;
+    }
+    match first {
+        'h' => Some(TimeUnit::Hour),
+        'm' => Some(TimeUnit::Minute),
+        's' => Some(TimeUnit::Second),
+        _ => None,
+    }
+}
+
+fn push_literal(tokens: &mut Vec<Token>, s: &str) {
+    if let Some(Token::Literal(last)) = tokens.last_mut() {
+        last.push_str(s);
+    } else {
+        tokens.push(Token::Literal(s.to_string()));
+    }
+}
+
+fn parse_section(code: &str) -> Section {
+    let chars: Vec<char> = code.chars().collect();
+    let is_date = section_is_date(&chars);
+    let mut section = Section {
+        is_date,
+        ..Default::default()
+    };
+    let tokens = &mut section.tokens;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // length of the run of identical (case insensitive) characters
+        let run = chars[i..]
+            .iter()
+            .take_while(|n| n.eq_ignore_ascii_case(&c))
+            .count();
+        match c {
+            '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .map_or(chars.len(), |p| i + 1 + p);
+                let s: String = chars[i + 1..end].iter().collect();
+                push_literal(tokens, &s);
+                i = end + 1;
+                continue;
+            }
+            '\\' => {
+                if let Some(n) = chars.get(i + 1) {
+                    push_literal(tokens, &n.to_string());
+                }
+                i += 2;
+                continue;
+            }
+            '_' => {
+                push_literal(tokens, " ");
+                i += 2;
+                continue;
+            }
+            '*' => {
+                i += 2;
+                continue;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map_or(chars.len(), |p| i + p);
+                let content = &chars[i + 1..end];
+                i = end + 1;
+                if let Some(unit) = elapsed_unit(content) {
+                    tokens.push(Token::Elapsed(unit, content.len()));
+                } else if content.first() == Some(&'$') {
+                    // currency and/or locale, e.g. [$€-407]
+                    let symbol: String = content[1..].iter().take_while(|&&c| c != '-').collect();
+                    push_literal(tokens, &symbol);
+                } else if let Some(condition) = parse_condition(content) {
+                    section.condition = Some(condition);
+                }
+                // anything else is a color, which is irrelevant for text rendering
+                continue;
+            }
+            _ if starts_with_ignore_case(&chars[i..], "general") => {
+                tokens.push(Token::General);
+                i += 7;
+                continue;
+            }
+            '@' => tokens.push(Token::Text),
+            '0' | '#' | '?' if !is_date => tokens.push(Token::Digit(c)),
+            '.' if is_date && chars.get(i + 1) == Some(&'0') => {
+                let n = chars[i + 1..].iter().take_while(|&&c| c == '0').count();
+                tokens.push(Token::SubSecond(n));
+                i += n + 1;
+                continue;
+            }
+            '.' if !is_date => tokens.push(Token::Point),
+            ',' if !is_date => tokens.push(Token::Comma),
+            '%' if !is_date => tokens.push(Token::Percent),
+            'E' | 'e' if !is_date && matches!(chars.get(i + 1), Some('+' | '-')) => {
+                tokens.push(Token::Exponent(chars[i + 1] == '+'));
+                i += 2;
+                continue;
+            }
+            '/' if !is_date => tokens.push(Token::Slash),
+            'a' | 'A' if is_date && starts_with_ignore_case(&chars[i..], "am/pm") => {
+                tokens.push(Token::AmPm(
+                    chars[i..i + 2].iter().collect(),
+                    chars[i + 3..i + 5].iter().collect(),
+                ));
+                i += 5;
+                continue;
+            }
+            'a' | 'A' if is_date && starts_with_ignore_case(&chars[i..], "a/p") => {
+                tokens.push(Token::AmPm(chars[i].to_string(), chars[i + 2].to_string()));
+                i += 3;
+                continue;
+            }
+            'y' | 'Y' | 'e' | 'E' | 'b' | 'B' if is_date => {
+                let n = if c.eq_ignore_ascii_case(&'e') { 4 } else { run };
+                tokens.push(Token::Year(n));
+                i += run;
+                continue;
+            }
+            'm' | 'M' if is_date => {
+                tokens.push(Token::Month(run));
+                i += run;
+                continue;
+            }
+            'd' | 'D' if is_date => {
+                tokens.push(Token::Day(run));
+                i += run;
+                continue;
+            }
+            'h' | 'H' if is_date => {
+                tokens.push(Token::Hour(run));
+                i += run;
+                continue;
+            }
+            's' | 'S' if is_date => {
+                tokens.push(Token::Second(run));
+                i += run;
+                continue;
+            }
+            'g' | 'G' if is_date => {
+                // era, not supported
+                i += run;
+                continue;
+            }
+            _ => push_literal(tokens, &c.to_string()),
+        }
+        i += 1;
+    }
+    if is_date {
+        resolve_minutes(tokens);
+    }
+    section
+}
+
+fn parse_condition(content: &[char]) -> Option<(Comparison, f64)> {
+    let s: String = content.iter().collect();
+    let (op, rest) = if let Some(r) = s.strip_prefix("<=") {
+        (Comparison::Le, r)
+    } else if let Some(r) = s.strip_prefix(">=") {
+        (Comparison::Ge, r)
+    } else if let Some(r) = s.strip_prefix("<>") {
+        (Comparison::Ne, r)
+    } else if let Some(r) = s.strip_prefix('<') {
+        (Comparison::Lt, r)
+    } else if let Some(r) = s.strip_prefix('>') {
+        (Comparison::Gt, r)
+    } else if let Some(r) = s.strip_prefix('=') {
+        (Comparison::Eq, r)
+    } else {
+        return None;
+    };
+    rest.trim().parse().ok().map(|v| (op, v))
+}
+
+/// `m` means minutes when it follows hours or precedes seconds
+fn resolve_minutes(tokens: &mut [Token]) {
+    let is_time = |t: &Token| !matches!(t, Token::Literal(_));
+    for i in 0..tokens.len() {
+        let Token::Month(n) = tokens[i] else {
+            continue;
+        };
+        if n > 2 {
+            continue;
+        }
+        let prev = tokens[..i].iter().rev().find(|t| is_time(t));
+        let next = tokens[i + 1..].iter().find(|t| is_time(t));
+        if matches!(
+            prev,
+            Some(Token::Hour(_) | Token::Elapsed(TimeUnit::Hour, _))
+        ) || matches!(
+            next,
+            Some(Token::Second(_) | Token::Elapsed(TimeUnit::Second, _))
+        ) {
+            tokens[i] = Token::Minute(n);
+        }
+    }
+}
+
+/// Excel's `General` format: up to 11 characters, scientific notation for
+/// very large or very small numbers
+pub(crate) fn format_general(value: f64) -> String {
+    general(value, 1e11, 5, |exp| {
+        let int_len = if exp >= 0 { exp as usize + 1 } else { 1 };
+        10usize.saturating_sub(int_len)
+    })
+}
+
+/// The `General` format used when numbers are converted to text, e.g. by the
+/// `&` operator: up to 15 significant digits
+#[cfg(feature = "eval")]
+pub(crate) fn format_general_text(value: f64) -> String {
+    general(value, 1e15, 14, |exp| (14 - exp).max(0) as usize)
+}
+
+/// Renders a number without exponent below `limit` with the given number of
+/// decimals for its decimal exponent, or in scientific notation with
+/// `sci_decimals` mantissa decimals
+fn general(value: f64, limit: f64, sci_decimals: usize, decimals: impl Fn(i32) -> usize) -> String {
+    if value == 0. {
+        return "0".to_string();
+    }
+    let abs = value.abs();
+    if value.fract() == 0. && abs < limit {
+        return format!("{}", value as i64);
+    }
+    if !(1e-9..limit).contains(&abs) {
+        let (digits, exp) = decimal_digits(abs);
+        let (int, frac) = round_digits(&digits, 1, sci_decimals);
+        let (int, frac, exp) = if int.len() > 1 {
+            (
+                int[..1].to_string(),
+                format!("{}{}", &int[1..], frac),
+                exp + 1,
+            )
+        } else {
+            (int, frac, exp)
+        };
+        let frac = frac[..sci_decimals].trim_end_matches('0');
+        let sign = if value < 0. { "-" } else { "" };
+        let exp_sign = if exp < 0 { '-' } else { '+' };
+        let point = if frac.is_empty() { "" } else { "." };
+        return format!("{sign}{int}{point}{frac}E{exp_sign}{:02}", exp.abs());
+    }
+    let (digits, exp) = decimal_digits(abs);
+    let (int, frac) = round_digits(&digits, exp + 1, decimals(exp));
+    let frac = frac.trim_end_matches('0');
+    let sign = if value < 0. { "-" } else { "" };
+    if frac.is_empty() {
+        format!("{sign}{int}")
+    } else {
+        format!("{sign}{int}.{frac}")
+    }
+}
+
+/// Returns the 15 significant digits of a positive number and its decimal exponent
+fn decimal_digits(abs: f64) -> (Vec<u8>, i32) {
+    let s = format!("{:.14e}", abs);
+    let (mantissa, exp) = s.split_once('e').unwrap_or((&s, "0"));
+    let digits = mantissa.bytes().filter(u8::is_ascii_digit).collect();
+    (digits, exp.parse().unwrap_or(0))
+}
+
+/// Rounds (half away from zero) a digit sequence where the decimal point is
+/// after `point` digits, returning the integer and the fractional parts
+fn round_digits(digits: &[u8], point: i32, decimals: usize) -> (String, String) {
+    let keep = point + decimals as i32;
+    let mut kept: Vec<u8> = if keep <= 0 {
+        Vec::new()
+    } else {
+        (0..keep as usize)
+            .map(|i| digits.get(i).copied().unwrap_or(b'0'))
+            .collect()
+    };
+    let mut point = point;
+    let round_up = keep >= 0 && digits.get(keep as usize).is_some_and(|&d| d >= b'5');
+    if round_up {
+        let mut i = kept.len();
+        loop {
+            if i == 0 {
+                kept.insert(0, b'1');
+                point += 1;
+                break;
+            }
+            i -= 1;
+            if kept[i] == b'9' {
+                kept[i] = b'0';
+            } else {
+                kept[i] += 1;
+                break;
+            }
+        }
+    }
+    // left pad so that the decimal point lands inside the digits, keeping at
+    // least one integer digit when the value rounds below the last decimal
+    let total = ((point + decimals as i32).max(0) as usize).max(decimals + 1);
+    if kept.len() < total {
+        let mut padded = vec![b'0'; total - kept.len()];
+        padded.extend(kept);
+        kept = padded;
+    }
+    let int_len = kept.len() - decimals;
+    let int = String::from_utf8_lossy(&kept[..int_len])
+        .trim_start_matches('0')
+        .to_string();
+    let frac = String::from_utf8_lossy(&kept[int_len..]).into_owned();
+    let int = if int.is_empty() { "0".to_string() } else { int };
+    (int, frac)
+}
+
+/// Fills integer digit placeholders, right to left
+///
+/// Returns the text rendered for each placeholder
+fn fill_integer(placeholders: &[char], digits: &str, grouping: bool) -> Vec<String> {
+    let digits: Vec<char> = if digits == "0" {
+        Vec::new()
+    } else {
+        digits.chars().collect()
+    };
+    let n = placeholders.len();
+    let mut rendered: Vec<Vec<char>> = vec![Vec::new(); n];
+    for (j, p) in placeholders.iter().enumerate() {
+        let r = n - 1 - j;
+        if j == 0 && digits.len() > n {
+            rendered[j].extend(&digits[..digits.len() - n]);
+        }
+        if r < digits.len() {
+            rendered[j].push(digits[digits.len() - 1 - r]);
+        } else {
+            match p {
+                '0' => rendered[j].push('0'),
+                '?' => rendered[j].push(' '),
+                _ => (),
+            }
+        }
+    }
+    if !grouping {
+        return rendered
+            .into_iter()
+            .map(|r| r.into_iter().collect())
+            .collect();
+    }
+    let total = rendered
+        .iter()
+        .flatten()
+        .filter(|c| c.is_ascii_digit())
+        .count();
+    let mut seen = 0;
+    rendered
+        .into_iter()
+        .map(|r| {
+            let mut s = String::new();
+            for c in r {
+                s.push(c);
+                if c.is_ascii_digit() {
+                    seen += 1;
+                    let q = total - seen;
+                    if q > 0 && q % 3 == 0 {
+                        s.push(',');
+                    }
+                }
+            }
+            s
+        })
+        .collect()
+}
+
+/// Fills fractional digit placeholders, left to right
+fn fill_fraction(placeholders: &[char], digits: &str) -> Vec<String> {
+    let mut rendered: Vec<String> = digits.chars().map(String::from).collect();
+    for (i, p) in placeholders.iter().enumerate().rev() {
+        if rendered[i] != "0" {
+            break;
+        }
+        match p {
+            '#' => rendered[i].clear(),
+            '?' => rendered[i] = " ".to_string(),
+            _ => break,
+        }
+    }
+    rendered
+}
+
+fn format_numeric(tokens: &[Token], value: f64) -> String {
+    let negative = value < 0.;
+    let mut abs = value.abs();
+    let has_digits = tokens.iter().any(|t| matches!(t, Token::Digit(_)));
+    let mut out = String::new();
+
+    if !has_digits {
+        for token in tokens {
+            match token {
+                Token::Literal(s) => out.push_str(s),
+                // a text only format renders numbers as `General`
+                Token::General | Token::Text => {
+                    if negative {
+                        out.push('-');
+                    }
+                    out.push_str(&format_general(abs));
+                }
+                Token::Percent => out.push('%'),
+                Token::Point => out.push('.'),
+                Token::Comma => out.push(','),
+                Token::Slash => out.push('/'),
+                _ => (),
+            }
+        }
+        return out;
+    }
+
+    for token in tokens {
+        if *token == Token::Percent {
+            abs *= 100.;
+        }
+    }
+
+    let exponent = tokens.iter().position(|t| matches!(t, Token::Exponent(_)));
+    let number_end = exponent.unwrap_or(tokens.len());
+    let point = tokens[..number_end].iter().position(|t| *t == Token::Point);
+    let int_end = point.unwrap_or(number_end);
+
+    // a slash between digit placeholders is a fraction
+    if exponent.is_none() && point.is_none() {
+        if let Some(slash) = tokens.iter().position(|t| *t == Token::Slash) {
+            if matches!(tokens.get(slash.wrapping_sub(1)), Some(Token::Digit(_))) {
+                return format_fraction(tokens, slash, abs, negative);
+            }
+        }
+    }
+
+    // commas: scaling when after the last digit placeholder, thousands
+    // separator when between integer digits
+    let is_digit = |t: &Token| matches!(t, Token::Digit(_));
+    let mut grouping = false;
+    let mut scaling_commas = Vec::new();
+    for (i, t) in tokens[..number_end].iter().enumerate() {
+        if *t != Token::Comma || !tokens[..i].iter().any(is_digit) {
+            continue;
+        }
+        if !tokens[i + 1..number_end].iter().any(is_digit) {
+            scaling_commas.push(i);
+            abs /= 1000.;
+        } else if i < int_end
+            && tokens[i + 1..int_end]
+                .iter()
+                .find(|t| **t != Token::Comma)
+                .is_some_and(is_digit)
+        {
+            grouping = true;
+        }
+    }
+
+    let digit = |t: &Token| match t {
+        Token::Digit(c) => Some(*c),
+        _ => None,
+    };
+    let int_placeholders: Vec<char> = tokens[..int_end].iter().filter_map(digit).collect();
+    let frac_placeholders: Vec<char> = point
+        .map(|p| tokens[p + 1..number_end].iter().filter_map(digit).collect())
+        .unwrap_or_default();
+    let decimals = frac_placeholders.len();
+
+    let (int_digits, frac_digits, exp) = if exponent.is_some() {
+        let k = int_placeholders.len() as i32;
+        let engineering = k > 1 && int_placeholders.contains(&'#');
+        let exp_for = |e10: i32| {
+            if engineering {
+                e10.div_euclid(k) * k
+            } else {
+                e10 - (k - 1)
+            }
+        };
+        let (digits, e10) = if abs == 0. {
+            (vec![b'0'; 15], 0)
+        } else {
+            decimal_digits(abs)
+        };
+        let mut exp = if abs == 0. { 0 } else { exp_for(e10) };
+        let (mut int, mut frac) = round_digits(&digits, e10 + 1 - exp, decimals);
+        if abs != 0. && int.len() as i32 > k.max(1) && !engineering {
+            // rounding overflowed the mantissa, e.g. 9.99 -> 10.0
+            exp += 1;
+            (int, frac) = round_digits(&digits, e10 + 1 - exp, decimals);
+        }
+        (int, frac, Some(exp))
+    } else {
+        let (digits, e10) = if abs == 0. {
+            (vec![b'0'; 15], 0)
+        } else {
+            decimal_digits(abs)
+        };
+        let (int, frac) = round_digits(&digits, e10 + 1, decimals);
+        (int, frac, None)
+    };
+
+    let int_rendered = fill_integer(&int_placeholders, &int_digits, grouping);
+    let frac_rendered = fill_fraction(&frac_placeholders, &frac_digits);
+    let is_zero = int_digits == "0" && frac_digits.bytes().all(|b| b == b'0');
+    if negative && !is_zero {
+        out.push('-');
+    }
+
+    let (mut int_i, mut frac_i) = (0, 0);
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Digit(_) if i < int_end => {
+                out.push_str(&int_rendered[int_i]);
+                int_i += 1;
+            }
+            Token::Digit(_) if i < number_end => {
+                out.push_str(&frac_rendered[frac_i]);
+                frac_i += 1;
+            }
+            Token::Digit(_) => (), // exponent digits, rendered with the exponent
+            Token::Exponent(plus) => {
+                let exp = exp.unwrap_or(0);
+                let width = tokens[i + 1..]
+                    .iter()
+                    .take_while(|t| matches!(t, Token::Digit(_)))
+                    .filter(|t| **t == Token::Digit('0'))
+                    .count();
+                out.push('E');
+                if exp < 0 {
+                    out.push('-');
+                } else if *plus {
+                    out.push('+');
+                }
+                out.push_str(&format!("{:0width$}", exp.abs(), width = width));
+            }
+            Token::Comma if !scaling_commas.contains(&i) && (i >= int_end || !grouping) => {
+                out.push(',')
+            }
+            Token::Point => out.push('.'),
+            Token::Percent => out.push('%'),
+            Token::Slash => out.push('/'),
+            Token::Literal(s) => out.push_str(s),
+            Token::General => out.push_str(&format_general(abs)),
+            _ => (),
+        }
+    }
+    out
+}
+
+fn format_fraction(tokens: &[Token], slash: usize, abs: f64, negative: bool) -> String {
+    let is_digit = |t: &Token| matches!(t, Token::Digit(_));
+    // numerator placeholders are right before the slash, the (optional)
+    // integer part is separated from them by some literal
+    let num_start = slash
+        - tokens[..slash]
+            .iter()
+            .rev()
+            .take_while(|t| is_digit(t))
+            .count();
+    let has_int = tokens[..num_start].iter().any(is_digit);
+    let den_placeholders: Vec<char> = tokens[slash + 1..]
+        .iter()
+        .map_while(|t| match t {
+            Token::Digit(c) => Some(*c),
+            _ => None,
+        })
+        .collect();
+    let fixed_den: Option<u64> = match tokens.get(slash + 1) {
+        Some(Token::Literal(s)) => {
+            let d: String = s.chars().take_while(char::is_ascii_digit).collect();
+            d.parse().ok().filter(|&d| d > 0)
+        }
+        _ => None,
+    };
+
+    let (mut whole, frac) = if has_int {
+        (abs.trunc(), abs.fract())
+    } else {
+        (0., abs)
+    };
+    let (mut num, den) = if let Some(den) = fixed_den {
+        ((frac * den as f64).round() as u64, den)
+    } else {
+        let max_den = 10u64.pow(den_placeholders.len().clamp(1, 4) as u32) - 1;
+        let mut best = (frac.round() as u64, 1u64);
+        let mut best_err = (frac - best.0 as f64).abs();
+        for d in 2..=max_den {
+            let n = (frac * d as f64).round();
+            let err = (frac - n / d as f64).abs();
+            if err < best_err - 1e-12 {
+                best = (n as u64, d);
+                best_err = err;
+            }
+        }
+        best
+    };
+    if has_int && num == den {
+        whole += 1.;
+        num = 0;
+    }
+
+    let mut out = String::new();
+    if negative && (whole != 0. || num != 0) {
+        out.push('-');
+    }
+    let int_placeholders: Vec<char> = tokens[..num_start]
+        .iter()
+        .filter_map(|t| match t {
+            Token::Digit(c) => Some(*c),
+            _ => None,
+        })
+        .collect();
+    let mut int_rendered = fill_integer(&int_placeholders, &(whole as u64).to_string(), false);
+    if whole == 0. && num == 0 {
+        // always show something for a zero value
+        if let Some(last) = int_rendered.last_mut() {
+            *last = "0".to_string();
+        }
+    }
+    let num_placeholders: Vec<char> = tokens[num_start..slash]
+        .iter()
+        .filter_map(|t| match t {
+            Token::Digit(c) => Some(*c),
+            _ => None,
+        })
+        .collect();
+    let num_rendered: String = fill_integer(&num_placeholders, &num.to_string(), false).concat();
+    let mut den_rendered = if fixed_den.is_some() {
+        String::new()
+    } else {
+        den.to_string()
+    };
+    for p in den_placeholders.iter().skip(den_rendered.len()) {
+        match p {
+            '0' => den_rendered.push('0'),
+            '?' => den_rendered.push(' '),
+            _ => (),
+        }
+    }
+    let hide_fraction = has_int && num == 0;
+
+    let mut int_i = 0;
+    let den_end = slash + 1 + den_placeholders.len();
+    for (i, token) in tokens.iter().enumerate() {
+        if i < num_start {
+            match token {
+                Token::Digit(_) => {
+                    out.push_str(&int_rendered[int_i]);
+                    int_i += 1;
+                }
+                Token::Literal(s) => out.push_str(s),
+                _ => (),
+            }
+        } else if i < slash {
+            if i == num_start {
+                if hide_fraction {
+                    out.push_str(&" ".repeat(num_rendered.len()));
+                } else {
+                    out.push_str(&num_rendered);
+                }
+            }
+        } else if i == slash {
+            out.push(if hide_fraction { ' ' } else { '/' });
+            if i + 1 == den_end || fixed_den.is_some() {
+                if hide_fraction {
+                    out.push_str(&" ".repeat(den_rendered.len()));
+                } else {
+                    out.push_str(&den_rendered);
+                }
+            }
+        } else if i < den_end {
+            if i + 1 == den_end {
+                if hide_fraction {
+                    out.push_str(&" ".repeat(den_rendered.len()));
+                } else {
+                    out.push_str(&den_rendered);
+                }
+            }
+        } else if let Token::Literal(s) = token {
+            if hide_fraction && i == slash + 1 && fixed_den.is_some() {
+                let d = s.chars().take_while(char::is_ascii_digit).count();
+                out.push_str(&" ".repeat(d));
+                out.push_str(&s[d..]);
+            } else {
+                out.push_str(s);
+            }
+        }
+    }
+    out
+}
+
+/// Converts an ISO 8601 date/datetime (e.g. `2024-01-31T12:00:00`) to a
+/// 1900 based excel serial
+pub(crate) fn iso_datetime_to_serial(iso: &str) -> Option<f64> {
+    let (date, time) = match iso.split_once('T') {
+        Some((d, t)) => (d, Some(t)),
+        None if iso.len() == 10 => (iso, None),
+        None => return iso_time_to_serial(iso),
+    };
+    let mut parts = date.splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: i64 = parts.next()?.parse().ok()?;
+    let d: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let mut days = days_from_civil(y, m, d) - days_from_civil(1899, 12, 30);
+    if days < 61 {
+        // before excel's fictitious 1900-02-29
+        days -= 1;
+    }
+    let time = match time {
+        Some(t) => iso_time_to_serial(t)?,
+        None => 0.,
+    };
+    Some(days as f64 + time)
+}
+
+/// Converts an ISO 8601 time (`12:30:00`) or duration (`PT12H30M00S`) to a
+/// fraction of days
+pub(crate) fn iso_time_to_serial(iso: &str) -> Option<f64> {
+    if let Some(duration) = iso.strip_prefix("PT").or_else(|| iso.strip_prefix("-PT")) {
+        let mut secs = 0.;
+        let mut num = String::new();
+        for c in duration.chars() {
+            match c {
+                'H' => secs += num.parse::<f64>().ok()? * 3600.,
+                'M' => secs += num.parse::<f64>().ok()? * 60.,
+                'S' => secs += num.parse::<f64>().ok()?,
+                _ => {
+                    num.push(c);
+                    continue;
+                }
+            }
+            num.clear();
+        }
+        let sign = if iso.starts_with('-') { -1. } else { 1. };
+        return Some(sign * secs / 86_400.);
+    }
+    // ignore any timezone
+    let time = iso.trim_end_matches('Z');
+    let time = time.split(['+', '-']).next()?;
+    let mut parts = time.splitn(3, ':');
+    let h: f64 = parts.next()?.parse().ok()?;
+    let m: f64 = parts.next()?.parse().ok()?;
+    let s: f64 = parts.next().map_or(Some(0.), |s| s.parse().ok())?;
+    Some((h * 3600. + m * 60. + s) / 86_400.)
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Days since 1970-01-01 of a civil date (proleptic gregorian)
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Civil date of a number of days since 1970-01-01
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+/// Converts an excel serial day into a (year, month, day, weekday) tuple
+///
+/// The 1900 date system keeps excel's fictitious 1900-02-29 (serial 60).
+fn serial_to_date(days: i64, is_1904: bool) -> (i64, i64, i64, usize) {
+    if is_1904 {
+        let (y, m, d) = civil_from_days(days_from_civil(1904, 1, 1) + days);
+        return (y, m, d, (days + 5).rem_euclid(7) as usize);
+    }
+    let weekday = (days + 6).rem_euclid(7) as usize;
+    match days {
+        0 => (1900, 1, 0, weekday),
+        60 => (1900, 2, 29, weekday),
+        1..=59 => {
+            let (y, m, d) = civil_from_days(days_from_civil(1899, 12, 31) + days);
+            (y, m, d, weekday)
+        }
+        _ => {
+            let (y, m, d) = civil_from_days(days_from_civil(1899, 12, 30) + days);
+            (y, m, d, weekday)
+        }
+    }
+}
+
+fn format_date(tokens: &[Token], value: f64, is_1904: bool) -> String {
+    if value < 0. {
+        return "#".repeat(8);
+    }
+    let sub_digits = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::SubSecond(n) => Some(*n),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        .min(3);
+    let scale = 10i64.pow(sub_digits as u32);
+    let total = (value * 86_400. * scale as f64).round() as i64;
+    let total_secs = total / scale;
+    let sub = total % scale;
+    let days = total_secs / 86_400;
+    let secs = total_secs % 86_400;
+    let (hour, minute, second) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    let (year, month, day, weekday) = serial_to_date(days, is_1904);
+    let twelve_hours = tokens.iter().any(|t| matches!(t, Token::AmPm(..)));
+
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Literal(s) => out.push_str(s),
+            Token::Year(n) if *n <= 2 => out.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Token::Year(_) => out.push_str(&format!("{:04}", year)),
+            Token::Month(1) => out.push_str(&month.to_string()),
+            Token::Month(2) => out.push_str(&format!("{:02}", month)),
+            Token::Month(n) => {
+                let name = MONTHS[(month - 1) as usize];
+                match n {
+                    3 => out.push_str(&name[..3]),
+                    4 => out.push_str(name),
+                    _ => out.push_str(&name[..1]),
+                }
+            }
+            Token::Day(1) => out.push_str(&day.to_string()),
+            Token::Day(2) => out.push_str(&format!("{:02}", day)),
+            Token::Day(3) => out.push_str(&WEEKDAYS[weekday][..3]),
+            Token::Day(_) => out.push_str(WEEKDAYS[weekday]),
+            Token::Hour(n) => {
+                let h = if twelve_hours {
+                    (hour + 11) % 12 + 1
+                } else {
+                    hour
+                };
+                out.push_str(&format!("{:0width$}", h, width = (*n).min(2)));
+            }
+            Token::Minute(n) => out.push_str(&format!("{:0width$}", minute, width = (*n).min(2))),
+            Token::Second(n) => out.push_str(&format!("{:0width$}", second, width = (*n).min(2))),
+            Token::SubSecond(n) => {
+                let digits = format!("{:0width$}", sub, width = sub_digits);
+                out.push('.');
+                out.push_str(&digits[..(*n).min(sub_digits)]);
+            }
+            Token::Elapsed(unit, n) => {
+                let v = match unit {
+                    TimeUnit::Hour => total_secs / 3600,
+                    TimeUnit::Minute => total_secs / 60,
+                    TimeUnit::Second => total_secs,
+                };
+                out.push_str(&format!("{:0width$}", v, width = *n));
+            }
+            Token::AmPm(am, pm) => out.push_str(if hour < 12 { am } else { pm }),
+            Token::General => out.push_str(&format_general(value)),
+            _ => (),
+        }
+    }
+    out
+}
+
 /// Ported from openpyxl, MIT License
 /// https://foss.heptapod.net/openpyxl/openpyxl/-/blob/a5e197c530aaa49814fd1d993dd776edcec35105/openpyxl/styles/tests/test_number_style.py
 #[test]
@@ -215,3 +1378,94 @@ fn test_is_date_format() {
         CellFormat::Other
     );
 }
+
+#[test]
+fn test_number_format() {
+    let f = |v: f64, code: &str| NumberFormat::parse(code).format_f64(v, false);
+    assert_eq!(f(0.1 + 0.2, "General"), "0.3");
+    assert_eq!(f(1.0 / 3.0, "General"), "0.333333333");
+    assert_eq!(f(123456789012.0, "General"), "1.23457E+11");
+    assert_eq!(f(-42.0, "General"), "-42");
+    assert_eq!(f(3.14159, "0.00"), "3.14");
+    assert_eq!(f(1.005, "0.00"), "1.01");
+    assert_eq!(f(2.5, "0"), "3");
+    assert_eq!(f(0.5, "#.##"), ".5");
+    assert_eq!(f(1234567.891, "#,##0.00"), "1,234,567.89");
+    assert_eq!(f(5.0, "0,000"), "0,005");
+    assert_eq!(f(1234567.0, "#,##0,"), "1,235");
+    assert_eq!(f(1234567.0, "0.0,,"), "1.2");
+    assert_eq!(f(1234567.0, "#,##0.0,\"K\""), "1,234.6K");
+    assert_eq!(f(0.256, "0.0%"), "25.6%");
+    assert_eq!(f(12345.0, "0.00E+00"), "1.23E+04");
+    assert_eq!(f(0.00012, "0.00E+00"), "1.20E-04");
+    assert_eq!(f(12345.0, "##0.0E+0"), "12.3E+3");
+    assert_eq!(f(1.5, "# ?/?"), "1 1/2");
+    assert_eq!(f(0.3333, "?/?"), "1/3");
+    assert_eq!(f(2.25, "# ?/8"), "2 2/8");
+    assert_eq!(f(3.0, "# ?/?"), "3    ");
+    assert_eq!(f(-5.0, "0;[Red](0)"), "(5)");
+    assert_eq!(f(0.0, "0;-0;\"zero\""), "zero");
+    assert_eq!(f(-1234.5, "\"$\"#,##0.00_);(\"$\"#,##0.00)"), "($1,234.50)");
+    assert_eq!(f(1234.5, "\"$\"#,##0.00_);(\"$\"#,##0.00)"), "$1,234.50 ");
+    assert_eq!(f(150.0, "[>=100][Red]\"big\";[<0]\"neg\";0"), "big");
+    assert_eq!(f(1234.0, "#,##0 [$€-407]"), "1,234 €");
+    assert_eq!(f(5551234.0, "000-0000"), "555-1234");
+    assert_eq!(f(-1.5, "@"), "-1.5");
+    assert_eq!(f(1.5, "\"<\"@\">\""), "<1.5>");
+}
+
+#[test]
+fn test_date_format() {
+    let f = |v: f64, code: &str| NumberFormat::parse(code).format_f64(v, false);
+    assert_eq!(f(45000.0, "yyyy-mm-dd"), "2023-03-15");
+    assert_eq!(f(45000.0, "d-mmm-yy"), "15-Mar-23");
+    assert_eq!(f(45000.0, "dddd, mmmm d"), "Wednesday, March 15");
+    assert_eq!(f(60.0, "yyyy-mm-dd"), "1900-02-29");
+    assert_eq!(f(1.0, "m/d/yy"), "1/1/00");
+    assert_eq!(f(0.75, "h:mm AM/PM"), "6:00 PM");
+    assert_eq!(f(0.5 + 1.0 / 86400.0, "hh:mm:ss"), "12:00:01");
+    assert_eq!(f(1.5, "[h]:mm:ss"), "36:00:00");
+    assert_eq!(f(1.0 / 86400.0 * 1.25, "mm:ss.00"), "00:01.25");
+    assert_eq!(f(45000.25, "yyyy-mm-dd hh:mm"), "2023-03-15 06:00");
+    assert_eq!(
+        NumberFormat::parse("yyyy-mm-dd").format_f64(0.0, true),
+        "1904-01-01"
+    );
+}
+
+#[test]
+fn test_text_format() {
+    let f = |v: &str, code: &str| NumberFormat::parse(code).format_str(v);
+    assert_eq!(f("abc", "General"), "abc");
+    assert_eq!(f("abc", "0.00"), "abc");
+    assert_eq!(f("abc", "\"<\"@\">\""), "<abc>");
+    assert_eq!(f("abc", "0;-0;0;\"text: \"@"), "text: abc");
+}
+
+#[test]
+fn test_builtin_format_code() {
+    assert_eq!(builtin_format_code(0), Some("General"));
+    assert_eq!(builtin_format_code(10), Some("0.00%"));
+    assert_eq!(builtin_format_code(14), Some("mm-dd-yy"));
+    assert_eq!(builtin_format_code(47), Some("mm:ss.0"));
+    assert_eq!(builtin_format_code(5), None);
+    assert_eq!(builtin_format_code(164), None);
+    for id in [14, 15, 22, 45, 46, 47] {
+        assert!(NumberFormat::parse(builtin_format_code(id).unwrap()).is_date());
+    }
+}
+
+#[test]
+fn test_iso_to_serial() {
+    assert_eq!(iso_datetime_to_serial("2021-01-01"), Some(44197.0));
+    assert_eq!(iso_datetime_to_serial("1900-01-01"), Some(1.0));
+    assert_eq!(iso_datetime_to_serial("2021-01-01T12:00:00"), Some(44197.5));
+    assert_eq!(iso_datetime_to_serial("12:00:00"), Some(0.5));
+    assert_eq!(iso_datetime_to_serial("2021-13-01"), None);
+    assert_eq!(iso_time_to_serial("PT36H"), Some(1.5));
+    assert_eq!(
+        iso_time_to_serial("PT10H10M10S"),
+        iso_time_to_serial("10:10:10")
+    );
+    assert_eq!(iso_time_to_serial("foo"), None);
+}
@@ -0,0 +1,1871 @@
+//! Formula parsing
+//!
+//! All readers can return their formulas as [`Expr`] trees, with
+//! [`Reader::worksheet_formula_expr`]. Formula strings can also be parsed directly,
+//! in Excel A1 syntax with [`parse_formula`] or in OpenDocument syntax with
+//! [`parse_open_formula`].
+//!
+//! [`Reader::worksheet_formula_expr`]: crate::Reader::worksheet_formula_expr
+
+use std::fmt;
+
+use crate::{CellErrorType, TableMetadata};
+
+/// An error while parsing a formula
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaError {
+    /// Unexpected character
+    Unexpected {
+        /// Character position in the formula
+        pos: usize,
+        /// Character found
+        found: char,
+    },
+    /// Unexpected end of formula
+    Eof,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormulaError::Unexpected { pos, found } => {
+                write!(f, "Unexpected character '{}' at position {}", found, pos)
+            }
+            FormulaError::Eof => write!(f, "Unexpected end of formula"),
+        }
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+/// A parsed formula expression
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Expr {
+    /// Number constant
+    Number(f64),
+    /// String constant
+    String(String),
+    /// Boolean constant
+    Bool(bool),
+    /// Error constant
+    Error(CellErrorType),
+    /// Cell or range reference
+    Reference(Reference),
+    /// Defined name
+    Name(String),
+    /// Function call, with its upper case name
+    Function {
+        /// Function name, without any `_xlfn.` prefix
+        name: String,
+        /// Arguments
+        args: Vec<Expr>,
+    },
+    /// Prefix or postfix operation
+    Unary(UnaryOp, Box<Expr>),
+    /// Binary operation
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Array constant, row by row
+    Array(Vec<Vec<Expr>>),
+    /// Omitted function argument, or empty cell
+    #[default]
+    Missing,
+}
+
+/// A unary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `+x`
+    Plus,
+    /// `-x`
+    Minus,
+    /// `x%`
+    Percent,
+}
+
+/// A binary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `^`
+    Pow,
+    /// `&`
+    Concat,
+    /// `=`
+    Eq,
+    /// `<>`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `:` between two references which are not plain cells
+    Range,
+    /// Intersection, a space in Excel and `!` in OpenDocument
+    Intersect,
+    /// Union, a `,` between parentheses in Excel and `~` in OpenDocument
+    Union,
+}
+
+impl BinaryOp {
+    /// Binding power, the higher the tighter
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => 1,
+            BinaryOp::Concat => 2,
+            BinaryOp::Add | BinaryOp::Sub => 3,
+            BinaryOp::Mul | BinaryOp::Div => 4,
+            BinaryOp::Pow => 5,
+            BinaryOp::Union => 8,
+            BinaryOp::Intersect => 9,
+            BinaryOp::Range => 10,
+        }
+    }
+}
+
+/// Precedence of the prefix `+` and `-`, which bind tighter than `^` in Excel
+const PREFIX_PRECEDENCE: u8 = 7;
+/// Precedence of the postfix `%`
+const PERCENT_PRECEDENCE: u8 = 6;
+
+/// A cell, a range, whole columns or whole rows, possibly on other sheets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// Sheet name, `None` for the formula sheet
+    pub sheet: Option<String>,
+    /// Last sheet of a 3-D reference, like `Sheet3` in `Sheet1:Sheet3!A1`
+    pub last_sheet: Option<String>,
+    /// Top left corner
+    pub start: CellRef,
+    /// Bottom right corner, equal to `start` for a single cell
+    pub end: CellRef,
+}
+
+/// One corner of a [`Reference`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRef {
+    /// 0-based row, `None` for whole columns
+    pub row: Option<u32>,
+    /// 0-based column, `None` for whole rows
+    pub col: Option<u32>,
+    /// Is the row absolute (`$1`)
+    pub row_absolute: bool,
+    /// Is the column absolute (`$A`)
+    pub col_absolute: bool,
+}
+
+/// Writes the formula in Excel A1 syntax, without its leading `=`
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Writer {
+            f,
+            dialect: Dialect::Excel,
+        }
+        .expr(self)
+    }
+}
+
+impl Expr {
+    /// Writes the formula in OpenDocument syntax, with its `of:=` prefix
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::formula::parse_formula;
+    ///
+    /// let expr = parse_formula("SUM(Sheet2!A1:B3, 1)").unwrap();
+    /// assert_eq!(expr.to_open_formula(), "of:=SUM([$Sheet2.A1:.B3];1)");
+    /// ```
+    pub fn to_open_formula(&self) -> String {
+        struct Open<'a>(&'a Expr);
+        impl fmt::Display for Open<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                Writer {
+                    f,
+                    dialect: Dialect::OpenFormula,
+                }
+                .expr(self.0)
+            }
+        }
+        format!("of:={}", Open(self))
+    }
+
+    /// Binding power of the outermost operation
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(op, ..) => op.precedence(),
+            Expr::Unary(UnaryOp::Percent, _) => PERCENT_PRECEDENCE,
+            Expr::Unary(..) => PREFIX_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+}
+
+/// Translates an OpenDocument formula into Excel A1 syntax, without its leading `=`
+///
+/// # Examples
+/// ```
+/// use calamine::formula::open_formula_to_excel;
+///
+/// let formula = open_formula_to_excel("of:=SUM([.A1:.B3];[$'My sheet'.C1])").unwrap();
+/// assert_eq!(formula, "SUM(A1:B3,'My sheet'!C1)");
+/// ```
+pub fn open_formula_to_excel(formula: &str) -> Result<String, FormulaError> {
+    parse_open_formula(formula).map(|expr| expr.to_string())
+}
+
+/// Translates an Excel A1 formula into OpenDocument syntax, with its `of:=` prefix
+pub fn excel_to_open_formula(formula: &str) -> Result<String, FormulaError> {
+    parse_formula(formula).map(|expr| expr.to_open_formula())
+}
+
+/// Excel functions which are not part of OpenFormula, written with a `COM.MICROSOFT.`
+/// prefix in OpenDocument formulas
+const MICROSOFT_FUNCTIONS: &[&str] = &[
+    "AGGREGATE",
+    "BETA.DIST",
+    "BETA.INV",
+    "BINOM.DIST",
+    "BINOM.INV",
+    "CEILING.MATH",
+    "CEILING.PRECISE",
+    "CHISQ.DIST",
+    "CHISQ.DIST.RT",
+    "CHISQ.INV",
+    "CHISQ.INV.RT",
+    "CHISQ.TEST",
+    "CONCAT",
+    "CONFIDENCE.NORM",
+    "CONFIDENCE.T",
+    "COVARIANCE.P",
+    "COVARIANCE.S",
+    "ENCODEURL",
+    "ERF.PRECISE",
+    "ERFC.PRECISE",
+    "EXPON.DIST",
+    "F.DIST",
+    "F.DIST.RT",
+    "F.INV",
+    "F.INV.RT",
+    "F.TEST",
+    "FILTER",
+    "FILTERXML",
+    "FLOOR.MATH",
+    "FLOOR.PRECISE",
+    "FORECAST.ETS",
+    "FORECAST.ETS.CONFINT",
+    "FORECAST.ETS.SEASONALITY",
+    "FORECAST.ETS.STAT",
+    "FORECAST.LINEAR",
+    "GAMMA.DIST",
+    "GAMMA.INV",
+    "GAMMALN.PRECISE",
+    "HYPGEOM.DIST",
+    "IFS",
+    "ISO.CEILING",
+    "LET",
+    "LOGNORM.DIST",
+    "LOGNORM.INV",
+    "MAXIFS",
+    "MINIFS",
+    "MODE.MULT",
+    "MODE.SNGL",
+    "NEGBINOM.DIST",
+    "NETWORKDAYS.INTL",
+    "NORM.DIST",
+    "NORM.INV",
+    "NORM.S.DIST",
+    "NORM.S.INV",
+    "PERCENTILE.EXC",
+    "PERCENTILE.INC",
+    "PERCENTRANK.EXC",
+    "PERCENTRANK.INC",
+    "POISSON.DIST",
+    "QUARTILE.EXC",
+    "QUARTILE.INC",
+    "RANDARRAY",
+    "RANK.AVG",
+    "RANK.EQ",
+    "SEQUENCE",
+    "SORT",
+    "SORTBY",
+    "STDEV.P",
+    "STDEV.S",
+    "SWITCH",
+    "T.DIST",
+    "T.DIST.2T",
+    "T.DIST.RT",
+    "T.INV",
+    "T.INV.2T",
+    "T.TEST",
+    "TEXTJOIN",
+    "UNIQUE",
+    "VAR.P",
+    "VAR.S",
+    "WEBSERVICE",
+    "WEIBULL.DIST",
+    "WORKDAY.INTL",
+    "XLOOKUP",
+    "XMATCH",
+];
+
+/// Writes expressions in a formula dialect
+struct Writer<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    dialect: Dialect,
+}
+
+impl Writer<'_, '_> {
+    fn expr(&mut self, expr: &Expr) -> fmt::Result {
+        match expr {
+            Expr::Number(n) => write!(self.f, "{}", n),
+            Expr::String(s) => write!(self.f, "\"{}\"", s.replace('"', "\"\"")),
+            Expr::Bool(b) => match self.dialect {
+                Dialect::Excel => write!(self.f, "{}", if *b { "TRUE" } else { "FALSE" }),
+                // OpenDocument has no boolean constants
+                Dialect::OpenFormula => write!(self.f, "{}()", if *b { "TRUE" } else { "FALSE" }),
+            },
+            Expr::Error(e) => write!(self.f, "{}", e),
+            Expr::Reference(r) => self.reference(r),
+            Expr::Name(name) => write!(self.f, "{}", name),
+            Expr::Function { name, args } => {
+                if self.dialect == Dialect::OpenFormula
+                    && MICROSOFT_FUNCTIONS.contains(&name.as_str())
+                {
+                    write!(self.f, "COM.MICROSOFT.")?;
+                }
+                write!(self.f, "{}(", name)?;
+                self.list(args, self.separator())?;
+                write!(self.f, ")")
+            }
+            Expr::Unary(UnaryOp::Percent, e) => {
+                self.operand(e, e.precedence() < PERCENT_PRECEDENCE)?;
+                write!(self.f, "%")
+            }
+            Expr::Unary(op, e) => {
+                write!(self.f, "{}", if *op == UnaryOp::Minus { "-" } else { "+" })?;
+                self.operand(e, e.precedence() < PREFIX_PRECEDENCE)
+            }
+            Expr::Binary(BinaryOp::Union, l, r) if self.dialect == Dialect::Excel => {
+                // a bare ',' would separate function arguments
+                write!(self.f, "(")?;
+                self.expr(l)?;
+                write!(self.f, ",")?;
+                self.expr(r)?;
+                write!(self.f, ")")
+            }
+            Expr::Binary(op, l, r) => {
+                let precedence = op.precedence();
+                self.operand(l, l.precedence() < precedence)?;
+                let op = match (op, self.dialect) {
+                    (BinaryOp::Add, _) => "+",
+                    (BinaryOp::Sub, _) => "-",
+                    (BinaryOp::Mul, _) => "*",
+                    (BinaryOp::Div, _) => "/",
+                    (BinaryOp::Pow, _) => "^",
+                    (BinaryOp::Concat, _) => "&",
+                    (BinaryOp::Eq, _) => "=",
+                    (BinaryOp::Ne, _) => "<>",
+                    (BinaryOp::Lt, _) => "<",
+                    (BinaryOp::Le, _) => "<=",
+                    (BinaryOp::Gt, _) => ">",
+                    (BinaryOp::Ge, _) => ">=",
+                    (BinaryOp::Range, _) => ":",
+                    (BinaryOp::Intersect, Dialect::Excel) => " ",
+                    (BinaryOp::Intersect, Dialect::OpenFormula) => "!",
+                    (BinaryOp::Union, _) => "~",
+                };
+                write!(self.f, "{}", op)?;
+                // all binary operators are left associative
+                self.operand(r, r.precedence() <= precedence)
+            }
+            Expr::Array(rows) => {
+                let (col_sep, row_sep) = match self.dialect {
+                    Dialect::Excel => (",", ";"),
+                    Dialect::OpenFormula => (";", "|"),
+                };
+                write!(self.f, "{{")?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.f, "{}", row_sep)?;
+                    }
+                    self.list(row, col_sep)?;
+                }
+                write!(self.f, "}}")
+            }
+            Expr::Missing => Ok(()),
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self.dialect {
+            Dialect::Excel => ",",
+            Dialect::OpenFormula => ";",
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr], separator: &str) -> fmt::Result {
+        for (i, e) in exprs.iter().enumerate() {
+            if i > 0 {
+                write!(self.f, "{}", separator)?;
+            }
+            self.expr(e)?;
+        }
+        Ok(())
+    }
+
+    fn operand(&mut self, expr: &Expr, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(self.f, "(")?;
+            self.expr(expr)?;
+            write!(self.f, ")")
+        } else {
+            self.expr(expr)
+        }
+    }
+
+    fn reference(&mut self, r: &Reference) -> fmt::Result {
+        match self.dialect {
+            Dialect::Excel => {
+                if let Some(sheet) = &r.sheet {
+                    let sheets = match &r.last_sheet {
+                        Some(last) => format!("{}:{}", sheet, last),
+                        None => sheet.clone(),
+                    };
+                    let plain = r.last_sheet.iter().chain(Some(sheet)).all(|s| {
+                        s.chars()
+                            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.'))
+                            && matches!(parse_formula(s), Ok(Expr::Name(_)))
+                    });
+                    if plain {
+                        write!(self.f, "{}!", sheets)?;
+                    } else {
+                        write!(self.f, "'{}'!", sheets.replace('\'', "''"))?;
+                    }
+                }
+                self.cell(&r.start)?;
+                if r.start != r.end || r.start.row.is_none() || r.start.col.is_none() {
+                    write!(self.f, ":")?;
+                    self.cell(&r.end)?;
+                }
+                Ok(())
+            }
+            Dialect::OpenFormula => {
+                write!(self.f, "[")?;
+                self.open_sheet(r.sheet.as_deref())?;
+                self.cell(&r.start)?;
+                let whole = r.start.row.is_none() || r.start.col.is_none();
+                if r.start != r.end || whole || r.last_sheet.is_some() {
+                    write!(self.f, ":")?;
+                    self.open_sheet(r.last_sheet.as_deref())?;
+                    self.cell(&r.end)?;
+                }
+                write!(self.f, "]")
+            }
+        }
+    }
+
+    /// Writes the sheet prefix of an OpenDocument reference
+    fn open_sheet(&mut self, sheet: Option<&str>) -> fmt::Result {
+        match sheet {
+            None => write!(self.f, "."),
+            Some(s) if s.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                write!(self.f, "${}.", s)
+            }
+            Some(s) => write!(self.f, "$'{}'.", s.replace('\'', "''")),
+        }
+    }
+
+    fn cell(&mut self, cell: &CellRef) -> fmt::Result {
+        if let Some(col) = cell.col {
+            if cell.col_absolute {
+                write!(self.f, "$")?;
+            }
+            let mut letters = Vec::new();
+            let mut col = col + 1;
+            while col > 0 {
+                letters.push((b'A' + ((col - 1) % 26) as u8) as char);
+                col = (col - 1) / 26;
+            }
+            write!(self.f, "{}", letters.iter().rev().collect::<String>())?;
+        }
+        if let Some(row) = cell.row {
+            if cell.row_absolute {
+                write!(self.f, "$")?;
+            }
+            write!(self.f, "{}", row + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Error(CellErrorType),
+    Reference(Reference),
+    Name(String),
+    Function(String),
+    Op(&'static str),
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    Semicolon,
+}
+
+const ERRORS: &[(&str, CellErrorType)] = &[
+    ("#DIV/0!", CellErrorType::Div0),
+    ("#N/A", CellErrorType::NA),
+    ("#NAME?", CellErrorType::Name),
+    ("#NULL!", CellErrorType::Null),
+    ("#NUM!", CellErrorType::Num),
+    ("#REF!", CellErrorType::Ref),
+    ("#VALUE!", CellErrorType::Value),
+    ("#GETTING_DATA", CellErrorType::GettingData),
+    ("#DATA!", CellErrorType::GettingData),
+];
+
+const OPERATORS: &[&str] = &[
+    "<>", "<=", ">=", "+", "-", "*", "/", "^", "&", "=", "<", ">", "%", ":",
+];
+
+/// Formula syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    /// Excel A1 syntax: `SUM(Sheet2!A1:B3, 1)`
+    Excel,
+    /// OpenDocument syntax: `SUM([$Sheet2.A1:.B3]; 1)`
+    OpenFormula,
+}
+
+/// Parses a formula in Excel A1 syntax, with or without its leading `=`
+///
+/// # Examples
+/// ```
+/// use calamine::formula::{parse_formula, Expr};
+///
+/// let expr = parse_formula("=SUM(Sheet2!A1:B3)").unwrap();
+/// assert!(matches!(expr, Expr::Function { name, .. } if name == "SUM"));
+/// ```
+pub fn parse_formula(formula: &str) -> Result<Expr, FormulaError> {
+    parse(formula, Dialect::Excel)
+}
+
+/// Parses a formula in OpenDocument syntax, with or without its `of:=` prefix
+///
+/// Formulas of the legacy `oooc:=` and `msoxl:=` namespaces are read in their own
+/// syntax. Functions stored with a `COM.MICROSOFT.` prefix get their Excel name.
+///
+/// # Examples
+/// ```
+/// use calamine::formula::{parse_formula, parse_open_formula};
+///
+/// assert_eq!(
+///     parse_open_formula("of:=SUM([$Sheet2.A1:.B3]; 1)"),
+///     parse_formula("SUM(Sheet2!A1:B3, 1)")
+/// );
+/// ```
+pub fn parse_open_formula(formula: &str) -> Result<Expr, FormulaError> {
+    match namespace(formula) {
+        Some((formula, dialect)) => parse(formula, dialect),
+        None => parse(formula, Dialect::OpenFormula),
+    }
+}
+
+/// Strips the namespace prefix of an OpenDocument formula, returning its dialect
+///
+/// Besides OpenFormula `of:`, formulas may use the legacy OpenOffice.org `oooc:`
+/// syntax, close to OpenFormula, or the Excel `msoxl:` one.
+fn namespace(formula: &str) -> Option<(&str, Dialect)> {
+    let formula = formula.trim_start();
+    [
+        ("of:", Dialect::OpenFormula),
+        ("oooc:", Dialect::OpenFormula),
+        ("msoxl:", Dialect::Excel),
+    ]
+    .into_iter()
+    .find_map(|(ns, dialect)| {
+        let prefix = formula.get(..ns.len())?;
+        prefix
+            .eq_ignore_ascii_case(ns)
+            .then(|| (&formula[ns.len()..], dialect))
+    })
+}
+
+/// Parses the formula of a defined name, as returned by [`Reader::defined_names`]
+///
+/// Excel formats use the A1 syntax, while OpenDocument names are either formulas
+/// or range addresses like `$Sheet1.$A$1:.$B$2`.
+///
+/// [`Reader::defined_names`]: crate::Reader::defined_names
+pub fn parse_defined_name(formula: &str) -> Result<Expr, FormulaError> {
+    if namespace(formula).is_some() {
+        return parse_open_formula(formula);
+    }
+    // OpenDocument addresses separate sheets with '.', which is also valid in Excel names
+    if formula.contains('.') && !formula.contains('!') {
+        if let Ok(expr) = parse_open_formula(&format!("[{}]", formula)) {
+            return Ok(expr);
+        }
+    }
+    parse_formula(formula)
+}
+
+/// Replaces the structured references to tables of an Excel formula, like `Sales[Amount]`
+/// or `Sales[[#This Row],[Amount]]`, with the A1 references of their cells
+///
+/// `sheet` and `cell` locate the formula: they resolve `#This Row` and the references
+/// omitting the table name, which are relative to the table holding the formula. Unknown
+/// columns and empty areas give `#REF!`, like in Excel. References to unknown tables are
+/// kept as is.
+///
+/// # Examples
+/// ```
+/// use calamine::formula::resolve_structured_references;
+/// use calamine::{Dimensions, TableColumn, TableMetadata};
+///
+/// let table = TableMetadata {
+///     name: "Sales".to_string(),
+///     sheet_name: "Sheet1".to_string(),
+///     range: Dimensions::new((0, 0), (3, 1)),
+///     header_row_count: 1,
+///     columns: ["Item", "Amount"]
+///         .iter()
+///         .map(|name| TableColumn {
+///             name: name.to_string(),
+///             ..TableColumn::default()
+///         })
+///         .collect(),
+///     ..TableMetadata::default()
+/// };
+/// let tables = [table];
+/// let resolve = |f| resolve_structured_references(f, &tables, "Sheet1", (2, 2));
+/// assert_eq!(resolve("SUM(Sales[Amount])"), "SUM($B$2:$B$4)");
+/// assert_eq!(resolve("Sales[[#This Row],[Amount]]*2"), "$B$3*2");
+/// assert_eq!(resolve("COUNTA(Sales[#Headers])"), "COUNTA($A$1:$B$1)");
+/// ```
+pub fn resolve_structured_references(
+    formula: &str,
+    tables: &[TableMetadata],
+    sheet: &str,
+    cell: (u32, u32),
+) -> String {
+    let chars: Vec<char> = formula.chars().collect();
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '\\');
+    let mut resolved = String::with_capacity(formula.len());
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        match c {
+            '"' | '\'' => {
+                // strings and quoted sheet names, with doubled quotes
+                let start = pos;
+                pos += 1;
+                while pos < chars.len() {
+                    pos += 1;
+                    if chars[pos - 1] == c {
+                        if chars.get(pos) != Some(&c) {
+                            break;
+                        }
+                        pos += 1;
+                    }
+                }
+                resolved.extend(&chars[start..pos]);
+                continue;
+            }
+            c if is_name_char(c) => {
+                let start = pos;
+                while pos < chars.len() && is_name_char(chars[pos]) {
+                    pos += 1;
+                }
+                let name: String = chars[start..pos].iter().collect();
+                let table = tables.iter().find(|t| t.name.eq_ignore_ascii_case(&name));
+                match (table, chars.get(pos)) {
+                    (Some(table), Some('[')) => {
+                        if let Some((specifier, end)) = parse_specifier(&chars, pos) {
+                            resolved.push_str(&resolve_specifier(table, &specifier, sheet, cell));
+                            pos = end;
+                            continue;
+                        }
+                    }
+                    // a table name alone refers to its data, it cannot be a function or a sheet
+                    (Some(table), next) if !matches!(next, Some('(' | '!')) => {
+                        let data = Specifier::default();
+                        resolved.push_str(&resolve_specifier(table, &data, sheet, cell));
+                        continue;
+                    }
+                    // keeps the brackets of unknown tables
+                    (None, Some('[')) => {
+                        if let Some((_, end)) = parse_specifier(&chars, pos) {
+                            resolved.push_str(&name);
+                            resolved.extend(&chars[pos..end]);
+                            pos = end;
+                            continue;
+                        }
+                    }
+                    _ => (),
+                }
+                resolved.push_str(&name);
+                continue;
+            }
+            '[' => {
+                // the table holding the formula, whose name is omitted
+                let table = tables.iter().find(|t| {
+                    t.sheet_name == sheet
+                        && t.range.start <= cell
+                        && cell.0 <= t.range.end.0
+                        && cell.1 <= t.range.end.1
+                        && cell.1 >= t.range.start.1
+                });
+                if let (Some(table), Some((specifier, end))) = (table, parse_specifier(&chars, pos))
+                {
+                    // external workbooks also start with '[', like `[1]Sheet1!A1`
+                    let known = specifier.columns.iter().all(|(first, last)| {
+                        [first, last]
+                            .iter()
+                            .all(|c| table.columns.iter().any(|t| t.name.eq_ignore_ascii_case(c)))
+                    });
+                    if known && (specifier.columns.is_some() || !specifier.items.is_empty()) {
+                        resolved.push_str(&resolve_specifier(table, &specifier, sheet, cell));
+                        pos = end;
+                        continue;
+                    }
+                }
+            }
+            _ => (),
+        }
+        resolved.push(c);
+        pos += 1;
+    }
+    resolved
+}
+
+/// Rows of a table selected by a structured reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpecialItem {
+    All,
+    Data,
+    Headers,
+    Totals,
+    ThisRow,
+}
+
+/// The part between brackets of a structured reference
+#[derive(Debug, Default)]
+struct Specifier {
+    /// Selected rows, the data rows when empty
+    items: Vec<SpecialItem>,
+    /// First and last selected columns, all the columns when `None`
+    columns: Option<(String, String)>,
+}
+
+/// Parses a structured reference specifier starting at the '[' at `pos`, like `[Amount]`,
+/// `[@Amount]`, `[#Totals]` or `[[#Headers],[Item]:[Amount]]`
+///
+/// Returns the specifier and the position after its closing ']'.
+fn parse_specifier(chars: &[char], mut pos: usize) -> Option<(Specifier, usize)> {
+    // reads a name up to its closing ']', `'` escaping special characters
+    fn item(chars: &[char], pos: &mut usize) -> Option<String> {
+        let mut name = String::new();
+        loop {
+            match *chars.get(*pos)? {
+                ']' => {
+                    *pos += 1;
+                    return Some(name);
+                }
+                '\'' => {
+                    name.push(*chars.get(*pos + 1)?);
+                    *pos += 2;
+                }
+                c => {
+                    name.push(c);
+                    *pos += 1;
+                }
+            }
+        }
+    }
+    fn special(name: &str) -> Option<SpecialItem> {
+        match name.trim().to_ascii_uppercase().as_str() {
+            "#ALL" => Some(SpecialItem::All),
+            "#DATA" => Some(SpecialItem::Data),
+            "#HEADERS" => Some(SpecialItem::Headers),
+            "#TOTALS" => Some(SpecialItem::Totals),
+            "#THIS ROW" => Some(SpecialItem::ThisRow),
+            _ => None,
+        }
+    }
+    let skip_spaces = |pos: &mut usize| {
+        while chars.get(*pos) == Some(&' ') {
+            *pos += 1;
+        }
+    };
+
+    let mut specifier = Specifier::default();
+    pos += 1;
+    if chars.get(pos) == Some(&'@') {
+        specifier.items.push(SpecialItem::ThisRow);
+        pos += 1;
+    }
+    if chars.get(pos) != Some(&'[') {
+        // simple form: `[]`, `[Amount]`, `[#Totals]`, `[@Amount]` or `[@]`
+        let name = item(chars, &mut pos)?;
+        match special(&name) {
+            Some(s) => specifier.items.push(s),
+            None if name.is_empty() => (),
+            None => specifier.columns = Some((name.clone(), name)),
+        }
+        return Some((specifier, pos));
+    }
+    loop {
+        skip_spaces(&mut pos);
+        if chars.get(pos) != Some(&'[') {
+            return None;
+        }
+        pos += 1;
+        let name = item(chars, &mut pos)?;
+        match special(&name) {
+            Some(s) => specifier.items.push(s),
+            None => {
+                let mut last = name.clone();
+                if chars.get(pos) == Some(&':') && chars.get(pos + 1) == Some(&'[') {
+                    pos += 2;
+                    last = item(chars, &mut pos)?;
+                }
+                specifier.columns = Some((name, last));
+            }
+        }
+        skip_spaces(&mut pos);
+        match chars.get(pos)? {
+            ',' => pos += 1,
+            ']' => return Some((specifier, pos + 1)),
+            _ => return None,
+        }
+    }
+}
+
+/// Writes the A1 reference of the cells of `table` selected by `specifier`, or `#REF!`
+fn resolve_specifier(
+    table: &TableMetadata,
+    specifier: &Specifier,
+    sheet: &str,
+    cell: (u32, u32),
+) -> String {
+    let range = table.range;
+    let data = table.data_dimensions();
+    let headers = (range.start.0, data.start.0.wrapping_sub(1));
+    let totals = (range.end.0 + 1 - table.totals_row_count, range.end.0);
+    let mut rows: Option<(u32, u32)> = None;
+    let items = match specifier.items.is_empty() {
+        true => &[SpecialItem::Data][..],
+        false => &specifier.items[..],
+    };
+    for item in items {
+        let (first, last) = match item {
+            SpecialItem::All => (range.start.0, range.end.0),
+            SpecialItem::Data => (data.start.0, data.end.0),
+            SpecialItem::Headers if table.header_row_count > 0 => headers,
+            SpecialItem::Totals if table.totals_row_count > 0 => totals,
+            SpecialItem::ThisRow => (cell.0, cell.0),
+            _ => return "#REF!".to_string(),
+        };
+        if first > last
+            || (*item == SpecialItem::ThisRow && (first < data.start.0 || last > data.end.0))
+        {
+            return "#REF!".to_string();
+        }
+        rows = Some(match rows {
+            Some((f, l)) => (f.min(first), l.max(last)),
+            None => (first, last),
+        });
+    }
+    let column = |name: &str| {
+        table
+            .columns
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+            .map(|i| range.start.1 + i as u32)
+    };
+    let columns = match &specifier.columns {
+        Some((first, last)) => match (column(first), column(last)) {
+            (Some(f), Some(l)) => (f.min(l), f.max(l)),
+            _ => return "#REF!".to_string(),
+        },
+        None => (range.start.1, range.end.1),
+    };
+    let Some((first_row, last_row)) = rows else {
+        return "#REF!".to_string();
+    };
+    let corner = |row, col| CellRef {
+        row: Some(row),
+        col: Some(col),
+        row_absolute: true,
+        col_absolute: true,
+    };
+    Expr::Reference(Reference {
+        sheet: Some(table.sheet_name.clone()).filter(|s| s != sheet),
+        last_sheet: None,
+        start: corner(first_row, columns.0),
+        end: corner(last_row, columns.1),
+    })
+    .to_string()
+}
+
+fn parse(formula: &str, dialect: Dialect) -> Result<Expr, FormulaError> {
+    let chars: Vec<char> = formula.chars().collect();
+    let start = match chars.iter().position(|c| !c.is_whitespace()) {
+        Some(p) if chars[p] == '=' => p + 1,
+        _ => 0,
+    };
+    let tokens = Lexer {
+        chars: &chars,
+        pos: start,
+        dialect,
+    }
+    .tokenize()?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        chars: &chars,
+        pos: 0,
+    };
+    let expr = parser.expr(0)?;
+    if parser.pos < tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+struct Lexer<'a> {
+    chars: &'a [char],
+    pos: usize,
+    dialect: Dialect,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn unexpected(&self) -> FormulaError {
+        match self.peek() {
+            Some(found) => FormulaError::Unexpected {
+                pos: self.pos,
+                found,
+            },
+            None => FormulaError::Eof,
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i).map(|p| p.to_ascii_uppercase()) == Some(c))
+    }
+
+    /// Splits the formula into tokens, along with their character position
+    fn tokenize(mut self) -> Result<Vec<(usize, Token)>, FormulaError> {
+        let mut tokens: Vec<(usize, Token)> = Vec::new();
+        let mut space = false;
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            let token = match c {
+                c if c.is_whitespace() => {
+                    self.pos += 1;
+                    space = true;
+                    continue;
+                }
+                '"' => Token::String(self.quoted('"')?),
+                '#' => Token::Error(self.error()?),
+                '[' if self.dialect == Dialect::OpenFormula => self.bracket_reference()?,
+                '[' if self.dialect == Dialect::Excel => self.external_reference()?,
+                '$' if self.dialect == Dialect::OpenFormula && self.starts_with("$$") => {
+                    // `$$Name` is a named expression
+                    self.pos += 2;
+                    self.word()?
+                }
+                '\'' if self.dialect == Dialect::Excel => {
+                    let sheets = self.quoted('\'')?;
+                    if self.peek() != Some('!') {
+                        return Err(self.unexpected());
+                    }
+                    self.pos += 1;
+                    // a sheet name cannot contain ':'
+                    let (sheet, last_sheet) = match sheets.split_once(':') {
+                        Some((first, last)) => (first.to_string(), Some(last.to_string())),
+                        None => (sheets, None),
+                    };
+                    self.sheet_reference(sheet, last_sheet)?
+                }
+                c if self.dialect == Dialect::Excel
+                    && (c.is_ascii_digit() || c == '.' || c == '$') =>
+                {
+                    match self.try_reference(None) {
+                        Some(r) => Token::Reference(r),
+                        None if c == '$' => return Err(self.unexpected()),
+                        None => self.number()?,
+                    }
+                }
+                c if c.is_ascii_digit() || c == '.' => self.number()?,
+                c if c.is_alphanumeric() || c == '_' || c == '\\' => self.word()?,
+                _ => self.punctuation()?,
+            };
+            // in Excel, a space between two references is the intersection operator
+            if space
+                && self.dialect == Dialect::Excel
+                && matches!(
+                    tokens.last(),
+                    Some((_, Token::Reference(_) | Token::Name(_) | Token::CloseParen))
+                )
+                && matches!(
+                    token,
+                    Token::Reference(_) | Token::Name(_) | Token::Function(_) | Token::OpenParen
+                )
+            {
+                tokens.push((start - 1, Token::Op(" ")));
+            }
+            space = false;
+            tokens.push((start, token));
+        }
+        Ok(tokens)
+    }
+
+    /// Reads an operator or a separator
+    fn punctuation(&mut self) -> Result<Token, FormulaError> {
+        let token = match (self.peek(), self.dialect) {
+            (Some('('), _) => Token::OpenParen,
+            (Some(')'), _) => Token::CloseParen,
+            (Some('{'), _) => Token::OpenBrace,
+            (Some('}'), _) => Token::CloseBrace,
+            (Some(','), Dialect::Excel) => Token::Comma,
+            (Some(';'), Dialect::Excel) => Token::Semicolon,
+            (Some(';'), Dialect::OpenFormula) => Token::Comma,
+            (Some('|'), Dialect::OpenFormula) => Token::Semicolon,
+            (Some('~'), Dialect::OpenFormula) => Token::Op("~"),
+            (Some('!'), Dialect::OpenFormula) => Token::Op("!"),
+            _ => {
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| self.starts_with(op))
+                    .ok_or_else(|| self.unexpected())?;
+                self.pos += op.len();
+                return Ok(Token::Op(op));
+            }
+        };
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Reads a string quoted with `quote`, a doubled quote being an escaped one
+    fn quoted(&mut self, quote: char) -> Result<String, FormulaError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(FormulaError::Eof),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    if self.peek() == Some(quote) {
+                        s.push(quote);
+                        self.pos += 1;
+                    } else {
+                        return Ok(s);
+                    }
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn error(&mut self) -> Result<CellErrorType, FormulaError> {
+        let (s, e) = ERRORS
+            .iter()
+            .find(|(s, _)| self.starts_with(s))
+            .ok_or_else(|| self.unexpected())?;
+        self.pos += s.len();
+        Ok(e.clone())
+    }
+
+    fn number(&mut self) -> Result<Token, FormulaError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let sign = usize::from(matches!(self.peek_at(1), Some('+' | '-')));
+            if matches!(self.peek_at(1 + sign), Some(c) if c.is_ascii_digit()) {
+                self.pos += 1 + sign;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            }
+        }
+        let s: String = self.chars[start..self.pos].iter().collect();
+        s.parse().map(Token::Number).map_err(|_| {
+            self.pos = start;
+            self.unexpected()
+        })
+    }
+
+    /// Reads an identifier: a function, a sheet prefix, a reference, a boolean or a name
+    fn word(&mut self) -> Result<Token, FormulaError> {
+        let start = self.pos;
+        if self.dialect == Dialect::Excel {
+            if let Some(r) = self.try_reference(None) {
+                // a reference followed by '(' is a function like LOG10
+                if self.peek() != Some('(') {
+                    return Ok(Token::Reference(r));
+                }
+                self.pos = start;
+            }
+        }
+        let word = self.identifier();
+        match self.peek() {
+            Some('(') => {
+                let name = word.to_ascii_uppercase();
+                let name = ["_XLFN._XLWS.", "_XLFN.", "COM.MICROSOFT."]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .unwrap_or(&name);
+                Ok(Token::Function(name.to_string()))
+            }
+            Some('!') if self.dialect == Dialect::Excel => {
+                self.pos += 1;
+                self.sheet_reference(word, None)
+            }
+            Some(':') if self.dialect == Dialect::Excel => {
+                // 3-D reference `Sheet1:Sheet3!A1`
+                let colon = self.pos;
+                self.pos += 1;
+                let last = self.identifier();
+                if !last.is_empty() && self.peek() == Some('!') {
+                    self.pos += 1;
+                    return self.sheet_reference(word, Some(last));
+                }
+                self.pos = colon;
+                Ok(Token::Name(word))
+            }
+            _ if word.eq_ignore_ascii_case("TRUE") => Ok(Token::Bool(true)),
+            _ if word.eq_ignore_ascii_case("FALSE") => Ok(Token::Bool(false)),
+            _ => Ok(Token::Name(word)),
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_alphanumeric() || matches!(c, '_' | '.' | '\\' | '?'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Reads the reference after a `sheet!` prefix
+    fn sheet_reference(
+        &mut self,
+        sheet: String,
+        last_sheet: Option<String>,
+    ) -> Result<Token, FormulaError> {
+        if self.peek() == Some('#') {
+            return self.error().map(Token::Error);
+        }
+        match self.try_reference(Some(sheet)) {
+            Some(r) => Ok(Token::Reference(Reference { last_sheet, ..r })),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    /// Reads a reference to an external workbook like `[1]Sheet1!A1`
+    ///
+    /// The workbook index is kept in the sheet name.
+    fn external_reference(&mut self) -> Result<Token, FormulaError> {
+        let start = self.pos;
+        while self.peek().ok_or(FormulaError::Eof)? != ']' {
+            self.pos += 1;
+        }
+        self.pos += 1;
+        let book: String = self.chars[start..self.pos].iter().collect();
+        match self.word()? {
+            Token::Reference(Reference {
+                sheet: Some(sheet),
+                last_sheet,
+                start,
+                end,
+            }) => Ok(Token::Reference(Reference {
+                sheet: Some(book + &sheet),
+                last_sheet,
+                start,
+                end,
+            })),
+            _ => {
+                self.pos = start;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    /// Reads an OpenDocument reference: `[.A1]`, `[$Sheet2.A1:.B3]` or `['My sheet'.A:.A]`
+    fn bracket_reference(&mut self) -> Result<Token, FormulaError> {
+        self.pos += 1;
+        let token = match self.bracket_part()? {
+            Ok((sheet, start)) if self.peek() == Some(':') => {
+                self.pos += 1;
+                match self.bracket_part()? {
+                    Ok((last, end)) => Token::Reference(Reference {
+                        last_sheet: last.filter(|l| Some(l) != sheet.as_ref()),
+                        sheet,
+                        start,
+                        end,
+                    }),
+                    Err(e) => Token::Error(e),
+                }
+            }
+            Ok((sheet, start)) => Token::Reference(Reference {
+                sheet,
+                last_sheet: None,
+                start,
+                end: start,
+            }),
+            Err(e) => {
+                // skip the rest of an invalid reference like `[.#REF!:.B2]`
+                while matches!(self.peek(), Some(c) if c != ']') {
+                    self.pos += 1;
+                }
+                Token::Error(e)
+            }
+        };
+        if self.peek() != Some(']') {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Reads one side of an OpenDocument reference, or a `#REF!` error
+    #[allow(clippy::type_complexity)]
+    fn bracket_part(
+        &mut self,
+    ) -> Result<Result<(Option<String>, CellRef), CellErrorType>, FormulaError> {
+        if self.peek() == Some('$') {
+            self.pos += 1;
+        }
+        let sheet = match self.peek() {
+            Some('\'') => Some(self.quoted('\'')?),
+            Some('.') => None,
+            Some('#') => return Ok(Err(self.error()?)),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if !matches!(c, '.' | ':' | ']')) {
+                    self.pos += 1;
+                }
+                Some(self.chars[start..self.pos].iter().collect())
+            }
+        };
+        if self.peek() != Some('.') {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        if self.peek() == Some('#') {
+            return Ok(Err(self.error()?));
+        }
+        match self.cell_ref() {
+            Some(cell) => Ok(Ok((sheet, cell))),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    /// Tries to read a cell, a range, whole columns (`A:B`) or whole rows (`1:2`)
+    fn try_reference(&mut self, sheet: Option<String>) -> Option<Reference> {
+        let start_pos = self.pos;
+        let start = self.cell_ref();
+        let end = if self.peek() == Some(':') {
+            self.pos += 1;
+            let end = self.cell_ref();
+            match (start, end) {
+                // column and row ranges need both sides
+                (Some(s), Some(e))
+                    if s.row.is_some() == e.row.is_some() && s.col.is_some() == e.col.is_some() =>
+                {
+                    Some(e)
+                }
+                (Some(s), _) if s.row.is_some() && s.col.is_some() => {
+                    self.pos -= 1;
+                    Some(s)
+                }
+                _ => None,
+            }
+        } else {
+            start.filter(|s| s.row.is_some() && s.col.is_some())
+        };
+        match (start, end) {
+            (Some(start), Some(end)) if !self.is_word_char() => Some(Reference {
+                sheet,
+                last_sheet: None,
+                start,
+                end,
+            }),
+            _ => {
+                self.pos = start_pos;
+                None
+            }
+        }
+    }
+
+    fn is_word_char(&self) -> bool {
+        matches!(self.peek(), Some(c) if c.is_alphanumeric() || matches!(c, '_' | '.' | '\\' | '?' | '!'))
+    }
+
+    /// Reads `$A$1`, `$A` or `$1`, leaving the position unchanged if there is none
+    fn cell_ref(&mut self) -> Option<CellRef> {
+        let start = self.pos;
+        let col_absolute = self.peek() == Some('$');
+        if col_absolute {
+            self.pos += 1;
+        }
+        let mut col: Option<u32> = None;
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_alphabetic()) {
+            let next =
+                col.map_or(0, |c| (c + 1) * 26) + (c.to_ascii_uppercase() as u32 - 'A' as u32);
+            if next >= 16_384 {
+                self.pos = start;
+                return None;
+            }
+            col = Some(next);
+            self.pos += 1;
+        }
+        let row_absolute = col.is_some() && self.peek() == Some('$');
+        if row_absolute {
+            self.pos += 1;
+        }
+        let (row_absolute, digits_start) = if col.is_none() {
+            // `$1` whole row: the '$' read as column one is the row one
+            (col_absolute, self.pos)
+        } else {
+            (row_absolute, self.pos)
+        };
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let row = if self.pos > digits_start {
+            let digits: String = self.chars[digits_start..self.pos].iter().collect();
+            match digits.parse::<u32>() {
+                Ok(r) if (1..=1_048_576).contains(&r) => Some(r - 1),
+                _ => {
+                    self.pos = start;
+                    return None;
+                }
+            }
+        } else {
+            None
+        };
+        if row.is_none() && col.is_none() {
+            self.pos = start;
+            return None;
+        }
+        Some(CellRef {
+            row,
+            col,
+            row_absolute,
+            col_absolute: col.is_some() && col_absolute,
+        })
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<&'a Token, FormulaError> {
+        let (_, t) = self.tokens.get(self.pos).ok_or(FormulaError::Eof)?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    /// Error on the current token
+    fn unexpected(&self) -> FormulaError {
+        match self.tokens.get(self.pos) {
+            Some(&(pos, _)) => FormulaError::Unexpected {
+                pos,
+                found: self.chars[pos],
+            },
+            None => FormulaError::Eof,
+        }
+    }
+
+    /// Error on the token just read
+    fn unexpected_previous(&mut self) -> FormulaError {
+        self.pos -= 1;
+        self.unexpected()
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), FormulaError> {
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err(self.unexpected_previous())
+        }
+    }
+
+    /// Pratt parser: parses operations binding tighter than `min`
+    fn expr(&mut self, min: u8) -> Result<Expr, FormulaError> {
+        let mut lhs = self.prefix()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("%")) => {
+                    if PERCENT_PRECEDENCE < min {
+                        break;
+                    }
+                    self.pos += 1;
+                    lhs = Expr::Unary(UnaryOp::Percent, Box::new(lhs));
+                    continue;
+                }
+                Some(Token::Op(op)) => match binary_op(op) {
+                    Some(op) => op,
+                    None => break,
+                },
+                _ => break,
+            };
+            let precedence = op.precedence();
+            if precedence < min {
+                break;
+            }
+            self.pos += 1;
+            // all binary operators are left associative
+            let rhs = self.expr(precedence + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn prefix(&mut self) -> Result<Expr, FormulaError> {
+        let token = self.next()?;
+        let expr = match token {
+            Token::Number(n) => Expr::Number(*n),
+            Token::String(s) => Expr::String(s.clone()),
+            Token::Bool(b) => Expr::Bool(*b),
+            Token::Error(e) => Expr::Error(e.clone()),
+            Token::Reference(r) => Expr::Reference(r.clone()),
+            Token::Name(n) => Expr::Name(n.clone()),
+            Token::Op("-") => Expr::Unary(UnaryOp::Minus, Box::new(self.expr(PREFIX_PRECEDENCE)?)),
+            Token::Op("+") => Expr::Unary(UnaryOp::Plus, Box::new(self.expr(PREFIX_PRECEDENCE)?)),
+            Token::OpenParen => {
+                let mut e = self.expr(0)?;
+                // `(A1,B2)` is a union
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    let rhs = self.expr(0)?;
+                    e = Expr::Binary(BinaryOp::Union, Box::new(e), Box::new(rhs));
+                }
+                self.expect(&Token::CloseParen)?;
+                e
+            }
+            Token::Function(name) => {
+                self.expect(&Token::OpenParen)?;
+                Expr::Function {
+                    name: name.clone(),
+                    args: self.args()?,
+                }
+            }
+            Token::OpenBrace => self.array()?,
+            _ => return Err(self.unexpected_previous()),
+        };
+        Ok(expr)
+    }
+
+    /// Parses function arguments, after the opening parenthesis
+    fn args(&mut self) -> Result<Vec<Expr>, FormulaError> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::CloseParen) {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            match self.peek() {
+                Some(Token::Comma | Token::CloseParen) => args.push(Expr::Missing),
+                _ => args.push(self.expr(0)?),
+            }
+            match self.next()? {
+                Token::Comma => (),
+                Token::CloseParen => return Ok(args),
+                _ => return Err(self.unexpected_previous()),
+            }
+        }
+    }
+
+    /// Parses an array constant, after the opening brace
+    fn array(&mut self) -> Result<Expr, FormulaError> {
+        let mut rows = vec![Vec::new()];
+        loop {
+            let value = self.expr(0)?;
+            rows.last_mut().unwrap().push(value);
+            match self.next()? {
+                Token::Comma => (),
+                Token::Semicolon => rows.push(Vec::new()),
+                Token::CloseBrace => return Ok(Expr::Array(rows)),
+                _ => return Err(self.unexpected_previous()),
+            }
+        }
+    }
+}
+
+fn binary_op(op: &str) -> Option<BinaryOp> {
+    let op = match op {
+        "+" => BinaryOp::Add,
+        "-" => BinaryOp::Sub,
+        "*" => BinaryOp::Mul,
+        "/" => BinaryOp::Div,
+        "^" => BinaryOp::Pow,
+        "&" => BinaryOp::Concat,
+        "=" => BinaryOp::Eq,
+        "<>" => BinaryOp::Ne,
+        "<" => BinaryOp::Lt,
+        "<=" => BinaryOp::Le,
+        ">" => BinaryOp::Gt,
+        ">=" => BinaryOp::Ge,
+        ":" => BinaryOp::Range,
+        " " | "!" => BinaryOp::Intersect,
+        "~" => BinaryOp::Union,
+        _ => return None,
+    };
+    Some(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dimensions, TableColumn};
+
+    fn cell(row: u32, col: u32) -> CellRef {
+        CellRef {
+            row: Some(row),
+            col: Some(col),
+            row_absolute: false,
+            col_absolute: false,
+        }
+    }
+
+    fn reference(sheet: Option<&str>, start: CellRef, end: CellRef) -> Expr {
+        Expr::Reference(Reference {
+            sheet: sheet.map(|s| s.to_string()),
+            last_sheet: None,
+            start,
+            end,
+        })
+    }
+
+    #[test]
+    fn parse_references() {
+        assert_eq!(
+            parse_formula("A1").unwrap(),
+            reference(None, cell(0, 0), cell(0, 0))
+        );
+        assert_eq!(
+            parse_formula("=Sheet2!B2:C3").unwrap(),
+            reference(Some("Sheet2"), cell(1, 1), cell(2, 2))
+        );
+        let abs = CellRef {
+            row_absolute: true,
+            col_absolute: true,
+            ..cell(0, 27)
+        };
+        assert_eq!(
+            parse_formula("'It''s'!$AB$1").unwrap(),
+            reference(Some("It's"), abs, abs)
+        );
+        let col = |c| CellRef {
+            row: None,
+            ..cell(0, c)
+        };
+        assert_eq!(
+            parse_formula("A:B").unwrap(),
+            reference(None, col(0), col(1))
+        );
+        let row = |r| CellRef {
+            col: None,
+            row_absolute: true,
+            ..cell(r, 0)
+        };
+        assert_eq!(
+            parse_formula("$2:$3").unwrap(),
+            reference(None, row(1), row(2))
+        );
+    }
+
+    #[test]
+    fn parse_operators() {
+        let n = |n| Box::new(Expr::Number(n));
+        // -2^2 is 4 in Excel
+        assert_eq!(
+            parse_formula("-2^2").unwrap(),
+            Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(Expr::Unary(UnaryOp::Minus, n(2.))),
+                n(2.)
+            )
+        );
+        assert_eq!(
+            parse_formula("1+2*3%").unwrap(),
+            Expr::Binary(
+                BinaryOp::Add,
+                n(1.),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    n(2.),
+                    Box::new(Expr::Unary(UnaryOp::Percent, n(3.)))
+                ))
+            )
+        );
+        assert_eq!(
+            parse_formula("1-2-3").unwrap(),
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Binary(BinaryOp::Sub, n(1.), n(2.))),
+                n(3.)
+            )
+        );
+    }
+
+    #[test]
+    fn parse_functions() {
+        assert_eq!(
+            parse_formula("_xlfn.CONCAT(\"a\"\"b\", , TRUE, #N/A, {1,2;3,4}, Rate)").unwrap(),
+            Expr::Function {
+                name: "CONCAT".to_string(),
+                args: vec![
+                    Expr::String("a\"b".to_string()),
+                    Expr::Missing,
+                    Expr::Bool(true),
+                    Expr::Error(CellErrorType::NA),
+                    Expr::Array(vec![
+                        vec![Expr::Number(1.), Expr::Number(2.)],
+                        vec![Expr::Number(3.), Expr::Number(4.)]
+                    ]),
+                    Expr::Name("Rate".to_string()),
+                ]
+            }
+        );
+        assert_eq!(
+            parse_formula("LOG10(1E+2)").unwrap(),
+            Expr::Function {
+                name: "LOG10".to_string(),
+                args: vec![Expr::Number(100.)]
+            }
+        );
+        assert_eq!(parse_formula("SUM(1,"), Err(FormulaError::Eof));
+    }
+
+    #[test]
+    fn parse_reference_operators() {
+        let a1 = || Box::new(reference(None, cell(0, 0), cell(0, 0)));
+        let b = || Box::new(reference(None, cell(0, 1), cell(2, 1)));
+        assert_eq!(
+            parse_formula("SUM(A1 B1:B3, (A1,B1:B3))").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![
+                    Expr::Binary(BinaryOp::Intersect, a1(), b()),
+                    Expr::Binary(BinaryOp::Union, a1(), b()),
+                ]
+            }
+        );
+        let three_d = Expr::Reference(Reference {
+            sheet: Some("Jan".to_string()),
+            last_sheet: Some("Mar".to_string()),
+            start: cell(0, 0),
+            end: cell(0, 0),
+        });
+        assert_eq!(parse_formula("Jan:Mar!A1").unwrap(), three_d);
+        assert_eq!(parse_formula("'Jan:Mar'!A1").unwrap(), three_d);
+        assert_eq!(
+            parse_formula("[1]Jan!A1").unwrap(),
+            reference(Some("[1]Jan"), cell(0, 0), cell(0, 0))
+        );
+        // spaces around operators are not intersections
+        assert_eq!(
+            parse_formula(" A1 + B1:B3 ").unwrap(),
+            Expr::Binary(BinaryOp::Add, a1(), b())
+        );
+    }
+
+    #[test]
+    fn write_formulas() {
+        for f in [
+            "SUM(A1:B3,Sheet2!$A$1,'My sheet'!A:A,Jan:Mar!$1:$2)",
+            "-(1+2)*3%-2^-2",
+            "1-2-(3-4)",
+            "IF(A1<>\"a\"\"b\",{1,2;3,4},#N/A)",
+            "SUM(A1 B1:B3,(A1,B1:B3))",
+            "OFFSET(A1,1,1):B5",
+            "'A1'!A1+'It''s'!A1+'[1]Jan'!A1",
+            "NOW(,)",
+        ] {
+            assert_eq!(parse_formula(f).unwrap().to_string(), f);
+        }
+        assert_eq!(parse_formula("= a1 + ( 2 )").unwrap().to_string(), "A1+2");
+
+        for (excel, open) in [
+            (
+                "SUM(A1:B3,Sheet2!$A$1,'My sheet'!A:A,Jan:Mar!$1:$2)",
+                "of:=SUM([.A1:.B3];[$Sheet2.$A$1];[$'My sheet'.A:.A];[$Jan.$1:$Mar.$2])",
+            ),
+            ("IF(A1,{1,2;3,4},\"\")", "of:=IF([.A1];{1;2|3;4};\"\")"),
+            (
+                "CONCAT(A1,STDEV.S(B1:B3))",
+                "of:=COM.MICROSOFT.CONCAT([.A1];COM.MICROSOFT.STDEV.S([.B1:.B3]))",
+            ),
+            (
+                "SUM(A1 B1:B3,(A1,B1:B3))",
+                "of:=SUM([.A1]![.B1:.B3];[.A1]~[.B1:.B3])",
+            ),
+        ] {
+            assert_eq!(excel_to_open_formula(excel).unwrap(), open);
+            assert_eq!(open_formula_to_excel(open).unwrap(), excel);
+        }
+        assert_eq!(
+            excel_to_open_formula("NOT(TRUE)").unwrap(),
+            "of:=NOT(TRUE())"
+        );
+        assert_eq!(
+            excel_to_open_formula("_xlfn.IFS(A1,1)").unwrap(),
+            "of:=COM.MICROSOFT.IFS([.A1];1)"
+        );
+
+        // legacy OpenOffice.org and Excel namespaces
+        assert_eq!(
+            open_formula_to_excel("oooc:=SUM([.A1:.B3];[$Sheet2.C1])").unwrap(),
+            "SUM(A1:B3,Sheet2!C1)"
+        );
+        assert_eq!(
+            open_formula_to_excel("msoxl:=SUM(A1:B3,Sheet2!C1)").unwrap(),
+            "SUM(A1:B3,Sheet2!C1)"
+        );
+        assert_eq!(
+            parse_defined_name("msoxl:=Sheet1!$A$1*2").unwrap(),
+            parse_formula("Sheet1!$A$1*2").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_defined_names() {
+        let mut abs = cell(0, 0);
+        abs.row_absolute = true;
+        abs.col_absolute = true;
+        assert_eq!(
+            parse_defined_name("Sheet1!$A$1").unwrap(),
+            reference(Some("Sheet1"), abs, abs)
+        );
+        assert_eq!(
+            parse_defined_name("$Sheet1.$A$1").unwrap(),
+            reference(Some("Sheet1"), abs, abs)
+        );
+        assert_eq!(
+            parse_defined_name("datatypes.A1:datatypes.A6").unwrap(),
+            reference(Some("datatypes"), cell(0, 0), cell(5, 0))
+        );
+        assert_eq!(
+            parse_defined_name("of:=[.A1]*2").unwrap(),
+            Expr::Binary(
+                BinaryOp::Mul,
+                Box::new(reference(None, cell(0, 0), cell(0, 0))),
+                Box::new(Expr::Number(2.))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_open_formulas() {
+        assert_eq!(
+            parse_open_formula("of:=SUM([.A1]![.B1:.B3];[$'My sheet'.A1]~[.B1:.B3])").unwrap(),
+            parse_formula("SUM(A1 B1:B3,(('My sheet'!A1,B1:B3)))").unwrap()
+        );
+        assert_eq!(
+            parse_open_formula("of:=COM.MICROSOFT.CONCAT({1;2|3;4};[.#REF!])").unwrap(),
+            parse_formula("_xlfn.CONCAT({1,2;3,4},#REF!)").unwrap()
+        );
+        assert_eq!(
+            parse_open_formula("[Jan.A1:Mar.A1]").unwrap(),
+            parse_formula("Jan:Mar!A1").unwrap()
+        );
+        assert_eq!(
+            parse_open_formula("of:=[.$A$1:.$B$2]").unwrap(),
+            parse_formula("$A$1:$B$2").unwrap()
+        );
+    }
+
+    #[test]
+    fn resolve_structured_refs() {
+        let table = TableMetadata {
+            name: "Sales".to_string(),
+            sheet_name: "Data".to_string(),
+            range: Dimensions::new((1, 1), (5, 3)),
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: ["Item", "Amount", "Tax #"]
+                .iter()
+                .map(|name| TableColumn {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let tables = [table];
+        let resolve = |f, sheet| resolve_structured_references(f, &tables, sheet, (3, 3));
+        assert_eq!(resolve("SUM(Sales[Amount])", "Data"), "SUM($C$3:$C$5)");
+        assert_eq!(resolve("sales[]+Sales", "Data"), "$B$3:$D$5+$B$3:$D$5");
+        assert_eq!(resolve("Sales[#All]", "Data"), "$B$2:$D$6");
+        assert_eq!(resolve("Sales[#Headers]", "Other"), "Data!$B$2:$D$2");
+        assert_eq!(resolve("Sales[[#Totals],[Amount]]", "Data"), "$C$6");
+        assert_eq!(
+            resolve("Sales[[#Headers],[#Data],[Item]:[Amount]]", "Data"),
+            "$B$2:$C$5"
+        );
+        assert_eq!(resolve("Sales[@Amount]*2", "Data"), "$C$4*2");
+        assert_eq!(resolve("Sales[@[Tax '#]]", "Data"), "$D$4");
+        assert_eq!(resolve("Sales[[#This Row], [Item]]", "Data"), "$B$4");
+        assert_eq!(resolve("Sales[Unknown]", "Data"), "#REF!");
+        assert_eq!(resolve("Sales[@Amount]", "Other"), "Data!$C$4");
+        // the formula is not in the table
+        assert_eq!(resolve("[@Amount]", "Other"), "[@Amount]");
+        assert_eq!(
+            resolve("[@Amount]+[1]Sheet1!A1", "Data"),
+            "$C$4+[1]Sheet1!A1"
+        );
+        // strings and sheets named like tables are kept
+        assert_eq!(
+            resolve("\"Sales[Amount]\"&Sales!A1&'Sales'!A1", "Data"),
+            "\"Sales[Amount]\"&Sales!A1&'Sales'!A1"
+        );
+        assert_eq!(resolve("Other[Amount]", "Data"), "Other[Amount]");
+    }
+}
@@ -62,31 +62,54 @@ mod utils;
 
 mod auto;
 mod cfb;
+mod crypto;
 mod datatype;
 mod formats;
 mod ods;
+mod style;
 mod xls;
 mod xlsb;
 mod xlsx;
 
 mod de;
+mod dependency;
 mod errors;
+#[cfg(feature = "eval")]
+mod eval;
+pub mod formula;
 pub mod vba;
 
 use serde::de::{Deserialize, DeserializeOwned, Deserializer};
 use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs::File;
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::io::{BufReader, Read, Seek};
 use std::ops::{Index, IndexMut};
 use std::path::Path;
 
+#[cfg(feature = "encryption")]
+pub use crate::auto::open_workbook_auto_with_password;
 pub use crate::auto::{open_workbook_auto, open_workbook_auto_from_rs, Sheets};
-pub use crate::datatype::{Data, DataRef, DataType, ExcelDateTime, ExcelDateTimeType};
-pub use crate::de::{DeError, RangeDeserializer, RangeDeserializerBuilder, ToCellDeserializer};
+pub use crate::crypto::CryptoError;
+pub use crate::datatype::{
+    Data, DataRef, DataType, DataWithFormat, ExcelDateTime, ExcelDateTimeType,
+};
+pub use crate::de::{
+    DeError, RangeDeserializer, RangeDeserializerBuilder, RowsDeserializer, ToCellDeserializer,
+};
+pub use crate::dependency::{DependencyGraph, SheetArea, SheetCell};
 pub use crate::errors::Error;
+#[cfg(feature = "eval")]
+pub use crate::eval::Evaluator;
 pub use crate::ods::{Ods, OdsError};
+pub use crate::style::{
+    Alignment, Border, BorderStyle, Borders, CellProtection, Color, Fill, FillPattern, Font,
+    HorizontalAlignment, RichText, Style, StyleId, VerticalAlignment,
+};
 pub use crate::xls::{Xls, XlsError, XlsOptions};
 pub use crate::xlsb::{Xlsb, XlsbError};
 pub use crate::xlsx::{Xlsx, XlsxError};
@@ -164,6 +187,8 @@ pub struct Metadata {
     sheets: Vec<Sheet>,
     /// Map of sheet names/sheet path within zip archive
     names: Vec<(String, String)>,
+    /// Workbook structure protection
+    protection: Protection,
 }
 
 /// Type of sheet
@@ -212,6 +237,329 @@ pub struct Sheet {
     pub typ: SheetType,
     /// Visible
     pub visible: SheetVisible,
+    /// Sheet protection
+    pub protection: Protection,
+}
+
+/// Protection of a sheet, or of the workbook structure
+///
+/// Whether a given cell is locked or hidden once its sheet is protected is
+/// part of its [`Style::protection`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Protection {
+    /// The sheet content, or the workbook structure, is locked
+    pub protected: bool,
+    /// The protection is guarded by a password
+    pub password: bool,
+}
+
+/// A cell comment, or note
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// Commented cell (row, column)
+    pub cell: (u32, u32),
+    /// Author, if known
+    pub author: Option<String>,
+    /// Text, without formatting
+    pub text: String,
+}
+
+/// A hyperlink attached to some cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperlink {
+    /// Linked cells
+    pub range: Dimensions,
+    /// External target (url, file path, mail address ...), if any
+    pub url: Option<String>,
+    /// Location within the workbook (e.g. `Sheet2!A1` or a defined name), if any
+    pub location: Option<String>,
+    /// Tooltip, if any
+    pub tooltip: Option<String>,
+}
+
+/// Properties of a row
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowProperties {
+    /// Hidden, either explicitly or as part of a collapsed group
+    pub hidden: bool,
+    /// Outline (grouping) level, 0 for rows out of any group
+    pub outline_level: u8,
+    /// The group next to this row is collapsed (Excel formats only)
+    pub collapsed: bool,
+    /// Height in points, `None` for the default height
+    pub height: Option<f64>,
+}
+
+/// Properties of a column
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnProperties {
+    /// Hidden, either explicitly or as part of a collapsed group
+    pub hidden: bool,
+    /// Outline (grouping) level, 0 for columns out of any group
+    pub outline_level: u8,
+    /// The group next to this column is collapsed (Excel formats only)
+    pub collapsed: bool,
+    /// Width in characters of the default font, `None` for the default width
+    ///
+    /// ODS widths, stored as lengths, are converted assuming 7 pixels per character.
+    pub width: Option<f64>,
+}
+
+/// Row and column properties of a worksheet
+///
+/// Only rows and columns whose properties differ from the defaults are set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowColumnProperties {
+    /// Properties of the rows which are not in `rows`
+    pub default_row: RowProperties,
+    /// Properties of the columns which are not in `columns`
+    pub default_column: ColumnProperties,
+    /// Rows properties, by absolute row index
+    pub rows: BTreeMap<u32, RowProperties>,
+    /// Columns properties, by absolute column index
+    pub columns: BTreeMap<u32, ColumnProperties>,
+}
+
+impl RowColumnProperties {
+    /// Removes the rows and columns having the default properties
+    pub(crate) fn remove_defaults(&mut self) {
+        let (row, column) = (&self.default_row, &self.default_column);
+        self.rows.retain(|_, r| {
+            r.hidden != row.hidden || r.outline_level > 0 || r.collapsed || r.height.is_some()
+        });
+        self.columns.retain(|_, c| {
+            c.hidden != column.hidden || c.outline_level > 0 || c.collapsed || c.width.is_some()
+        });
+    }
+}
+
+/// The autofilter of a worksheet, with the criteria currently applied
+///
+/// Rows filtered out are also hidden, see [`Reader::worksheet_row_column_properties`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AutoFilter {
+    /// Cells covered by the filter, header row included
+    pub range: Dimensions,
+    /// Criteria of the filtered columns
+    pub columns: Vec<FilterColumn>,
+    /// Sort state of the filtered cells, if any (xlsx and ods only)
+    pub sort: Option<SortState>,
+}
+
+/// Criteria of a column of an [`AutoFilter`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterColumn {
+    /// Column index, relative to the first column of the filter range
+    pub index: u32,
+    /// Criteria
+    pub filter: Filter,
+}
+
+/// Criteria of a [`FilterColumn`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Keeps the cells whose displayed value is one of `values` or, if `blank`, empty cells
+    Values {
+        /// Displayed values
+        values: Vec<String>,
+        /// Keep empty cells
+        blank: bool,
+    },
+    /// Keeps the cells matching any, or all if `and`, of the conditions
+    Custom {
+        /// Conditions
+        conditions: Vec<FilterCondition>,
+        /// All conditions must match
+        and: bool,
+    },
+    /// Keeps the `value` greatest, or smallest, values or percents
+    Top10 {
+        /// Keep the greatest values
+        top: bool,
+        /// `value` is a percent of the values
+        percent: bool,
+        /// Count or percent of values
+        value: f64,
+    },
+    /// A dynamic filter by its OOXML type, e.g. `aboveAverage` or `today` (xlsx and xlsb only)
+    Dynamic(String),
+    /// A filter which is not read (color, icon or regular expression filters)
+    Other,
+}
+
+/// A condition of a [`Filter::Custom`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterCondition {
+    /// Comparison operator
+    pub operator: FilterOperator,
+    /// Compared value, strings may contain `*` and `?` wildcards
+    ///
+    /// [`Data::Empty`] matches empty cells.
+    pub value: Data,
+}
+
+/// Comparison operator of a [`FilterCondition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    /// `=`
+    Equal,
+    /// `<>`
+    NotEqual,
+    /// `<`
+    LessThan,
+    /// `<=`
+    LessThanOrEqual,
+    /// `>`
+    GreaterThan,
+    /// `>=`
+    GreaterThanOrEqual,
+}
+
+/// Sort state of an [`AutoFilter`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortState {
+    /// Sorted cells, header row excluded
+    pub range: Dimensions,
+    /// Sort keys, by priority
+    pub conditions: Vec<SortCondition>,
+}
+
+/// A sort key of a [`SortState`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortCondition {
+    /// Absolute index of the sorted column
+    pub column: u32,
+    /// Sorted in descending order
+    pub descending: bool,
+}
+
+/// A data validation rule of a worksheet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataValidation {
+    /// Cells the rule applies to
+    pub ranges: Vec<Dimensions>,
+    /// Type of the allowed values
+    pub typ: DataValidationType,
+    /// Comparison of the values with the constraint formulas, unused by lists and custom rules
+    pub operator: DataValidationOperator,
+    /// First constraint, in Excel A1 syntax
+    ///
+    /// The allowed values of a list, either as a range or as a quoted, comma separated,
+    /// literal (e.g. `"a,b,c"`), the minimum of a between comparison, the compared value
+    /// or the custom formula.
+    pub formula1: Option<String>,
+    /// Second constraint, the maximum of a between comparison
+    pub formula2: Option<String>,
+    /// Empty cells are valid
+    pub allow_blank: bool,
+    /// Show the in-cell dropdown of a list
+    pub show_dropdown: bool,
+    /// Show the input message when the cell is selected
+    pub show_prompt: bool,
+    /// Title of the input message
+    pub prompt_title: Option<String>,
+    /// Input message
+    pub prompt: Option<String>,
+    /// Show the error alert on invalid values
+    pub show_error: bool,
+    /// Kind of error alert
+    pub error_style: DataValidationErrorStyle,
+    /// Title of the error alert
+    pub error_title: Option<String>,
+    /// Error alert message
+    pub error: Option<String>,
+}
+
+/// Type of the values allowed by a [`DataValidation`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataValidationType {
+    /// Any value
+    #[default]
+    Any,
+    /// Whole numbers
+    Whole,
+    /// Decimal numbers
+    Decimal,
+    /// Values of a list
+    List,
+    /// Dates
+    Date,
+    /// Times
+    Time,
+    /// Texts, by their length
+    TextLength,
+    /// Values for which a custom formula is true
+    Custom,
+}
+
+/// Comparison operator of a [`DataValidation`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataValidationOperator {
+    /// Between `formula1` and `formula2`, inclusive
+    #[default]
+    Between,
+    /// Not between `formula1` and `formula2`
+    NotBetween,
+    /// `=`
+    Equal,
+    /// `<>`
+    NotEqual,
+    /// `>`
+    GreaterThan,
+    /// `<`
+    LessThan,
+    /// `>=`
+    GreaterThanOrEqual,
+    /// `<=`
+    LessThanOrEqual,
+}
+
+/// Error alert of a [`DataValidation`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataValidationErrorStyle {
+    /// Invalid values are rejected
+    #[default]
+    Stop,
+    /// Invalid values may be accepted after a warning
+    Warning,
+    /// Invalid values are accepted after an information message
+    Information,
+}
+
+/// A cell formula, with the way it is shared with other cells
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormulaInfo {
+    /// Formula, as returned by [`Reader::worksheet_formula`]
+    pub text: String,
+    /// Kind of formula
+    pub kind: FormulaKind,
+}
+
+/// Kind of a [`FormulaInfo`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FormulaKind {
+    /// Formula of a single cell
+    #[default]
+    Normal,
+    /// Formula shared by a range of cells, its relative references being adjusted to each cell
+    Shared,
+    /// Array formula, set on the top left cell of the cells holding its results
+    ///
+    /// Values within `range` and outside of the top left cell are computed by the formula,
+    /// not typed in.
+    Array {
+        /// Cells holding the results
+        range: Dimensions,
+        /// Is it a dynamic array formula, spilling its results, rather than a legacy (CSE)
+        /// array formula. Only xlsx files flag dynamic arrays.
+        dynamic: bool,
+    },
+    /// Data table (what-if analysis), set on the top left cell of the cells holding its
+    /// results, with a `TABLE(row_input,column_input)` formula
+    DataTable {
+        /// Cells holding the results
+        range: Dimensions,
+    },
 }
 
 // FIXME `Reader` must only be seek `Seek` for `Xls::xls`. Because of the present API this limits
@@ -242,6 +590,175 @@ where
     /// Read worksheet formula in corresponding worksheet path
     fn worksheet_formula(&mut self, _: &str) -> Result<Range<String>, Self::Error>;
 
+    /// Read worksheet formulas parsed as [`formula::Expr`] trees
+    ///
+    /// Array formulas and data tables are only set on their top left cell, see
+    /// [`Reader::worksheet_formula_info`]. The default implementation parses the
+    /// [`Reader::worksheet_formula`] cells, skipping the formulas it cannot parse.
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<formula::Expr>, Self::Error> {
+        let formulas = self.worksheet_formula(name)?;
+        let cells = formulas
+            .used_cells()
+            .filter(|(_, _, f)| !f.is_empty())
+            .filter_map(|(row, col, f)| {
+                let expr = formula::parse_formula(f).ok()?;
+                let pos = (formulas.start.0 + row as u32, formulas.start.1 + col as u32);
+                Some(Cell::new(pos, expr))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    /// Read worksheet formulas along with their kind, to tell array formulas results and
+    /// shared formulas apart from other cells
+    ///
+    /// The default implementation returns the [`Reader::worksheet_formula`] cells as
+    /// [`FormulaKind::Normal`] formulas.
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, Self::Error> {
+        let formulas = self.worksheet_formula(name)?;
+        Ok(Range {
+            start: formulas.start,
+            end: formulas.end,
+            inner: formulas
+                .inner
+                .into_iter()
+                .map(|text| FormulaInfo {
+                    text,
+                    kind: FormulaKind::Normal,
+                })
+                .collect(),
+        })
+    }
+
+    /// Read worksheet data along with the number format code of each cell
+    ///
+    /// The default implementation returns the cells without format.
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, Self::Error> {
+        let range = self.worksheet_range(name)?;
+        Ok(Range {
+            start: range.start,
+            end: range.end,
+            inner: range
+                .inner
+                .into_iter()
+                .map(|value| DataWithFormat::new(value, None))
+                .collect(),
+        })
+    }
+
+    /// Read the style of each cell, as an index in [`Reader::styles`]
+    ///
+    /// The default implementation returns an empty range.
+    fn worksheet_styles(&mut self, _name: &str) -> Result<Range<StyleId>, Self::Error> {
+        Ok(Range::empty())
+    }
+
+    /// Get the workbook cell styles, indexed by [`StyleId`]
+    ///
+    /// The default implementation returns no style.
+    fn styles(&self) -> &[Style] {
+        &[]
+    }
+
+    /// Read the comments of a worksheet
+    ///
+    /// Threaded comments replies are returned as separate comments on the same cell.
+    /// The default implementation returns no comment.
+    fn worksheet_comments(&mut self, _name: &str) -> Result<Vec<Comment>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the hyperlinks of a worksheet
+    ///
+    /// The default implementation returns no hyperlink.
+    fn worksheet_hyperlinks(&mut self, _name: &str) -> Result<Vec<Hyperlink>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the formatted runs of the rich text strings of a worksheet
+    ///
+    /// Only cells whose string has its own formatting are set, other cells are empty.
+    /// The default implementation returns an empty range.
+    fn worksheet_rich_text(&mut self, _name: &str) -> Result<Range<RichText>, Self::Error> {
+        Ok(Range::empty())
+    }
+
+    /// Read the merged cells of a worksheet, each as the area it covers
+    ///
+    /// The default implementation returns no merged cells.
+    fn merged_cells(&mut self, _name: &str) -> Result<Vec<Dimensions>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Read the row and column properties of a worksheet: hidden state, outline
+    /// level, height and width
+    ///
+    /// The default implementation returns the default properties.
+    fn worksheet_row_column_properties(
+        &mut self,
+        _name: &str,
+    ) -> Result<RowColumnProperties, Self::Error> {
+        Ok(RowColumnProperties::default())
+    }
+
+    /// Read the autofilter of a worksheet, if any, with its filtering criteria and sort state
+    ///
+    /// Tables autofilters are not returned, see [`TableMetadata::auto_filter`].
+    /// The default implementation returns no autofilter.
+    fn worksheet_auto_filter(&mut self, _name: &str) -> Result<Option<AutoFilter>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Read the data validation rules of a worksheet: dropdown lists and constraints on
+    /// the cell values, with their messages
+    ///
+    /// The default implementation returns no rule.
+    fn worksheet_data_validations(
+        &mut self,
+        _name: &str,
+    ) -> Result<Vec<DataValidation>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Iterate over the non-empty rows of a worksheet, without building a [`Range`]
+    ///
+    /// Each row is yielded as its absolute index and its cells, indexed by
+    /// absolute column and padded with [`DataRef::Empty`].
+    ///
+    /// The default implementation reads the whole [`Reader::worksheet_range`] first.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{open_workbook_auto, DataType, Reader};
+    ///
+    /// # let path = format!("{}/tests/issue3.xlsm", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook = open_workbook_auto(path).unwrap();
+    /// for row in workbook.worksheet_rows("Sheet1").unwrap() {
+    ///     let (index, cells) = row.unwrap();
+    ///     let used = cells.iter().filter(|c| !c.is_empty()).count();
+    ///     println!("row {}: {} non-empty cells", index, used);
+    /// }
+    /// ```
+    fn worksheet_rows<'a>(
+        &'a mut self,
+        name: &str,
+    ) -> Result<WorksheetRows<'a, Self::Error>, Self::Error>
+    where
+        Self::Error: 'a,
+    {
+        let range = self.worksheet_range(name)?;
+        let start = range.start().unwrap_or_default();
+        let width = range.width();
+        let cells = range.inner.into_iter().enumerate().map(move |(i, v)| {
+            let pos = (start.0 + (i / width) as u32, start.1 + (i % width) as u32);
+            Ok(Cell::new(pos, DataRef::from(v)))
+        });
+        Ok(cell_rows(cells))
+    }
+
     /// Get all sheet names of this workbook, in workbook order
     ///
     /// # Examples
@@ -270,6 +787,11 @@ where
         &self.metadata().names
     }
 
+    /// Get the workbook structure protection
+    fn workbook_protection(&self) -> &Protection {
+        &self.metadata().protection
+    }
+
     /// Get the nth worksheet. Shortcut for getting the nth
     /// sheet_name, then the corresponding worksheet.
     fn worksheet_range_at(&mut self, n: usize) -> Option<Result<Range<Data>, Self::Error>> {
@@ -282,6 +804,17 @@ where
     fn pictures(&self) -> Option<Vec<(String, Vec<u8>)>>;
 }
 
+/// A trait to decrypt password protected workbooks
+///
+/// The whole file is decrypted in memory, hence readers over a `Cursor<Vec<u8>>`.
+#[cfg(feature = "encryption")]
+pub trait ReaderWithPassword: Reader<Cursor<Vec<u8>>> {
+    /// Creates a new instance, decrypting the workbook with `password`.
+    ///
+    /// Workbooks which are not encrypted are read as usual.
+    fn new_with_password<R: Read>(reader: R, password: &str) -> Result<Self, Self::Error>;
+}
+
 /// Convenient function to open a file with a BufReader<File>
 pub fn open_workbook<R, P>(path: P) -> Result<R, R::Error>
 where
@@ -301,12 +834,36 @@ where
     R::new(rs)
 }
 
+/// Convenient function to open a password protected file
+///
+/// ```
+/// use calamine::{open_workbook_with_password, Reader, Xlsx};
+///
+/// let path = format!("{}/tests/pass_protected.xlsx", env!("CARGO_MANIFEST_DIR"));
+/// let mut workbook: Xlsx<_> = open_workbook_with_password(path, "123").unwrap();
+/// assert!(workbook.worksheet_range_at(0).is_some());
+/// ```
+#[cfg(feature = "encryption")]
+pub fn open_workbook_with_password<R, P>(path: P, password: &str) -> Result<R, R::Error>
+where
+    R: ReaderWithPassword,
+    P: AsRef<Path>,
+{
+    let file = BufReader::new(File::open(path)?);
+    R::new_with_password(file, password)
+}
+
 /// A trait to constrain cells
 pub trait CellType: Default + Clone + PartialEq {}
 
 impl CellType for Data {}
 impl<'a> CellType for DataRef<'a> {}
+impl CellType for DataWithFormat {}
 impl CellType for String {}
+impl CellType for StyleId {}
+impl CellType for RichText {}
+impl CellType for formula::Expr {}
+impl CellType for FormulaInfo {}
 impl CellType for usize {} // for tests
 
 /// A struct to hold cell position and value
@@ -338,6 +895,50 @@ impl<T: CellType> Cell<T> {
     }
 }
 
+/// An iterator over the rows of a worksheet, see [`Reader::worksheet_rows`]
+pub type WorksheetRows<'a, E> = Box<dyn Iterator<Item = Result<(u32, Vec<DataRef<'a>>), E>> + 'a>;
+
+/// Builds [`WorksheetRows`] out of an iterator over cells sorted by row
+pub(crate) fn cell_rows<'a, I, E>(cells: I) -> WorksheetRows<'a, E>
+where
+    I: Iterator<Item = Result<Cell<DataRef<'a>>, E>> + 'a,
+    E: 'a,
+{
+    let mut cells = cells
+        .fuse()
+        .filter(|c| !c.as_ref().is_ok_and(|c| c.val.is_empty()));
+    // first cell of the next row, or an error to report after the current row
+    let mut pending = None;
+    let mut failed = false;
+    Box::new(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let first = match pending.take().or_else(|| cells.next())? {
+            Ok(cell) => cell,
+            Err(e) => {
+                failed = true;
+                return Some(Err(e));
+            }
+        };
+        let row = first.pos.0;
+        let mut values = Vec::new();
+        let mut cell = Some(first);
+        while let Some(Cell { pos, val }) = cell.take() {
+            let col = pos.1 as usize;
+            if values.len() <= col {
+                values.resize(col + 1, DataRef::Empty);
+            }
+            values[col] = val;
+            match cells.next() {
+                Some(Ok(next)) if next.pos.0 == row => cell = Some(next),
+                next => pending = next,
+            }
+        }
+        Some(Ok((row, values)))
+    }))
+}
+
 /// A struct which represents a squared selection of cells
 #[derive(Debug, Default, Clone)]
 pub struct Range<T> {
@@ -509,7 +1110,7 @@ impl<T: CellType> Range<T> {
         ) {
             (false, false) => (), // regular case, position within bounds
             (true, false) => {
-                let len = (absolute_position.0 - self.end.0 + 1) as usize * self.width();
+                let len = (absolute_position.0 - self.end.0) as usize * self.width();
                 self.inner.extend_from_slice(&vec![T::default(); len]);
                 self.end.0 = absolute_position.0;
             }
@@ -744,6 +1345,98 @@ impl<T: CellType> Range<T> {
 
         other
     }
+
+    /// Copies the value of the top left cell of each merged area to all its cells
+    ///
+    /// Only the top left cell of merged cells holds a value, the others are empty. Filling
+    /// them helps reading multi-level headers or grouped row labels. The range grows to
+    /// include the merged areas, whose top left cell must be in the range, others are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Data, Dimensions, Range};
+    ///
+    /// let mut range = Range::new((0, 0), (1, 1));
+    /// range.set_value((0, 0), Data::String("header".to_string()));
+    /// range.fill_merged(&[Dimensions::new((0, 0), (0, 2))]);
+    /// assert_eq!(range.get_value((0, 2)), Some(&Data::String("header".to_string())));
+    /// assert_eq!(range.get_value((1, 1)), Some(&Data::Empty));
+    /// assert_eq!(range.get_size(), (2, 3));
+    /// ```
+    pub fn fill_merged(&mut self, merged: &[Dimensions]) {
+        for area in merged {
+            let value = match self.get_value(area.start) {
+                Some(value) => value.clone(),
+                None => continue,
+            };
+            for row in area.start.0..=area.end.0 {
+                for col in area.start.1..=area.end.1 {
+                    self.set_value((row, col), value.clone());
+                }
+            }
+        }
+    }
+}
+
+impl Range<Data> {
+    /// Renders all cells as displayed by a spreadsheet application for the
+    /// given number format code
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{Range, Data};
+    ///
+    /// let mut range = Range::new((0, 0), (0, 1));
+    /// range.set_value((0, 0), Data::Float(1234.5));
+    /// range.set_value((0, 1), Data::String("total".to_string()));
+    /// let formatted = range.formatted("#,##0.00;-#,##0.00;0;\"[\"@\"]\"");
+    /// assert_eq!(formatted.get((0, 0)).unwrap(), "1,234.50");
+    /// assert_eq!(formatted.get((0, 1)).unwrap(), "[total]");
+    /// ```
+    pub fn formatted(&self, format: &str) -> Range<String> {
+        let format = formats::NumberFormat::parse(format);
+        Range {
+            start: self.start,
+            end: self.end,
+            inner: self
+                .inner
+                .iter()
+                .map(|v| v.to_display_string_with(&format))
+                .collect(),
+        }
+    }
+}
+
+impl Range<DataWithFormat> {
+    /// Renders all cells with their own number format
+    pub fn formatted(&self) -> Range<String> {
+        let mut formats = std::collections::HashMap::new();
+        let inner = self
+            .inner
+            .iter()
+            .map(|v| {
+                let code = v.format.as_deref().unwrap_or("General");
+                let format = formats
+                    .entry(code)
+                    .or_insert_with(|| formats::NumberFormat::parse(code));
+                v.value.to_display_string_with(format)
+            })
+            .collect();
+        Range {
+            start: self.start,
+            end: self.end,
+            inner,
+        }
+    }
+
+    /// Gets the values, dropping the number formats
+    pub fn values(&self) -> Range<Data> {
+        Range {
+            start: self.start,
+            end: self.end,
+            inner: self.inner.iter().map(|v| v.value.clone()).collect(),
+        }
+    }
 }
 
 impl<T: CellType + fmt::Display> Range<T> {
@@ -905,30 +1598,143 @@ impl<'a, T: 'a + CellType> DoubleEndedIterator for Rows<'a, T> {
 
 impl<'a, T: 'a + CellType> ExactSizeIterator for Rows<'a, T> {}
 
+/// Tables of a workbook, once loaded
+pub(crate) type Tables = Option<Vec<TableMetadata>>;
+
+/// Definition of a table, besides its cells
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableMetadata {
+    /// Table name
+    pub name: String,
+    /// Name of the sheet the table exists within
+    pub sheet_name: String,
+    /// Cells of the whole table, including its header and totals rows
+    pub range: Dimensions,
+    /// Number of header rows, 0 when the table has no header row
+    pub header_row_count: u32,
+    /// Number of totals rows, 0 when the totals row is hidden
+    pub totals_row_count: u32,
+    /// Has the totals row ever been shown
+    pub totals_row_shown: bool,
+    /// Is the last row the empty row used to insert new rows
+    pub insert_row: bool,
+    /// Columns, in the order they occur
+    pub columns: Vec<TableColumn>,
+    /// Cells covered by the autofilter, if any
+    pub auto_filter: Option<Dimensions>,
+    /// Table style, if any
+    pub style: Option<TableStyle>,
+}
+
+impl TableMetadata {
+    /// Cells of the table data, without the header, totals and insert rows
+    pub fn data_dimensions(&self) -> Dimensions {
+        let mut dims = self.range;
+        dims.start.0 += self.header_row_count;
+        dims.end.0 = dims
+            .end
+            .0
+            .saturating_sub(self.totals_row_count + self.insert_row as u32);
+        dims
+    }
+}
+
+/// A column of a [`TableMetadata`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableColumn {
+    /// Column name, as shown in the header row
+    pub name: String,
+    /// Formula computing every cell of a calculated column
+    pub calculated_formula: Option<String>,
+    /// Function of the totals row cell
+    pub totals_row_function: Option<TotalsRowFunction>,
+    /// Text of the totals row cell, when it has no function
+    pub totals_row_label: Option<String>,
+}
+
+/// Function of a table totals row cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum TotalsRowFunction {
+    /// Average
+    Average,
+    /// Count of non empty cells
+    Count,
+    /// Count of numbers
+    CountNums,
+    /// Maximum
+    Max,
+    /// Minimum
+    Min,
+    /// Standard deviation
+    StdDev,
+    /// Sum
+    Sum,
+    /// Variance
+    Var,
+    /// Custom formula
+    Custom(String),
+}
+
+/// Style of a table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableStyle {
+    /// Style name, like `TableStyleMedium2`
+    pub name: String,
+    /// Is the first column highlighted
+    pub show_first_column: bool,
+    /// Is the last column highlighted
+    pub show_last_column: bool,
+    /// Are rows banded
+    pub show_row_stripes: bool,
+    /// Are columns banded
+    pub show_column_stripes: bool,
+}
+
 /// Struct with the key elements of a table
 pub struct Table<T> {
-    pub(crate) name: String,
-    pub(crate) sheet_name: String,
     pub(crate) columns: Vec<String>,
     pub(crate) data: Range<T>,
+    pub(crate) metadata: TableMetadata,
+}
+impl<T: CellType> Table<T> {
+    /// Creates a table from its metadata and the cells of its worksheet
+    pub(crate) fn new(metadata: TableMetadata, range: &Range<T>) -> Self {
+        let dims = metadata.data_dimensions();
+        // a table may only have a header row
+        let data = if dims.start.0 > dims.end.0 {
+            Range::empty()
+        } else {
+            range.range(dims.start, dims.end)
+        };
+        Table {
+            columns: metadata.columns.iter().map(|c| c.name.clone()).collect(),
+            data,
+            metadata,
+        }
+    }
 }
 impl<T> Table<T> {
     /// Get the name of the table
     pub fn name(&self) -> &str {
-        &self.name
+        &self.metadata.name
     }
     /// Get the name of the sheet that table exists within
     pub fn sheet_name(&self) -> &str {
-        &self.sheet_name
+        &self.metadata.sheet_name
     }
     /// Get the names of the columns in the order they occur
     pub fn columns(&self) -> &[String] {
         &self.columns
     }
-    /// Get a range representing the data from the table (excludes column headers)
+    /// Get a range representing the data from the table (excludes column headers and
+    /// totals rows)
     pub fn data(&self) -> &Range<T> {
         &self.data
     }
+    /// Get the table definition: header and totals rows, columns formulas, style...
+    pub fn metadata(&self) -> &TableMetadata {
+        &self.metadata
+    }
 }
 
 /// A helper function to deserialize cell values as `i64`,
@@ -6,18 +6,35 @@
 
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::io::{BufReader, Read, Seek};
 
+use log::warn;
 use quick_xml::events::attributes::Attributes;
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::name::QName;
 use quick_xml::Reader as XmlReader;
 use zip::read::{ZipArchive, ZipFile};
 use zip::result::ZipError;
 
+use crate::formula::{open_formula_to_excel, parse_defined_name, parse_open_formula, Expr};
+use crate::style::{
+    Border, BorderStyle, Borders, Color, Fill, FillPattern, Font, HorizontalAlignment, RichText,
+    Style, StyleId, VerticalAlignment,
+};
 use crate::vba::VbaProject;
-use crate::{Data, DataType, Metadata, Range, Reader, Sheet, SheetType, SheetVisible};
-use std::marker::PhantomData;
+#[cfg(feature = "encryption")]
+use crate::ReaderWithPassword;
+use crate::{
+    cell_rows, AutoFilter, Cell, ColumnProperties, Comment, Data, DataType, DataValidation,
+    DataValidationErrorStyle, DataValidationOperator, DataValidationType, DataWithFormat,
+    Dimensions, Filter, FilterColumn, FilterCondition, FilterOperator, FormulaInfo, FormulaKind,
+    Hyperlink, Metadata, Protection, Range, Reader, RowColumnProperties, RowProperties, Sheet,
+    SheetType, SheetVisible, SortCondition, SortState, Table, TableColumn, TableMetadata, Tables,
+    WorksheetRows,
+};
+use std::sync::Arc;
 
 const MIMETYPE: &[u8] = b"application/vnd.oasis.opendocument.spreadsheet";
 
@@ -42,6 +59,8 @@ pub enum OdsError {
     ParseFloat(std::num::ParseFloatError),
     /// Error while parsing bool
     ParseBool(std::str::ParseBoolError),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
 
     /// Invalid MIME
     InvalidMime(Vec<u8>),
@@ -60,10 +79,13 @@ pub enum OdsError {
     Password,
     /// Worksheet not found
     WorksheetNotFound(String),
+    /// Table not found
+    TableNotFound(String),
 }
 
 from_err!(std::io::Error, OdsError, Io);
 from_err!(zip::result::ZipError, OdsError, Zip);
+from_err!(crate::crypto::CryptoError, OdsError, Crypto);
 from_err!(quick_xml::Error, OdsError, Xml);
 from_err!(std::string::ParseError, OdsError, Parse);
 from_err!(std::num::ParseFloatError, OdsError, ParseFloat);
@@ -79,6 +101,7 @@ impl std::fmt::Display for OdsError {
             OdsError::ParseInt(e) => write!(f, "Parse integer error: {e}"),
             OdsError::ParseFloat(e) => write!(f, "Parse float error: {e}"),
             OdsError::ParseBool(e) => write!(f, "Parse bool error: {e}"),
+            OdsError::Crypto(e) => write!(f, "Decryption error: {e}"),
             OdsError::InvalidMime(mime) => write!(f, "Invalid MIME type: {mime:?}"),
             OdsError::FileNotFound(file) => write!(f, "'{file}' file not found in archive"),
             OdsError::Eof(node) => write!(f, "Expecting '{node}' node, found end of xml file"),
@@ -87,6 +110,7 @@ impl std::fmt::Display for OdsError {
             }
             OdsError::Password => write!(f, "Workbook is password protected"),
             OdsError::WorksheetNotFound(name) => write!(f, "Worksheet '{name}' not found"),
+            OdsError::TableNotFound(name) => write!(f, "Table '{name}' not found"),
         }
     }
 }
@@ -100,6 +124,7 @@ impl std::error::Error for OdsError {
             OdsError::Parse(e) => Some(e),
             OdsError::ParseInt(e) => Some(e),
             OdsError::ParseFloat(e) => Some(e),
+            OdsError::Crypto(e) => Some(e),
             _ => None,
         }
     }
@@ -111,13 +136,46 @@ impl std::error::Error for OdsError {
 /// OASIS Open Document Format for Office Application 1.2 (ODF 1.2)
 /// http://docs.oasis-open.org/office/v1.2/OpenDocument-v1.2.pdf
 pub struct Ods<RS> {
-    sheets: BTreeMap<String, (Range<Data>, Range<String>)>,
+    zip: ZipArchive<RS>,
+    /// Sheets parsed so far
+    sheets: BTreeMap<String, SheetData>,
+    /// Offset of each table content in content.xml, `None` for empty tables
+    sheet_offsets: BTreeMap<String, Option<u64>>,
+    /// Number format codes, by cell style name
+    number_formats: HashMap<String, Arc<str>>,
+    /// Cell styles, the default style being first
+    styles: Vec<Style>,
+    /// Index in `styles`, by cell style name
+    style_ids: HashMap<String, usize>,
+    /// Fonts of the text styles, used by rich text spans
+    text_fonts: HashMap<String, Font>,
+    /// Row heights and column widths, by row or column style name
+    row_column_styles: RowColumnStyles,
+    /// Translate formulas to Excel A1 syntax
+    excel_formulas: bool,
+    /// Named database ranges, the tables of ods, without their columns
+    database_ranges: Vec<TableMetadata>,
+    /// Sheets autofilters, by sheet name
+    auto_filters: BTreeMap<String, AutoFilter>,
+    /// Content validations, without their cells, by name
+    validations: Vec<(String, DataValidation)>,
+    /// Tables: Name, Sheet, Columns, Data dimensions
+    tables: Tables,
     metadata: Metadata,
-    marker: PhantomData<RS>,
     #[cfg(feature = "picture")]
     pictures: Option<Vec<(String, Vec<u8>)>>,
 }
 
+#[cfg(feature = "encryption")]
+impl ReaderWithPassword for Ods<Cursor<Vec<u8>>> {
+    fn new_with_password<R: Read>(mut reader: R, password: &str) -> Result<Self, OdsError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let data = crate::crypto::odf::decrypt(data, password)?;
+        Self::new(Cursor::new(data))
+    }
+}
+
 impl<RS> Reader<RS> for Ods<RS>
 where
     RS: Read + Seek,
@@ -145,20 +203,39 @@ where
         #[cfg(feature = "picture")]
         let pictures = read_pictures(&mut zip)?;
 
+        let mut styles = read_styles(&mut zip)?;
         let Content {
-            sheets,
+            sheet_offsets,
             sheets_metadata,
             defined_names,
-        } = parse_content(zip)?;
+            database_ranges,
+            auto_filters,
+            validations,
+            protection,
+            row_column_styles,
+        } = parse_content(&mut zip, &mut styles)?;
         let metadata = Metadata {
             sheets: sheets_metadata,
             names: defined_names,
+            protection,
         };
 
+        let (cell_styles, style_ids) = styles.cell_styles();
         Ok(Ods {
-            marker: PhantomData,
+            zip,
             metadata,
-            sheets,
+            sheets: BTreeMap::new(),
+            sheet_offsets,
+            number_formats: styles.number_formats(),
+            styles: cell_styles,
+            style_ids,
+            text_fonts: styles.text_fonts(),
+            row_column_styles,
+            excel_formulas: false,
+            database_ranges,
+            auto_filters,
+            validations,
+            tables: None,
             #[cfg(feature = "picture")]
             pictures,
         })
@@ -176,25 +253,247 @@ where
 
     /// Read worksheet data in corresponding worksheet path
     fn worksheet_range(&mut self, name: &str) -> Result<Range<Data>, OdsError> {
-        self.sheets
-            .get(name)
-            .ok_or_else(|| OdsError::WorksheetNotFound(name.into()))
-            .map(|r| r.0.to_owned())
+        self.sheet(name).map(|r| r.range.to_owned())
     }
 
     fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
-        self.sheets
-            .iter()
-            .map(|(name, (range, _formula))| (name.to_owned(), range.clone()))
+        let names: Vec<_> = self.sheet_offsets.keys().cloned().collect();
+        names
+            .into_iter()
+            .filter_map(|name| match self.worksheet_range(&name) {
+                Ok(range) => Some((name, range)),
+                Err(e) => {
+                    warn!("cannot read worksheet '{}': {}", name, e);
+                    None
+                }
+            })
             .collect()
     }
 
     /// Read worksheet data in corresponding worksheet path
     fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>, OdsError> {
-        self.sheets
+        let excel_formulas = self.excel_formulas;
+        let formulas = &self.sheet(name)?.formulas;
+        if !excel_formulas {
+            return Ok(formulas.to_owned());
+        }
+        let start = formulas.start().unwrap_or_default();
+        let cells = formulas
+            .used_cells()
+            .map(|(row, col, f)| {
+                let formula = open_formula_to_excel(f).unwrap_or_else(|e| {
+                    warn!("cannot translate formula '{}': {}", f, e);
+                    f.clone()
+                });
+                Cell::new((start.0 + row as u32, start.1 + col as u32), formula)
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, OdsError> {
+        let matrices = self.sheet(name)?.matrices.clone();
+        let formulas = self.worksheet_formula(name)?;
+        let start = formulas.start().unwrap_or_default();
+        let cells = formulas
+            .used_cells()
+            .map(|(row, col, f)| {
+                let pos = (start.0 + row as u32, start.1 + col as u32);
+                let kind = match matrices.iter().find(|m| m.start == pos) {
+                    Some(range) => FormulaKind::Array {
+                        range: *range,
+                        dynamic: false,
+                    },
+                    None => FormulaKind::Normal,
+                };
+                let text = f.clone();
+                Cell::new(pos, FormulaInfo { text, kind })
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<Expr>, OdsError> {
+        let formulas = &self.sheet(name)?.formulas;
+        let start = formulas.start().unwrap_or_default();
+        let cells = formulas
+            .used_cells()
+            .filter(|(_, _, f)| !f.is_empty())
+            .filter_map(|(row, col, f)| match parse_open_formula(f) {
+                Ok(expr) => Some(Cell::new(
+                    (start.0 + row as u32, start.1 + col as u32),
+                    expr,
+                )),
+                Err(e) => {
+                    warn!("cannot parse formula '{}': {}", f, e);
+                    None
+                }
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, OdsError> {
+        self.sheet(name)?;
+        let sheet = &self.sheets[name];
+        let start = sheet.range.start().unwrap_or_default();
+        let cells = sheet
+            .range
+            .used_cells()
+            .map(|(row, col, value)| {
+                let pos = (start.0 + row as u32, start.1 + col as u32);
+                let format = sheet
+                    .styles
+                    .get_value(pos)
+                    .and_then(|style| self.number_formats.get(style))
+                    .cloned();
+                Cell::new(pos, DataWithFormat::new(value.clone(), format))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, OdsError> {
+        self.sheet(name)?;
+        let sheet = &self.sheets[name];
+        let start = sheet.styles.start().unwrap_or_default();
+        let cells = sheet
+            .styles
+            .used_cells()
+            .filter_map(|(row, col, style)| {
+                let pos = (start.0 + row as u32, start.1 + col as u32);
+                let id = *self.style_ids.get(style)?;
+                Some(Cell::new(pos, StyleId(id)))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn styles(&self) -> &[Style] {
+        &self.styles
+    }
+
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, OdsError> {
+        self.sheet(name).map(|r| r.comments.clone())
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, OdsError> {
+        self.sheet(name).map(|r| r.hyperlinks.clone())
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<RichText>, OdsError> {
+        self.sheet(name).map(|r| r.rich_text.clone())
+    }
+
+    fn merged_cells(&mut self, name: &str) -> Result<Vec<Dimensions>, OdsError> {
+        self.sheet(name).map(|r| r.merged_cells.clone())
+    }
+
+    fn worksheet_row_column_properties(
+        &mut self,
+        name: &str,
+    ) -> Result<RowColumnProperties, OdsError> {
+        self.sheet(name).map(|r| r.row_column_properties.clone())
+    }
+
+    fn worksheet_auto_filter(&mut self, name: &str) -> Result<Option<AutoFilter>, OdsError> {
+        if !self.sheet_offsets.contains_key(name) {
+            return Err(OdsError::WorksheetNotFound(name.into()));
+        }
+        Ok(self.auto_filters.get(name).cloned())
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, OdsError> {
+        let cells = self.sheet(name)?.validations.clone();
+        Ok(self
+            .validations
+            .iter()
+            .filter_map(|(name, validation)| {
+                // merge the runs of cells of consecutive rows
+                let mut ranges: Vec<Dimensions> = Vec::new();
+                for (_, run) in cells.iter().filter(|(n, _)| n == name) {
+                    match ranges.iter_mut().find(|r| {
+                        r.end.0 + 1 == run.start.0
+                            && r.start.1 == run.start.1
+                            && r.end.1 == run.end.1
+                    }) {
+                        Some(range) => range.end.0 = run.end.0,
+                        None => ranges.push(*run),
+                    }
+                }
+                (!ranges.is_empty()).then(|| DataValidation {
+                    ranges,
+                    ..validation.clone()
+                })
+            })
+            .collect())
+    }
+
+    fn worksheet_rows<'a>(&'a mut self, name: &str) -> Result<WorksheetRows<'a, OdsError>, OdsError>
+    where
+        OdsError: 'a,
+    {
+        let offset = *self
+            .sheet_offsets
             .get(name)
-            .ok_or_else(|| OdsError::WorksheetNotFound(name.into()))
-            .map(|r| r.1.to_owned())
+            .ok_or_else(|| OdsError::WorksheetNotFound(name.into()))?;
+        let Some(offset) = offset else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+        let mut reader = table_reader(&mut self.zip, offset)?;
+        let text_fonts = &self.text_fonts;
+        let mut buf = Vec::with_capacity(1024);
+        let mut row_buf = Vec::with_capacity(1024);
+        let mut cell_buf = Vec::with_capacity(1024);
+        let mut row = 0;
+        // the non-empty cells of each table-row, read one at a time
+        let rows = std::iter::from_fn(move || loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                    let repeats = match rows_repeated(&reader, e) {
+                        Ok(repeats) => repeats as u32,
+                        Err(e) => return Some(vec![Err(e)]),
+                    };
+                    let mut cells = Vec::new();
+                    if let Err(e) = read_row(
+                        &mut reader,
+                        row,
+                        &mut row_buf,
+                        &mut cell_buf,
+                        &mut cells,
+                        &mut Vec::new(),
+                        &mut Vec::new(),
+                        &mut TableExtras::default(),
+                        &[],
+                        text_fonts,
+                    ) {
+                        return Some(vec![Err(e)]);
+                    }
+                    let first = row;
+                    row += repeats;
+                    if cells.iter().all(|c| c.is_empty()) {
+                        continue;
+                    }
+                    let cells = (first..row)
+                        .flat_map(|r| {
+                            cells.iter().enumerate().filter(|(_, c)| !c.is_empty()).map(
+                                move |(col, c)| Ok(Cell::new((r, col as u32), c.clone().into())),
+                            )
+                        })
+                        .collect();
+                    return Some(cells);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => return None,
+                Ok(Event::Eof) => return None,
+                Err(e) => return Some(vec![Err(OdsError::Xml(e))]),
+                Ok(_) => (),
+            }
+        });
+        Ok(cell_rows(rows.flatten()))
     }
 
     #[cfg(feature = "picture")]
@@ -203,10 +502,177 @@ where
     }
 }
 
+impl<RS> Ods<RS> {
+    /// Returns the formulas of [`Reader::worksheet_formula`] in Excel A1 syntax, like the
+    /// other formats, instead of the OpenDocument syntax
+    ///
+    /// Formulas which cannot be translated are returned unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use calamine::{open_workbook, Ods, Reader};
+    ///
+    /// # let path = format!("{}/tests/issues.ods", env!("CARGO_MANIFEST_DIR"));
+    /// let mut workbook: Ods<_> = open_workbook(path).unwrap();
+    /// let formulas = workbook.with_excel_formulas(true).worksheet_formula("Sheet1").unwrap();
+    /// assert_eq!(formulas.get((0, 0)).unwrap(), "B1+OneRange");
+    /// ```
+    pub fn with_excel_formulas(&mut self, excel: bool) -> &mut Self {
+        self.excel_formulas = excel;
+        self
+    }
+}
+
+impl<RS: Read + Seek> Ods<RS> {
+    /// Gets a worksheet, parsing its table on first access
+    fn sheet(&mut self, name: &str) -> Result<&SheetData, OdsError> {
+        if !self.sheets.contains_key(name) {
+            let offset = *self
+                .sheet_offsets
+                .get(name)
+                .ok_or_else(|| OdsError::WorksheetNotFound(name.into()))?;
+            let sheet = match offset {
+                Some(offset) => read_table(
+                    &mut table_reader(&mut self.zip, offset)?,
+                    &self.text_fonts,
+                    &self.row_column_styles,
+                )?,
+                None => SheetData::default(),
+            };
+            self.sheets.insert(name.to_string(), sheet);
+        }
+        Ok(&self.sheets[name])
+    }
+
+    /// Load the tables, which are the named database ranges
+    ///
+    /// Must be called before any other table method. Column names are read from the header
+    /// row, or are `Column1`, `Column2`... for ranges without one, like in Excel.
+    pub fn load_tables(&mut self) -> Result<(), OdsError> {
+        if self.tables.is_some() {
+            return Ok(());
+        }
+        let mut tables = Vec::new();
+        for mut table in self.database_ranges.clone() {
+            let (start, end) = (table.range.start, table.range.end);
+            let names: Vec<String> = if table.header_row_count > 0 {
+                let range = &self.sheet(&table.sheet_name)?.range;
+                (start.1..=end.1)
+                    .map(|col| {
+                        range
+                            .get_value((start.0, col))
+                            .map_or_else(String::new, |v| v.to_string())
+                    })
+                    .collect()
+            } else {
+                (1..=end.1 - start.1 + 1)
+                    .map(|i| format!("Column{i}"))
+                    .collect()
+            };
+            table.columns = names
+                .into_iter()
+                .map(|name| TableColumn {
+                    name,
+                    ..TableColumn::default()
+                })
+                .collect();
+            tables.push(table);
+        }
+        self.tables = Some(tables);
+        Ok(())
+    }
+
+    /// Get the names of all the tables
+    pub fn table_names(&self) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    /// Get the names of all the tables in a sheet
+    pub fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    /// Get the table by name
+    pub fn table_by_name(&mut self, table_name: &str) -> Result<Table<Data>, OdsError> {
+        let metadata = self
+            .tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .find(|t| t.name == table_name)
+            .cloned()
+            .ok_or_else(|| OdsError::TableNotFound(table_name.into()))?;
+        let range = &self.sheet(&metadata.sheet_name)?.range;
+        Ok(Table::new(metadata, range))
+    }
+}
+
+#[derive(Debug, Default)]
+struct SheetData {
+    range: Range<Data>,
+    formulas: Range<String>,
+    /// Cell style name of each non empty cell
+    styles: Range<String>,
+    comments: Vec<Comment>,
+    hyperlinks: Vec<Hyperlink>,
+    rich_text: Range<RichText>,
+    /// Cells of the matrix (array) formulas
+    matrices: Vec<Dimensions>,
+    merged_cells: Vec<Dimensions>,
+    row_column_properties: RowColumnProperties,
+    /// Runs of cells of a row having a content validation, by validation name
+    validations: Vec<(String, Dimensions)>,
+}
+
+/// Content of a table besides its cells values
+#[derive(Default)]
+struct TableExtras {
+    comments: Vec<Comment>,
+    hyperlinks: Vec<Hyperlink>,
+    rich_text: Vec<Cell<RichText>>,
+    matrices: Vec<Dimensions>,
+    merged_cells: Vec<Dimensions>,
+    validations: Vec<(String, Dimensions)>,
+}
+
+/// Content of a table cell besides its value
+#[derive(Default)]
+struct CellExtras {
+    /// `office:annotation` author and text
+    annotation: Option<(Option<String>, String)>,
+    /// `text:a` target and title
+    link: Option<(String, Option<String>)>,
+    /// String runs, if some text is in a `text:span`
+    rich_text: Option<RichText>,
+}
+
+/// Row heights (custom only, in points) and column widths (in characters), by style name
+#[derive(Default)]
+struct RowColumnStyles {
+    row_heights: HashMap<String, f64>,
+    column_widths: HashMap<String, f64>,
+}
+
 struct Content {
-    sheets: BTreeMap<String, (Range<Data>, Range<String>)>,
+    sheet_offsets: BTreeMap<String, Option<u64>>,
     sheets_metadata: Vec<Sheet>,
     defined_names: Vec<(String, String)>,
+    database_ranges: Vec<TableMetadata>,
+    auto_filters: BTreeMap<String, AutoFilter>,
+    validations: Vec<(String, DataValidation)>,
+    protection: Protection,
+    row_column_styles: RowColumnStyles,
 }
 
 /// Check password protection
@@ -253,29 +719,59 @@ fn check_for_password_protected<RS: Read + Seek>(zip: &mut ZipArchive<RS>) -> Re
     Ok(())
 }
 
-/// Parses content.xml and store the result in `self.content`
-fn parse_content<RS: Read + Seek>(mut zip: ZipArchive<RS>) -> Result<Content, OdsError> {
+/// Creates the xml reader of content.xml, starting at the table at `offset`
+fn table_reader<RS: Read + Seek>(
+    zip: &mut ZipArchive<RS>,
+    offset: u64,
+) -> Result<OdsReader<'_>, OdsError> {
+    let mut file = match zip.by_name("content.xml") {
+        Ok(f) => f,
+        Err(ZipError::FileNotFound) => return Err(OdsError::FileNotFound("content.xml")),
+        Err(e) => return Err(OdsError::Zip(e)),
+    };
+    std::io::copy(&mut (&mut file).take(offset), &mut std::io::sink())?;
+    Ok(content_reader(file))
+}
+
+/// Creates the xml reader used to parse content.xml
+fn content_reader(file: ZipFile<'_>) -> OdsReader<'_> {
+    let mut r = XmlReader::from_reader(BufReader::new(file));
+    r.check_end_names(false)
+        .trim_text(false)
+        .check_comments(false)
+        .expand_empty_elements(true);
+    r
+}
+
+/// Parses content.xml, skipping over the tables content whose offsets are recorded
+/// so they can be read on demand
+fn parse_content<RS: Read + Seek>(
+    zip: &mut ZipArchive<RS>,
+    cell_styles: &mut Styles,
+) -> Result<Content, OdsError> {
     let mut reader = match zip.by_name("content.xml") {
-        Ok(f) => {
-            let mut r = XmlReader::from_reader(BufReader::new(f));
-            r.check_end_names(false)
-                .trim_text(false)
-                .check_comments(false)
-                .expand_empty_elements(true);
-            r
-        }
+        Ok(f) => content_reader(f),
         Err(ZipError::FileNotFound) => return Err(OdsError::FileNotFound("content.xml")),
         Err(e) => return Err(OdsError::Zip(e)),
     };
     let mut buf = Vec::with_capacity(1024);
-    let mut sheets = BTreeMap::new();
+    let mut sheet_offsets = BTreeMap::new();
     let mut defined_names = Vec::new();
+    let mut database_ranges = Vec::new();
+    let mut auto_filters = BTreeMap::new();
+    let mut validations = Vec::new();
     let mut sheets_metadata = Vec::new();
     let mut styles = HashMap::new();
     let mut style_name: Option<String> = None;
+    let mut protection = Protection::default();
+    let mut row_column_styles = RowColumnStyles::default();
     loop {
         match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"office:spreadsheet") => {
+                protection = read_protection(&reader, e, QName(b"table:structure-protected"))?;
+            }
             Ok(Event::Start(ref e)) if e.name() == QName(b"style:style") => {
+                cell_styles.read_cell_style(&mut reader, e)?;
                 style_name = e
                     .try_get_attribute(b"style:name")?
                     .map(|a| a.decode_and_unescape_value(&reader))
@@ -300,6 +796,29 @@ fn parse_content<RS: Read + Seek>(mut zip: ZipArchive<RS>) -> Result<Content, Od
                 };
                 styles.insert(style_name.clone(), visible);
             }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:table-row-properties") => {
+                let optimal = get_attribute(&reader, e, b"style:use-optimal-row-height")?;
+                let height = get_attribute(&reader, e, b"style:row-height")?
+                    .and_then(|h| length_to_points(&h));
+                if let (Some(name), Some(height)) = (&style_name, height) {
+                    if optimal.as_deref() != Some("true") {
+                        row_column_styles.row_heights.insert(name.clone(), height);
+                    }
+                }
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"style:table-column-properties") => {
+                let width = get_attribute(&reader, e, b"style:column-width")?
+                    .and_then(|w| length_to_points(&w));
+                if let (Some(name), Some(width)) = (&style_name, width) {
+                    // 7 pixels, or 5.25 points, per character
+                    row_column_styles
+                        .column_widths
+                        .insert(name.clone(), width / 5.25);
+                }
+            }
+            Ok(Event::Start(ref e)) if is_data_style(e) => {
+                cell_styles.read_data_style(&mut reader, e)?;
+            }
             Ok(Event::Start(ref e)) if e.name() == QName(b"table:table") => {
                 let visible = styles
                     .get(
@@ -311,6 +830,7 @@ fn parse_content<RS: Read + Seek>(mut zip: ZipArchive<RS>) -> Result<Content, Od
                     )
                     .map(|v| v.to_owned())
                     .unwrap_or(SheetVisible::Visible);
+                let protection = read_protection(&reader, e, QName(b"table:protected"))?;
                 if let Some(ref a) = e
                     .attributes()
                     .filter_map(|a| a.ok())
@@ -320,18 +840,35 @@ fn parse_content<RS: Read + Seek>(mut zip: ZipArchive<RS>) -> Result<Content, Od
                         .decode_and_unescape_value(&reader)
                         .map_err(OdsError::Xml)?
                         .to_string();
-                    let (range, formulas) = read_table(&mut reader)?;
+                    let offset = reader.buffer_position() as u64;
+                    let mut table_buf = Vec::new();
+                    let offset = match reader.read_event_into(&mut table_buf) {
+                        Ok(Event::End(ref e)) if e.name() == QName(b"table:table") => None,
+                        Ok(_) => {
+                            reader.read_to_end_into(QName(b"table:table"), &mut table_buf)?;
+                            Some(offset)
+                        }
+                        Err(e) => return Err(OdsError::Xml(e)),
+                    };
                     sheets_metadata.push(Sheet {
                         name: name.clone(),
                         typ: SheetType::WorkSheet,
                         visible,
+                        protection,
                     });
-                    sheets.insert(name, (range, formulas));
+                    sheet_offsets.insert(name, offset);
                 }
             }
             Ok(Event::Start(ref e)) if e.name() == QName(b"table:named-expressions") => {
                 defined_names = read_named_expressions(&mut reader)?;
             }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:database-range") => {
+                database_ranges.extend(read_database_range(&reader, e)?);
+                auto_filters.extend(read_auto_filter(&mut reader, e)?);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:content-validation") => {
+                validations.push(read_content_validation(&mut reader, e)?);
+            }
             Ok(Event::Eof) => break,
             Err(e) => return Err(OdsError::Xml(e)),
             _ => (),
@@ -339,25 +876,73 @@ fn parse_content<RS: Read + Seek>(mut zip: ZipArchive<RS>) -> Result<Content, Od
         buf.clear();
     }
     Ok(Content {
-        sheets,
+        sheet_offsets,
         sheets_metadata,
         defined_names,
+        database_ranges,
+        auto_filters,
+        validations,
+        protection,
+        row_column_styles,
     })
 }
 
-fn read_table(reader: &mut OdsReader<'_>) -> Result<(Range<Data>, Range<String>), OdsError> {
+/// Reads the protection of a `table:table` or `office:spreadsheet` element
+///
+/// `flag` is the attribute locking the table, or the structure of the document.
+fn read_protection(
+    reader: &OdsReader<'_>,
+    e: &BytesStart<'_>,
+    flag: QName<'_>,
+) -> Result<Protection, OdsError> {
+    let mut protection = Protection::default();
+    for a in e.attributes() {
+        let a = a.map_err(OdsError::XmlAttr)?;
+        if a.key == flag {
+            protection.protected = a.decode_and_unescape_value(reader)? == "true";
+        } else if a.key == QName(b"table:protection-key") && !a.value.is_empty() {
+            protection.password = true;
+        }
+    }
+    Ok(protection)
+}
+
+fn read_table(
+    reader: &mut OdsReader<'_>,
+    text_fonts: &HashMap<String, Font>,
+    row_column_styles: &RowColumnStyles,
+) -> Result<SheetData, OdsError> {
     let mut cells = Vec::new();
     let mut rows_repeats = Vec::new();
+    let mut extras = TableExtras::default();
     let mut formulas = Vec::new();
+    let mut styles = Vec::new();
+    let mut column_styles = Vec::new();
     let mut cols = Vec::new();
     let mut buf = Vec::with_capacity(1024);
     let mut row_buf = Vec::with_capacity(1024);
     let mut cell_buf = Vec::with_capacity(1024);
+    // runs of repeated rows and columns properties, with their outline levels
+    let mut row_runs = Vec::new();
+    let mut column_runs = Vec::new();
+    let (mut row_level, mut column_level) = (0u8, 0u8);
     cols.push(0);
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
-                let row_repeats = match e.try_get_attribute(b"table:number-rows-repeated")? {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row-group") => {
+                row_level = row_level.saturating_add(1);
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:table-row-group") => {
+                row_level = row_level.saturating_sub(1);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-column-group") => {
+                column_level = column_level.saturating_add(1);
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:table-column-group") => {
+                column_level = column_level.saturating_sub(1);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-column") => {
+                let repeats: usize = match e.try_get_attribute(b"table:number-columns-repeated")? {
                     Some(c) => c
                         .decode_and_unescape_value(reader)
                         .map_err(OdsError::Xml)?
@@ -365,13 +950,51 @@ fn read_table(reader: &mut OdsReader<'_>) -> Result<(Range<Data>, Range<String>)
                         .map_err(OdsError::ParseInt)?,
                     None => 1,
                 };
+                let style = e
+                    .try_get_attribute(b"table:default-cell-style-name")?
+                    .map(|a| a.decode_and_unescape_value(reader))
+                    .transpose()
+                    .map_err(OdsError::Xml)?
+                    .map(|s| s.into_owned());
+                column_styles.push((repeats, style));
+                let width = get_attribute(reader, e, b"table:style-name")?
+                    .and_then(|s| row_column_styles.column_widths.get(&s).copied());
+                let column = ColumnProperties {
+                    hidden: is_hidden(reader, e)?,
+                    outline_level: column_level,
+                    collapsed: false,
+                    width,
+                };
+                column_runs.push((repeats, column));
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:table-row") => {
+                let row_repeats = rows_repeated(reader, e)?;
+                let height = get_attribute(reader, e, b"table:style-name")?
+                    .and_then(|s| row_column_styles.row_heights.get(&s).copied());
+                let properties = RowProperties {
+                    hidden: is_hidden(reader, e)?,
+                    outline_level: row_level,
+                    collapsed: false,
+                    height,
+                };
+                row_runs.push((row_repeats, properties));
+                let row = rows_repeats.iter().sum::<usize>() as u32;
+                let first_validation = extras.validations.len();
                 read_row(
                     reader,
+                    row,
                     &mut row_buf,
                     &mut cell_buf,
                     &mut cells,
                     &mut formulas,
+                    &mut styles,
+                    &mut extras,
+                    &column_styles,
+                    text_fonts,
                 )?;
+                for (_, range) in &mut extras.validations[first_validation..] {
+                    range.end.0 = row + row_repeats as u32 - 1;
+                }
                 cols.push(cells.len());
                 rows_repeats.push(row_repeats);
             }
@@ -381,10 +1004,79 @@ fn read_table(reader: &mut OdsReader<'_>) -> Result<(Range<Data>, Range<String>)
         }
         buf.clear();
     }
-    Ok((
-        get_range(cells, &cols, &rows_repeats),
-        get_range(formulas, &cols, &rows_repeats),
-    ))
+    Ok(SheetData {
+        range: get_range(cells, &cols, &rows_repeats),
+        formulas: get_range(formulas, &cols, &rows_repeats),
+        styles: get_range(styles, &cols, &rows_repeats),
+        comments: extras.comments,
+        hyperlinks: extras.hyperlinks,
+        rich_text: Range::from_sparse(extras.rich_text),
+        matrices: extras.matrices,
+        merged_cells: extras.merged_cells,
+        validations: extras.validations,
+        row_column_properties: row_column_properties(row_runs, column_runs),
+    })
+}
+
+/// Number of times a `table:table-row` is repeated
+fn rows_repeated(reader: &OdsReader<'_>, e: &BytesStart<'_>) -> Result<usize, OdsError> {
+    match e.try_get_attribute(b"table:number-rows-repeated")? {
+        Some(c) => c
+            .decode_and_unescape_value(reader)
+            .map_err(OdsError::Xml)?
+            .parse()
+            .map_err(OdsError::ParseInt),
+        None => Ok(1),
+    }
+}
+
+/// Checks the `table:visibility` of a row or a column, either `collapsed` or `filter`
+/// when hidden
+fn is_hidden(reader: &OdsReader<'_>, e: &BytesStart<'_>) -> Result<bool, OdsError> {
+    Ok(get_attribute(reader, e, b"table:visibility")?.is_some_and(|v| v != "visible"))
+}
+
+/// Expands the runs of repeated rows and columns
+///
+/// Tables usually end with a run of rows, or columns, up to the last one of the
+/// sheet: its properties are the defaults.
+fn row_column_properties(
+    mut row_runs: Vec<(usize, RowProperties)>,
+    mut column_runs: Vec<(usize, ColumnProperties)>,
+) -> RowColumnProperties {
+    let mut props = RowColumnProperties::default();
+    if row_runs.last().is_some_and(|r| r.0 > 1) {
+        props.default_row = row_runs.pop().map(|r| r.1).unwrap_or_default();
+    }
+    if column_runs.last().is_some_and(|c| c.0 > 1) {
+        props.default_column = column_runs.pop().map(|c| c.1).unwrap_or_default();
+    }
+    let mut start = 0;
+    for (repeats, mut row) in row_runs {
+        if row.height == props.default_row.height {
+            row.height = None;
+        }
+        if row != RowProperties::default() || props.default_row.hidden {
+            props
+                .rows
+                .extend((start..).take(repeats).map(|r| (r, row.clone())));
+        }
+        start += repeats as u32;
+    }
+    let mut start = 0;
+    for (repeats, mut column) in column_runs {
+        if column.width == props.default_column.width {
+            column.width = None;
+        }
+        if column != ColumnProperties::default() || props.default_column.hidden {
+            props
+                .columns
+                .extend((start..).take(repeats).map(|c| (c, column.clone())));
+        }
+        start += repeats as u32;
+    }
+    props.remove_defaults();
+    props
 }
 
 fn is_empty_row<T: Default + Clone + PartialEq>(row: &[T]) -> bool {
@@ -486,14 +1178,21 @@ fn get_range<T: Default + Clone + PartialEq>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn read_row(
     reader: &mut OdsReader<'_>,
+    row: u32,
     row_buf: &mut Vec<u8>,
     cell_buf: &mut Vec<u8>,
     cells: &mut Vec<Data>,
     formulas: &mut Vec<String>,
+    styles: &mut Vec<String>,
+    extras: &mut TableExtras,
+    column_styles: &[(usize, Option<String>)],
+    text_fonts: &HashMap<String, Font>,
 ) -> Result<(), OdsError> {
     let mut empty_col_repeats = 0;
+    let mut col = 0;
     loop {
         row_buf.clear();
         match reader.read_event_into(row_buf) {
@@ -502,37 +1201,151 @@ fn read_row(
                     || e.name() == QName(b"table:covered-table-cell") =>
             {
                 let mut repeats = 1;
+                let mut style = None;
+                let mut matrix = None;
+                let mut span = (1, 1);
+                let mut validation = None;
                 for a in e.attributes() {
                     let a = a.map_err(OdsError::XmlAttr)?;
-                    if a.key == QName(b"table:number-columns-repeated") {
-                        repeats = reader
-                            .decoder()
-                            .decode(&a.value)?
-                            .parse()
-                            .map_err(OdsError::ParseInt)?;
-                        break;
+                    match a.key {
+                        QName(b"table:number-columns-repeated") => {
+                            repeats = reader
+                                .decoder()
+                                .decode(&a.value)?
+                                .parse()
+                                .map_err(OdsError::ParseInt)?;
+                        }
+                        QName(b"table:style-name") => {
+                            style = Some(
+                                a.decode_and_unescape_value(reader)
+                                    .map_err(OdsError::Xml)?
+                                    .into_owned(),
+                            );
+                        }
+                        QName(b"table:content-validation-name") => {
+                            validation = Some(
+                                a.decode_and_unescape_value(reader)
+                                    .map_err(OdsError::Xml)?
+                                    .into_owned(),
+                            );
+                        }
+                        QName(b"table:number-rows-spanned") => {
+                            span.0 = reader
+                                .decoder()
+                                .decode(&a.value)?
+                                .parse()
+                                .map_err(OdsError::ParseInt)?;
+                        }
+                        QName(b"table:number-columns-spanned") => {
+                            span.1 = reader
+                                .decoder()
+                                .decode(&a.value)?
+                                .parse()
+                                .map_err(OdsError::ParseInt)?;
+                        }
+                        QName(
+                            key @ (b"table:number-matrix-columns-spanned"
+                            | b"table:number-matrix-rows-spanned"),
+                        ) => {
+                            let span: u32 = reader
+                                .decoder()
+                                .decode(&a.value)?
+                                .parse()
+                                .map_err(OdsError::ParseInt)?;
+                            let (rows, cols) = matrix.get_or_insert((1, 1));
+                            match key {
+                                b"table:number-matrix-rows-spanned" => *rows = span.max(1),
+                                _ => *cols = span.max(1),
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                if let Some((rows, cols)) = matrix {
+                    let start = (row, col as u32);
+                    let end = (row + rows - 1, col as u32 + cols - 1);
+                    extras.matrices.push(Dimensions::new(start, end));
+                }
+                if let Some(name) = validation {
+                    let (first, last) = (col as u32, (col + repeats - 1) as u32);
+                    match extras.validations.last_mut() {
+                        Some((n, range))
+                            if *n == name && range.start.0 == row && range.end.1 + 1 == first =>
+                        {
+                            range.end.1 = last;
+                        }
+                        _ => extras
+                            .validations
+                            .push((name, Dimensions::new((row, first), (row, last)))),
                     }
                 }
+                if span != (1, 1) && span.0 > 0 && span.1 > 0 {
+                    let start = (row, col as u32);
+                    let end = (row + span.0 - 1, col as u32 + span.1 - 1);
+                    extras.merged_cells.push(Dimensions::new(start, end));
+                }
 
-                let (value, formula, is_closed) = get_datatype(reader, e.attributes(), cell_buf)?;
+                let mut cell_extras = CellExtras::default();
+                let (value, formula, is_closed) = get_datatype(
+                    reader,
+                    e.attributes(),
+                    cell_buf,
+                    &mut cell_extras,
+                    text_fonts,
+                )?;
+                if !is_closed {
+                    read_cell_end(reader, e.name(), cell_buf, &mut cell_extras)?;
+                }
+                if let Some((author, text)) = cell_extras.annotation {
+                    extras.comments.push(Comment {
+                        cell: (row, col as u32),
+                        author,
+                        text,
+                    });
+                }
+                if let Some(rich) = cell_extras.rich_text {
+                    for i in 0..repeats {
+                        let pos = (row, (col + i) as u32);
+                        extras.rich_text.push(Cell::new(pos, rich.clone()));
+                    }
+                }
+                if let Some((href, tooltip)) = cell_extras.link {
+                    let (url, location) = match href.strip_prefix('#') {
+                        Some(location) => (None, Some(location.to_string())),
+                        None => (Some(href), None),
+                    };
+                    extras.hyperlinks.push(Hyperlink {
+                        range: Dimensions::new(
+                            (row, col as u32),
+                            (row, (col + repeats - 1) as u32),
+                        ),
+                        url,
+                        location,
+                        tooltip,
+                    });
+                }
 
                 for _ in 0..empty_col_repeats {
                     cells.push(Data::Empty);
                     formulas.push("".to_string());
+                    styles.push("".to_string());
                 }
                 empty_col_repeats = 0;
 
                 if value.is_empty() && formula.is_empty() {
                     empty_col_repeats = repeats;
                 } else {
-                    for _ in 0..repeats {
+                    for i in 0..repeats {
+                        let style = style
+                            .as_deref()
+                            .or_else(|| column_style(column_styles, col + i))
+                            .unwrap_or_default();
                         cells.push(value.clone());
                         formulas.push(formula.clone());
+                        styles.push(style.to_string());
                     }
                 }
-                if !is_closed {
-                    reader.read_to_end_into(e.name(), cell_buf)?;
-                }
+                col += repeats;
             }
             Ok(Event::End(ref e)) if e.name() == QName(b"table:table-row") => break,
             Err(e) => return Err(OdsError::Xml(e)),
@@ -554,6 +1367,8 @@ fn get_datatype(
     reader: &mut OdsReader<'_>,
     atts: Attributes<'_>,
     buf: &mut Vec<u8>,
+    extras: &mut CellExtras,
+    text_fonts: &HashMap<String, Font>,
 ) -> Result<(Data, String, bool), OdsError> {
     let mut is_string = false;
     let mut is_value_set = false;
@@ -601,23 +1416,53 @@ fn get_datatype(
         // is not present, the element content defines the value.
         let mut s = String::new();
         let mut first_paragraph = true;
+        // fonts of the nested spans and the runs read so far
+        let mut spans: Vec<Font> = Vec::new();
+        let mut runs = RichText::default();
+        let mut is_rich = false;
         loop {
             buf.clear();
             match reader.read_event_into(buf) {
                 Ok(Event::Text(ref e)) => {
-                    s.push_str(&e.unescape()?);
+                    let text = e.unescape()?;
+                    s.push_str(&text);
+                    push_run(&mut runs, &text, spans.last());
                 }
                 Ok(Event::End(ref e))
                     if e.name() == QName(b"table:table-cell")
                         || e.name() == QName(b"table:covered-table-cell") =>
                 {
+                    if is_rich {
+                        extras.rich_text = Some(runs);
+                    }
                     return Ok((Data::String(s), formula, true));
                 }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"text:span") => {
+                    is_rich = true;
+                    let font = match e.try_get_attribute(b"text:style-name")? {
+                        Some(name) => {
+                            let name = name.decode_and_unescape_value(reader)?;
+                            text_fonts.get(&*name).cloned().unwrap_or_default()
+                        }
+                        None => Font::default(),
+                    };
+                    spans.push(font);
+                }
+                Ok(Event::End(ref e)) if e.name() == QName(b"text:span") => {
+                    spans.pop();
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                    extras.annotation = Some(read_annotation(reader)?);
+                }
+                Ok(Event::Start(ref e)) if e.name() == QName(b"text:a") => {
+                    extras.link = Some(read_link(reader, e)?);
+                }
                 Ok(Event::Start(ref e)) if e.name() == QName(b"text:p") => {
                     if first_paragraph {
                         first_paragraph = false;
                     } else {
                         s.push('\n');
+                        push_run(&mut runs, "\n", None);
                     }
                 }
                 Ok(Event::Start(ref e)) if e.name() == QName(b"text:s") => {
@@ -629,9 +1474,9 @@ fn get_datatype(
                             .map_err(OdsError::ParseInt)?,
                         None => 1,
                     };
-                    for _ in 0..count {
-                        s.push(' ');
-                    }
+                    let spaces = " ".repeat(count);
+                    s.push_str(&spaces);
+                    push_run(&mut runs, &spaces, spans.last());
                 }
                 Err(e) => return Err(OdsError::Xml(e)),
                 Ok(Event::Eof) => return Err(OdsError::Eof("table:table-cell")),
@@ -643,6 +1488,128 @@ fn get_datatype(
     }
 }
 
+/// Appends some text to rich text runs, merging it with the last run if it has the same font
+fn push_run(rich: &mut RichText, text: &str, font: Option<&Font>) {
+    let default = Font::default();
+    let font = font.unwrap_or(&default);
+    match rich.runs.last_mut() {
+        Some((last, last_font)) if last_font == font => last.push_str(text),
+        _ => rich.runs.push((text.to_string(), font.clone())),
+    }
+}
+
+/// Skips the content of a table cell, but its annotation and link
+fn read_cell_end(
+    reader: &mut OdsReader<'_>,
+    cell: QName<'_>,
+    buf: &mut Vec<u8>,
+    extras: &mut CellExtras,
+) -> Result<(), OdsError> {
+    loop {
+        buf.clear();
+        match reader.read_event_into(buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"office:annotation") => {
+                extras.annotation = Some(read_annotation(reader)?);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:a") => {
+                extras.link = Some(read_link(reader, e)?);
+            }
+            Ok(Event::End(ref e)) if e.name() == cell => return Ok(()),
+            Ok(Event::Eof) => return Err(OdsError::Eof("table:table-cell")),
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the target and title of a `text:a` element
+///
+/// ODF 1.2-6.1.8
+fn read_link(
+    reader: &OdsReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<(String, Option<String>), OdsError> {
+    let mut href = String::new();
+    let mut title = None;
+    for a in e.attributes() {
+        let a = a.map_err(OdsError::XmlAttr)?;
+        match a.key {
+            QName(b"xlink:href") => {
+                href = a
+                    .decode_and_unescape_value(reader)
+                    .map_err(OdsError::Xml)?
+                    .into_owned()
+            }
+            QName(b"office:title") => {
+                title = Some(
+                    a.decode_and_unescape_value(reader)
+                        .map_err(OdsError::Xml)?
+                        .into_owned(),
+                )
+            }
+            _ => (),
+        }
+    }
+    Ok((href, title))
+}
+
+/// Reads an `office:annotation` element, returns its author and text
+///
+/// ODF 1.2-14.1
+fn read_annotation(reader: &mut OdsReader<'_>) -> Result<(Option<String>, String), OdsError> {
+    let mut buf = Vec::with_capacity(256);
+    let mut author = None;
+    let mut text = String::new();
+    let mut first_paragraph = true;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"dc:creator") => {
+                author = Some(read_text(reader, b"dc:creator")?);
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"dc:date") => {
+                reader.read_to_end_into(e.name(), &mut Vec::new())?;
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:p") => {
+                if !first_paragraph {
+                    text.push('\n');
+                }
+                first_paragraph = false;
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:s") => {
+                let count = match e.try_get_attribute("text:c")? {
+                    Some(c) => c
+                        .decode_and_unescape_value(reader)
+                        .map_err(OdsError::Xml)?
+                        .parse()
+                        .map_err(OdsError::ParseInt)?,
+                    None => 1,
+                };
+                text.extend(std::iter::repeat(' ').take(count));
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:line-break") => text.push('\n'),
+            Ok(Event::Text(ref e)) => text.push_str(&e.unescape()?),
+            Ok(Event::End(ref e)) if e.name() == QName(b"office:annotation") => break,
+            Ok(Event::Eof) => return Err(OdsError::Eof("office:annotation")),
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok((author, text))
+}
+
+/// Gets the default cell style of a column
+fn column_style(column_styles: &[(usize, Option<String>)], col: usize) -> Option<&str> {
+    let mut end = 0;
+    for (repeats, style) in column_styles {
+        end += repeats;
+        if col < end {
+            return style.as_deref();
+        }
+    }
+    None
+}
+
 fn read_named_expressions(reader: &mut OdsReader<'_>) -> Result<Vec<(String, String)>, OdsError> {
     let mut defined_names = Vec::new();
     let mut buf = Vec::with_capacity(512);
@@ -691,6 +1658,994 @@ fn read_named_expressions(reader: &mut OdsReader<'_>) -> Result<Vec<(String, Str
     Ok(defined_names)
 }
 
+/// Reads a `table:database-range`, skipping the anonymous ranges LibreOffice creates for
+/// the sheets autofilters
+fn read_database_range(
+    reader: &OdsReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<Option<TableMetadata>, OdsError> {
+    let mut name = String::new();
+    let mut address = String::new();
+    let mut has_header = true;
+    let mut has_filter = false;
+    for a in e.attributes() {
+        let a = a.map_err(OdsError::XmlAttr)?;
+        let value = a.decode_and_unescape_value(reader).map_err(OdsError::Xml)?;
+        match a.key {
+            QName(b"table:name") => name = value.into_owned(),
+            QName(b"table:target-range-address") => address = value.into_owned(),
+            QName(b"table:contains-header") => has_header = value != "false",
+            QName(b"table:display-filter-buttons") => has_filter = value == "true",
+            _ => (),
+        }
+    }
+    if name.starts_with("__Anonymous_Sheet_DB__") {
+        return Ok(None);
+    }
+    let reference = match parse_defined_name(&address) {
+        Ok(Expr::Reference(reference)) => reference,
+        _ => return Ok(None),
+    };
+    let (start, end) = (reference.start, reference.end);
+    match (reference.sheet, start.row, start.col, end.row, end.col) {
+        (Some(sheet_name), Some(r1), Some(c1), Some(r2), Some(c2)) => {
+            let range = Dimensions::new((r1, c1), (r2, c2));
+            Ok(Some(TableMetadata {
+                name,
+                sheet_name,
+                range,
+                header_row_count: has_header as u32,
+                auto_filter: Some(range).filter(|_| has_filter),
+                ..TableMetadata::default()
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Reads the autofilter of a sheet, stored by LibreOffice as an anonymous `table:database-range`
+///
+/// Returns the sheet name and the autofilter, the other database ranges being left unread.
+fn read_auto_filter(
+    reader: &mut OdsReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<Option<(String, AutoFilter)>, OdsError> {
+    let name = get_attribute(reader, e, b"table:name")?.unwrap_or_default();
+    let buttons = get_attribute(reader, e, b"table:display-filter-buttons")?;
+    if !name.starts_with("__Anonymous_Sheet_DB__") || buttons.as_deref() != Some("true") {
+        return Ok(None);
+    }
+    let address = get_attribute(reader, e, b"table:target-range-address")?.unwrap_or_default();
+    let has_header =
+        get_attribute(reader, e, b"table:contains-header")?.as_deref() != Some("false");
+    let reference = match parse_defined_name(&address) {
+        Ok(Expr::Reference(reference)) => reference,
+        _ => return Ok(None),
+    };
+    let (start, end) = (reference.start, reference.end);
+    let (sheet_name, range) = match (reference.sheet, start.row, start.col, end.row, end.col) {
+        (Some(sheet_name), Some(r1), Some(c1), Some(r2), Some(c2)) => {
+            (sheet_name, Dimensions::new((r1, c1), (r2, c2)))
+        }
+        _ => return Ok(None),
+    };
+    let mut filter = AutoFilter {
+        range,
+        ..AutoFilter::default()
+    };
+    let mut and = false;
+    let mut condition: Option<(u32, String, Data)> = None;
+    let mut set_items = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:filter-and") => and = true,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:filter-or") => and = false,
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:filter-condition") => {
+                let field = get_attribute(reader, e, b"table:field-number")?;
+                let operator = get_attribute(reader, e, b"table:operator")?;
+                let value = get_attribute(reader, e, b"table:value")?.unwrap_or_default();
+                let data_type = get_attribute(reader, e, b"table:data-type")?;
+                let value = match value.parse() {
+                    Ok(f) if data_type.as_deref() == Some("number") => Data::Float(f),
+                    _ => Data::String(value),
+                };
+                condition = Some((
+                    field.and_then(|f| f.parse().ok()).unwrap_or(0),
+                    operator.unwrap_or_else(|| "=".to_string()),
+                    value,
+                ));
+                set_items.clear();
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:filter-set-item") => {
+                set_items.push(get_attribute(reader, e, b"table:value")?.unwrap_or_default());
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:filter-condition") => {
+                if let Some((index, operator, value)) = condition.take() {
+                    let items = std::mem::take(&mut set_items);
+                    push_filter_condition(&mut filter.columns, index, &operator, value, items, and);
+                }
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:sort-by") => {
+                let field = get_attribute(reader, e, b"table:field-number")?;
+                let order = get_attribute(reader, e, b"table:order")?;
+                let field: u32 = field.and_then(|f| f.parse().ok()).unwrap_or(0);
+                let sort = filter.sort.get_or_insert_with(|| SortState {
+                    range: Dimensions::new(
+                        (range.start.0 + has_header as u32, range.start.1),
+                        range.end,
+                    ),
+                    conditions: Vec::new(),
+                });
+                sort.conditions.push(SortCondition {
+                    column: range.start.1 + field,
+                    descending: order.as_deref() == Some("descending"),
+                });
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:database-range") => break,
+            Ok(Event::Eof) => return Err(OdsError::Eof("table:database-range")),
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(Some((sheet_name, filter)))
+}
+
+/// Adds a `table:filter-condition` to the criteria of its column
+///
+/// Text operators are converted to wildcards, like Excel does. Several conditions on the
+/// same column are combined as custom conditions, joined by `and`.
+fn push_filter_condition(
+    columns: &mut Vec<FilterColumn>,
+    index: u32,
+    operator: &str,
+    value: Data,
+    set_items: Vec<String>,
+    and: bool,
+) {
+    let text = value.to_string();
+    let (operator, value) = match operator {
+        "=" if !set_items.is_empty() => {
+            let blank = set_items.iter().any(|v| v.is_empty());
+            let values = set_items.into_iter().filter(|v| !v.is_empty()).collect();
+            columns.push(FilterColumn {
+                index,
+                filter: Filter::Values { values, blank },
+            });
+            return;
+        }
+        "empty" => {
+            columns.push(FilterColumn {
+                index,
+                filter: Filter::Values {
+                    values: Vec::new(),
+                    blank: true,
+                },
+            });
+            return;
+        }
+        "top values" | "bottom values" | "top percent" | "bottom percent" => {
+            columns.push(FilterColumn {
+                index,
+                filter: Filter::Top10 {
+                    top: operator.starts_with("top"),
+                    percent: operator.ends_with("percent"),
+                    value: value.as_f64().unwrap_or(10.),
+                },
+            });
+            return;
+        }
+        "=" => (FilterOperator::Equal, value),
+        "!=" => (FilterOperator::NotEqual, value),
+        "<" => (FilterOperator::LessThan, value),
+        "<=" => (FilterOperator::LessThanOrEqual, value),
+        ">" => (FilterOperator::GreaterThan, value),
+        ">=" => (FilterOperator::GreaterThanOrEqual, value),
+        "!empty" => (FilterOperator::NotEqual, Data::Empty),
+        "contains" => (FilterOperator::Equal, Data::String(format!("*{text}*"))),
+        "!contains" => (FilterOperator::NotEqual, Data::String(format!("*{text}*"))),
+        "begins" => (FilterOperator::Equal, Data::String(format!("{text}*"))),
+        "!begins" => (FilterOperator::NotEqual, Data::String(format!("{text}*"))),
+        "ends" => (FilterOperator::Equal, Data::String(format!("*{text}"))),
+        "!ends" => (FilterOperator::NotEqual, Data::String(format!("*{text}"))),
+        _ => {
+            columns.push(FilterColumn {
+                index,
+                filter: Filter::Other,
+            });
+            return;
+        }
+    };
+    let condition = FilterCondition { operator, value };
+    match columns.iter_mut().find(|c| c.index == index) {
+        Some(FilterColumn {
+            filter: Filter::Custom { conditions, and: a },
+            ..
+        }) => {
+            conditions.push(condition);
+            *a = and;
+        }
+        _ => columns.push(FilterColumn {
+            index,
+            filter: Filter::Custom {
+                conditions: vec![condition],
+                and,
+            },
+        }),
+    }
+}
+
+/// Reads a `table:content-validation`, returning its name and the validation without
+/// its cells
+fn read_content_validation(
+    reader: &mut OdsReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<(String, DataValidation), OdsError> {
+    let name = get_attribute(reader, e, b"table:name")?.unwrap_or_default();
+    let condition = get_attribute(reader, e, b"table:condition")?.unwrap_or_default();
+    let mut validation = parse_validation_condition(&condition);
+    validation.allow_blank =
+        get_attribute(reader, e, b"table:allow-empty-cell")?.as_deref() != Some("false");
+    validation.show_dropdown =
+        get_attribute(reader, e, b"table:display-list")?.as_deref() != Some("none");
+    let mut message: Option<(bool, String)> = None;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:help-message") => {
+                validation.prompt_title = get_attribute(reader, e, b"table:title")?;
+                validation.show_prompt =
+                    get_attribute(reader, e, b"table:display")?.as_deref() == Some("true");
+                message = Some((false, String::new()));
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"table:error-message") => {
+                validation.error_title = get_attribute(reader, e, b"table:title")?;
+                validation.show_error =
+                    get_attribute(reader, e, b"table:display")?.as_deref() == Some("true");
+                validation.error_style =
+                    match get_attribute(reader, e, b"table:message-type")?.as_deref() {
+                        Some("warning") => DataValidationErrorStyle::Warning,
+                        Some("information") => DataValidationErrorStyle::Information,
+                        _ => DataValidationErrorStyle::Stop,
+                    };
+                message = Some((true, String::new()));
+            }
+            Ok(Event::Start(ref e)) if e.name() == QName(b"text:p") => {
+                if let Some((_, text)) = message.as_mut().filter(|(_, t)| !t.is_empty()) {
+                    text.push('\n');
+                }
+            }
+            Ok(Event::Text(ref t)) => {
+                if let Some((_, text)) = message.as_mut() {
+                    text.push_str(&t.unescape()?);
+                }
+            }
+            Ok(Event::End(ref e))
+                if e.name() == QName(b"table:help-message")
+                    || e.name() == QName(b"table:error-message") =>
+            {
+                if let Some((is_error, text)) = message.take() {
+                    let text = Some(text).filter(|t| !t.is_empty());
+                    match is_error {
+                        true => validation.error = text,
+                        false => validation.prompt = text,
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.name() == QName(b"table:content-validation") => break,
+            Ok(Event::Eof) => return Err(OdsError::Eof("table:content-validation")),
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    validation.prompt_title = validation.prompt_title.filter(|t| !t.is_empty());
+    validation.error_title = validation.error_title.filter(|t| !t.is_empty());
+    Ok((name, validation))
+}
+
+/// Parses the `table:condition` of a content validation, e.g.
+/// `of:cell-content-is-whole-number() and cell-content-is-between(1;10)`
+///
+/// Operands are translated to Excel A1 syntax and literal lists to a quoted, comma
+/// separated, list.
+fn parse_validation_condition(condition: &str) -> DataValidation {
+    let mut validation = DataValidation::default();
+    let condition = ["of:", "oooc:"]
+        .into_iter()
+        .find_map(|ns| condition.strip_prefix(ns))
+        .unwrap_or(condition);
+    let translate = |operand: &str| {
+        let operand = operand.trim();
+        open_formula_to_excel(operand).unwrap_or_else(|_| operand.to_string())
+    };
+    let (typ, condition) = [
+        ("cell-content-is-whole-number()", DataValidationType::Whole),
+        (
+            "cell-content-is-decimal-number()",
+            DataValidationType::Decimal,
+        ),
+        ("cell-content-is-date()", DataValidationType::Date),
+        ("cell-content-is-time()", DataValidationType::Time),
+    ]
+    .into_iter()
+    .find_map(|(prefix, typ)| {
+        let condition = condition.strip_prefix(prefix)?.trim_start();
+        Some((typ, condition.strip_prefix("and")?.trim_start()))
+    })
+    .unwrap_or((DataValidationType::Any, condition.trim()));
+    validation.typ = typ;
+    if let Some(args) = function_args(condition, "cell-content-is-in-list") {
+        validation.typ = DataValidationType::List;
+        let args = split_args(args);
+        validation.formula1 = Some(if args.iter().all(|a| a.starts_with('"')) {
+            let values = args
+                .iter()
+                .map(|a| a.trim_matches('"').replace("\"\"", "\""));
+            format!("\"{}\"", values.collect::<Vec<_>>().join(","))
+        } else {
+            translate(args.first().copied().unwrap_or_default())
+        });
+        return validation;
+    }
+    if let Some(formula) = function_args(condition, "is-true-formula") {
+        validation.typ = DataValidationType::Custom;
+        validation.formula1 = Some(translate(formula));
+        return validation;
+    }
+    let condition = match condition.strip_prefix("cell-content-text-length") {
+        Some(condition) => {
+            validation.typ = DataValidationType::TextLength;
+            condition
+        }
+        None => condition.strip_prefix("cell-content").unwrap_or(condition),
+    };
+    for (prefix, operator) in [
+        ("-is-between(", DataValidationOperator::Between),
+        ("-is-not-between(", DataValidationOperator::NotBetween),
+    ] {
+        if let Some(args) = condition
+            .strip_prefix(prefix)
+            .and_then(|a| a.strip_suffix(')'))
+        {
+            let args = split_args(args);
+            validation.operator = operator;
+            validation.formula1 = args.first().map(|a| translate(a));
+            validation.formula2 = args.get(1).map(|a| translate(a));
+            return validation;
+        }
+    }
+    if let Some(comparison) = condition.strip_prefix("()").map(str::trim_start) {
+        for (prefix, operator) in [
+            ("<=", DataValidationOperator::LessThanOrEqual),
+            (">=", DataValidationOperator::GreaterThanOrEqual),
+            ("!=", DataValidationOperator::NotEqual),
+            ("<", DataValidationOperator::LessThan),
+            (">", DataValidationOperator::GreaterThan),
+            ("=", DataValidationOperator::Equal),
+        ] {
+            if let Some(value) = comparison.strip_prefix(prefix) {
+                validation.operator = operator;
+                validation.formula1 = Some(translate(value));
+                break;
+            }
+        }
+    }
+    validation
+}
+
+/// Returns the arguments of a `name(args)` call
+fn function_args<'a>(call: &'a str, name: &str) -> Option<&'a str> {
+    call.strip_prefix(name)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// Splits the `;` separated arguments of a function, out of strings, parentheses and references
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut in_string, mut start) = (0i32, false, 0);
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            ';' if !in_string && depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
+/// Data styles (number formats) and the cell styles referencing them
+#[derive(Default)]
+struct Styles {
+    /// Excel-like format code and conditional maps, by data style name
+    data_styles: HashMap<String, DataStyle>,
+    /// Cell styles, by name
+    cell_styles: HashMap<String, CellStyle>,
+    /// Text properties of the `text` family styles, by name
+    text_styles: HashMap<String, Vec<(String, String)>>,
+    /// Properties of the `table-cell` default style
+    default_properties: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct CellStyle {
+    data_style: Option<String>,
+    parent: Option<String>,
+    /// Text, paragraph and table cell properties, as (attribute, value)
+    properties: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct DataStyle {
+    code: String,
+    /// (condition, applied data style name)
+    maps: Vec<(String, String)>,
+}
+
+impl Styles {
+    /// Reads a `style:style` or `style:default-style` node of the `table-cell` or `text` family
+    fn read_cell_style(
+        &mut self,
+        reader: &mut OdsReader<'_>,
+        e: &BytesStart<'_>,
+    ) -> Result<(), OdsError> {
+        let family = get_attribute(reader, e, b"style:family")?;
+        if !matches!(family.as_deref(), Some("table-cell" | "text")) {
+            return Ok(());
+        }
+        let name = get_attribute(reader, e, b"style:name")?;
+        let data_style = get_attribute(reader, e, b"style:data-style-name")?;
+        let parent = get_attribute(reader, e, b"style:parent-style-name")?;
+        let end = e.name().as_ref().to_vec();
+        let mut properties = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e))
+                    if matches!(
+                        e.name().as_ref(),
+                        b"style:text-properties"
+                            | b"style:paragraph-properties"
+                            | b"style:table-cell-properties"
+                    ) =>
+                {
+                    for a in e.attributes() {
+                        let a = a.map_err(OdsError::XmlAttr)?;
+                        let key = reader.decoder().decode(a.key.as_ref())?.into_owned();
+                        let value = a.decode_and_unescape_value(reader)?.into_owned();
+                        properties.push((key, value));
+                    }
+                }
+                Ok(Event::End(ref e)) if e.name().as_ref() == end => break,
+                Err(e) => return Err(OdsError::Xml(e)),
+                Ok(Event::Eof) => return Err(OdsError::Eof("style:style")),
+                _ => (),
+            }
+            buf.clear();
+        }
+        match name {
+            Some(name) if family.as_deref() == Some("text") => {
+                self.text_styles.insert(name, properties);
+            }
+            Some(name) => {
+                let style = CellStyle {
+                    data_style,
+                    parent,
+                    properties,
+                };
+                self.cell_styles.insert(name, style);
+            }
+            None if end == b"style:default-style" => self.default_properties = properties,
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Reads a `number:*-style` node and converts it into an Excel-like format code
+    fn read_data_style(
+        &mut self,
+        reader: &mut OdsReader<'_>,
+        e: &BytesStart<'_>,
+    ) -> Result<(), OdsError> {
+        let end = e.name().as_ref().to_vec();
+        let Some(name) = get_attribute(reader, e, b"style:name")? else {
+            reader.read_to_end_into(QName(&end), &mut Vec::new())?;
+            return Ok(());
+        };
+        let is_date = matches!(
+            e.name().as_ref(),
+            b"number:date-style" | b"number:time-style"
+        );
+        let is_percentage = e.name() == QName(b"number:percentage-style");
+        let elapsed = e.name() == QName(b"number:time-style")
+            && get_attribute(reader, e, b"number:truncate-on-overflow")?.as_deref()
+                == Some("false");
+        let mut style = DataStyle::default();
+        let mut color = None;
+        let mut has_hours = false;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) => {
+                    let is_long = || -> Result<bool, OdsError> {
+                        Ok(get_attribute(reader, e, b"number:style")?.as_deref() == Some("long"))
+                    };
+                    let count = |attr: &[u8], default: usize| -> Result<usize, OdsError> {
+                        match get_attribute(reader, e, attr)? {
+                            Some(v) => v.parse().map_err(OdsError::ParseInt),
+                            None => Ok(default),
+                        }
+                    };
+                    match e.name().as_ref() {
+                        b"number:number" => {
+                            let grouping = get_attribute(reader, e, b"number:grouping")?.as_deref()
+                                == Some("true");
+                            let integers = count(b"number:min-integer-digits", 1)?;
+                            let decimals = count(b"number:decimal-places", 0)?;
+                            let code = &mut style.code;
+                            if grouping {
+                                code.push_str(match integers {
+                                    0 => "#,###",
+                                    1 => "#,##0",
+                                    2 => "#,#00",
+                                    _ => "#,000",
+                                });
+                                code.extend(
+                                    std::iter::repeat('0').take(integers.saturating_sub(4)),
+                                );
+                            } else if integers == 0 {
+                                code.push('#');
+                            } else {
+                                code.extend(std::iter::repeat('0').take(integers));
+                            }
+                            push_decimals(code, decimals);
+                        }
+                        b"number:scientific-number" => {
+                            let decimals = count(b"number:decimal-places", 0)?;
+                            let exponent = count(b"number:min-exponent-digits", 2)?;
+                            style.code.push('0');
+                            push_decimals(&mut style.code, decimals);
+                            style.code.push_str("E+");
+                            style
+                                .code
+                                .extend(std::iter::repeat('0').take(exponent.max(1)));
+                        }
+                        b"number:fraction" => {
+                            let integers = count(b"number:min-integer-digits", 0)?;
+                            let numerator = count(b"number:min-numerator-digits", 1)?;
+                            let code = &mut style.code;
+                            code.push_str(if integers == 0 { "# " } else { "0 " });
+                            code.extend(std::iter::repeat('?').take(numerator.max(1)));
+                            code.push('/');
+                            match get_attribute(reader, e, b"number:denominator-value")? {
+                                Some(d) => code.push_str(&d),
+                                None => {
+                                    let denominator = count(b"number:min-denominator-digits", 1)?;
+                                    code.extend(std::iter::repeat('?').take(denominator.max(1)));
+                                }
+                            }
+                        }
+                        b"number:year" => {
+                            style.code.push_str(if is_long()? { "yyyy" } else { "yy" })
+                        }
+                        b"number:month" => {
+                            let textual = get_attribute(reader, e, b"number:textual")?.as_deref()
+                                == Some("true");
+                            style.code.push_str(match (textual, is_long()?) {
+                                (true, true) => "mmmm",
+                                (true, false) => "mmm",
+                                (false, true) => "mm",
+                                (false, false) => "m",
+                            });
+                        }
+                        b"number:day" => style.code.push_str(if is_long()? { "dd" } else { "d" }),
+                        b"number:day-of-week" => {
+                            style.code.push_str(if is_long()? { "dddd" } else { "ddd" })
+                        }
+                        b"number:hours" => {
+                            let hours = if is_long()? { "hh" } else { "h" };
+                            if elapsed && !has_hours {
+                                style.code.push('[');
+                                style.code.push_str(hours);
+                                style.code.push(']');
+                            } else {
+                                style.code.push_str(hours);
+                            }
+                            has_hours = true;
+                        }
+                        b"number:minutes" => {
+                            style.code.push_str(if is_long()? { "mm" } else { "m" })
+                        }
+                        b"number:seconds" => {
+                            let decimals = count(b"number:decimal-places", 0)?;
+                            style.code.push_str(if is_long()? { "ss" } else { "s" });
+                            push_decimals(&mut style.code, decimals);
+                        }
+                        b"number:am-pm" => style.code.push_str("AM/PM"),
+                        b"number:text-content" => style.code.push('@'),
+                        b"number:boolean" => style.code.push_str("General"),
+                        b"number:text" => {
+                            let text = read_text(reader, b"number:text")?;
+                            if is_percentage && text == "%" {
+                                style.code.push('%');
+                            } else {
+                                push_literal(&mut style.code, &text, is_date);
+                            }
+                        }
+                        b"number:currency-symbol" => {
+                            let text = read_text(reader, b"number:currency-symbol")?;
+                            push_literal(&mut style.code, &text, false);
+                        }
+                        b"style:text-properties" => {
+                            color =
+                                get_attribute(reader, e, b"fo:color")?.and_then(|c| color_name(&c));
+                        }
+                        b"style:map" => {
+                            let condition = get_attribute(reader, e, b"style:condition")?;
+                            let applied = get_attribute(reader, e, b"style:apply-style-name")?;
+                            if let (Some(condition), Some(applied)) = (condition, applied) {
+                                style.maps.push((condition, applied));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(Event::End(ref e)) if e.name().as_ref() == end => break,
+                Err(e) => return Err(OdsError::Xml(e)),
+                Ok(Event::Eof) => return Err(OdsError::Eof("number:number-style")),
+                _ => (),
+            }
+            buf.clear();
+        }
+        if let Some(color) = color {
+            style.code.insert_str(0, color);
+        }
+        self.data_styles.insert(name, style);
+        Ok(())
+    }
+
+    /// Gets the format code of a data style, including its conditional sections
+    fn format_code(&self, name: &str) -> Option<String> {
+        let style = self.data_styles.get(name)?;
+        if style.maps.is_empty() {
+            return Some(style.code.clone());
+        }
+        let mut code = String::new();
+        for (condition, applied) in &style.maps {
+            let applied = self
+                .data_styles
+                .get(applied)
+                .map_or("General", |s| s.code.as_str());
+            let condition = condition.trim().trim_start_matches("value()").trim();
+            // a lone `>=0` section is Excel's implicit positive section
+            if !(style.maps.len() == 1 && condition == ">=0") {
+                code.push('[');
+                code.push_str(condition);
+                code.push(']');
+            }
+            code.push_str(applied);
+            code.push(';');
+        }
+        code.push_str(&style.code);
+        Some(code)
+    }
+
+    /// Resolves the format code of every cell style
+    fn number_formats(&self) -> HashMap<String, Arc<str>> {
+        let mut codes: HashMap<&str, Arc<str>> = HashMap::new();
+        let mut formats = HashMap::new();
+        for name in self.cell_styles.keys() {
+            // follow parents until a data style is found
+            let Some(data_style) = self
+                .ancestors(name)
+                .find_map(|style| style.data_style.as_deref())
+            else {
+                continue;
+            };
+            let code = match codes.get(data_style) {
+                Some(code) => code.clone(),
+                None => match self.format_code(data_style) {
+                    Some(code) => {
+                        let code: Arc<str> = code.into();
+                        codes.insert(data_style, code.clone());
+                        code
+                    }
+                    None => continue,
+                },
+            };
+            formats.insert(name.clone(), code);
+        }
+        formats
+    }
+
+    /// Iterates over a cell style and its parents
+    fn ancestors<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a CellStyle> {
+        let mut current = self.cell_styles.get(name);
+        // bound the depth in case of a parent cycle
+        (0..self.cell_styles.len()).map_while(move |_| {
+            let style = current?;
+            current = style
+                .parent
+                .as_deref()
+                .and_then(|p| self.cell_styles.get(p));
+            Some(style)
+        })
+    }
+
+    /// Resolves the fonts of the text styles
+    fn text_fonts(&self) -> HashMap<String, Font> {
+        self.text_styles
+            .iter()
+            .map(|(name, properties)| {
+                let mut style = Style::default();
+                for (key, value) in properties {
+                    apply_style_property(&mut style, key, value);
+                }
+                (name.clone(), style.font)
+            })
+            .collect()
+    }
+
+    /// Resolves the cell styles into a style table, the default style being first
+    ///
+    /// Returns the style table and the index of each cell style
+    fn cell_styles(&self) -> (Vec<Style>, HashMap<String, usize>) {
+        let mut default = Style::default();
+        for (key, value) in &self.default_properties {
+            apply_style_property(&mut default, key, value);
+        }
+        let mut names: Vec<&String> = self.cell_styles.keys().collect();
+        names.sort();
+        let mut styles = vec![default.clone()];
+        let mut ids = HashMap::with_capacity(names.len());
+        for name in names {
+            let chain: Vec<_> = self.ancestors(name).collect();
+            let mut style = default.clone();
+            for (key, value) in chain.iter().rev().flat_map(|s| &s.properties) {
+                apply_style_property(&mut style, key, value);
+            }
+            ids.insert(name.clone(), styles.len());
+            styles.push(style);
+        }
+        (styles, ids)
+    }
+}
+
+/// Applies a `style:*-properties` attribute to a style
+fn apply_style_property(style: &mut Style, key: &str, value: &str) {
+    match key {
+        "fo:font-weight" => {
+            style.font.bold = value == "bold" || value.parse::<u32>().is_ok_and(|w| w >= 600)
+        }
+        "fo:font-style" => style.font.italic = value == "italic" || value == "oblique",
+        "style:text-underline-style" => style.font.underline = value != "none",
+        "style:text-line-through-style" => style.font.strikethrough = value != "none",
+        "fo:color" => style.font.color = Color::from_hex(value),
+        "fo:font-size" => {
+            if let Some(size) = value.strip_suffix("pt").and_then(|v| v.parse().ok()) {
+                style.font.size = Some(size);
+            }
+        }
+        "style:font-name" => style.font.name = Some(value.to_string()),
+        "fo:background-color" => {
+            style.fill = match Color::from_hex(value) {
+                Some(color) => Fill {
+                    pattern: FillPattern::Solid,
+                    foreground: Some(color),
+                    background: None,
+                },
+                None => Fill::default(),
+            }
+        }
+        "fo:border" => {
+            let border = parse_border(value);
+            style.borders = Borders {
+                left: border.clone(),
+                right: border.clone(),
+                top: border.clone(),
+                bottom: border,
+            };
+        }
+        "fo:border-left" => style.borders.left = parse_border(value),
+        "fo:border-right" => style.borders.right = parse_border(value),
+        "fo:border-top" => style.borders.top = parse_border(value),
+        "fo:border-bottom" => style.borders.bottom = parse_border(value),
+        "fo:wrap-option" => style.alignment.wrap_text = value == "wrap",
+        "style:shrink-to-fit" => style.alignment.shrink_to_fit = value == "true",
+        "style:vertical-align" => {
+            style.alignment.vertical = match value {
+                "top" => VerticalAlignment::Top,
+                "middle" => VerticalAlignment::Center,
+                _ => VerticalAlignment::Bottom,
+            }
+        }
+        "fo:text-align" => {
+            style.alignment.horizontal = match value {
+                "start" | "left" => HorizontalAlignment::Left,
+                "center" => HorizontalAlignment::Center,
+                "end" | "right" => HorizontalAlignment::Right,
+                "justify" => HorizontalAlignment::Justify,
+                _ => HorizontalAlignment::General,
+            }
+        }
+        "style:text-align-source" if value == "value-type" => {
+            style.alignment.horizontal = HorizontalAlignment::General
+        }
+        "style:cell-protect" => {
+            style.protection.locked = value.contains("protected");
+            style.protection.hidden = value.contains("hidden");
+        }
+        _ => (),
+    }
+}
+
+/// Parses a `fo:border` value, e.g. "0.06pt solid #000000"
+fn parse_border(value: &str) -> Border {
+    let mut width = 0.75;
+    let mut line = "none";
+    let mut color = None;
+    for token in value.split_whitespace() {
+        if token.starts_with('#') {
+            color = Color::from_hex(token);
+        } else if let Some(w) = length_to_points(token) {
+            width = w;
+        } else {
+            line = token;
+        }
+    }
+    let style = match line {
+        "solid" if width >= 2.5 => BorderStyle::Thick,
+        "solid" if width >= 1.5 => BorderStyle::Medium,
+        "solid" => BorderStyle::Thin,
+        "dashed" if width >= 1.5 => BorderStyle::MediumDashed,
+        "dashed" => BorderStyle::Dashed,
+        "dotted" => BorderStyle::Dotted,
+        "dash-dot" => BorderStyle::DashDot,
+        "dash-dot-dot" => BorderStyle::DashDotDot,
+        "double" | "double-thin" => BorderStyle::Double,
+        _ => return Border::default(),
+    };
+    Border { style, color }
+}
+
+/// Converts a length (e.g. "0.5pt", "0.1cm") into points
+fn length_to_points(length: &str) -> Option<f64> {
+    let split = length.find(|c: char| c.is_ascii_alphabetic())?;
+    let value: f64 = length[..split].parse().ok()?;
+    let factor = match &length[split..] {
+        "pt" => 1.,
+        "px" => 0.75,
+        "in" => 72.,
+        "cm" => 72. / 2.54,
+        "mm" => 72. / 25.4,
+        "pc" => 12.,
+        _ => return None,
+    };
+    Some(value * factor)
+}
+
+fn is_data_style(e: &BytesStart<'_>) -> bool {
+    matches!(
+        e.name().as_ref(),
+        b"number:number-style"
+            | b"number:currency-style"
+            | b"number:percentage-style"
+            | b"number:date-style"
+            | b"number:time-style"
+            | b"number:boolean-style"
+            | b"number:text-style"
+    )
+}
+
+/// Reads the common and automatic styles of styles.xml
+fn read_styles<RS: Read + Seek>(zip: &mut ZipArchive<RS>) -> Result<Styles, OdsError> {
+    let mut styles = Styles::default();
+    let mut reader = match zip.by_name("styles.xml") {
+        Ok(f) => {
+            let mut r = XmlReader::from_reader(BufReader::new(f));
+            r.check_end_names(false)
+                .trim_text(false)
+                .check_comments(false)
+                .expand_empty_elements(true);
+            r
+        }
+        Err(ZipError::FileNotFound) => return Ok(styles),
+        Err(e) => return Err(OdsError::Zip(e)),
+    };
+    let mut buf = Vec::with_capacity(1024);
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e))
+                if e.name() == QName(b"style:style")
+                    || e.name() == QName(b"style:default-style") =>
+            {
+                styles.read_cell_style(&mut reader, e)?;
+            }
+            Ok(Event::Start(ref e)) if is_data_style(e) => {
+                styles.read_data_style(&mut reader, e)?;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(OdsError::Xml(e)),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(styles)
+}
+
+fn get_attribute(
+    reader: &OdsReader<'_>,
+    e: &BytesStart<'_>,
+    name: &[u8],
+) -> Result<Option<String>, OdsError> {
+    e.try_get_attribute(name)?
+        .map(|a| a.decode_and_unescape_value(reader).map(|v| v.into_owned()))
+        .transpose()
+        .map_err(OdsError::Xml)
+}
+
+/// Reads the text content of a node up to its end
+fn read_text(reader: &mut OdsReader<'_>, end: &[u8]) -> Result<String, OdsError> {
+    let mut text = String::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Text(ref t)) => text.push_str(&t.unescape()?),
+            Ok(Event::End(ref e)) if e.name().as_ref() == end => return Ok(text),
+            Err(e) => return Err(OdsError::Xml(e)),
+            Ok(Event::Eof) => return Err(OdsError::Eof("number:text")),
+            _ => (),
+        }
+        buf.clear();
+    }
+}
+
+fn push_decimals(code: &mut String, decimals: usize) {
+    if decimals > 0 {
+        code.push('.');
+        code.extend(std::iter::repeat('0').take(decimals));
+    }
+}
+
+/// Appends a literal text, quoting it unless it only contains separators
+fn push_literal(code: &mut String, text: &str, is_date: bool) {
+    let separators: &[char] = if is_date {
+        &[' ', '-', '/', ':', '.', ',', '(', ')']
+    } else {
+        &[' ', '-', '(', ')']
+    };
+    if text.chars().all(|c| separators.contains(&c)) {
+        code.push_str(text);
+    } else {
+        code.push('"');
+        code.push_str(&text.replace('"', "\\\""));
+        code.push('"');
+    }
+}
+
+/// Converts a `fo:color` into one of the Excel named colors
+fn color_name(color: &str) -> Option<&'static str> {
+    match color.to_ascii_lowercase().as_str() {
+        "#000000" => Some("[Black]"),
+        "#ffffff" => Some("[White]"),
+        "#ff0000" => Some("[Red]"),
+        "#00ff00" => Some("[Green]"),
+        "#0000ff" => Some("[Blue]"),
+        "#ffff00" => Some("[Yellow]"),
+        "#ff00ff" => Some("[Magenta]"),
+        "#00ffff" => Some("[Cyan]"),
+        _ => None,
+    }
+}
+
 /// Read pictures
 #[cfg(feature = "picture")]
 fn read_pictures<RS: Read + Seek>(
@@ -723,3 +2678,70 @@ fn read_pictures<RS: Read + Seek>(
         Ok(Some(pics))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn fixtures() -> Vec<std::path::PathBuf> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "ods"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn lazy_sheets() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/issues.ods");
+        let mut ods = Ods::new(BufReader::new(File::open(path).unwrap())).unwrap();
+        let names = ods.sheet_names();
+        assert!(names.len() > 1);
+        assert!(ods.sheets.is_empty());
+        ods.worksheet_rows(&names[1]).unwrap().for_each(drop);
+        assert!(ods.sheets.is_empty());
+        ods.worksheet_range(&names[1]).unwrap();
+        assert_eq!(ods.sheets.keys().collect::<Vec<_>>(), [&names[1]]);
+    }
+
+    /// Tables read from their offset match the tables read in a single pass over
+    /// content.xml, as they were when all sheets were parsed on opening
+    #[test]
+    fn lazy_sheets_match_eager() {
+        for path in fixtures() {
+            let Ok(mut ods) = Ods::new(BufReader::new(File::open(&path).unwrap())) else {
+                continue;
+            };
+            let mut eager = Vec::new();
+            let mut reader = content_reader(ods.zip.by_name("content.xml").unwrap());
+            let mut buf = Vec::new();
+            loop {
+                match reader.read_event_into(&mut buf).unwrap() {
+                    Event::Start(ref e) if e.name() == QName(b"table:table") => {
+                        let sheet =
+                            read_table(&mut reader, &ods.text_fonts, &ods.row_column_styles);
+                        eager.push(format!("{:?}", sheet.unwrap()));
+                    }
+                    Event::Eof => break,
+                    _ => (),
+                }
+                buf.clear();
+            }
+            drop(reader);
+
+            let names = ods.sheet_names();
+            assert_eq!(names.len(), eager.len(), "{path:?}");
+            for (name, eager) in names.iter().zip(&eager).rev() {
+                let lazy = format!("{:?}", ods.sheet(name).unwrap());
+                // tables without rows have no offset
+                if ods.sheet_offsets[name].is_some() {
+                    assert_eq!(&lazy, eager, "{path:?} {name}");
+                }
+            }
+        }
+    }
+}
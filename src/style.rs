@@ -0,0 +1,517 @@
+//! Cell styles: fonts, fills, borders, alignment and protection
+//!
+//! Every workbook exposes a style table (see [`Reader::styles`]) and the style of each cell can
+//! be read with [`Reader::worksheet_styles`], which returns a [`StyleId`] per cell.
+//! Strings formatted with several fonts are read with [`Reader::worksheet_rich_text`].
+//!
+//! [`Reader::styles`]: crate::Reader::styles
+//! [`Reader::worksheet_styles`]: crate::Reader::worksheet_styles
+//! [`Reader::worksheet_rich_text`]: crate::Reader::worksheet_rich_text
+
+/// Index of a [`Style`] in the workbook style table
+///
+/// Cells without any style information are at index 0, the workbook default style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StyleId(pub usize);
+
+/// A cell style
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    /// Font
+    pub font: Font,
+    /// Background fill
+    pub fill: Fill,
+    /// Cell borders
+    pub borders: Borders,
+    /// Text alignment
+    pub alignment: Alignment,
+    /// Locked and hidden flags
+    pub protection: CellProtection,
+}
+
+/// A color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// An explicit red, green, blue color
+    Rgb(u8, u8, u8),
+    /// An index in the workbook palette
+    Indexed(u32),
+    /// An index in the theme colors
+    Theme(u32),
+}
+
+impl Color {
+    /// Gets the red, green and blue components of the color
+    ///
+    /// Indexed colors are resolved with the default Excel palette. Theme colors are not resolved.
+    pub fn to_rgb(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            Color::Indexed(i) => {
+                let c = *DEFAULT_PALETTE.get(i as usize)?;
+                Some(((c >> 16) as u8, (c >> 8) as u8, c as u8))
+            }
+            Color::Theme(_) => None,
+        }
+    }
+
+    /// Parses a `RRGGBB` or `AARRGGBB` hexadecimal color, with an optional leading `#`
+    pub(crate) fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.trim_start_matches('#');
+        let hex = match hex.len() {
+            6 => hex,
+            8 => &hex[2..],
+            _ => return None,
+        };
+        let c = u32::from_str_radix(hex, 16).ok()?;
+        Some(Color::Rgb((c >> 16) as u8, (c >> 8) as u8, c as u8))
+    }
+
+    /// Gets an indexed color, `None` for system colors (64 and above)
+    pub(crate) fn from_index(index: u32) -> Option<Color> {
+        if (index as usize) < DEFAULT_PALETTE.len() {
+            Some(Color::Indexed(index))
+        } else {
+            None
+        }
+    }
+}
+
+/// Font properties
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Font {
+    /// Font name
+    pub name: Option<String>,
+    /// Size in points
+    pub size: Option<f64>,
+    /// Bold
+    pub bold: bool,
+    /// Italic
+    pub italic: bool,
+    /// Underline
+    pub underline: bool,
+    /// Strikethrough
+    pub strikethrough: bool,
+    /// Color, `None` if automatic
+    pub color: Option<Color>,
+}
+
+/// A string made of differently formatted runs
+///
+/// Runs without formatting of their own have a default [`Font`]: they are displayed with the
+/// font of the cell style.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+    /// Text runs, with their font
+    pub runs: Vec<(String, Font)>,
+}
+
+impl RichText {
+    /// Builds a rich text from a string and the (character position, font) where runs start
+    pub(crate) fn from_runs(text: &str, runs: impl IntoIterator<Item = (usize, Font)>) -> Self {
+        let mut rich = RichText::default();
+        let mut chars = text.chars();
+        let mut pos = 0;
+        let mut font = Font::default();
+        for (start, next) in runs {
+            let run = chars
+                .by_ref()
+                .take(start.saturating_sub(pos))
+                .collect::<String>();
+            pos += run.chars().count();
+            if !run.is_empty() {
+                rich.runs.push((run, font));
+            }
+            font = next;
+        }
+        let run = chars.collect::<String>();
+        if !run.is_empty() {
+            rich.runs.push((run, font));
+        }
+        rich
+    }
+
+    /// Gets the text, without formatting
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|(s, _)| s.as_str()).collect()
+    }
+
+    /// Whether the text is empty
+    pub fn is_empty(&self) -> bool {
+        self.runs.iter().all(|(s, _)| s.is_empty())
+    }
+}
+
+/// Cell background fill
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fill {
+    /// Pattern
+    pub pattern: FillPattern,
+    /// Pattern (foreground) color
+    pub foreground: Option<Color>,
+    /// Background color
+    pub background: Option<Color>,
+}
+
+impl Fill {
+    /// Gets the color a cell appears filled with, if any
+    pub fn color(&self) -> Option<Color> {
+        match self.pattern {
+            FillPattern::None => None,
+            FillPattern::Solid => self.foreground.or(self.background),
+            _ => self.background.or(self.foreground),
+        }
+    }
+}
+
+/// Fill pattern
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FillPattern {
+    /// No fill
+    #[default]
+    None,
+    /// Solid color
+    Solid,
+    /// 50% gray
+    MediumGray,
+    /// 75% gray
+    DarkGray,
+    /// 25% gray
+    LightGray,
+    /// Horizontal stripes
+    DarkHorizontal,
+    /// Vertical stripes
+    DarkVertical,
+    /// Reverse diagonal stripes
+    DarkDown,
+    /// Diagonal stripes
+    DarkUp,
+    /// Diagonal crosshatch
+    DarkGrid,
+    /// Thick diagonal crosshatch
+    DarkTrellis,
+    /// Thin horizontal stripes
+    LightHorizontal,
+    /// Thin vertical stripes
+    LightVertical,
+    /// Thin reverse diagonal stripes
+    LightDown,
+    /// Thin diagonal stripes
+    LightUp,
+    /// Thin horizontal crosshatch
+    LightGrid,
+    /// Thin diagonal crosshatch
+    LightTrellis,
+    /// 12.5% gray
+    Gray125,
+    /// 6.25% gray
+    Gray0625,
+}
+
+impl FillPattern {
+    const ALL: [FillPattern; 19] = [
+        FillPattern::None,
+        FillPattern::Solid,
+        FillPattern::MediumGray,
+        FillPattern::DarkGray,
+        FillPattern::LightGray,
+        FillPattern::DarkHorizontal,
+        FillPattern::DarkVertical,
+        FillPattern::DarkDown,
+        FillPattern::DarkUp,
+        FillPattern::DarkGrid,
+        FillPattern::DarkTrellis,
+        FillPattern::LightHorizontal,
+        FillPattern::LightVertical,
+        FillPattern::LightDown,
+        FillPattern::LightUp,
+        FillPattern::LightGrid,
+        FillPattern::LightTrellis,
+        FillPattern::Gray125,
+        FillPattern::Gray0625,
+    ];
+
+    /// Gets the pattern from its xls/xlsb `fls` index
+    pub(crate) fn from_index(index: u32) -> FillPattern {
+        Self::ALL
+            .get(index as usize)
+            .copied()
+            .unwrap_or(FillPattern::None)
+    }
+
+    /// Gets the pattern from its xlsx `patternType` name
+    pub(crate) fn from_name(name: &[u8]) -> FillPattern {
+        match name {
+            b"solid" => FillPattern::Solid,
+            b"mediumGray" => FillPattern::MediumGray,
+            b"darkGray" => FillPattern::DarkGray,
+            b"lightGray" => FillPattern::LightGray,
+            b"darkHorizontal" => FillPattern::DarkHorizontal,
+            b"darkVertical" => FillPattern::DarkVertical,
+            b"darkDown" => FillPattern::DarkDown,
+            b"darkUp" => FillPattern::DarkUp,
+            b"darkGrid" => FillPattern::DarkGrid,
+            b"darkTrellis" => FillPattern::DarkTrellis,
+            b"lightHorizontal" => FillPattern::LightHorizontal,
+            b"lightVertical" => FillPattern::LightVertical,
+            b"lightDown" => FillPattern::LightDown,
+            b"lightUp" => FillPattern::LightUp,
+            b"lightGrid" => FillPattern::LightGrid,
+            b"lightTrellis" => FillPattern::LightTrellis,
+            b"gray125" => FillPattern::Gray125,
+            b"gray0625" => FillPattern::Gray0625,
+            _ => FillPattern::None,
+        }
+    }
+}
+
+/// Left, right, top and bottom borders of a cell
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Borders {
+    /// Left border
+    pub left: Border,
+    /// Right border
+    pub right: Border,
+    /// Top border
+    pub top: Border,
+    /// Bottom border
+    pub bottom: Border,
+}
+
+/// A cell border
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Border {
+    /// Line style
+    pub style: BorderStyle,
+    /// Color, `None` if automatic
+    pub color: Option<Color>,
+}
+
+/// Border line style
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BorderStyle {
+    /// No border
+    #[default]
+    None,
+    /// Thin line
+    Thin,
+    /// Medium line
+    Medium,
+    /// Dashed line
+    Dashed,
+    /// Dotted line
+    Dotted,
+    /// Thick line
+    Thick,
+    /// Double line
+    Double,
+    /// Hairline
+    Hair,
+    /// Medium dashed line
+    MediumDashed,
+    /// Dash dot line
+    DashDot,
+    /// Medium dash dot line
+    MediumDashDot,
+    /// Dash dot dot line
+    DashDotDot,
+    /// Medium dash dot dot line
+    MediumDashDotDot,
+    /// Slanted dash dot line
+    SlantDashDot,
+}
+
+impl BorderStyle {
+    const ALL: [BorderStyle; 14] = [
+        BorderStyle::None,
+        BorderStyle::Thin,
+        BorderStyle::Medium,
+        BorderStyle::Dashed,
+        BorderStyle::Dotted,
+        BorderStyle::Thick,
+        BorderStyle::Double,
+        BorderStyle::Hair,
+        BorderStyle::MediumDashed,
+        BorderStyle::DashDot,
+        BorderStyle::MediumDashDot,
+        BorderStyle::DashDotDot,
+        BorderStyle::MediumDashDotDot,
+        BorderStyle::SlantDashDot,
+    ];
+
+    /// Gets the style from its xls/xlsb `dg` index
+    pub(crate) fn from_index(index: u32) -> BorderStyle {
+        Self::ALL
+            .get(index as usize)
+            .copied()
+            .unwrap_or(BorderStyle::None)
+    }
+
+    /// Gets the style from its xlsx `style` name
+    pub(crate) fn from_name(name: &[u8]) -> BorderStyle {
+        match name {
+            b"thin" => BorderStyle::Thin,
+            b"medium" => BorderStyle::Medium,
+            b"dashed" => BorderStyle::Dashed,
+            b"dotted" => BorderStyle::Dotted,
+            b"thick" => BorderStyle::Thick,
+            b"double" => BorderStyle::Double,
+            b"hair" => BorderStyle::Hair,
+            b"mediumDashed" => BorderStyle::MediumDashed,
+            b"dashDot" => BorderStyle::DashDot,
+            b"mediumDashDot" => BorderStyle::MediumDashDot,
+            b"dashDotDot" => BorderStyle::DashDotDot,
+            b"mediumDashDotDot" => BorderStyle::MediumDashDotDot,
+            b"slantDashDot" => BorderStyle::SlantDashDot,
+            _ => BorderStyle::None,
+        }
+    }
+}
+
+/// Text alignment within a cell
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Alignment {
+    /// Horizontal alignment
+    pub horizontal: HorizontalAlignment,
+    /// Vertical alignment
+    pub vertical: VerticalAlignment,
+    /// Text wraps on several lines
+    pub wrap_text: bool,
+    /// Shrink text to fit the cell
+    pub shrink_to_fit: bool,
+    /// Indentation level
+    pub indent: u32,
+}
+
+/// Horizontal alignment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HorizontalAlignment {
+    /// Text on the left, numbers on the right
+    #[default]
+    General,
+    /// Left
+    Left,
+    /// Centered
+    Center,
+    /// Right
+    Right,
+    /// Content repeated to fill the cell
+    Fill,
+    /// Justified
+    Justify,
+    /// Centered across the selection
+    CenterContinuous,
+    /// Distributed
+    Distributed,
+}
+
+impl HorizontalAlignment {
+    /// Gets the alignment from its xls/xlsb `alc` index
+    pub(crate) fn from_index(index: u32) -> HorizontalAlignment {
+        match index {
+            1 => HorizontalAlignment::Left,
+            2 => HorizontalAlignment::Center,
+            3 => HorizontalAlignment::Right,
+            4 => HorizontalAlignment::Fill,
+            5 => HorizontalAlignment::Justify,
+            6 => HorizontalAlignment::CenterContinuous,
+            7 => HorizontalAlignment::Distributed,
+            _ => HorizontalAlignment::General,
+        }
+    }
+
+    /// Gets the alignment from its xlsx `horizontal` name
+    pub(crate) fn from_name(name: &[u8]) -> HorizontalAlignment {
+        match name {
+            b"left" => HorizontalAlignment::Left,
+            b"center" => HorizontalAlignment::Center,
+            b"right" => HorizontalAlignment::Right,
+            b"fill" => HorizontalAlignment::Fill,
+            b"justify" => HorizontalAlignment::Justify,
+            b"centerContinuous" => HorizontalAlignment::CenterContinuous,
+            b"distributed" => HorizontalAlignment::Distributed,
+            _ => HorizontalAlignment::General,
+        }
+    }
+}
+
+/// Vertical alignment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum VerticalAlignment {
+    /// Top
+    Top,
+    /// Centered
+    Center,
+    /// Bottom
+    #[default]
+    Bottom,
+    /// Justified
+    Justify,
+    /// Distributed
+    Distributed,
+}
+
+impl VerticalAlignment {
+    /// Gets the alignment from its xls/xlsb `alcV` index
+    pub(crate) fn from_index(index: u32) -> VerticalAlignment {
+        match index {
+            0 => VerticalAlignment::Top,
+            1 => VerticalAlignment::Center,
+            3 => VerticalAlignment::Justify,
+            4 => VerticalAlignment::Distributed,
+            _ => VerticalAlignment::Bottom,
+        }
+    }
+
+    /// Gets the alignment from its xlsx `vertical` name
+    pub(crate) fn from_name(name: &[u8]) -> VerticalAlignment {
+        match name {
+            b"top" => VerticalAlignment::Top,
+            b"center" => VerticalAlignment::Center,
+            b"justify" => VerticalAlignment::Justify,
+            b"distributed" => VerticalAlignment::Distributed,
+            _ => VerticalAlignment::Bottom,
+        }
+    }
+}
+
+/// Cell protection flags, only enforced when the sheet is protected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellProtection {
+    /// The cell cannot be edited
+    pub locked: bool,
+    /// The formula of the cell is hidden
+    pub hidden: bool,
+}
+
+impl Default for CellProtection {
+    fn default() -> Self {
+        CellProtection {
+            locked: true,
+            hidden: false,
+        }
+    }
+}
+
+/// Default Excel color palette, as 0xRRGGBB
+const DEFAULT_PALETTE: [u32; 64] = [
+    0x000000, 0xFFFFFF, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFF00, 0xFF00FF, 0x00FFFF, // 0-7
+    0x000000, 0xFFFFFF, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFF00, 0xFF00FF, 0x00FFFF, // 8-15
+    0x800000, 0x008000, 0x000080, 0x808000, 0x800080, 0x008080, 0xC0C0C0, 0x808080, // 16-23
+    0x9999FF, 0x993366, 0xFFFFCC, 0xCCFFFF, 0x660066, 0xFF8080, 0x0066CC, 0xCCCCFF, // 24-31
+    0x000080, 0xFF00FF, 0xFFFF00, 0x00FFFF, 0x800080, 0x800000, 0x008080, 0x0000FF, // 32-39
+    0x00CCFF, 0xCCFFFF, 0xCCFFCC, 0xFFFF99, 0x99CCFF, 0xFF99CC, 0xCC99FF, 0xFFCC99, // 40-47
+    0x3366FF, 0x33CCCC, 0x99CC00, 0xFFCC00, 0xFF9900, 0xFF6600, 0x666699, 0x969696, // 48-55
+    0x003366, 0x339966, 0x003300, 0x333300, 0x993300, 0x993366, 0x333399, 0x333333, // 56-63
+];
+
+#[test]
+fn test_color() {
+    assert_eq!(Color::from_hex("FFFF0000"), Some(Color::Rgb(255, 0, 0)));
+    assert_eq!(Color::from_hex("#00ff80"), Some(Color::Rgb(0, 255, 128)));
+    assert_eq!(Color::from_hex("red"), None);
+    assert_eq!(Color::Indexed(10).to_rgb(), Some((255, 0, 0)));
+    assert_eq!(Color::Indexed(22).to_rgb(), Some((192, 192, 192)));
+    assert_eq!(Color::from_index(64), None);
+    assert_eq!(Color::Theme(1).to_rgb(), None);
+}
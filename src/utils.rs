@@ -2,6 +2,12 @@
 
 use std::convert::TryInto;
 
+use crate::formula::{BinaryOp, CellRef, Expr, Reference};
+use crate::{
+    CellErrorType, DataValidation, DataValidationErrorStyle, DataValidationOperator,
+    DataValidationType,
+};
+
 macro_rules! from_err {
     ($from:ty, $to:tt, $var:tt) => {
         impl From<$from> for $to {
@@ -70,6 +76,33 @@ pub fn push_column(mut col: u32, buf: &mut String) {
     }
 }
 
+/// Push a cell reference into a String buffer, `colu` holding the column and its
+/// relative flags (ColRelU)
+pub fn push_cell(row: u32, colu: u16, buf: &mut String) {
+    if colu & 0x4000 == 0 {
+        buf.push('$');
+    }
+    push_column((colu & 0x3FFF) as u32, buf);
+    if colu & 0x8000 == 0 {
+        buf.push('$');
+    }
+    buf.push_str(&(row + 1).to_string());
+}
+
+/// Formula of a data table, like Excel displays it, from its row and column input cells
+pub fn data_table_formula(row_input: Option<(u32, u32)>, col_input: Option<(u32, u32)>) -> String {
+    let mut formula = String::from("TABLE(");
+    if let Some((row, col)) = row_input {
+        push_cell(row, 0xC000 | col as u16, &mut formula);
+    }
+    formula.push(',');
+    if let Some((row, col)) = col_input {
+        push_cell(row, 0xC000 | col as u16, &mut formula);
+    }
+    formula.push(')');
+    formula
+}
+
 pub const FTAB_LEN: usize = 485;
 
 /* [MS-XLS] 2.5.198.17 */
@@ -1065,3 +1098,154 @@ mod tests {
         );
     }
 }
+
+/// Data validation of the flags of a DV (xls) or BrtDVal (xlsb) record
+pub fn dval_flags(flags: u32) -> DataValidation {
+    DataValidation {
+        typ: match flags & 0xF {
+            1 => DataValidationType::Whole,
+            2 => DataValidationType::Decimal,
+            3 => DataValidationType::List,
+            4 => DataValidationType::Date,
+            5 => DataValidationType::Time,
+            6 => DataValidationType::TextLength,
+            7 => DataValidationType::Custom,
+            _ => DataValidationType::Any,
+        },
+        error_style: match (flags >> 4) & 0x7 {
+            1 => DataValidationErrorStyle::Warning,
+            2 => DataValidationErrorStyle::Information,
+            _ => DataValidationErrorStyle::Stop,
+        },
+        allow_blank: flags & 0x100 != 0,
+        // fSuppressCombo
+        show_dropdown: flags & 0x200 == 0,
+        show_prompt: flags & 0x4_0000 != 0,
+        show_error: flags & 0x8_0000 != 0,
+        operator: match (flags >> 20) & 0xF {
+            1 => DataValidationOperator::NotBetween,
+            2 => DataValidationOperator::Equal,
+            3 => DataValidationOperator::NotEqual,
+            4 => DataValidationOperator::GreaterThan,
+            5 => DataValidationOperator::LessThan,
+            6 => DataValidationOperator::GreaterThanOrEqual,
+            7 => DataValidationOperator::LessThanOrEqual,
+            _ => DataValidationOperator::Between,
+        },
+        ..DataValidation::default()
+    }
+}
+
+/// Resolves the target of a part relationship into a path in the archive
+pub fn resolve_target(part_path: &str, target: &str) -> String {
+    if let Some(target) = target.strip_prefix('/') {
+        return target.to_string();
+    }
+    let mut parts: Vec<&str> = part_path.split('/').collect();
+    parts.pop(); // file name
+    for part in target.split('/') {
+        match part {
+            ".." => {
+                parts.pop();
+            }
+            "." => (),
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Binary operator of a Ptg in `0x03..=0x11`
+pub fn ptg_binary_op(ptg: u8) -> Option<BinaryOp> {
+    let op = match ptg {
+        0x03 => BinaryOp::Add,
+        0x04 => BinaryOp::Sub,
+        0x05 => BinaryOp::Mul,
+        0x06 => BinaryOp::Div,
+        0x07 => BinaryOp::Pow,
+        0x08 => BinaryOp::Concat,
+        0x09 => BinaryOp::Lt,
+        0x0A => BinaryOp::Le,
+        0x0B => BinaryOp::Eq,
+        0x0C => BinaryOp::Ge,
+        0x0D => BinaryOp::Gt,
+        0x0E => BinaryOp::Ne,
+        0x0F => BinaryOp::Intersect,
+        0x10 => BinaryOp::Union,
+        0x11 => BinaryOp::Range,
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Error value of a PtgErr or a BErr
+pub fn ptg_error(err: u8) -> Option<CellErrorType> {
+    let err = match err {
+        0x00 => CellErrorType::Null,
+        0x07 => CellErrorType::Div0,
+        0x0F => CellErrorType::Value,
+        0x17 => CellErrorType::Ref,
+        0x1D => CellErrorType::Name,
+        0x24 => CellErrorType::Num,
+        0x2A => CellErrorType::NA,
+        0x2B => CellErrorType::GettingData,
+        _ => return None,
+    };
+    Some(err)
+}
+
+/// Cell of a row and a ColRelU, whose two high bits are set for relative coordinates
+pub fn ptg_cell(row: u32, col: u16) -> CellRef {
+    CellRef {
+        row: Some(row),
+        col: Some((col & 0x3FFF) as u32),
+        row_absolute: col & 0x8000 == 0,
+        col_absolute: col & 0x4000 == 0,
+    }
+}
+
+/// Reference of a single cell
+pub fn ptg_ref(cell: CellRef) -> Reference {
+    Reference {
+        sheet: None,
+        last_sheet: None,
+        start: cell,
+        end: cell,
+    }
+}
+
+/// Reference of an area, spanning whole rows or columns up to `last_row` and `last_col`
+pub fn ptg_area(mut start: CellRef, mut end: CellRef, last_row: u32, last_col: u32) -> Reference {
+    if start.row == Some(0) && end.row == Some(last_row) {
+        start.row = None;
+        end.row = None;
+    } else if start.col == Some(0) && end.col == Some(last_col) {
+        start.col = None;
+        end.col = None;
+    }
+    Reference {
+        start,
+        end,
+        ..ptg_ref(start)
+    }
+}
+
+/// Pops the arguments of a PtgFunc or a PtgFuncVar into a function call
+pub fn ptg_function(stack: &mut Vec<Expr>, iftab: usize, argc: usize) -> Option<Expr> {
+    if stack.len() < argc {
+        return None;
+    }
+    let mut args = stack.split_off(stack.len() - argc);
+    let name = if iftab == 255 {
+        // user defined function, named by its first argument
+        let name = match args.first()? {
+            Expr::Name(name) => name.clone(),
+            _ => return None,
+        };
+        args.remove(0);
+        name
+    } else {
+        FTAB.get(iftab)?.to_string()
+    };
+    Some(Expr::Function { name, args })
+}
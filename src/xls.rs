@@ -3,22 +3,38 @@ use std::cmp::min;
 use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fmt::Write;
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::io::{Read, Seek, SeekFrom};
 use std::marker::PhantomData;
+use std::sync::Arc;
 
-use log::debug;
+use log::{debug, warn};
 
 use crate::cfb::{Cfb, XlsEncoding};
 use crate::formats::{
-    builtin_format_by_code, detect_custom_number_format, format_excel_f64, format_excel_i64,
-    CellFormat,
+    builtin_format_by_code, builtin_format_code, detect_custom_number_format, format_excel_f64,
+    format_excel_i64, CellFormat,
+};
+use crate::formula::{Expr, Reference, UnaryOp};
+use crate::style::{
+    Alignment, Border, BorderStyle, Borders, CellProtection, Color, Fill, FillPattern, Font,
+    HorizontalAlignment, RichText, Style, StyleId, VerticalAlignment,
 };
 #[cfg(feature = "picture")]
 use crate::utils::read_usize;
-use crate::utils::{push_column, read_f64, read_i16, read_i32, read_u16, read_u32};
+use crate::utils::{
+    data_table_formula, dval_flags, ptg_area, ptg_binary_op, ptg_cell, ptg_error, ptg_function,
+    ptg_ref, push_cell, push_column, read_f64, read_i16, read_i32, read_u16, read_u32,
+};
 use crate::vba::VbaProject;
+#[cfg(feature = "encryption")]
+use crate::ReaderWithPassword;
 use crate::{
-    Cell, CellErrorType, Data, Dimensions, Metadata, Range, Reader, Sheet, SheetType, SheetVisible,
+    cell_rows, AutoFilter, Cell, CellErrorType, ColumnProperties, Comment, Data, DataValidation,
+    DataWithFormat, Dimensions, Filter, FilterColumn, FilterCondition, FilterOperator, FormulaInfo,
+    FormulaKind, Hyperlink, Metadata, Protection, Range, Reader, RowColumnProperties,
+    RowProperties, Sheet, SheetType, SheetVisible, WorksheetRows,
 };
 
 #[derive(Debug)]
@@ -30,6 +46,8 @@ pub enum XlsError {
     Cfb(crate::cfb::CfbError),
     /// Vba error
     Vba(crate::vba::VbaError),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
 
     /// Cannot parse formula, stack is too short
     StackLen,
@@ -77,6 +95,7 @@ pub enum XlsError {
 from_err!(std::io::Error, XlsError, Io);
 from_err!(crate::cfb::CfbError, XlsError, Cfb);
 from_err!(crate::vba::VbaError, XlsError, Vba);
+from_err!(crate::crypto::CryptoError, XlsError, Crypto);
 
 impl std::fmt::Display for XlsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -84,6 +103,7 @@ impl std::fmt::Display for XlsError {
             XlsError::Io(e) => write!(f, "I/O error: {e}"),
             XlsError::Cfb(e) => write!(f, "Cfb error: {e}"),
             XlsError::Vba(e) => write!(f, "Vba error: {e}"),
+            XlsError::Crypto(e) => write!(f, "Decryption error: {e}"),
             XlsError::StackLen => write!(f, "Invalid stack length"),
             XlsError::Unrecognized { typ, val } => write!(f, "Unrecognized {typ}: 0x{val:0X}"),
             XlsError::Password => write!(f, "Workbook is password protected"),
@@ -119,6 +139,7 @@ impl std::error::Error for XlsError {
             XlsError::Io(e) => Some(e),
             XlsError::Cfb(e) => Some(e),
             XlsError::Vba(e) => Some(e),
+            XlsError::Crypto(e) => Some(e),
             _ => None,
         }
     }
@@ -137,22 +158,56 @@ pub struct XlsOptions {
     ///
     /// [code page]: https://docs.microsoft.com/en-us/windows/win32/intl/code-page-identifiers
     pub force_codepage: Option<u16>,
+    /// Password used to decrypt a protected workbook (RC4, RC4 CryptoAPI or XOR obfuscation).
+    ///
+    /// Protected workbooks fail with [`XlsError::Password`] when no password is set.
+    #[cfg(feature = "encryption")]
+    pub password: Option<String>,
 }
 
+#[derive(Debug)]
 struct SheetData {
     range: Range<Data>,
-    formula: Range<String>,
+    formula: Range<FormulaInfo>,
+    formula_expr: Range<Expr>,
+    /// XF (style) index of each cell
+    xfs: Range<usize>,
     merge_cells: Vec<Dimensions>,
+    comments: Vec<Comment>,
+    hyperlinks: Vec<Hyperlink>,
+    rich_text: Range<RichText>,
+    row_column_properties: RowColumnProperties,
+    /// Criteria of the autofilter columns
+    filter_columns: Vec<FilterColumn>,
+    data_validations: Vec<DataValidation>,
 }
 
 /// A struct representing an old xls format file (CFB)
 pub struct Xls<RS> {
+    /// Sheets parsed so far
     sheets: BTreeMap<String, SheetData>,
+    /// Workbook stream
+    stream: Vec<u8>,
+    /// Offset of each sheet substream in `stream` (BoundSheet8)
+    sheet_offsets: BTreeMap<String, usize>,
+    /// Range of the sheets autofilters, from their `_FilterDatabase` defined name
+    filter_ranges: BTreeMap<String, Dimensions>,
+    /// Shared strings
+    strings: Vec<String>,
+    /// Formatted shared strings, by index
+    rich_strings: BTreeMap<usize, RichText>,
+    encoding: XlsEncoding,
+    biff: Biff,
+    xtis: Vec<Xti>,
     vba: Option<VbaProject>,
     metadata: Metadata,
     marker: PhantomData<RS>,
     options: XlsOptions,
     formats: Vec<CellFormat>,
+    /// Number format codes, by XF index
+    format_codes: Vec<Option<Arc<str>>>,
+    /// Cell styles, by XF index
+    styles: Vec<Style>,
     is_1904: bool,
     #[cfg(feature = "picture")]
     pictures: Option<Vec<(String, Vec<u8>)>>,
@@ -192,14 +247,25 @@ impl<RS: Read + Seek> Xls<RS> {
 
         debug!("vba ok");
 
+        let codepage = options.force_codepage.unwrap_or(1200);
         let mut xls = Xls {
             sheets: BTreeMap::new(),
+            stream: Vec::new(),
+            sheet_offsets: BTreeMap::new(),
+            filter_ranges: BTreeMap::new(),
+            strings: Vec::new(),
+            rich_strings: BTreeMap::new(),
+            encoding: XlsEncoding::from_codepage(codepage)?,
+            biff: Biff::Biff8,
+            xtis: Vec::new(),
             vba,
             marker: PhantomData,
             metadata: Metadata::default(),
             options,
             is_1904: false,
             formats: Vec::new(),
+            format_codes: Vec::new(),
+            styles: Vec::new(),
             #[cfg(feature = "picture")]
             pictures: None,
         };
@@ -213,7 +279,24 @@ impl<RS: Read + Seek> Xls<RS> {
 
     /// Gets the worksheet merge cell dimensions
     pub fn worksheet_merge_cells(&self, name: &str) -> Option<Vec<Dimensions>> {
-        self.sheets.get(name).map(|r| r.merge_cells.clone())
+        if let Some(sheet) = self.sheets.get(name) {
+            return Some(sheet.merge_cells.clone());
+        }
+        // only read the MergeCells records of sheets which are not parsed yet
+        let pos = *self.sheet_offsets.get(name)?;
+        let mut merge_cells = Vec::new();
+        for r in (RecordIter {
+            stream: &self.stream[pos..],
+        })
+        .map_while(Result::ok)
+        {
+            match r.typ {
+                0x00E5 => parse_merge_cells(r.data, &mut merge_cells).ok()?,
+                0x000A => break,
+                _ => (),
+            }
+        }
+        Some(merge_cells)
     }
 
     /// Get the nth worksheet. Shortcut for getting the nth
@@ -223,6 +306,32 @@ impl<RS: Read + Seek> Xls<RS> {
 
         self.worksheet_merge_cells(&sheet.name)
     }
+
+    /// Gets a worksheet, parsing it on first access
+    fn sheet(&mut self, name: &str) -> Result<&SheetData, XlsError> {
+        if !self.sheets.contains_key(name) {
+            let pos = *self
+                .sheet_offsets
+                .get(name)
+                .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))?;
+            let sheet = self.parse_sheet(pos)?;
+            self.sheets.insert(name.to_string(), sheet);
+        }
+        Ok(&self.sheets[name])
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl ReaderWithPassword for Xls<Cursor<Vec<u8>>> {
+    fn new_with_password<R: Read>(mut reader: R, password: &str) -> Result<Self, XlsError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let options = XlsOptions {
+            password: Some(password.to_string()),
+            ..XlsOptions::default()
+        };
+        Self::new_with_options(Cursor::new(data), options)
+    }
 }
 
 impl<RS: Read + Seek> Reader<RS> for Xls<RS> {
@@ -242,24 +351,148 @@ impl<RS: Read + Seek> Reader<RS> for Xls<RS> {
     }
 
     fn worksheet_range(&mut self, name: &str) -> Result<Range<Data>, XlsError> {
-        self.sheets
-            .get(name)
-            .map(|r| r.range.clone())
-            .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))
+        self.sheet(name).map(|r| r.range.clone())
     }
 
     fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
-        self.sheets
-            .iter()
-            .map(|(name, sheet)| (name.to_owned(), sheet.range.clone()))
+        let names: Vec<_> = self.sheet_offsets.keys().cloned().collect();
+        names
+            .into_iter()
+            .filter_map(|name| match self.worksheet_range(&name) {
+                Ok(range) => Some((name, range)),
+                Err(e) => {
+                    warn!("cannot read worksheet '{}': {}", name, e);
+                    None
+                }
+            })
             .collect()
     }
 
     fn worksheet_formula(&mut self, name: &str) -> Result<Range<String>, XlsError> {
-        self.sheets
+        self.sheet(name).map(|r| {
+            let start = r.formula.start().unwrap_or_default();
+            let cells = r
+                .formula
+                .used_cells()
+                .map(|(row, col, f)| {
+                    let pos = (start.0 + row as u32, start.1 + col as u32);
+                    Cell::new(pos, f.text.clone())
+                })
+                .collect();
+            Range::from_sparse(cells)
+        })
+    }
+
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, XlsError> {
+        self.sheet(name).map(|r| r.formula.clone())
+    }
+
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<Expr>, XlsError> {
+        self.sheet(name).map(|r| r.formula_expr.clone())
+    }
+
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, XlsError> {
+        self.sheet(name)?;
+        let sheet = &self.sheets[name];
+        let cells = sheet
+            .range
+            .used_cells()
+            .map(|(row, col, value)| {
+                let pos = (
+                    sheet.range.start.0 + row as u32,
+                    sheet.range.start.1 + col as u32,
+                );
+                let xf = sheet.xfs.get_value(pos).copied().unwrap_or(0);
+                let format = self.format_codes.get(xf).cloned().flatten();
+                Cell::new(pos, DataWithFormat::new(value.clone(), format))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsError> {
+        let sheet = self.sheet(name)?;
+        let start = sheet.xfs.start().unwrap_or_default();
+        let cells = sheet
+            .xfs
+            .used_cells()
+            .map(|(row, col, xf)| {
+                let pos = (start.0 + row as u32, start.1 + col as u32);
+                Cell::new(pos, StyleId(*xf))
+            })
+            .collect();
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn styles(&self) -> &[Style] {
+        &self.styles
+    }
+
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsError> {
+        self.sheet(name).map(|r| r.comments.clone())
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsError> {
+        self.sheet(name).map(|r| r.hyperlinks.clone())
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<RichText>, XlsError> {
+        self.sheet(name).map(|r| r.rich_text.clone())
+    }
+
+    fn merged_cells(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsError> {
+        self.sheet(name).map(|r| r.merge_cells.clone())
+    }
+
+    fn worksheet_row_column_properties(
+        &mut self,
+        name: &str,
+    ) -> Result<RowColumnProperties, XlsError> {
+        self.sheet(name).map(|r| r.row_column_properties.clone())
+    }
+
+    /// Sort states and the filters of more than two values (AutoFilter12) are not read
+    fn worksheet_auto_filter(&mut self, name: &str) -> Result<Option<AutoFilter>, XlsError> {
+        let range = self.filter_ranges.get(name).copied();
+        let sheet = self.sheet(name)?;
+        Ok(range.map(|range| AutoFilter {
+            range,
+            columns: sheet.filter_columns.clone(),
+            sort: None,
+        }))
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsError> {
+        self.sheet(name).map(|r| r.data_validations.clone())
+    }
+
+    fn worksheet_rows<'a>(&'a mut self, name: &str) -> Result<WorksheetRows<'a, XlsError>, XlsError>
+    where
+        XlsError: 'a,
+    {
+        let pos = *self
+            .sheet_offsets
             .get(name)
-            .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))
-            .map(|r| r.formula.clone())
+            .ok_or_else(|| XlsError::WorksheetNotFound(name.into()))?;
+        let this = &*self;
+        let mut fmla_pos = (0, 0);
+        // cell records are sorted by row, so they can be read on demand
+        let cells = RecordIter {
+            stream: &this.stream[pos..],
+        }
+        .take_while(|r| !matches!(r, Ok(r) if r.typ == 0x000A))
+        .flat_map(move |r| {
+            let mut cells = Vec::new();
+            let res = r.and_then(|r| this.parse_cell_value(&r, &mut fmla_pos, &mut cells));
+            cells
+                .into_iter()
+                .map(|c| Ok(Cell::new(c.pos, c.val.into())))
+                .chain(res.err().map(Err))
+        });
+        Ok(cell_rows(cells))
     }
 
     #[cfg(feature = "picture")]
@@ -272,22 +505,33 @@ impl<RS: Read + Seek> Reader<RS> for Xls<RS> {
 struct Xti {
     _isup_book: u16,
     itab_first: i16,
-    _itab_last: i16,
+    itab_last: i16,
 }
 
 impl<RS: Read + Seek> Xls<RS> {
     fn parse_workbook(&mut self, mut reader: RS, mut cfb: Cfb) -> Result<(), XlsError> {
         // gets workbook and worksheets stream, or early exit
-        let stream = cfb
+        #[cfg_attr(not(feature = "encryption"), allow(unused_mut))]
+        let mut stream = cfb
             .get_stream("Workbook", &mut reader)
             .or_else(|_| cfb.get_stream("Book", &mut reader))?;
+        #[cfg(feature = "encryption")]
+        let decrypted = match self.options.password {
+            Some(ref password) => crate::crypto::biff::decrypt_workbook(&mut stream, password)?,
+            None => false,
+        };
+        #[cfg(not(feature = "encryption"))]
+        let decrypted = false;
 
         let mut sheet_names = Vec::new();
         let mut strings = Vec::new();
         let mut defined_names = Vec::new();
+        let mut filter_ranges = Vec::new();
         let mut xtis = Vec::new();
         let mut formats = BTreeMap::new();
         let mut xfs = Vec::new();
+        let mut fonts = Vec::new();
+        let mut palette = Vec::new();
         let mut biff = Biff::Biff8; // Binary Interchange File Format (BIFF) version
         let codepage = self.options.force_codepage.unwrap_or(1200);
         let mut encoding = XlsEncoding::from_codepage(codepage)?;
@@ -300,7 +544,9 @@ impl<RS: Read + Seek> Xls<RS> {
                 let mut r = record?;
                 match r.typ {
                     // 2.4.117 FilePass
-                    0x002F if read_u16(r.data) != 0 => return Err(XlsError::Password),
+                    0x002F if read_u16(r.data) != 0 && !decrypted => {
+                        return Err(XlsError::Password)
+                    }
                     // CodePage
                     0x0042 => {
                         if self.options.force_codepage.is_none() {
@@ -312,6 +558,10 @@ impl<RS: Read + Seek> Xls<RS> {
                         sheet_names.reserve(sheet_len);
                         self.metadata.sheets.reserve(sheet_len);
                     }
+                    // Protect: workbook structure
+                    0x0012 => self.metadata.protection.protected = read_u16(r.data) != 0,
+                    // Password
+                    0x0013 => self.metadata.protection.password = read_u16(r.data) != 0,
                     // Date1904
                     0x0022 => {
                         if read_u16(r.data) == 1 {
@@ -323,10 +573,14 @@ impl<RS: Read + Seek> Xls<RS> {
                         let (idx, format) = parse_format(&mut r, &encoding)?;
                         formats.insert(idx, format);
                     }
+                    // Font
+                    0x0031 => fonts.push(parse_font(r.data, &encoding, biff)),
                     // XFS
                     0x00E0 => {
-                        xfs.push(parse_xf(&r)?);
+                        xfs.push(parse_xf(&r, &fonts)?);
                     }
+                    // Palette
+                    0x0092 => palette = parse_palette(r.data),
                     // RRTabId
                     0x0085 => {
                         let (pos, sheet) = parse_sheet_metadata(&mut r, &encoding, biff)?;
@@ -345,6 +599,17 @@ impl<RS: Read + Seek> Xls<RS> {
                         let mut name = String::new();
                         read_unicode_string_no_cch(&encoding, &r.data[14..], &cch, &mut name);
                         let rgce = &r.data[r.data.len() - cce..];
+                        // fBuiltin, the autofilter range being the _FilterDatabase name
+                        // local to the sheet itab
+                        if r.data[0] & 0x20 != 0 && name == "\u{d}" && rgce.len() >= 11 {
+                            filter_ranges.push((
+                                read_u16(&r.data[8..]) as usize,
+                                Dimensions::new(
+                                    (read_u16(&rgce[3..]) as u32, read_u16(&rgce[7..]) as u32),
+                                    (read_u16(&rgce[5..]) as u32, read_u16(&rgce[9..]) as u32),
+                                ),
+                            ));
+                        }
                         let formula = parse_defined_names(rgce)?;
                         defined_names.push((name, formula));
                     }
@@ -354,7 +619,7 @@ impl<RS: Read + Seek> Xls<RS> {
                         xtis.extend(r.data[2..].chunks(6).take(cxti).map(|xti| Xti {
                             _isup_book: read_u16(&xti[..2]),
                             itab_first: read_i16(&xti[2..4]),
-                            _itab_last: read_i16(&xti[4..]),
+                            itab_last: read_i16(&xti[4..]),
                         }));
                     }
                     0x00FC => strings = parse_sst(&mut r, &encoding)?, // SST
@@ -373,10 +638,24 @@ impl<RS: Read + Seek> Xls<RS> {
         }
 
         self.formats = xfs
+            .iter()
+            .map(|(fmt, _)| match formats.get(fmt) {
+                Some(s) => detect_custom_number_format(s),
+                _ => builtin_format_by_code(*fmt),
+            })
+            .collect();
+        self.format_codes = xfs
+            .iter()
+            .map(|(fmt, _)| match formats.get(fmt) {
+                Some(s) => Some(s.as_str().into()),
+                _ => builtin_format_code(*fmt).map(Arc::from),
+            })
+            .collect();
+        self.styles = xfs
             .into_iter()
-            .map(|fmt| match formats.get(&fmt) {
-                Some(s) => *s,
-                _ => builtin_format_by_code(fmt),
+            .map(|(_, mut style)| {
+                apply_palette(&mut style, &palette);
+                style
             })
             .collect();
 
@@ -398,93 +677,36 @@ impl<RS: Read + Seek> Xls<RS> {
 
         debug!("defined_names: {:?}", defined_names);
 
-        let mut sheets = BTreeMap::new();
-        let fmla_sheet_names = sheet_names
-            .iter()
-            .map(|(_, n)| n.clone())
-            .collect::<Vec<_>>();
-        for (pos, name) in sheet_names {
-            let sh = &stream[pos..];
-            let records = RecordIter { stream: sh };
-            let mut cells = Vec::new();
-            let mut formulas = Vec::new();
-            let mut fmla_pos = (0, 0);
-            let mut merge_cells = Vec::new();
-            for record in records {
-                let r = record?;
-                match r.typ {
-                    // 512: Dimensions
-                    0x0200 => {
-                        let Dimensions { start, end } = parse_dimensions(r.data)?;
-                        let rows = (end.0 - start.0 + 1) as usize;
-                        let cols = (end.1 - start.1 + 1) as usize;
-                        cells.reserve(rows.saturating_mul(cols));
-                    }
-                    //0x0201 => cells.push(parse_blank(r.data)?), // 513: Blank
-                    0x0203 => cells.push(parse_number(r.data, &self.formats, self.is_1904)?), // 515: Number
-                    0x0204 => cells.extend(parse_label(r.data, &encoding, biff)?), // 516: Label [MS-XLS 2.4.148]
-                    0x0205 => cells.push(parse_bool_err(r.data)?),                 // 517: BoolErr
-                    0x0207 => {
-                        // 519 String (formula value)
-                        let val = Data::String(parse_string(r.data, &encoding, biff)?);
-                        cells.push(Cell::new(fmla_pos, val))
-                    }
-                    0x027E => cells.push(parse_rk(r.data, &self.formats, self.is_1904)?), // 638: Rk
-                    0x00FD => cells.extend(parse_label_sst(r.data, &strings)?), // LabelSst
-                    0x00BD => parse_mul_rk(r.data, &mut cells, &self.formats, self.is_1904)?, // 189: MulRk
-                    0x00E5 => parse_merge_cells(r.data, &mut merge_cells)?, // 229: Merge Cells
-                    0x000A => break,                                        // 10: EOF,
-                    0x0006 => {
-                        // 6: Formula
-                        if r.data.len() < 20 {
-                            return Err(XlsError::Len {
-                                expected: 20,
-                                found: r.data.len(),
-                                typ: "Formula",
-                            });
-                        }
-                        let row = read_u16(r.data);
-                        let col = read_u16(&r.data[2..]);
-                        fmla_pos = (row as u32, col as u32);
-                        if let Some(val) = parse_formula_value(&r.data[6..14])? {
-                            // If the value is a string
-                            // it will appear in 0x0207 record coming next
-                            cells.push(Cell::new(fmla_pos, val));
-                        }
-                        let fmla = parse_formula(
-                            &r.data[20..],
-                            &fmla_sheet_names,
-                            &defined_names,
-                            &xtis,
-                            &encoding,
-                        )
-                        .unwrap_or_else(|e| {
-                            debug!("{}", e);
-                            format!(
-                                "Unrecognised formula \
-                                 for cell ({}, {}): {:?}",
-                                row, col, e
-                            )
-                        });
-                        formulas.push(Cell::new(fmla_pos, fmla));
-                    }
-                    _ => (),
-                }
+        self.filter_ranges = filter_ranges
+            .into_iter()
+            .filter_map(|(itab, range)| {
+                let name = &sheet_names.get(itab.checked_sub(1)?)?.1;
+                Some((name.clone(), range))
+            })
+            .collect();
+        for (sheet, (pos, _)) in self.metadata.sheets.iter_mut().zip(&sheet_names) {
+            if let Some(sh) = stream.get(*pos..) {
+                sheet.protection = parse_sheet_protection(sh);
             }
-            let range = Range::from_sparse(cells);
-            let formula = Range::from_sparse(formulas);
-            sheets.insert(
-                name,
-                SheetData {
-                    range,
-                    formula,
-                    merge_cells,
-                },
-            );
         }
-
-        self.sheets = sheets;
+        self.sheet_offsets = sheet_names
+            .into_iter()
+            .map(|(pos, name)| (name, pos))
+            .collect();
         self.metadata.names = defined_names;
+        for (i, (s, runs)) in strings.into_iter().enumerate() {
+            if !runs.is_empty() {
+                let runs = runs.into_iter().map(|(ich, ifnt)| {
+                    let font = fonts.get(font_index(ifnt)).cloned().unwrap_or_default();
+                    (ich as usize, font)
+                });
+                self.rich_strings.insert(i, RichText::from_runs(&s, runs));
+            }
+            self.strings.push(s);
+        }
+        self.encoding = encoding;
+        self.biff = biff;
+        self.xtis = xtis;
 
         #[cfg(feature = "picture")]
         if !draw_group.is_empty() {
@@ -494,8 +716,291 @@ impl<RS: Read + Seek> Xls<RS> {
             }
         }
 
+        self.stream = stream;
+
+        Ok(())
+    }
+
+    /// Parses the worksheet substream starting at `pos`
+    /// Parses the values of a cell record, if any, into `cells`
+    ///
+    /// `fmla_pos` is the position of the last Formula record, whose string value is in
+    /// the String record following it.
+    fn parse_cell_value(
+        &self,
+        r: &Record<'_>,
+        fmla_pos: &mut (u32, u32),
+        cells: &mut Vec<Cell<Data>>,
+    ) -> Result<(), XlsError> {
+        let (encoding, biff) = (&self.encoding, self.biff);
+        match r.typ {
+            //0x0201 => cells.push(parse_blank(r.data)?), // 513: Blank
+            0x0203 => cells.push(parse_number(r.data, &self.formats, self.is_1904)?), // 515: Number
+            0x0204 => cells.extend(parse_label(r.data, encoding, biff)?), // 516: Label [MS-XLS 2.4.148]
+            0x0205 => cells.push(parse_bool_err(r.data)?),                // 517: BoolErr
+            0x0207 => {
+                // 519 String (formula value)
+                let val = Data::String(parse_string(r.data, encoding, biff)?);
+                cells.push(Cell::new(*fmla_pos, val))
+            }
+            0x027E => cells.push(parse_rk(r.data, &self.formats, self.is_1904)?), // 638: Rk
+            0x00FD => cells.extend(parse_label_sst(r.data, &self.strings)?),      // LabelSst
+            0x00BD => parse_mul_rk(r.data, cells, &self.formats, self.is_1904)?,  // 189: MulRk
+            0x0006 => {
+                // 6: Formula
+                if r.data.len() < 20 {
+                    return Err(XlsError::Len {
+                        expected: 20,
+                        found: r.data.len(),
+                        typ: "Formula",
+                    });
+                }
+                let row = read_u16(r.data);
+                let col = read_u16(&r.data[2..]);
+                *fmla_pos = (row as u32, col as u32);
+                if let Some(val) = parse_formula_value(&r.data[6..14])? {
+                    // If the value is a string
+                    // it will appear in 0x0207 record coming next
+                    cells.push(Cell::new(*fmla_pos, val));
+                }
+            }
+            _ => (),
+        }
         Ok(())
     }
+
+    fn parse_sheet(&self, pos: usize) -> Result<SheetData, XlsError> {
+        let fmla_sheet_names = self
+            .metadata
+            .sheets
+            .iter()
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        let (encoding, biff) = (&self.encoding, self.biff);
+        let sh = &self.stream[pos..];
+        let records = RecordIter { stream: sh };
+        let mut cells = Vec::new();
+        let mut formulas = Vec::new();
+        let mut formula_exprs = Vec::new();
+        let parse = |rgce: &[u8], origin: Option<(u32, u32)>, pos: (u32, u32)| {
+            let text = parse_formula(
+                rgce,
+                &fmla_sheet_names,
+                &self.metadata.names,
+                &self.xtis,
+                encoding,
+                origin,
+            )
+            .unwrap_or_else(|e| {
+                debug!("{}", e);
+                format!(
+                    "Unrecognised formula \
+                     for cell ({}, {}): {:?}",
+                    pos.0, pos.1, e
+                )
+            });
+            let expr = parse_formula_expr(
+                rgce,
+                &fmla_sheet_names,
+                &self.metadata.names,
+                &self.xtis,
+                encoding,
+                origin,
+            );
+            (text, expr)
+        };
+        let mut push_formula = |pos, (text, expr): (String, Option<Expr>), kind| {
+            formulas.push(Cell::new(pos, FormulaInfo { text, kind }));
+            if let Some(expr) = expr {
+                formula_exprs.push(Cell::new(pos, expr));
+            }
+        };
+        // shared formulas, array formulas and data tables, with their top left cell
+        // waiting for the definition record
+        let mut shared_formulas = Vec::new();
+        let mut pending_master = None;
+        let mut fmla_pos = (0, 0);
+        let mut merge_cells = Vec::new();
+        let mut xfs = Vec::new();
+        let mut notes = Vec::new();
+        let mut note_texts = BTreeMap::new();
+        let mut note_obj = None;
+        let mut hyperlinks = Vec::new();
+        let mut rich_text = Vec::new();
+        let mut row_col = RowColumnProperties::default();
+        let mut filter_columns = Vec::new();
+        let mut data_validations = Vec::new();
+        for record in records {
+            let r = record?;
+            self.parse_cell_value(&r, &mut fmla_pos, &mut cells)?;
+            match r.typ {
+                // Number, Label, BoolErr, Rk, LabelSst and Formula all start with a Cell
+                0x0203 | 0x0204 | 0x0205 | 0x027E | 0x00FD | 0x0006 if r.data.len() >= 6 => {
+                    let pos = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+                    xfs.push(Cell::new(pos, read_u16(&r.data[4..]) as usize));
+                }
+                // Blank
+                0x0201 if r.data.len() >= 6 => {
+                    let pos = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+                    xfs.push(Cell::new(pos, read_u16(&r.data[4..]) as usize));
+                }
+                0x00BE if r.data.len() >= 6 => {
+                    // MulBlank: an ixfe per cell
+                    let row = read_u16(r.data) as u32;
+                    let col_first = read_u16(&r.data[2..]) as u32;
+                    let ixfes = &r.data[4..r.data.len() - 2];
+                    for (i, ixfe) in ixfes.chunks_exact(2).enumerate() {
+                        xfs.push(Cell::new(
+                            (row, col_first + i as u32),
+                            read_u16(ixfe) as usize,
+                        ));
+                    }
+                }
+                0x00BD if r.data.len() >= 6 => {
+                    // MulRk: an ixfe per RkRec
+                    let row = read_u16(r.data) as u32;
+                    let col_first = read_u16(&r.data[2..]) as u32;
+                    let rk_recs = &r.data[4..r.data.len() - 2];
+                    for (i, rk) in rk_recs.chunks_exact(6).enumerate() {
+                        xfs.push(Cell::new(
+                            (row, col_first + i as u32),
+                            read_u16(rk) as usize,
+                        ));
+                    }
+                }
+                _ => (),
+            }
+            match r.typ {
+                // 512: Dimensions
+                0x0200 => {
+                    let Dimensions { start, end } = parse_dimensions(r.data)?;
+                    let rows = (end.0 - start.0 + 1) as usize;
+                    let cols = (end.1 - start.1 + 1) as usize;
+                    cells.reserve(rows.saturating_mul(cols));
+                }
+                0x00FD => {
+                    // LabelSst
+                    if let Some(rich) = self.rich_strings.get(&(read_u32(&r.data[6..]) as usize)) {
+                        let pos = (read_u16(r.data) as u32, read_u16(&r.data[2..]) as u32);
+                        rich_text.push(Cell::new(pos, rich.clone()));
+                    }
+                }
+                0x00E5 => parse_merge_cells(r.data, &mut merge_cells)?, // 229: Merge Cells
+                0x0225 => parse_default_row_height(r.data, &mut row_col), // 549: DefaultRowHeight
+                0x0099 if r.data.len() >= 2 => {
+                    // 153: StandardWidth
+                    row_col.default_column.width = Some(read_u16(r.data) as f64 / 256.);
+                }
+                0x007D => parse_col_info(r.data, &mut row_col), // 125: ColInfo
+                0x0208 => parse_row(r.data, &mut row_col),      // 520: Row
+                0x009E => filter_columns.extend(parse_auto_filter(r.data, encoding)), // 158: AutoFilter
+                0x01BE => {
+                    // 446: DV
+                    let formula = |rgce: &[u8]| parse(rgce, None, (0, 0)).0;
+                    data_validations.extend(parse_data_validation(r.data, encoding, formula));
+                }
+                0x005D => note_obj = parse_note_obj(r.data), // 93: Obj
+                0x01B6 => {
+                    // 438: TxO, text of the preceding Obj
+                    if let Some(id) = note_obj.take() {
+                        note_texts.insert(id, parse_txo(&r, encoding));
+                    }
+                }
+                0x001C if matches!(biff, Biff::Biff8) => notes.push(parse_note(r.data, encoding)?), // 28: Note
+                0x01B8 => hyperlinks.push(parse_hyperlink(r.data)?), // 440: HLink
+                0x0800 => parse_hyperlink_tooltip(r.data, &mut hyperlinks), // 2048: HLinkTooltip
+                0x000A => break,                                     // 10: EOF,
+                0x0006 => {
+                    // 6: Formula, its value and position being read by parse_cell_value
+                    let rgce = &r.data[20..];
+                    if !is_exp_or_tbl(rgce) {
+                        push_formula(fmla_pos, parse(rgce, None, fmla_pos), FormulaKind::Normal);
+                        continue;
+                    }
+                    match shared_formulas
+                        .iter()
+                        .find(|s: &&SharedFormula| s.range.contains(fmla_pos.0, fmla_pos.1))
+                    {
+                        Some(s) if s.kind == FormulaKind::Shared => {
+                            let formula = parse(s.rgce, Some(fmla_pos), fmla_pos);
+                            push_formula(fmla_pos, formula, FormulaKind::Shared);
+                        }
+                        // array formulas and data tables are only kept on their top left
+                        // cell, like in xlsx
+                        Some(_) => (),
+                        // the top left cell, followed by the ShrFmla, Array or Table record
+                        None => pending_master = Some(fmla_pos),
+                    }
+                }
+                0x04BC | 0x0221 | 0x0236 => {
+                    // 1212: ShrFmla, 545: Array, 566: Table
+                    let (typ, len) = match r.typ {
+                        0x04BC => ("ShrFmla", 10),
+                        0x0221 => ("Array", 14),
+                        _ => ("Table", 16),
+                    };
+                    if r.data.len() < len {
+                        return Err(XlsError::Len {
+                            expected: len,
+                            found: r.data.len(),
+                            typ,
+                        });
+                    }
+                    let range = Dimensions {
+                        start: (read_u16(r.data) as u32, r.data[4] as u32),
+                        end: (read_u16(&r.data[2..]) as u32, r.data[5] as u32),
+                    };
+                    let (rgce, kind) = match r.typ {
+                        0x04BC => (&r.data[8..], FormulaKind::Shared),
+                        0x0221 => (
+                            &r.data[12..],
+                            FormulaKind::Array {
+                                range,
+                                dynamic: false,
+                            },
+                        ),
+                        _ => (&[][..], FormulaKind::DataTable { range }),
+                    };
+                    let pending = pending_master.take();
+                    if let Some(pos) = pending.filter(|p| range.contains(p.0, p.1)) {
+                        let formula = match kind {
+                            FormulaKind::Shared => parse(rgce, Some(pos), pos),
+                            FormulaKind::DataTable { .. } => {
+                                let text = parse_data_table(r.data);
+                                let expr = crate::formula::parse_formula(&text).ok();
+                                (text, expr)
+                            }
+                            _ => parse(rgce, None, pos),
+                        };
+                        push_formula(pos, formula, kind);
+                    }
+                    shared_formulas.push(SharedFormula { range, rgce, kind });
+                }
+                _ => (),
+            }
+        }
+        row_col.remove_defaults();
+        Ok(SheetData {
+            range: Range::from_sparse(cells),
+            formula: Range::from_sparse(formulas),
+            formula_expr: Range::from_sparse(formula_exprs),
+            xfs: Range::from_sparse(xfs),
+            merge_cells,
+            comments: notes
+                .into_iter()
+                .map(|(cell, id, author)| Comment {
+                    cell,
+                    author,
+                    text: note_texts.remove(&id).unwrap_or_default(),
+                })
+                .collect(),
+            hyperlinks,
+            rich_text: Range::from_sparse(rich_text),
+            row_column_properties: row_col,
+            filter_columns,
+            data_validations,
+        })
+    }
 }
 
 /// https://learn.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/4d6a3d1e-d7c5-405f-bbae-d01e9cb79366
@@ -583,7 +1088,32 @@ fn parse_sheet_metadata(
         .filter(|b| *b != 0)
         .collect::<Vec<_>>();
     let name = String::from_utf8(sheet_name).unwrap();
-    Ok((pos, Sheet { name, visible, typ }))
+    Ok((
+        pos,
+        Sheet {
+            name,
+            visible,
+            typ,
+            protection: Protection::default(),
+        },
+    ))
+}
+
+/// Protect and Password records of a sheet substream [MS-XLS 2.4.207, 2.4.191]
+///
+/// They are part of the PROTECTION block, which precedes the Dimensions record.
+fn parse_sheet_protection(stream: &[u8]) -> Protection {
+    let mut protection = Protection::default();
+    for r in (RecordIter { stream }).map_while(Result::ok) {
+        match r.typ {
+            0x0012 if r.data.len() >= 2 => protection.protected = read_u16(r.data) != 0,
+            0x0013 if r.data.len() >= 2 => protection.password = read_u16(r.data) != 0,
+            // Dimensions or EOF
+            0x0200 | 0x000A => break,
+            _ => (),
+        }
+    }
+    protection
 }
 
 fn parse_number(r: &[u8], formats: &[CellFormat], is_1904: bool) -> Result<Cell<Data>, XlsError> {
@@ -657,6 +1187,354 @@ fn parse_rk(r: &[u8], formats: &[CellFormat], is_1904: bool) -> Result<Cell<Data
     ))
 }
 
+/// URLMoniker class id [MS-OSHARED 2.3.7.6]
+const URL_MONIKER: [u8; 16] = [
+    0xE0, 0xC9, 0xEA, 0x79, 0xF9, 0xBA, 0xCE, 0x11, 0x8C, 0x82, 0x00, 0xAA, 0x00, 0x4B, 0xA9, 0x0B,
+];
+/// FileMoniker class id [MS-OSHARED 2.3.7.8]
+const FILE_MONIKER: [u8; 16] = [
+    0x03, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+/// HLink [MS-XLS 2.4.140], with its Hyperlink Object [MS-OSHARED 2.3.7.1]
+fn parse_hyperlink(r: &[u8]) -> Result<Hyperlink, XlsError> {
+    if r.len() < 32 {
+        return Err(XlsError::Len {
+            typ: "hyperlink",
+            expected: 32,
+            found: r.len(),
+        });
+    }
+    let mut hyperlink = Hyperlink {
+        range: parse_ref8u(r),
+        url: None,
+        location: None,
+        tooltip: None,
+    };
+    let flags = read_u32(&r[28..]);
+    let mut buf = &r[32..];
+    if flags & 0x10 != 0 {
+        // hlstmfHasDisplayName
+        read_hyperlink_string(&mut buf)?;
+    }
+    if flags & 0x80 != 0 {
+        // hlstmfHasFrameName
+        read_hyperlink_string(&mut buf)?;
+    }
+    if flags & 0x01 != 0 {
+        // hlstmfHasMoniker
+        if flags & 0x100 != 0 {
+            // hlstmfMonikerSavedAsStr
+            hyperlink.url = Some(read_hyperlink_string(&mut buf)?);
+        } else {
+            match read_moniker(&mut buf)? {
+                Some(url) => hyperlink.url = Some(url),
+                // the length of unsupported monikers is unknown, nothing can be read after them
+                None => return Ok(hyperlink),
+            }
+        }
+    }
+    if flags & 0x08 != 0 {
+        // hlstmfHasLocationStr
+        hyperlink.location = Some(read_hyperlink_string(&mut buf)?);
+    }
+    Ok(hyperlink)
+}
+
+/// HLinkTooltip [MS-XLS 2.4.141], sets the tooltip of the matching hyperlink
+fn parse_hyperlink_tooltip(r: &[u8], hyperlinks: &mut [Hyperlink]) {
+    if r.len() < 10 {
+        return;
+    }
+    let range = parse_ref8u(&r[2..]);
+    if let Some(h) = hyperlinks.iter_mut().rev().find(|h| h.range == range) {
+        h.tooltip = Some(read_utf16_until_nul(&r[10..]));
+    }
+}
+
+/// Ref8U [MS-XLS 2.5.211]
+fn parse_ref8u(r: &[u8]) -> Dimensions {
+    Dimensions::new(
+        (read_u16(r) as u32, read_u16(&r[4..]) as u32),
+        (read_u16(&r[2..]) as u32, read_u16(&r[6..]) as u32),
+    )
+}
+
+fn read_utf16_until_nul(buf: &[u8]) -> String {
+    let units = buf
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+/// Reads `len` bytes of `buf`, advancing it
+fn take_bytes<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], XlsError> {
+    if buf.len() < len {
+        return Err(XlsError::Len {
+            typ: "hyperlink",
+            expected: len,
+            found: buf.len(),
+        });
+    }
+    let (bytes, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(bytes)
+}
+
+/// HyperlinkString [MS-OSHARED 2.3.7.9]
+fn read_hyperlink_string(buf: &mut &[u8]) -> Result<String, XlsError> {
+    let len = read_u32(take_bytes(buf, 4)?) as usize;
+    Ok(read_utf16_until_nul(take_bytes(buf, 2 * len)?))
+}
+
+/// HyperlinkMoniker [MS-OSHARED 2.3.7.2], returns `None` for unsupported monikers
+fn read_moniker(buf: &mut &[u8]) -> Result<Option<String>, XlsError> {
+    let clsid = take_bytes(buf, 16)?;
+    if clsid == URL_MONIKER {
+        let len = read_u32(take_bytes(buf, 4)?) as usize;
+        Ok(Some(read_utf16_until_nul(take_bytes(buf, len)?)))
+    } else if clsid == FILE_MONIKER {
+        let up_dirs = read_u16(take_bytes(buf, 2)?) as usize;
+        let ansi_len = read_u32(take_bytes(buf, 4)?) as usize;
+        let ansi = take_bytes(buf, ansi_len)?;
+        // endServer, versionNumber and reserved fields
+        take_bytes(buf, 24)?;
+        let unicode_size = read_u32(take_bytes(buf, 4)?) as usize;
+        let path = if unicode_size > 0 {
+            let len = read_u32(take_bytes(buf, 4)?) as usize;
+            // usKeyValue
+            take_bytes(buf, 2)?;
+            read_utf16_until_nul(take_bytes(buf, len)?)
+        } else {
+            // ansi paths are nul terminated
+            ansi.iter()
+                .take_while(|&&b| b != 0)
+                .map(|&b| b as char)
+                .collect()
+        };
+        Ok(Some("..\\".repeat(up_dirs) + &path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Obj [MS-XLS 2.4.181], returns the object id of comments
+fn parse_note_obj(r: &[u8]) -> Option<u16> {
+    // FtCmo: ft, cb, ot, id
+    if r.len() >= 8 && read_u16(r) == 0x0015 && read_u16(&r[4..]) == 0x0019 {
+        Some(read_u16(&r[6..]))
+    } else {
+        None
+    }
+}
+
+/// TxO [MS-XLS 2.4.329], the text is stored in the following Continue records
+fn parse_txo(r: &Record<'_>, encoding: &XlsEncoding) -> String {
+    let mut s = String::new();
+    if r.data.len() < 12 {
+        return s;
+    }
+    let mut remaining = read_u16(&r.data[10..]) as usize;
+    for cont in r.cont.iter().flatten() {
+        if remaining == 0 || cont.is_empty() {
+            break;
+        }
+        let high_byte = cont[0] & 0x1 != 0;
+        let (read, _) = encoding.decode_to(&cont[1..], remaining, &mut s, Some(high_byte));
+        remaining -= read;
+    }
+    s
+}
+
+/// Note [MS-XLS 2.4.179], returns the cell, object id and author
+#[allow(clippy::type_complexity)]
+fn parse_note(
+    r: &[u8],
+    encoding: &XlsEncoding,
+) -> Result<((u32, u32), u16, Option<String>), XlsError> {
+    if r.len() < 8 {
+        return Err(XlsError::Len {
+            typ: "note",
+            expected: 8,
+            found: r.len(),
+        });
+    }
+    let cell = (read_u16(r) as u32, read_u16(&r[2..]) as u32);
+    let author = match r.get(8..) {
+        Some(author) if author.len() >= 4 => Some(parse_string(author, encoding, Biff::Biff8)?),
+        _ => None,
+    };
+    Ok((cell, read_u16(&r[6..]), author.filter(|a| !a.is_empty())))
+}
+
+/// DefaultRowHeight [MS-XLS 2.4.87]
+fn parse_default_row_height(r: &[u8], props: &mut RowColumnProperties) {
+    if r.len() >= 4 {
+        let flags = read_u16(r);
+        props.default_row.hidden = flags & 0x2 != 0;
+        props.default_row.height = Some(read_u16(&r[2..]) as f64 / 20.);
+    }
+}
+
+/// ColInfo [MS-XLS 2.4.53]
+fn parse_col_info(r: &[u8], props: &mut RowColumnProperties) {
+    if r.len() < 10 {
+        return;
+    }
+    let flags = read_u16(&r[8..]);
+    let column = ColumnProperties {
+        hidden: flags & 0x1 != 0,
+        outline_level: ((flags >> 8) & 0x7) as u8,
+        collapsed: flags & 0x1000 != 0,
+        // fUserSet
+        width: (flags & 0x2 != 0).then(|| read_u16(&r[4..]) as f64 / 256.),
+    };
+    // colLast may be 256, past the last column
+    for col in read_u16(r)..=read_u16(&r[2..]).min(255) {
+        props.columns.insert(col as u32, column.clone());
+    }
+}
+
+/// Row [MS-XLS 2.4.221]
+fn parse_row(r: &[u8], props: &mut RowColumnProperties) {
+    if r.len() < 13 {
+        return;
+    }
+    let flags = r[12];
+    let row = RowProperties {
+        hidden: flags & 0x20 != 0,
+        outline_level: flags & 0x7,
+        collapsed: flags & 0x10 != 0,
+        // fUnsynced
+        height: (flags & 0x40 != 0).then(|| (read_u16(&r[6..]) & 0x7FFF) as f64 / 20.),
+    };
+    props.rows.insert(read_u16(r) as u32, row);
+}
+
+/// AutoFilter [MS-XLS 2.4.8]: iEntry, flags, two DOPERs then their strings
+fn parse_auto_filter(r: &[u8], encoding: &XlsEncoding) -> Option<FilterColumn> {
+    if r.len() < 24 {
+        return None;
+    }
+    let flags = read_u16(&r[2..]);
+    let filter = if flags & 0x10 != 0 {
+        // fTopN
+        Filter::Top10 {
+            top: flags & 0x20 != 0,
+            percent: flags & 0x40 != 0,
+            value: (flags >> 7) as f64,
+        }
+    } else {
+        let mut strings = &r[24..];
+        let mut conditions = Vec::new();
+        for doper in [&r[4..14], &r[14..24]] {
+            let operator = match doper[1] {
+                0x01 => FilterOperator::LessThan,
+                0x03 => FilterOperator::LessThanOrEqual,
+                0x04 => FilterOperator::GreaterThan,
+                0x05 => FilterOperator::NotEqual,
+                0x06 => FilterOperator::GreaterThanOrEqual,
+                _ => FilterOperator::Equal,
+            };
+            let value = match doper[0] {
+                0x02 => match rk_num(&doper[2..6], &[], false) {
+                    Data::Int(i) => Data::Float(i as f64),
+                    v => v,
+                },
+                0x04 => Data::Float(read_f64(&doper[2..])),
+                0x06 if !strings.is_empty() => {
+                    // XLUnicodeStringNoCch, cch being in the DOPER
+                    let mut s = String::new();
+                    let high_byte = strings[0] & 0x1 != 0;
+                    let (_, len) = encoding.decode_to(
+                        &strings[1..],
+                        doper[6] as usize,
+                        &mut s,
+                        Some(high_byte),
+                    );
+                    strings = &strings[1 + len..];
+                    Data::String(s)
+                }
+                0x08 if doper[2] == 0 => Data::Bool(doper[3] != 0),
+                // all blanks or all non blanks
+                0x0C | 0x0E => Data::Empty,
+                _ => continue,
+            };
+            conditions.push(FilterCondition { operator, value });
+        }
+        Filter::Custom {
+            conditions,
+            // wJoin
+            and: flags & 0x3 == 0,
+        }
+    };
+    Some(FilterColumn {
+        index: read_u16(r) as u32,
+        filter,
+    })
+}
+
+/// DV [MS-XLS 2.4.96]: flags, the prompt and error strings, the two formulas then the ranges
+///
+/// `formula` parses a CellParsedFormula.
+fn parse_data_validation(
+    r: &[u8],
+    encoding: &XlsEncoding,
+    formula: impl Fn(&[u8]) -> String,
+) -> Option<DataValidation> {
+    let mut validation = dval_flags(read_u32(r.get(..4)?));
+    let mut r = &r[4..];
+    let mut strings = [None, None, None, None];
+    for s in &mut strings {
+        // XLUnicodeString, empty strings being a single null character
+        if r.len() < 3 {
+            return None;
+        }
+        let mut text = String::new();
+        let high_byte = r[2] & 0x1 != 0;
+        let (_, len) =
+            encoding.decode_to(&r[3..], read_u16(r) as usize, &mut text, Some(high_byte));
+        r = &r[3 + len..];
+        *s = Some(text).filter(|t| !t.is_empty() && t != "\0");
+    }
+    let [prompt_title, error_title, prompt, error] = strings;
+    validation.prompt_title = prompt_title;
+    validation.error_title = error_title;
+    validation.prompt = prompt;
+    validation.error = error;
+    let mut formulas = [None, None];
+    for f in &mut formulas {
+        // DVParsedFormula: cce, unused then rgce
+        let cce = read_u16(r.get(..4)?) as usize;
+        let rgce = r.get(4..4 + cce)?;
+        if cce > 0 {
+            let mut cell_formula = r[..2].to_vec();
+            cell_formula.extend_from_slice(rgce);
+            // list literals are separated by null characters
+            *f = Some(formula(&cell_formula).replace('\0', ","));
+        }
+        r = &r[4 + cce..];
+    }
+    let [formula1, formula2] = formulas;
+    validation.formula1 = formula1;
+    validation.formula2 = formula2;
+    // SqRefU
+    let cref = read_u16(r.get(..2)?) as usize;
+    validation.ranges = r[2..]
+        .chunks_exact(8)
+        .take(cref)
+        .map(|rf| {
+            Dimensions::new(
+                (read_u16(rf) as u32, read_u16(&rf[4..]) as u32),
+                (read_u16(&rf[2..]) as u32, read_u16(&rf[6..]) as u32),
+            )
+        })
+        .collect();
+    Some(validation)
+}
+
 fn parse_merge_cells(r: &[u8], merge_cells: &mut Vec<Dimensions>) -> Result<(), XlsError> {
     let count = read_u16(r);
 
@@ -860,7 +1738,10 @@ fn parse_dimensions(r: &[u8]) -> Result<Dimensions, XlsError> {
     }
 }
 
-fn parse_sst(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<Vec<String>, XlsError> {
+/// Shared strings, with their (character position, font index) formatting runs
+type Sst = Vec<(String, Vec<(u16, u16)>)>;
+
+fn parse_sst(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<Sst, XlsError> {
     if r.data.len() < 8 {
         return Err(XlsError::Len {
             typ: "sst",
@@ -878,10 +1759,10 @@ fn parse_sst(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<Vec<String>,
     Ok(sst)
 }
 
-/// Decode XF (extract only ifmt - Format identifier)
+/// Decode XF: format identifier and cell style
 ///
 /// See: https://learn.microsoft.com/ru-ru/openspecs/office_file_formats/ms-xls/993d15c4-ec04-43e9-ba36-594dfb336c6d
-fn parse_xf(r: &Record<'_>) -> Result<u16, XlsError> {
+fn parse_xf(r: &Record<'_>, fonts: &[Font]) -> Result<(u16, Style), XlsError> {
     if r.data.len() < 4 {
         return Err(XlsError::Len {
             typ: "xf",
@@ -890,13 +1771,124 @@ fn parse_xf(r: &Record<'_>) -> Result<u16, XlsError> {
         });
     }
 
-    Ok(read_u16(&r.data[2..]))
+    let ifmt = read_u16(&r.data[2..]);
+    let mut style = Style {
+        font: fonts
+            .get(font_index(read_u16(r.data)))
+            .cloned()
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    if r.data.len() < 20 {
+        // BIFF5 and older xf, only keep the font
+        return Ok((ifmt, style));
+    }
+
+    let flags = read_u16(&r.data[4..]);
+    style.protection = CellProtection {
+        locked: flags & 0x0001 != 0,
+        hidden: flags & 0x0002 != 0,
+    };
+    let alignment = r.data[6];
+    style.alignment = Alignment {
+        horizontal: HorizontalAlignment::from_index((alignment & 0x07) as u32),
+        vertical: VerticalAlignment::from_index(((alignment >> 4) & 0x07) as u32),
+        wrap_text: alignment & 0x08 != 0,
+        shrink_to_fit: r.data[8] & 0x10 != 0,
+        indent: (r.data[8] & 0x0F) as u32,
+    };
+    let borders = read_u32(&r.data[10..]);
+    let colors = read_u32(&r.data[14..]);
+    let border = |dg: u32, icv: u32| match BorderStyle::from_index(dg & 0x0F) {
+        BorderStyle::None => Border::default(),
+        style => Border {
+            style,
+            color: Color::from_index(icv & 0x7F),
+        },
+    };
+    style.borders = Borders {
+        left: border(borders, borders >> 16),
+        right: border(borders >> 4, borders >> 23),
+        top: border(borders >> 8, colors),
+        bottom: border(borders >> 12, colors >> 7),
+    };
+    let fill_colors = read_u16(&r.data[18..]) as u32;
+    style.fill = Fill {
+        pattern: FillPattern::from_index(colors >> 26),
+        foreground: Color::from_index(fill_colors & 0x7F),
+        background: Color::from_index((fill_colors >> 7) & 0x7F),
+    };
+    Ok((ifmt, style))
+}
+
+/// Decode Font
+///
+/// See: https://learn.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/b5f4a1c0-f1f5-4a69-a43b-6ef6a1a6f9d1
+fn parse_font(data: &[u8], encoding: &XlsEncoding, biff: Biff) -> Font {
+    if data.len() < 14 {
+        return Font::default();
+    }
+    let grbit = read_u16(&data[2..]);
+    // FontName is a ShortXLUnicodeString
+    let mut name = None;
+    if matches!(biff, Biff::Biff8) && data.len() > 16 {
+        let cch = data[14] as usize;
+        let high_byte = data[15] & 0x1 != 0;
+        let mut s = String::with_capacity(cch);
+        encoding.decode_to(&data[16..], cch, &mut s, high_byte.then_some(true));
+        name = Some(s);
+    }
+    Font {
+        name,
+        size: Some(read_u16(data) as f64 / 20.),
+        bold: read_u16(&data[6..]) >= 700,
+        italic: grbit & 0x0002 != 0,
+        underline: data[10] != 0,
+        strikethrough: grbit & 0x0008 != 0,
+        color: Color::from_index(read_u16(&data[4..]) as u32),
+    }
+}
+
+/// Decode Palette, the custom colors replacing indexes 8 to 63
+fn parse_palette(data: &[u8]) -> Vec<Color> {
+    if data.len() < 2 {
+        return Vec::new();
+    }
+    let ccv = read_u16(data) as usize;
+    data[2..]
+        .chunks_exact(4)
+        .take(ccv)
+        .map(|c| Color::Rgb(c[0], c[1], c[2]))
+        .collect()
+}
+
+/// Replaces the indexed colors of a style with the workbook custom palette, if any
+fn apply_palette(style: &mut Style, palette: &[Color]) {
+    if palette.is_empty() {
+        return;
+    }
+    let colors = [
+        &mut style.font.color,
+        &mut style.fill.foreground,
+        &mut style.fill.background,
+        &mut style.borders.left.color,
+        &mut style.borders.right.color,
+        &mut style.borders.top.color,
+        &mut style.borders.bottom.color,
+    ];
+    for color in colors {
+        if let Some(Color::Indexed(i)) = *color {
+            if let Some(c) = (i as usize).checked_sub(8).and_then(|i| palette.get(i)) {
+                *color = Some(*c);
+            }
+        }
+    }
 }
 
 /// Decode Format
 ///
 /// See: https://learn.microsoft.com/ru-ru/openspecs/office_file_formats/ms-xls/300280fd-e4fe-4675-a924-4d383af48d3b
-fn parse_format(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<(u16, CellFormat), XlsError> {
+fn parse_format(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<(u16, String), XlsError> {
     if r.data.len() < 4 {
         return Err(XlsError::Len {
             typ: "format",
@@ -913,16 +1905,24 @@ fn parse_format(r: &mut Record<'_>, encoding: &XlsEncoding) -> Result<(u16, Cell
     let mut s = String::with_capacity(cch);
     encoding.decode_to(r.data, cch, &mut s, Some(high_byte));
 
-    Ok((idx, detect_custom_number_format(&s)))
+    Ok((idx, s))
 }
 
-/// Decode XLUnicodeRichExtendedString.
+/// FontIndex [MS-XLS 2.5.129], there is no font at index 4
+fn font_index(ifnt: u16) -> usize {
+    match ifnt as usize {
+        i if i >= 4 => i - 1,
+        i => i,
+    }
+}
+
+/// Decode XLUnicodeRichExtendedString, with its (character position, font index) runs.
 ///
 /// See: <https://docs.microsoft.com/en-us/openspecs/office_file_formats/ms-xls/173d9f51-e5d3-43da-8de2-be7f22e119b9>
 fn read_rich_extended_string(
     r: &mut Record<'_>,
     encoding: &XlsEncoding,
-) -> Result<String, XlsError> {
+) -> Result<(String, Vec<(u16, u16)>), XlsError> {
     if r.data.is_empty() && !r.continue_record() || r.data.len() < 3 {
         return Err(XlsError::Len {
             typ: "rich extended string",
@@ -959,13 +1959,17 @@ fn read_rich_extended_string(
     // read rgb data block for the string we want
     let s = read_dbcs(encoding, cch, r, high_byte)?;
 
-    // skip rgRun data block. Note: each FormatRun contain 4 bytes.
-    r.skip(c_run * 4)?;
+    // read rgRun data block. Note: each FormatRun contain 4 bytes.
+    let runs = r
+        .read_bytes(c_run * 4)?
+        .chunks_exact(4)
+        .map(|run| (read_u16(run), read_u16(&run[2..])))
+        .collect();
 
     // skip ExtRst data block.
     r.skip(cb_ext_rst)?;
 
-    Ok(s)
+    Ok((s, runs))
 }
 
 fn read_dbcs(
@@ -991,8 +1995,15 @@ fn read_dbcs(
     Ok(s)
 }
 
-fn read_unicode_string_no_cch(encoding: &XlsEncoding, buf: &[u8], len: &usize, s: &mut String) {
-    encoding.decode_to(&buf[1..=*len], *len, s, Some(buf[0] & 0x1 != 0));
+fn read_unicode_string_no_cch(
+    encoding: &XlsEncoding,
+    buf: &[u8],
+    len: &usize,
+    s: &mut String,
+) -> usize {
+    encoding
+        .decode_to(&buf[1..], *len, s, Some(buf[0] & 0x1 != 0))
+        .1
 }
 
 struct Record<'a> {
@@ -1016,6 +2027,22 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Reads `len` bytes, which may span several Continue records
+    fn read_bytes(&mut self, mut len: usize) -> Result<Vec<u8>, XlsError> {
+        let mut bytes = Vec::with_capacity(len);
+        while len > 0 {
+            if self.data.is_empty() && !self.continue_record() {
+                return Err(XlsError::ContinueRecordTooShort);
+            }
+            let l = min(len, self.data.len());
+            let (read, next) = self.data.split_at(l);
+            bytes.extend_from_slice(read);
+            self.data = next;
+            len -= l;
+        }
+        Ok(bytes)
+    }
+
     fn skip(&mut self, mut len: usize) -> Result<(), XlsError> {
         while len > 0 {
             if self.data.is_empty() && !self.continue_record() {
@@ -1134,6 +2161,7 @@ fn parse_formula(
     names: &[(String, String)],
     xtis: &[Xti],
     encoding: &XlsEncoding,
+    origin: Option<(u32, u32)>,
 ) -> Result<String, XlsError> {
     let mut stack = Vec::new();
     let mut formula = String::with_capacity(rgce.len());
@@ -1252,9 +2280,9 @@ fn parse_formula(
                 stack.push(formula.len());
                 formula.push('\"');
                 let cch = rgce[0] as usize;
-                read_unicode_string_no_cch(encoding, &rgce[1..], &cch, &mut formula);
+                let len = read_unicode_string_no_cch(encoding, &rgce[1..], &cch, &mut formula);
                 formula.push('\"');
-                rgce = &rgce[2 + cch..];
+                rgce = &rgce[2 + len..];
             }
             0x18 => {
                 rgce = &rgce[5..];
@@ -1410,11 +2438,32 @@ fn parse_formula(
             }
             0x25 | 0x45 | 0x65 => {
                 stack.push(formula.len());
-                formula.push('$');
-                push_column(read_u16(&rgce[4..6]) as u32, &mut formula);
-                write!(&mut formula, "${}:$", read_u16(&rgce[0..2]) as u32 + 1).unwrap();
-                push_column(read_u16(&rgce[6..8]) as u32, &mut formula);
-                write!(&mut formula, "${}", read_u16(&rgce[2..4]) as u32 + 1).unwrap();
+                push_cell(read_u16(rgce) as u32, read_u16(&rgce[4..]), &mut formula);
+                formula.push(':');
+                push_cell(
+                    read_u16(&rgce[2..]) as u32,
+                    read_u16(&rgce[6..]),
+                    &mut formula,
+                );
+                rgce = &rgce[8..];
+            }
+            0x2C | 0x4C | 0x6C => {
+                // PtgRefN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xls_rel_cell(read_u16(rgce), read_u16(&rgce[2..]), origin);
+                stack.push(formula.len());
+                push_cell(row, col, &mut formula);
+                rgce = &rgce[4..];
+            }
+            0x2D | 0x4D | 0x6D => {
+                // PtgAreaN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xls_rel_cell(read_u16(rgce), read_u16(&rgce[4..]), origin);
+                stack.push(formula.len());
+                push_cell(row, col, &mut formula);
+                formula.push(':');
+                let (row, col) = xls_rel_cell(read_u16(&rgce[2..]), read_u16(&rgce[6..]), origin);
+                push_cell(row, col, &mut formula);
                 rgce = &rgce[8..];
             }
             0x2A | 0x4A | 0x6A => {
@@ -1450,6 +2499,263 @@ fn parse_formula(
     }
 }
 
+/// Formula parsing into an expression tree
+///
+/// Relative references of shared formulas are offsets from `origin`, the formula cell.
+/// Returns `None` for external references and unsupported tokens.
+///
+/// CellParsedFormula [MS-XLS 2.5.198.3]
+fn parse_formula_expr(
+    rgce: &[u8],
+    sheets: &[String],
+    names: &[(String, String)],
+    xtis: &[Xti],
+    encoding: &XlsEncoding,
+    origin: Option<(u32, u32)>,
+) -> Option<Expr> {
+    let cce = read_u16(rgce) as usize;
+    let mut rgce = rgce.get(2..2 + cce)?;
+    let mut stack = Vec::new();
+    while !rgce.is_empty() {
+        let ptg = rgce[0];
+        rgce = &rgce[1..];
+        let expr = match ptg {
+            0x3a | 0x5a | 0x7a | 0x3b | 0x5b | 0x7b => {
+                // PtgRef3d | PtgArea3d
+                let xti = xtis.get(read_u16(rgce) as usize)?;
+                let sheet = sheets.get(usize::try_from(xti.itab_first).ok()?)?;
+                let last_sheet = match xti.itab_last {
+                    last if last == xti.itab_first => None,
+                    last => Some(sheets.get(usize::try_from(last).ok()?)?.clone()),
+                };
+                let reference = if ptg & 0x1F == 0x1A {
+                    let (row, col) = (read_u16(&rgce[2..]), read_u16(&rgce[4..]));
+                    let (row, col) = match origin {
+                        Some(origin) => xls_rel_cell(row, col, origin),
+                        None => (row as u32, col),
+                    };
+                    rgce = &rgce[6..];
+                    ptg_ref(ptg_cell(row, col))
+                } else {
+                    let reference = xls_area(&rgce[2..], origin);
+                    rgce = &rgce[10..];
+                    reference
+                };
+                Expr::Reference(Reference {
+                    sheet: Some(sheet.clone()),
+                    last_sheet,
+                    ..reference
+                })
+            }
+            0x3c | 0x5c | 0x7c | 0x3d | 0x5d | 0x7d => {
+                // PtgRefErr3d | PtgAreaErr3d
+                rgce = &rgce[if ptg & 0x1F == 0x1C { 6 } else { 10 }..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            0x03..=0x11 => {
+                let rhs = stack.pop()?;
+                let lhs = stack.pop()?;
+                Expr::Binary(ptg_binary_op(ptg)?, Box::new(lhs), Box::new(rhs))
+            }
+            0x12..=0x14 => {
+                let op = match ptg {
+                    0x12 => UnaryOp::Plus,
+                    0x13 => UnaryOp::Minus,
+                    _ => UnaryOp::Percent,
+                };
+                Expr::Unary(op, Box::new(stack.pop()?))
+            }
+            0x15 => continue, // PtgParen
+            0x16 => Expr::Missing,
+            0x17 => {
+                // PtgStr
+                let cch = rgce[0] as usize;
+                let mut s = String::with_capacity(cch);
+                let len = read_unicode_string_no_cch(encoding, &rgce[1..], &cch, &mut s);
+                rgce = &rgce[2 + len..];
+                Expr::String(s)
+            }
+            0x19 => {
+                let etpg = rgce[0];
+                rgce = &rgce[1..];
+                match etpg {
+                    0x01 | 0x02 | 0x08 | 0x20 | 0x21 | 0x40 | 0x41 => {
+                        rgce = &rgce[2..];
+                        continue;
+                    }
+                    0x04 => {
+                        // PtgAttrChoose
+                        let n = read_u16(&rgce[..2]) as usize + 1;
+                        rgce = &rgce[2 + 2 * n..];
+                        continue;
+                    }
+                    0x10 => {
+                        // PtgAttrSum
+                        rgce = &rgce[2..];
+                        let args = vec![stack.pop()?];
+                        Expr::Function {
+                            name: "SUM".to_string(),
+                            args,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            0x1C => {
+                let err = ptg_error(rgce[0])?;
+                rgce = &rgce[1..];
+                Expr::Error(err)
+            }
+            0x1D => {
+                let b = rgce[0] != 0;
+                rgce = &rgce[1..];
+                Expr::Bool(b)
+            }
+            0x1E => {
+                let n = read_u16(rgce);
+                rgce = &rgce[2..];
+                Expr::Number(n as f64)
+            }
+            0x1F => {
+                let n = read_f64(rgce);
+                rgce = &rgce[8..];
+                Expr::Number(n)
+            }
+            0x21 | 0x41 | 0x61 => {
+                // PtgFunc
+                let iftab = read_u16(rgce) as usize;
+                rgce = &rgce[2..];
+                let argc = *crate::utils::FTAB_ARGC.get(iftab)? as usize;
+                ptg_function(&mut stack, iftab, argc)?
+            }
+            0x22 | 0x42 | 0x62 => {
+                // PtgFuncVar
+                let argc = rgce[0] as usize;
+                let iftab = (read_u16(&rgce[1..]) & 0x7FFF) as usize;
+                rgce = &rgce[3..];
+                ptg_function(&mut stack, iftab, argc)?
+            }
+            0x23 | 0x43 | 0x63 => {
+                let iname = (read_u32(rgce) as usize).checked_sub(1)?; // one-based
+                rgce = &rgce[4..];
+                Expr::Name(names.get(iname)?.0.clone())
+            }
+            0x24 | 0x44 | 0x64 => {
+                let cell = ptg_cell(read_u16(rgce) as u32, read_u16(&rgce[2..]));
+                rgce = &rgce[4..];
+                Expr::Reference(ptg_ref(cell))
+            }
+            0x25 | 0x45 | 0x65 => {
+                let reference = xls_area(rgce, None);
+                rgce = &rgce[8..];
+                Expr::Reference(reference)
+            }
+            0x26..=0x28 | 0x46..=0x48 | 0x66..=0x68 => {
+                // PtgMemArea | PtgMemErr | PtgMemNoMem: the sub-expression follows
+                rgce = &rgce[6..];
+                continue;
+            }
+            0x29 | 0x49 | 0x69 => {
+                // PtgMemFunc
+                rgce = &rgce[2..];
+                continue;
+            }
+            0x2C | 0x4C | 0x6C => {
+                // PtgRefN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xls_rel_cell(read_u16(rgce), read_u16(&rgce[2..]), origin);
+                rgce = &rgce[4..];
+                Expr::Reference(ptg_ref(ptg_cell(row, col)))
+            }
+            0x2D | 0x4D | 0x6D => {
+                // PtgAreaN
+                let reference = xls_area(rgce, Some(origin.unwrap_or_default()));
+                rgce = &rgce[8..];
+                Expr::Reference(reference)
+            }
+            0x2A | 0x4A | 0x6A => {
+                rgce = &rgce[4..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            0x2B | 0x4B | 0x6B => {
+                rgce = &rgce[8..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            _ => {
+                // PtgExp, PtgArray, PtgNameX ...
+                debug!("unsupported ptg {:x} in formula expression", ptg);
+                return None;
+            }
+        };
+        stack.push(expr);
+    }
+    match stack.len() {
+        1 => stack.pop(),
+        _ => None,
+    }
+}
+
+/// RgceArea [MS-XLS 2.5.198.105], or RgceAreaRel with an `origin`
+fn xls_area(rgce: &[u8], origin: Option<(u32, u32)>) -> Reference {
+    let cell = |row: u16, col: u16| {
+        let (row, col) = match origin {
+            Some(origin) => xls_rel_cell(row, col, origin),
+            None => (row as u32, col),
+        };
+        ptg_cell(row, col)
+    };
+    let start = cell(read_u16(rgce), read_u16(&rgce[4..]));
+    let end = cell(read_u16(&rgce[2..]), read_u16(&rgce[6..]));
+    ptg_area(start, end, 0xFFFF, 0xFF)
+}
+
+/// RgceLocRel [MS-XLS 2.5.198.87]
+///
+/// Relative rows and columns are offsets from `origin`, wrapping around the worksheet.
+fn xls_rel_cell(row: u16, colu: u16, origin: (u32, u32)) -> (u32, u16) {
+    let row = if colu & 0x8000 != 0 {
+        (origin.0 as i32 + row as i16 as i32).rem_euclid(0x1_0000) as u32
+    } else {
+        row as u32
+    };
+    let col = if colu & 0x4000 != 0 {
+        (origin.1 as i32 + colu as u8 as i8 as i32).rem_euclid(0x100) as u16
+    } else {
+        colu & 0xFF
+    };
+    (row, (colu & 0xC000) | col)
+}
+
+/// Is the CellParsedFormula a single PtgExp or PtgTbl, pointing to the top left cell of a
+/// shared formula, an array formula or a data table
+fn is_exp_or_tbl(formula: &[u8]) -> bool {
+    formula.len() >= 7 && read_u16(formula) == 5 && matches!(formula[2], 0x01 | 0x02)
+}
+
+/// Table [MS-XLS 2.4.319], as a `TABLE(row_input,column_input)` formula
+fn parse_data_table(r: &[u8]) -> String {
+    let flags = read_u16(&r[6..]);
+    let first = (read_u16(&r[8..]) as u32, read_u16(&r[10..]) as u32);
+    let second = (read_u16(&r[12..]) as u32, read_u16(&r[14..]) as u32);
+    match (flags & 0x0008 != 0, flags & 0x0004 != 0) {
+        // fTbl2: two inputs table
+        (true, _) => data_table_formula(Some(first), Some(second)),
+        // fRw: row input
+        (false, true) => data_table_formula(Some(first), None),
+        (false, false) => data_table_formula(None, Some(first)),
+    }
+}
+
+/// A ShrFmla, Array or Table record [MS-XLS 2.4.260, 2.4.4, 2.4.319]
+struct SharedFormula<'a> {
+    /// Cells sharing the formula
+    range: Dimensions,
+    /// SharedParsedFormula or ArrayParsedFormula, empty for data tables
+    rgce: &'a [u8],
+    /// Shared, array or data table
+    kind: FormulaKind,
+}
+
 /// FormulaValue [MS-XLS 2.5.133]
 fn parse_formula_value(r: &[u8]) -> Result<Option<Data>, XlsError> {
     match *r {
@@ -1605,3 +2911,57 @@ fn parse_pictures(stream: &[u8]) -> Result<Vec<(String, Vec<u8>)>, XlsError> {
     }
     Ok(pics)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn open(path: &std::path::Path) -> Result<Xls<BufReader<File>>, XlsError> {
+        Xls::new(BufReader::new(File::open(path)?))
+    }
+
+    #[test]
+    fn lazy_sheets() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/issues.xls");
+        let mut xls = open(path.as_ref()).unwrap();
+        let names = xls.sheet_names();
+        assert!(names.len() > 1);
+        assert!(xls.sheets.is_empty());
+        // merge cells and rows are read without parsing the whole sheet
+        xls.worksheet_merge_cells(&names[1]).unwrap();
+        xls.worksheet_rows(&names[1]).unwrap().for_each(drop);
+        assert!(xls.sheets.is_empty());
+        xls.worksheet_range(&names[1]).unwrap();
+        assert_eq!(xls.sheets.keys().collect::<Vec<_>>(), [&names[1]]);
+    }
+
+    /// Sheets parsed one at a time, in any order, match the sheets parsed in workbook
+    /// order right after the globals, as they were when parsed on opening
+    #[test]
+    fn lazy_sheets_match_eager() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |e| e != "xls") {
+                continue;
+            }
+            let Ok(mut xls) = open(&path) else {
+                continue;
+            };
+            let names = xls.sheet_names();
+            let eager: Vec<_> = names
+                .iter()
+                .map(|name| format!("{:?}", xls.sheet(name).unwrap()))
+                .collect();
+            for (name, eager) in names.iter().zip(&eager).rev() {
+                let mut xls = open(&path).unwrap();
+                let merge_cells = xls.worksheet_merge_cells(name);
+                let lazy = xls.sheet(name).unwrap();
+                assert_eq!(&format!("{lazy:?}"), eager, "{path:?} {name}");
+                assert_eq!(merge_cells.as_ref(), Some(&lazy.merge_cells));
+            }
+        }
+    }
+}
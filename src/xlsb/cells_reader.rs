@@ -1,11 +1,12 @@
 use crate::{
     datatype::DataRef,
     formats::{format_excel_f64_ref, CellFormat},
-    utils::{read_f64, read_i32, read_u32, read_usize},
-    Cell, CellErrorType, Dimensions, XlsbError,
+    formula::Expr,
+    utils::{data_table_formula, read_f64, read_i32, read_u32, read_usize},
+    Cell, CellErrorType, Dimensions, FormulaInfo, FormulaKind, XlsbError,
 };
 
-use super::{cell_format, parse_formula, wide_str, RecordIter};
+use super::{cell_format, parse_formula, parse_formula_expr, wide_str, RecordIter};
 
 /// A cells reader for xlsb files
 pub struct XlsbCellsReader<'a> {
@@ -13,12 +14,27 @@ pub struct XlsbCellsReader<'a> {
     formats: &'a [CellFormat],
     strings: &'a [String],
     extern_sheets: &'a [String],
+    extern_last_sheets: &'a [Option<String>],
     metadata_names: &'a [(String, String)],
     typ: u16,
     row: u32,
     is_1904: bool,
     dimensions: Dimensions,
     buf: Vec<u8>,
+    /// Shared and array formulas read so far
+    shared_formulas: Vec<SharedFormula>,
+    /// Master cell of a shared or array formula, waiting for its definition
+    pending_master: Option<(u32, u32)>,
+}
+
+/// A BrtShrFmla, BrtArrFmla or BrtTable record
+struct SharedFormula {
+    /// Cells sharing the formula
+    range: Dimensions,
+    /// SharedParsedFormula or ArrayParsedFormula rgce, empty for data tables
+    rgce: Vec<u8>,
+    /// Shared, array or data table
+    kind: FormulaKind,
 }
 
 impl<'a> XlsbCellsReader<'a> {
@@ -27,6 +43,7 @@ impl<'a> XlsbCellsReader<'a> {
         formats: &'a [CellFormat],
         strings: &'a [String],
         extern_sheets: &'a [String],
+        extern_last_sheets: &'a [Option<String>],
         metadata_names: &'a [(String, String)],
         is_1904: bool,
     ) -> Result<Self, XlsbError> {
@@ -60,11 +77,14 @@ impl<'a> XlsbCellsReader<'a> {
             is_1904,
             strings,
             extern_sheets,
+            extern_last_sheets,
             metadata_names,
             dimensions,
             typ: 0,
             row: 0,
             buf,
+            shared_formulas: Vec::new(),
+            pending_master: None,
         })
     }
 
@@ -73,13 +93,27 @@ impl<'a> XlsbCellsReader<'a> {
     }
 
     pub fn next_cell(&mut self) -> Result<Option<Cell<DataRef<'a>>>, XlsbError> {
+        loop {
+            match self.next_cell_with_style()? {
+                Some((cell, _)) if cell.val == DataRef::Empty => continue,
+                cell => return Ok(cell.map(|(cell, _)| cell)),
+            }
+        }
+    }
+
+    /// Gets the next cell along with its style index
+    ///
+    /// Unlike `next_cell`, blank (styled but empty) cells are returned.
+    pub(crate) fn next_cell_with_style(
+        &mut self,
+    ) -> Result<Option<(Cell<DataRef<'a>>, usize)>, XlsbError> {
         // loop until end of sheet
         let value = loop {
             self.buf.clear();
             self.typ = self.iter.read_type()?;
             let _ = self.iter.fill_buffer(&mut self.buf)?;
             let value = match self.typ {
-                // 0x0001 => continue, // Data::Empty, // BrtCellBlank
+                0x0001 => DataRef::Empty, // BrtCellBlank
                 0x0002 => {
                     // BrtCellRk MS-XLSB 2.5.122
                     let d100 = (self.buf[8] & 1) != 0;
@@ -127,6 +161,7 @@ impl<'a> XlsbCellsReader<'a> {
                     format_excel_f64_ref(v, cell_format(&self.formats, &self.buf), self.is_1904)
                 } // BrtCellReal or BrtFmlaNum
                 0x0006 | 0x0008 => DataRef::String(wide_str(&self.buf[8..], &mut 0)?.into_owned()), // BrtCellSt or BrtFmlaString
+                0x003E => DataRef::String(wide_str(&self.buf[9..], &mut 0)?.into_owned()), // BrtCellRString
                 0x0007 => {
                     // BrtCellIsst
                     let isst = read_usize(&self.buf[8..12]);
@@ -146,37 +181,124 @@ impl<'a> XlsbCellsReader<'a> {
             break value;
         };
         let col = read_u32(&self.buf);
-        Ok(Some(Cell::new((self.row, col), value)))
+        // iStyleRef is stored as a 24bit integer starting at the fifth byte
+        let style = u32::from_le_bytes([self.buf[4], self.buf[5], self.buf[6], 0]) as usize;
+        Ok(Some((Cell::new((self.row, col), value), style)))
     }
 
     pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, XlsbError> {
-        let value = loop {
+        let (sheets, names) = (self.extern_sheets, self.metadata_names);
+        let formula = self.next_formula_with(|f| f.into_text(sheets, names))?;
+        Ok(formula.map(|(pos, formula, _)| Cell::new(pos, formula)))
+    }
+
+    /// Reads the next formula along with its kind
+    pub fn next_formula_info(&mut self) -> Result<Option<Cell<FormulaInfo>>, XlsbError> {
+        let (sheets, names) = (self.extern_sheets, self.metadata_names);
+        let formula = self.next_formula_with(|f| f.into_text(sheets, names))?;
+        Ok(formula.map(|(pos, text, kind)| Cell::new(pos, FormulaInfo { text, kind })))
+    }
+
+    /// Reads the next formula which can be represented as an expression tree
+    pub fn next_formula_expr(&mut self) -> Result<Option<Cell<Expr>>, XlsbError> {
+        let (sheets, last_sheets, names) = (
+            self.extern_sheets,
+            self.extern_last_sheets,
+            self.metadata_names,
+        );
+        loop {
+            let formula = self.next_formula_with(|f| {
+                Ok(match f {
+                    CellFormula::Rgce(rgce, origin) => {
+                        parse_formula_expr(rgce, sheets, last_sheets, names, origin)
+                    }
+                    CellFormula::DataTable(text) => crate::formula::parse_formula(&text).ok(),
+                })
+            })?;
+            match formula {
+                Some((pos, Some(expr), _)) => return Ok(Some(Cell::new(pos, expr))),
+                Some((_, None, _)) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads the next formula cell, decoding its formula with `decode`
+    ///
+    /// Shared formulas are decoded for each of their cells, with the cell as origin of the
+    /// relative references, and array formulas and data tables for their top left cell only,
+    /// like in xlsx.
+    #[allow(clippy::type_complexity)]
+    fn next_formula_with<T>(
+        &mut self,
+        mut decode: impl FnMut(CellFormula<'_>) -> Result<T, XlsbError>,
+    ) -> Result<Option<((u32, u32), T, FormulaKind)>, XlsbError> {
+        let formula = loop {
             self.typ = self.iter.read_type()?;
             let _ = self.iter.fill_buffer(&mut self.buf)?;
 
-            let value = match self.typ {
+            let rgce = match self.typ {
                 // 0x0001 => continue, // Data::Empty, // BrtCellBlank
                 0x0008 => {
                     // BrtFmlaString
                     let cch = read_u32(&self.buf[8..]) as usize;
                     let formula = &self.buf[14 + cch * 2..];
                     let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
+                    &formula[4..4 + cce]
                 }
                 0x0009 => {
                     // BrtFmlaNum
                     let formula = &self.buf[18..];
                     let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
+                    &formula[4..4 + cce]
                 }
                 0x000A | 0x000B => {
                     // BrtFmlaBool | BrtFmlaError
                     let formula = &self.buf[11..];
                     let cce = read_u32(formula) as usize;
-                    let rgce = &formula[4..4 + cce];
-                    parse_formula(rgce, &self.extern_sheets, &self.metadata_names)?
+                    &formula[4..4 + cce]
+                }
+                0x01AA..=0x01AC => {
+                    // BrtArrFmla | BrtShrFmla | BrtTable, following the record of their top
+                    // left cell
+                    let range = parse_dimensions(&self.buf[..16]);
+                    let (rgce, kind) = match self.typ {
+                        0x01AA | 0x01AB => {
+                            let array = self.typ == 0x01AA;
+                            let formula = &self.buf[if array { 17 } else { 16 }..];
+                            let cce = read_u32(formula) as usize;
+                            let kind = if array {
+                                FormulaKind::Array {
+                                    range,
+                                    dynamic: false,
+                                }
+                            } else {
+                                FormulaKind::Shared
+                            };
+                            (formula[4..4 + cce].to_vec(), kind)
+                        }
+                        _ => (Vec::new(), FormulaKind::DataTable { range }),
+                    };
+                    let pending = self.pending_master.take();
+                    let formula = match pending.filter(|p| range.contains(p.0, p.1)) {
+                        Some(pos) => Some(match kind {
+                            FormulaKind::Shared => {
+                                (pos, decode(CellFormula::Rgce(&rgce, Some(pos)))?)
+                            }
+                            FormulaKind::DataTable { .. } => {
+                                let text = parse_data_table(&self.buf);
+                                (pos, decode(CellFormula::DataTable(text))?)
+                            }
+                            _ => (pos, decode(CellFormula::Rgce(&rgce, None))?),
+                        }),
+                        None => None,
+                    };
+                    self.shared_formulas
+                        .push(SharedFormula { range, rgce, kind });
+                    match formula {
+                        Some((pos, formula)) => break (pos, formula, kind),
+                        None => continue,
+                    }
                 }
                 0x0000 => {
                     // BrtRowHdr
@@ -189,10 +311,58 @@ impl<'a> XlsbCellsReader<'a> {
                 0x0092 => return Ok(None), // BrtEndSheetData
                 _ => continue, // anything else, ignore and try next, without changing idx
             };
-            break value;
+            let pos = (self.row, read_u32(&self.buf));
+            // PtgExp or PtgTbl, the only token of shared formulas, array formulas and data
+            // tables cells
+            if !matches!(rgce.first(), Some(0x01 | 0x02)) {
+                let formula = decode(CellFormula::Rgce(rgce, None))?;
+                break (pos, formula, FormulaKind::Normal);
+            }
+            match self
+                .shared_formulas
+                .iter()
+                .find(|s| s.range.contains(pos.0, pos.1))
+            {
+                Some(s) if s.kind == FormulaKind::Shared => {
+                    let formula = decode(CellFormula::Rgce(&s.rgce, Some(pos)))?;
+                    break (pos, formula, FormulaKind::Shared);
+                }
+                // array formulas and data tables are only kept on their top left cell
+                Some(_) => (),
+                None => self.pending_master = Some(pos),
+            }
         };
-        let col = read_u32(&self.buf);
-        Ok(Some(Cell::new((self.row, col), value)))
+        Ok(Some(formula))
+    }
+}
+
+/// The formula of a cell, before decoding
+enum CellFormula<'a> {
+    /// Formula tokens, with the cell its relative references are offsets from, if any
+    Rgce(&'a [u8], Option<(u32, u32)>),
+    /// Data table, as a `TABLE(row_input,column_input)` formula
+    DataTable(String),
+}
+
+impl CellFormula<'_> {
+    fn into_text(self, sheets: &[String], names: &[(String, String)]) -> Result<String, XlsbError> {
+        match self {
+            CellFormula::Rgce(rgce, origin) => parse_formula(rgce, sheets, names, origin),
+            CellFormula::DataTable(text) => Ok(text),
+        }
+    }
+}
+
+/// BrtTable record, as a `TABLE(row_input,column_input)` formula
+fn parse_data_table(buf: &[u8]) -> String {
+    let first = (read_u32(&buf[16..]), read_u32(&buf[20..]));
+    let second = (read_u32(&buf[24..]), read_u32(&buf[28..]));
+    match (buf[32] & 0x04 != 0, buf[32] & 0x02 != 0) {
+        // fTbl2: two inputs table
+        (true, _) => data_table_formula(Some(first), Some(second)),
+        // fRw: row input
+        (false, true) => data_table_formula(Some(first), None),
+        (false, false) => data_table_formula(None, Some(first)),
     }
 }
 
@@ -4,8 +4,11 @@ pub use cells_reader::XlsbCellsReader;
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::io::{BufReader, Read, Seek};
 use std::string::String;
+use std::sync::Arc;
 
 use log::debug;
 
@@ -18,10 +21,28 @@ use zip::read::{ZipArchive, ZipFile};
 use zip::result::ZipError;
 
 use crate::datatype::DataRef;
-use crate::formats::{builtin_format_by_code, detect_custom_number_format, CellFormat};
-use crate::utils::{push_column, read_f64, read_i32, read_u16, read_u32, read_usize};
+use crate::formats::{
+    builtin_format_by_code, builtin_format_code, detect_custom_number_format, CellFormat,
+};
+use crate::formula::{Expr, Reference, UnaryOp};
+use crate::style::{
+    Alignment, Border, BorderStyle, Borders, CellProtection, Color, Fill, FillPattern, Font,
+    HorizontalAlignment, RichText, Style, StyleId, VerticalAlignment,
+};
+use crate::utils::{
+    dval_flags, ptg_area, ptg_binary_op, ptg_cell, ptg_error, ptg_function, ptg_ref, push_cell,
+    push_column, read_f64, read_i32, read_u16, read_u32, read_usize, resolve_target,
+};
 use crate::vba::VbaProject;
-use crate::{Cell, Data, Metadata, Range, Reader, Sheet, SheetType, SheetVisible};
+#[cfg(feature = "encryption")]
+use crate::ReaderWithPassword;
+use crate::{
+    cell_rows, AutoFilter, Cell, CellErrorType, ColumnProperties, Comment, Data, DataValidation,
+    DataWithFormat, Dimensions, Filter, FilterColumn, FilterCondition, FilterOperator, FormulaInfo,
+    Hyperlink, Metadata, Protection, Range, Reader, RowColumnProperties, RowProperties, Sheet,
+    SheetType, SheetVisible, Table, TableColumn, TableMetadata, TableStyle, Tables,
+    TotalsRowFunction, WorksheetRows,
+};
 
 /// A Xlsb specific error
 #[derive(Debug)]
@@ -36,6 +57,8 @@ pub enum XlsbError {
     XmlAttr(quick_xml::events::attributes::AttrError),
     /// Vba error
     Vba(crate::vba::VbaError),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
 
     /// Mismatch value
     Mismatch {
@@ -79,10 +102,13 @@ pub enum XlsbError {
     Password,
     /// Worksheet not found
     WorksheetNotFound(String),
+    /// Table not found
+    TableNotFound(String),
 }
 
 from_err!(std::io::Error, XlsbError, Io);
 from_err!(zip::result::ZipError, XlsbError, Zip);
+from_err!(crate::crypto::CryptoError, XlsbError, Crypto);
 from_err!(quick_xml::Error, XlsbError, Xml);
 
 impl std::fmt::Display for XlsbError {
@@ -93,6 +119,7 @@ impl std::fmt::Display for XlsbError {
             XlsbError::Xml(e) => write!(f, "Xml error: {e}"),
             XlsbError::XmlAttr(e) => write!(f, "Xml attribute error: {e}"),
             XlsbError::Vba(e) => write!(f, "Vba error: {e}"),
+            XlsbError::Crypto(e) => write!(f, "Decryption error: {e}"),
             XlsbError::Mismatch { expected, found } => {
                 write!(f, "Expecting {expected}, got {found:X}")
             }
@@ -113,6 +140,7 @@ impl std::fmt::Display for XlsbError {
             }
             XlsbError::Password => write!(f, "Workbook is password protected"),
             XlsbError::WorksheetNotFound(name) => write!(f, "Worksheet '{name}' not found"),
+            XlsbError::TableNotFound(name) => write!(f, "Table '{name}' not found"),
         }
     }
 }
@@ -124,6 +152,7 @@ impl std::error::Error for XlsbError {
             XlsbError::Zip(e) => Some(e),
             XlsbError::Xml(e) => Some(e),
             XlsbError::Vba(e) => Some(e),
+            XlsbError::Crypto(e) => Some(e),
             _ => None,
         }
     }
@@ -133,12 +162,24 @@ impl std::error::Error for XlsbError {
 pub struct Xlsb<RS> {
     zip: ZipArchive<RS>,
     extern_sheets: Vec<String>,
+    /// Last sheet of each 3-D extern sheet, if different from the first one
+    extern_last_sheets: Vec<Option<String>>,
     sheets: Vec<(String, String)>,
     strings: Vec<String>,
+    /// Formatted shared strings, by index
+    rich_strings: BTreeMap<usize, RichText>,
     /// Cell (number) formats
     formats: Vec<CellFormat>,
+    /// Number format codes, by style index
+    format_codes: Vec<Option<Arc<str>>>,
+    /// Cell styles
+    styles: Vec<Style>,
+    /// Fonts, used by rich text runs
+    fonts: Vec<Font>,
     is_1904: bool,
     metadata: Metadata,
+    /// Tables: Name, Sheet, Columns, Data dimensions
+    tables: Tables,
     #[cfg(feature = "picture")]
     pictures: Option<Vec<(String, Vec<u8>)>>,
 }
@@ -195,6 +236,60 @@ impl<RS: Read + Seek> Xlsb<RS> {
         Ok(relationships)
     }
 
+    /// Reads the relationships of a worksheet, as (id, type, target)
+    #[allow(clippy::type_complexity)]
+    fn read_sheet_relationships(
+        &mut self,
+        path: &str,
+    ) -> Result<Vec<(String, String, String)>, XlsbError> {
+        let (base_folder, file_name) = path.split_at(path.rfind('/').unwrap_or(0));
+        let rel_path = format!("{}/_rels{}.rels", base_folder, file_name);
+        let mut relationships = Vec::new();
+        match self.zip.by_name(&rel_path) {
+            Ok(f) => {
+                let mut xml = XmlReader::from_reader(BufReader::new(f));
+                xml.check_end_names(false)
+                    .trim_text(false)
+                    .check_comments(false)
+                    .expand_empty_elements(true);
+                let mut buf: Vec<u8> = Vec::with_capacity(64);
+
+                loop {
+                    match xml.read_event_into(&mut buf) {
+                        Ok(Event::Start(ref e)) if e.name() == QName(b"Relationship") => {
+                            let mut id = String::new();
+                            let mut typ = String::new();
+                            let mut target = String::new();
+                            for a in e.attributes() {
+                                let a = a.map_err(XlsbError::XmlAttr)?;
+                                match a.key {
+                                    QName(b"Id") => {
+                                        id = xml.decoder().decode(&a.value)?.into_owned()
+                                    }
+                                    QName(b"Type") => {
+                                        typ = xml.decoder().decode(&a.value)?.into_owned()
+                                    }
+                                    QName(b"Target") => {
+                                        target = a.decode_and_unescape_value(&xml)?.into_owned()
+                                    }
+                                    _ => (),
+                                }
+                            }
+                            relationships.push((id, typ, target));
+                        }
+                        Ok(Event::Eof) => break,
+                        Err(e) => return Err(XlsbError::Xml(e)),
+                        _ => (),
+                    }
+                    buf.clear();
+                }
+            }
+            Err(ZipError::FileNotFound) => (),
+            Err(e) => return Err(XlsbError::Zip(e)),
+        }
+        Ok(relationships)
+    }
+
     /// MS-XLSB 2.1.7.50 Styles
     fn read_styles(&mut self) -> Result<(), XlsbError> {
         let mut iter = match RecordIter::from_zip(&mut self.zip, "xl/styles.bin") {
@@ -204,27 +299,35 @@ impl<RS: Read + Seek> Xlsb<RS> {
         let mut buf = Vec::with_capacity(1024);
         let mut number_formats = BTreeMap::new();
 
+        let mut fonts = Vec::new();
+        let mut fills = Vec::new();
+        let mut borders = Vec::new();
+
         loop {
-            match iter.read_type()? {
+            let typ = iter.read_type()?;
+            let record_len = iter.fill_buffer(&mut buf)?;
+            match typ {
                 0x0267 => {
                     // BrtBeginFmts
-                    let _len = iter.fill_buffer(&mut buf)?;
                     let len = read_usize(&buf);
 
                     for _ in 0..len {
                         let _ = iter.next_skip_blocks(0x002C, &[], &mut buf)?; // BrtFmt
                         let fmt_code = read_u16(&buf);
                         let fmt_str = wide_str(&buf[2..], &mut 0)?;
-                        number_formats
-                            .insert(fmt_code, detect_custom_number_format(fmt_str.as_ref()));
+                        number_formats.insert(fmt_code, fmt_str.into_owned());
                     }
                 }
+                0x002B => fonts.push(parse_font(&buf[..record_len])?), // BrtFont
+                0x002D => fills.push(parse_fill(&buf[..record_len])),  // BrtFill
+                0x002E => borders.push(parse_border(&buf[..record_len])), // BrtBorder
                 0x0269 => {
                     // BrtBeginCellXFs
-                    let _len = iter.fill_buffer(&mut buf)?;
                     let len = read_usize(&buf);
                     for _ in 0..len {
-                        let _ = iter.next_skip_blocks(0x002F, &[], &mut buf)?; // BrtXF
+                        let xf_len = iter.next_skip_blocks(0x002F, &[], &mut buf)?; // BrtXF
+                        self.styles
+                            .push(parse_xf(&buf[..xf_len], &fonts, &fills, &borders));
                         let fmt_code = read_u16(&buf[2..4]);
                         match builtin_format_by_code(fmt_code) {
                             CellFormat::DateTime => self.formats.push(CellFormat::DateTime),
@@ -233,11 +336,16 @@ impl<RS: Read + Seek> Xlsb<RS> {
                                 self.formats.push(
                                     number_formats
                                         .get(&fmt_code)
-                                        .copied()
-                                        .unwrap_or(CellFormat::Other),
+                                        .map_or(CellFormat::Other, |f| {
+                                            detect_custom_number_format(f)
+                                        }),
                                 );
                             }
                         }
+                        self.format_codes.push(match number_formats.get(&fmt_code) {
+                            Some(f) => Some(f.as_str().into()),
+                            None => builtin_format_code(fmt_code).map(Arc::from),
+                        });
                     }
                     // BrtBeginCellXFs is always present and always after BrtBeginFmts
                     break;
@@ -246,6 +354,7 @@ impl<RS: Read + Seek> Xlsb<RS> {
             }
             buf.clear();
         }
+        self.fonts = fonts;
 
         Ok(())
     }
@@ -262,15 +371,19 @@ impl<RS: Read + Seek> Xlsb<RS> {
         let len = read_usize(&buf[4..8]);
 
         // BrtSSTItems
-        for _ in 0..len {
-            let _ = iter.next_skip_blocks(
+        for idx in 0..len {
+            let item_len = iter.next_skip_blocks(
                 0x0013,
                 &[
                     (0x0023, Some(0x0024)), // future
                 ],
                 &mut buf,
             )?; // BrtSSTItem
-            self.strings.push(wide_str(&buf[1..], &mut 0)?.into_owned());
+            let (s, rich) = parse_rich_str(&buf[..item_len], &self.fonts)?;
+            if let Some(rich) = rich {
+                self.rich_strings.insert(idx, rich);
+            }
+            self.strings.push(s);
         }
         Ok(())
     }
@@ -284,14 +397,28 @@ impl<RS: Read + Seek> Xlsb<RS> {
         let mut buf = Vec::with_capacity(1024);
 
         loop {
-            match iter.read_type()? {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
                 0x0099 => {
-                    let _ = iter.fill_buffer(&mut buf)?;
                     self.is_1904 = &buf[0] & 0x1 != 0;
                 } // BrtWbProp
+                0x0216 if len >= 6 => {
+                    // BrtBookProtection: protpwdBook, protpwdRev then fLockStructure
+                    self.metadata.protection = Protection {
+                        protected: read_u16(&buf[4..]) & 0x1 != 0,
+                        password: read_u16(&buf) != 0,
+                    };
+                }
+                0x0827 if len >= 10 => {
+                    // BrtBookProtectionIso: hashed password
+                    self.metadata.protection = Protection {
+                        protected: read_u16(&buf[8..]) & 0x1 != 0,
+                        password: true,
+                    };
+                }
                 0x009C => {
                     // BrtBundleSh
-                    let len = iter.fill_buffer(&mut buf)?;
                     let rel_len = read_u32(&buf[8..len]);
                     if rel_len != 0xFFFF_FFFF {
                         let rel_len = rel_len as usize * 2;
@@ -327,6 +454,7 @@ impl<RS: Read + Seek> Xlsb<RS> {
                             name: name.to_string(),
                             typ,
                             visible,
+                            protection: Protection::default(),
                         });
                         self.sheets.push((name.into_owned(), path));
                     };
@@ -364,6 +492,16 @@ impl<RS: Read + Seek> Xlsb<RS> {
                         .take(cxti)
                         .collect();
                     self.extern_sheets = extern_sheets;
+                    self.extern_last_sheets = buf[4..]
+                        .chunks(12)
+                        .map(|xti| match (read_i32(&xti[4..8]), read_i32(&xti[8..12])) {
+                            (first, last) if first != last && last >= 0 => {
+                                sheets.get(last as usize).map(|s| s.0.clone())
+                            }
+                            _ => None,
+                        })
+                        .take(cxti)
+                        .collect();
                 }
                 0x0027 => {
                     // BrtName
@@ -372,7 +510,7 @@ impl<RS: Read + Seek> Xlsb<RS> {
                     let name = wide_str(&buf[9..len], &mut str_len)?.into_owned();
                     let rgce_len = read_u32(&buf[9 + str_len..]) as usize;
                     let rgce = &buf[13 + str_len..13 + str_len + rgce_len];
-                    let formula = parse_formula(rgce, &self.extern_sheets, &defined_names)?;
+                    let formula = parse_formula(rgce, &self.extern_sheets, &defined_names, None)?;
                     defined_names.push((name, formula));
                 }
                 0x009D | 0x0225 | 0x018D | 0x0180 | 0x009A | 0x0252 | 0x0229 | 0x009B | 0x0084 => {
@@ -385,6 +523,164 @@ impl<RS: Read + Seek> Xlsb<RS> {
         }
     }
 
+    /// Reads the BrtSheetProtection of each sheet, found after its data
+    fn read_sheet_protections(&mut self) -> Result<(), XlsbError> {
+        let mut buf = Vec::with_capacity(1024);
+        for ((_, path), sheet) in self.sheets.iter().zip(&mut self.metadata.sheets) {
+            let mut iter = match RecordIter::from_zip(&mut self.zip, path) {
+                Ok(iter) => iter,
+                Err(XlsbError::FileNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            loop {
+                let typ = match iter.read_type() {
+                    Ok(typ) => typ,
+                    // chart sheets have no BrtEndSheet
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e.into()),
+                };
+                let len = iter.fill_buffer(&mut buf)?;
+                match typ {
+                    // BrtSheetProtection: protpwd then fLocked
+                    0x0217 if len >= 6 => {
+                        sheet.protection = Protection {
+                            protected: read_u32(&buf[2..]) != 0,
+                            password: read_u16(&buf) != 0,
+                        };
+                        break;
+                    }
+                    // BrtSheetProtectionIso: dwSpinCount then fLocked, hashed password
+                    0x0828 if len >= 8 => {
+                        sheet.protection = Protection {
+                            protected: read_u32(&buf[4..]) != 0,
+                            password: true,
+                        };
+                        break;
+                    }
+                    0x0082 => break, // BrtEndSheet
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the table parts of all worksheets
+    fn read_table_metadata(&mut self) -> Result<(), XlsbError> {
+        let mut tables = Vec::new();
+        for (sheet_name, path) in self.sheets.clone() {
+            for (_, typ, target) in self.read_sheet_relationships(&path)? {
+                if !typ.ends_with("/table") {
+                    continue;
+                }
+                let mut iter =
+                    RecordIter::from_zip(&mut self.zip, &resolve_target(&path, &target))?;
+                let mut buf = Vec::with_capacity(1024);
+                let mut table = TableMetadata {
+                    sheet_name: sheet_name.clone(),
+                    ..TableMetadata::default()
+                };
+                loop {
+                    let typ = iter.read_type()?;
+                    let len = iter.fill_buffer(&mut buf)?;
+                    let buf = &buf[..len];
+                    match typ {
+                        0x0157 => parse_list(buf, &mut table)?, // BrtBeginList
+                        0x00A1 => {
+                            // BrtBeginAFilter: rfx
+                            table.auto_filter = Some(Dimensions::new(
+                                (read_u32(buf), read_u32(&buf[8..])),
+                                (read_u32(&buf[4..]), read_u32(&buf[12..])),
+                            ));
+                        }
+                        0x015B => table.columns.push(parse_list_column(buf)?), // BrtBeginListCol
+                        0x015E | 0x015F => {
+                            // BrtListCCFmla or BrtListTrFmla: flags, then the formula
+                            let cce = read_usize(&buf[2..]);
+                            let formula = buf.get(6..6 + cce).and_then(|rgce| {
+                                parse_formula(rgce, &self.extern_sheets, &self.metadata.names, None)
+                                    .ok()
+                            });
+                            if let (Some(column), Some(formula)) =
+                                (table.columns.last_mut(), formula)
+                            {
+                                if typ == 0x015E {
+                                    column.calculated_formula = Some(formula);
+                                } else {
+                                    column.totals_row_function =
+                                        Some(TotalsRowFunction::Custom(formula));
+                                }
+                            }
+                        }
+                        0x0201 => {
+                            // BrtTableStyleClient: flags, stStyleName
+                            let flags = read_u16(buf);
+                            let name = nullable_wide_str(&buf[2..], &mut 0)?;
+                            table.style = Some(TableStyle {
+                                name: name.unwrap_or_default().into_owned(),
+                                show_first_column: flags & 0x0001 != 0,
+                                show_last_column: flags & 0x0002 != 0,
+                                show_row_stripes: flags & 0x0004 != 0,
+                                show_column_stripes: flags & 0x0008 != 0,
+                            });
+                        }
+                        0x0158 => break, // BrtEndList
+                        _ => (),
+                    }
+                }
+                tables.push(table);
+            }
+        }
+        self.tables = Some(tables);
+        Ok(())
+    }
+
+    /// Load the tables of all worksheets
+    ///
+    /// Must be called before any other table method.
+    pub fn load_tables(&mut self) -> Result<(), XlsbError> {
+        if self.tables.is_none() {
+            self.read_table_metadata()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the names of all the tables
+    pub fn table_names(&self) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    /// Get the names of all the tables in a sheet
+    pub fn table_names_in_sheet(&self, sheet_name: &str) -> Vec<&String> {
+        self.tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
+            .collect()
+    }
+
+    /// Get the table by name
+    pub fn table_by_name(&mut self, table_name: &str) -> Result<Table<Data>, XlsbError> {
+        let metadata = self
+            .tables
+            .as_ref()
+            .expect("Tables must be loaded before they are referenced")
+            .iter()
+            .find(|t| t.name == table_name)
+            .cloned()
+            .ok_or_else(|| XlsbError::TableNotFound(table_name.into()))?;
+        let range = self.worksheet_range(&metadata.sheet_name)?;
+        Ok(Table::new(metadata, &range))
+    }
+
     /// Get a cells reader for a given worksheet
     pub fn worksheet_cells_reader<'a>(
         &'a mut self,
@@ -400,6 +696,7 @@ impl<RS: Read + Seek> Xlsb<RS> {
             &self.formats,
             &self.strings,
             &self.extern_sheets,
+            &self.extern_last_sheets,
             &self.metadata.names,
             self.is_1904,
         )
@@ -434,6 +731,16 @@ impl<RS: Read + Seek> Xlsb<RS> {
     }
 }
 
+#[cfg(feature = "encryption")]
+impl ReaderWithPassword for Xlsb<Cursor<Vec<u8>>> {
+    fn new_with_password<R: Read>(mut reader: R, password: &str) -> Result<Self, XlsbError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let data = crate::crypto::ooxml::decrypt(data, password)?;
+        Self::new(Cursor::new(data))
+    }
+}
+
 impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
     type Error = XlsbError;
 
@@ -444,17 +751,24 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
             zip: ZipArchive::new(reader)?,
             sheets: Vec::new(),
             strings: Vec::new(),
+            rich_strings: BTreeMap::new(),
             extern_sheets: Vec::new(),
+            extern_last_sheets: Vec::new(),
             formats: Vec::new(),
+            format_codes: Vec::new(),
+            styles: Vec::new(),
+            fonts: Vec::new(),
             is_1904: false,
             metadata: Metadata::default(),
+            tables: None,
             #[cfg(feature = "picture")]
             pictures: None,
         };
-        xlsb.read_shared_strings()?;
         xlsb.read_styles()?;
+        xlsb.read_shared_strings()?;
         let relationships = xlsb.read_relationships()?;
         xlsb.read_workbook(&relationships)?;
+        xlsb.read_sheet_protections()?;
         #[cfg(feature = "picture")]
         xlsb.read_pictures()?;
 
@@ -498,6 +812,354 @@ impl<RS: Read + Seek> Reader<RS> for Xlsb<RS> {
         Ok(Range::from_sparse(cells))
     }
 
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, XlsbError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_formula_info()? {
+            if !cell.val.text.is_empty() {
+                cells.push(cell);
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<Expr>, XlsbError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cells_reader.next_formula_expr()? {
+            cells.push(cell);
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, XlsbError> {
+        let format_codes = self.format_codes.clone();
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::with_capacity(cells_reader.dimensions().len().min(1_000_000) as _);
+        while let Some((cell, style)) = cells_reader.next_cell_with_style()? {
+            if cell.val != DataRef::Empty {
+                let format = format_codes.get(style).cloned().flatten();
+                let value = DataWithFormat::new(cell.val.into(), format);
+                cells.push(Cell::new(cell.pos, value));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsbError> {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some((cell, style)) = cells_reader.next_cell_with_style()? {
+            if style != 0 {
+                cells.push(Cell::new(cell.pos, StyleId(style)));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn styles(&self) -> &[Style] {
+        &self.styles
+    }
+
+    /// MS-XLSB 2.1.7.8 Comments
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut comments = Vec::new();
+        for (_, typ, target) in self.read_sheet_relationships(&path)? {
+            if !typ.ends_with("/comments") {
+                continue;
+            }
+            let mut iter = RecordIter::from_zip(&mut self.zip, &resolve_target(&path, &target))?;
+            let mut buf = Vec::with_capacity(1024);
+            let mut authors = Vec::new();
+            let mut current = None;
+            loop {
+                let typ = iter.read_type()?;
+                let len = iter.fill_buffer(&mut buf)?;
+                match typ {
+                    0x0278 => authors.push(wide_str(&buf[..len], &mut 0)?.into_owned()), // BrtCommentAuthor
+                    0x027B => {
+                        // BrtBeginComment: iauthor, rfx (rwFirst, rwLast, colFirst, colLast), guid
+                        let author = authors.get(read_usize(&buf)).cloned();
+                        current = Some(((read_u32(&buf[4..]), read_u32(&buf[12..])), author));
+                    }
+                    0x027D => {
+                        // BrtCommentText: RichStr
+                        if let Some((cell, author)) = current.take() {
+                            let text = wide_str(&buf[1..len], &mut 0)?.into_owned();
+                            comments.push(Comment { cell, author, text });
+                        }
+                    }
+                    0x0275 => break, // BrtEndComments
+                    _ => (),
+                }
+            }
+        }
+        Ok(comments)
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        // hyperlink targets are urls, not parts of the package: they are kept as is
+        let urls = self
+            .read_sheet_relationships(&path)?
+            .into_iter()
+            .filter(|(_, typ, _)| typ.ends_with("/hyperlink"))
+            .map(|(id, _, target)| (id, target))
+            .collect::<BTreeMap<_, _>>();
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut hyperlinks = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                0x01EE => hyperlinks.push(parse_hyperlink(&buf[..len], &urls)?), // BrtHLink
+                0x0082 => break,                                                 // BrtEndSheet
+                _ => (),
+            }
+        }
+        Ok(hyperlinks)
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<RichText>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut cells = Vec::new();
+        let mut row = 0;
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            let rich = match typ {
+                0x0000 => {
+                    // BrtRowHdr
+                    row = read_u32(&buf);
+                    continue;
+                }
+                0x0007 => self.rich_strings.get(&read_usize(&buf[8..12])).cloned(), // BrtCellIsst
+                0x003E => parse_rich_str(&buf[8..len], &self.fonts)?.1, // BrtCellRString
+                0x0092 => break,                                        // BrtEndSheetData
+                _ => None,
+            };
+            if let Some(rich) = rich {
+                cells.push(Cell::new((row, read_u32(&buf)), rich));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn merged_cells(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut merged_cells = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                0x00B0 if len >= 16 => {
+                    // BrtMergeCell: rfx
+                    merged_cells.push(Dimensions::new(
+                        (read_u32(&buf), read_u32(&buf[8..])),
+                        (read_u32(&buf[4..]), read_u32(&buf[12..])),
+                    ));
+                }
+                0x00B2 | 0x0082 => break, // BrtEndMergeCells or BrtEndSheet
+                _ => (),
+            }
+        }
+        Ok(merged_cells)
+    }
+
+    fn worksheet_row_column_properties(
+        &mut self,
+        name: &str,
+    ) -> Result<RowColumnProperties, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut props = RowColumnProperties::default();
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                0x01E5 if len >= 10 => {
+                    // BrtWsFmtInfo: dxGCol, cchDefColWidth, miyDefRwHeight then flags
+                    let width = read_u32(&buf);
+                    if width != 0xFFFF_FFFF {
+                        props.default_column.width = Some(width as f64 / 256.);
+                    }
+                    props.default_row.height = Some(read_u16(&buf[6..]) as f64 / 20.);
+                    props.default_row.hidden = read_u16(&buf[8..]) & 0x2 != 0;
+                }
+                0x003C if len >= 18 => {
+                    // BrtColInfo: colFirst, colLast, coldx, ixfe then flags
+                    let flags = read_u16(&buf[16..]);
+                    let column = ColumnProperties {
+                        hidden: flags & 0x1 != 0,
+                        outline_level: ((flags >> 8) & 0x7) as u8,
+                        collapsed: flags & 0x1000 != 0,
+                        // fUserSet
+                        width: (flags & 0x2 != 0).then(|| read_u32(&buf[8..]) as f64 / 256.),
+                    };
+                    let last = read_u32(&buf[4..]).min(16_383);
+                    for col in read_u32(&buf)..=last {
+                        props.columns.insert(col, column.clone());
+                    }
+                }
+                0x0000 if len >= 12 => {
+                    // BrtRowHdr: rw, ixfe, miyRw then flags
+                    let flags = buf[11];
+                    let row = RowProperties {
+                        hidden: flags & 0x10 != 0,
+                        outline_level: flags & 0x7,
+                        collapsed: flags & 0x8 != 0,
+                        // fUnsynced
+                        height: (flags & 0x20 != 0).then(|| read_u16(&buf[8..]) as f64 / 20.),
+                    };
+                    props.rows.insert(read_u32(&buf), row);
+                }
+                0x0092 | 0x0082 => break, // BrtEndSheetData or BrtEndSheet
+                _ => (),
+            }
+        }
+        props.remove_defaults();
+        Ok(props)
+    }
+
+    /// Sort states are not read
+    fn worksheet_auto_filter(&mut self, name: &str) -> Result<Option<AutoFilter>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut filter: Option<AutoFilter> = None;
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            let columns = filter.as_mut().map(|f| &mut f.columns);
+            let column = columns.and_then(|c| c.last_mut()).map(|c| &mut c.filter);
+            match (typ, column) {
+                (0x00A1, _) if len >= 16 => {
+                    // BrtBeginAFilter: rfx
+                    filter = Some(AutoFilter {
+                        range: Dimensions::new(
+                            (read_u32(&buf), read_u32(&buf[8..])),
+                            (read_u32(&buf[4..]), read_u32(&buf[12..])),
+                        ),
+                        ..AutoFilter::default()
+                    });
+                }
+                (0x00A3, _) if len >= 4 => {
+                    // BrtBeginFilterColumn: dwCol then flags
+                    if let Some(ref mut filter) = filter {
+                        filter.columns.push(FilterColumn {
+                            index: read_u32(&buf),
+                            filter: Filter::Other,
+                        });
+                    }
+                }
+                (0x00A5, Some(column)) if len >= 4 => {
+                    // BrtBeginFilters: fBlank then iCalendarType
+                    *column = Filter::Values {
+                        values: Vec::new(),
+                        blank: read_u32(&buf) != 0,
+                    };
+                }
+                (0x00A7, Some(Filter::Values { values, .. })) => {
+                    // BrtFilter: rgst
+                    values.push(wide_str(&buf[..len], &mut 0)?.into_owned());
+                }
+                (0x00AC, Some(column)) if len >= 4 => {
+                    // BrtBeginCustomFilters: fAnd
+                    *column = Filter::Custom {
+                        conditions: Vec::new(),
+                        and: read_u32(&buf) != 0,
+                    };
+                }
+                (0x00AE, Some(Filter::Custom { conditions, .. })) if len >= 2 => {
+                    conditions.push(parse_custom_filter(&buf[..len])?);
+                }
+                (0x00AA, Some(column)) if len >= 8 => {
+                    // BrtTop10Filter: flags then iTopN, xnumFilter
+                    let flags = read_u32(&buf);
+                    *column = Filter::Top10 {
+                        top: flags & 0x1 != 0,
+                        percent: flags & 0x2 != 0,
+                        value: read_u32(&buf[4..]) as f64,
+                    };
+                }
+                (0x00AB, Some(column)) if len >= 4 => {
+                    // BrtDynamicFilter: cft then xnumValue, xnumValueMax
+                    if let Some(typ) = dynamic_filter_type(read_u32(&buf)) {
+                        *column = Filter::Dynamic(typ.to_string());
+                    }
+                }
+                (0x00A2 | 0x0082, _) => break, // BrtEndAFilter or BrtEndSheet
+                _ => (),
+            }
+        }
+        Ok(filter)
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsbError> {
+        let path = match self.sheets.iter().find(|&(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsbError::WorksheetNotFound(name.into())),
+        };
+        let mut iter = RecordIter::from_zip(&mut self.zip, &path)?;
+        let mut buf = Vec::with_capacity(1024);
+        let mut validations = Vec::new();
+        loop {
+            let typ = iter.read_type()?;
+            let len = iter.fill_buffer(&mut buf)?;
+            match typ {
+                0x0040 => validations.push(parse_data_validation(
+                    &buf[..len],
+                    &self.extern_sheets,
+                    &self.metadata.names,
+                )?),
+                0x023E | 0x0082 => break, // BrtEndDVals or BrtEndSheet
+                _ => (),
+            }
+        }
+        Ok(validations)
+    }
+
+    fn worksheet_rows<'a>(
+        &'a mut self,
+        name: &str,
+    ) -> Result<WorksheetRows<'a, XlsbError>, XlsbError>
+    where
+        XlsbError: 'a,
+    {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        Ok(cell_rows(std::iter::from_fn(move || {
+            cells_reader.next_cell().transpose()
+        })))
+    }
+
     /// MS-XLSB 2.1.7.62
     fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
         let sheets = self
@@ -597,6 +1259,228 @@ impl<'a> RecordIter<'a> {
     }
 }
 
+/// Decode BrtFont
+///
+/// See: MS-XLSB 2.4.490
+fn parse_font(buf: &[u8]) -> Result<Font, XlsbError> {
+    if buf.len() < 21 {
+        return Ok(Font::default());
+    }
+    let grbit = read_u16(&buf[2..4]);
+    let name = if buf.len() > 21 {
+        Some(wide_str(&buf[21..], &mut 0)?.into_owned())
+    } else {
+        None
+    };
+    Ok(Font {
+        name,
+        size: Some(read_u16(buf) as f64 / 20.),
+        bold: read_u16(&buf[4..6]) >= 700,
+        italic: grbit & 0x0002 != 0,
+        underline: buf[8] != 0,
+        strikethrough: grbit & 0x0008 != 0,
+        color: parse_color(&buf[12..20]),
+    })
+}
+
+/// Decode BrtFill
+///
+/// See: MS-XLSB 2.4.498
+fn parse_fill(buf: &[u8]) -> Fill {
+    if buf.len() < 20 {
+        return Fill::default();
+    }
+    Fill {
+        pattern: FillPattern::from_index(read_u32(buf)),
+        foreground: parse_color(&buf[4..12]),
+        background: parse_color(&buf[12..20]),
+    }
+}
+
+/// Decode BrtBorder, made of top, bottom, left and right Blxf
+///
+/// See: MS-XLSB 2.4.301
+fn parse_border(buf: &[u8]) -> Borders {
+    let side = |offset: usize| {
+        buf.get(offset..offset + 10)
+            .map_or_else(Border::default, |b| Border {
+                style: BorderStyle::from_index(b[0] as u32),
+                color: parse_color(&b[2..10]),
+            })
+    };
+    Borders {
+        top: side(1),
+        bottom: side(11),
+        left: side(21),
+        right: side(31),
+    }
+}
+
+/// Decode BrtXF
+///
+/// See: MS-XLSB 2.4.861
+fn parse_xf(buf: &[u8], fonts: &[Font], fills: &[Fill], borders: &[Borders]) -> Style {
+    if buf.len() < 14 {
+        return Style::default();
+    }
+    let flags = read_u16(&buf[12..14]);
+    Style {
+        font: fonts
+            .get(read_u16(&buf[4..6]) as usize)
+            .cloned()
+            .unwrap_or_default(),
+        fill: fills
+            .get(read_u16(&buf[6..8]) as usize)
+            .cloned()
+            .unwrap_or_default(),
+        borders: borders
+            .get(read_u16(&buf[8..10]) as usize)
+            .cloned()
+            .unwrap_or_default(),
+        alignment: Alignment {
+            horizontal: HorizontalAlignment::from_index((flags & 0x7) as u32),
+            vertical: VerticalAlignment::from_index(((flags >> 3) & 0x7) as u32),
+            wrap_text: flags & 0x0040 != 0,
+            shrink_to_fit: flags & 0x0100 != 0,
+            indent: buf[11] as u32,
+        },
+        protection: CellProtection {
+            locked: flags & 0x1000 != 0,
+            hidden: flags & 0x2000 != 0,
+        },
+    }
+}
+
+/// Decode BrtColor
+///
+/// See: MS-XLSB 2.4.324
+fn parse_color(buf: &[u8]) -> Option<Color> {
+    match buf[0] >> 1 {
+        1 => Color::from_index(buf[1] as u32),
+        2 => Some(Color::Rgb(buf[4], buf[5], buf[6])),
+        3 => Some(Color::Theme(buf[1] as u32)),
+        _ => None,
+    }
+}
+
+/// BrtCustomFilter [MS-XLSB 2.4.352]: vts, grbitSgn then the compared value
+fn parse_custom_filter(buf: &[u8]) -> Result<FilterCondition, XlsbError> {
+    let operator = match buf[1] {
+        0x01 => FilterOperator::LessThan,
+        0x03 => FilterOperator::LessThanOrEqual,
+        0x04 => FilterOperator::GreaterThan,
+        0x05 => FilterOperator::NotEqual,
+        0x06 => FilterOperator::GreaterThanOrEqual,
+        _ => FilterOperator::Equal,
+    };
+    let value = match buf[0] {
+        0x04 if buf.len() >= 10 => Data::Float(read_f64(&buf[2..])),
+        0x06 => Data::String(wide_str(&buf[2..], &mut 0)?.into_owned()),
+        0x08 if buf.len() >= 3 => Data::Bool(buf[2] != 0),
+        // all blanks or all non blanks
+        _ => Data::Empty,
+    };
+    Ok(FilterCondition { operator, value })
+}
+
+/// BrtDVal: flags, sqrfx, the error and prompt strings then the two formulas
+fn parse_data_validation(
+    buf: &[u8],
+    sheets: &[String],
+    names: &[(String, String)],
+) -> Result<DataValidation, XlsbError> {
+    if buf.len() < 8 {
+        return Ok(DataValidation::default());
+    }
+    let mut validation = dval_flags(read_u32(buf));
+    let crfx = read_usize(&buf[4..]);
+    validation.ranges = buf[8..]
+        .chunks_exact(16)
+        .take(crfx)
+        .map(|rfx| {
+            Dimensions::new(
+                (read_u32(rfx), read_u32(&rfx[8..])),
+                (read_u32(&rfx[4..]), read_u32(&rfx[12..])),
+            )
+        })
+        .collect();
+    let mut buf = buf
+        .get(8 + 16 * validation.ranges.len()..)
+        .unwrap_or_default();
+    let mut strings = [None, None, None, None];
+    for s in &mut strings {
+        if buf.len() < 4 {
+            break;
+        }
+        let mut len = 0;
+        *s = nullable_wide_str(buf, &mut len)?
+            .map(|s| s.into_owned())
+            .filter(|s| !s.is_empty());
+        buf = &buf[len..];
+    }
+    let [error_title, error, prompt_title, prompt] = strings;
+    validation.error_title = error_title;
+    validation.error = error;
+    validation.prompt_title = prompt_title;
+    validation.prompt = prompt;
+    let mut formulas = [None, None];
+    for formula in &mut formulas {
+        // cce, rgce, cb then rgcb
+        if buf.len() < 4 {
+            break;
+        }
+        let cce = read_usize(buf);
+        let rgce = match buf.get(4..4 + cce) {
+            Some(rgce) => rgce,
+            None => break,
+        };
+        if cce > 0 {
+            // list literals are separated by null characters
+            *formula = Some(parse_formula(rgce, sheets, names, None)?.replace('\0', ","));
+        }
+        let cb = buf.get(4 + cce..).map_or(0, read_usize);
+        buf = buf.get(8 + cce + cb..).unwrap_or_default();
+    }
+    let [formula1, formula2] = formulas;
+    validation.formula1 = formula1;
+    validation.formula2 = formula2;
+    Ok(validation)
+}
+
+/// Converts a dynamic filter type to its OOXML name
+fn dynamic_filter_type(cft: u32) -> Option<&'static str> {
+    const MONTHS: [&str; 12] = [
+        "M1", "M2", "M3", "M4", "M5", "M6", "M7", "M8", "M9", "M10", "M11", "M12",
+    ];
+    let typ = match cft {
+        0x01 => "aboveAverage",
+        0x02 => "belowAverage",
+        0x08 => "tomorrow",
+        0x09 => "today",
+        0x0A => "yesterday",
+        0x0B => "nextWeek",
+        0x0C => "thisWeek",
+        0x0D => "lastWeek",
+        0x0E => "nextMonth",
+        0x0F => "thisMonth",
+        0x10 => "lastMonth",
+        0x11 => "nextQuarter",
+        0x12 => "thisQuarter",
+        0x13 => "lastQuarter",
+        0x14 => "nextYear",
+        0x15 => "thisYear",
+        0x16 => "lastYear",
+        0x17 => "yearToDate",
+        0x18 => "Q1",
+        0x19 => "Q2",
+        0x1A => "Q3",
+        0x1B => "Q4",
+        0x1C..=0x27 => MONTHS[cft as usize - 0x1C],
+        _ => return None,
+    };
+    Some(typ)
+}
+
 fn wide_str<'a>(buf: &'a [u8], str_len: &mut usize) -> Result<Cow<'a, str>, XlsbError> {
     let len = read_u32(buf) as usize;
     if buf.len() < 4 + len * 2 {
@@ -610,6 +1494,147 @@ fn wide_str<'a>(buf: &'a [u8], str_len: &mut usize) -> Result<Cow<'a, str>, Xlsb
     Ok(UTF_16LE.decode(s).0)
 }
 
+/// XLNullableWideString [MS-XLSB 2.5.166], `None` when null
+fn nullable_wide_str<'a>(
+    buf: &'a [u8],
+    str_len: &mut usize,
+) -> Result<Option<Cow<'a, str>>, XlsbError> {
+    if buf.len() >= 4 && read_u32(buf) == 0xFFFF_FFFF {
+        *str_len = 4;
+        return Ok(None);
+    }
+    wide_str(buf, str_len).map(Some)
+}
+
+/// RichStr [MS-XLSB 2.5.121], returns the string and its runs, if formatted
+fn parse_rich_str(buf: &[u8], fonts: &[Font]) -> Result<(String, Option<RichText>), XlsbError> {
+    let mut str_len = 0;
+    let s = wide_str(&buf[1..], &mut str_len)?.into_owned();
+    if buf[0] & 0x1 == 0 {
+        // fRichStr
+        return Ok((s, None));
+    }
+    let runs = match buf.get(1 + str_len..) {
+        Some(runs) if runs.len() >= 4 => runs[4..]
+            .chunks_exact(4)
+            .take(read_usize(runs))
+            .map(|run| {
+                // StrRun: ich, ifnt
+                let font = fonts.get(read_u16(&run[2..]) as usize);
+                (read_u16(run) as usize, font.cloned().unwrap_or_default())
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let rich = RichText::from_runs(&s, runs);
+    Ok((s, Some(rich)))
+}
+
+/// BrtHLink [MS-XLSB 2.4.679]
+fn parse_hyperlink(buf: &[u8], urls: &BTreeMap<String, String>) -> Result<Hyperlink, XlsbError> {
+    if buf.len() < 20 {
+        return Err(XlsbError::WideStr {
+            ws_len: 20,
+            buf_len: buf.len(),
+        });
+    }
+    // rfx: rwFirst, rwLast, colFirst, colLast
+    let range = Dimensions::new(
+        (read_u32(buf), read_u32(&buf[8..])),
+        (read_u32(&buf[4..]), read_u32(&buf[12..])),
+    );
+    let mut pos = 16;
+    let mut consumed = 0;
+    // relId is a XLNullableWideString
+    let url = if read_u32(&buf[pos..]) == 0xFFFF_FFFF {
+        pos += 4;
+        None
+    } else {
+        let id = wide_str(&buf[pos..], &mut consumed)?;
+        pos += consumed;
+        urls.get(&*id).cloned()
+    };
+    let location = wide_str(&buf[pos..], &mut consumed)?.into_owned();
+    pos += consumed;
+    let tooltip = wide_str(&buf[pos..], &mut consumed)?.into_owned();
+    Ok(Hyperlink {
+        range,
+        url,
+        location: Some(location).filter(|l| !l.is_empty()),
+        tooltip: Some(tooltip).filter(|t| !t.is_empty()),
+    })
+}
+
+/// BrtBeginList
+fn parse_list(buf: &[u8], table: &mut TableMetadata) -> Result<(), XlsbError> {
+    if buf.len() < 64 {
+        return Err(XlsbError::WideStr {
+            ws_len: 64,
+            buf_len: buf.len(),
+        });
+    }
+    // rfxList: rwFirst, rwLast, colFirst, colLast
+    table.range = Dimensions::new(
+        (read_u32(buf), read_u32(&buf[8..])),
+        (read_u32(&buf[4..]), read_u32(&buf[12..])),
+    );
+    // lt, idList, crwHeader, crwTotals, flags
+    table.header_row_count = read_u32(&buf[24..]);
+    table.totals_row_count = read_u32(&buf[28..]);
+    table.totals_row_shown = buf[32] & 0x01 != 0;
+    table.insert_row = buf[32] & 0x04 != 0;
+    // 6 dxf ids and dwConnID are followed by stName and stDisplayName
+    let mut consumed = 0;
+    nullable_wide_str(&buf[64..], &mut consumed)?;
+    table.name = wide_str(&buf[64 + consumed..], &mut 0)?.into_owned();
+    Ok(())
+}
+
+/// BrtBeginListCol
+fn parse_list_column(buf: &[u8]) -> Result<TableColumn, XlsbError> {
+    if buf.len() < 24 {
+        return Err(XlsbError::WideStr {
+            ws_len: 24,
+            buf_len: buf.len(),
+        });
+    }
+    // idField, ilta
+    let totals_row_function = match read_u32(&buf[4..]) {
+        1 => Some(TotalsRowFunction::Average),
+        2 => Some(TotalsRowFunction::Count),
+        3 => Some(TotalsRowFunction::CountNums),
+        4 => Some(TotalsRowFunction::Max),
+        5 => Some(TotalsRowFunction::Min),
+        6 => Some(TotalsRowFunction::Sum),
+        7 => Some(TotalsRowFunction::StdDev),
+        8 => Some(TotalsRowFunction::Var),
+        // the formula is in BrtListTrFmla
+        9 => Some(TotalsRowFunction::Custom(String::new())),
+        _ => None,
+    };
+    // nDxfHdr, nDxfInsertRow, nDxfAgg and idqsif are followed by stName, stCaption and
+    // stTotal, the caption being the header text when set
+    let mut pos = 24;
+    let mut consumed = 0;
+    let name = nullable_wide_str(&buf[pos..], &mut consumed)?;
+    pos += consumed;
+    let caption = nullable_wide_str(&buf[pos..], &mut consumed)?;
+    pos += consumed;
+    let totals_row_label = nullable_wide_str(&buf[pos..], &mut 0)?;
+    Ok(TableColumn {
+        name: caption
+            .filter(|c| !c.is_empty())
+            .or(name)
+            .unwrap_or_default()
+            .into_owned(),
+        calculated_formula: None,
+        totals_row_function,
+        totals_row_label: totals_row_label
+            .filter(|l| !l.is_empty())
+            .map(Cow::into_owned),
+    })
+}
+
 /// Formula parsing
 ///
 /// [MS-XLSB 2.2.2]
@@ -620,6 +1645,7 @@ fn parse_formula(
     mut rgce: &[u8],
     sheets: &[String],
     names: &[(String, String)],
+    origin: Option<(u32, u32)>,
 ) -> Result<String, XlsbError> {
     if rgce.is_empty() {
         return Ok(String::new());
@@ -872,15 +1898,28 @@ fn parse_formula(
             }
             0x25 | 0x45 | 0x65 => {
                 stack.push(formula.len());
-                formula.push('$');
-                push_column(read_u16(&rgce[8..10]) as u32, &mut formula);
-                formula.push('$');
-                formula.push_str(&format!("{}", read_u32(&rgce[0..4]) + 1));
+                push_cell(read_u32(rgce), read_u16(&rgce[8..]), &mut formula);
                 formula.push(':');
-                formula.push('$');
-                push_column(read_u16(&rgce[10..12]) as u32, &mut formula);
-                formula.push('$');
-                formula.push_str(&format!("{}", read_u32(&rgce[4..8]) + 1));
+                push_cell(read_u32(&rgce[4..]), read_u16(&rgce[10..]), &mut formula);
+                rgce = &rgce[12..];
+            }
+            0x2C | 0x4C | 0x6C => {
+                // PtgRefN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xlsb_rel_cell(read_u32(rgce), read_u16(&rgce[4..]), origin);
+                stack.push(formula.len());
+                push_cell(row, col, &mut formula);
+                rgce = &rgce[6..];
+            }
+            0x2D | 0x4D | 0x6D => {
+                // PtgAreaN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xlsb_rel_cell(read_u32(rgce), read_u16(&rgce[8..]), origin);
+                stack.push(formula.len());
+                push_cell(row, col, &mut formula);
+                formula.push(':');
+                let (row, col) = xlsb_rel_cell(read_u32(&rgce[4..]), read_u16(&rgce[10..]), origin);
+                push_cell(row, col, &mut formula);
                 rgce = &rgce[12..];
             }
             0x2A | 0x4A | 0x6A => {
@@ -896,7 +1935,7 @@ fn parse_formula(
             0x29 | 0x49 | 0x69 => {
                 let cce = read_u16(rgce) as usize;
                 rgce = &rgce[2..];
-                let f = parse_formula(&rgce[..cce], sheets, names)?;
+                let f = parse_formula(&rgce[..cce], sheets, names, origin)?;
                 stack.push(formula.len());
                 formula.push_str(&f);
                 rgce = &rgce[cce..];
@@ -918,6 +1957,227 @@ fn parse_formula(
     }
 }
 
+/// Formula parsing into an expression tree
+///
+/// Relative references of shared formulas are offsets from `origin`, the formula cell.
+/// Returns `None` for structured and external references and unsupported tokens.
+///
+/// See Ptg [2.5.97.16]
+fn parse_formula_expr(
+    mut rgce: &[u8],
+    sheets: &[String],
+    last_sheets: &[Option<String>],
+    names: &[(String, String)],
+    origin: Option<(u32, u32)>,
+) -> Option<Expr> {
+    let mut stack = Vec::new();
+    while !rgce.is_empty() {
+        let ptg = rgce[0];
+        rgce = &rgce[1..];
+        let expr = match ptg {
+            0x3a | 0x5a | 0x7a | 0x3b | 0x5b | 0x7b => {
+                // PtgRef3d | PtgArea3d
+                let ixti = read_u16(rgce) as usize;
+                let sheet = sheets.get(ixti).filter(|s| !s.starts_with('#'))?;
+                let reference = if ptg & 0x1F == 0x1A {
+                    let (row, col) = (read_u32(&rgce[2..]), read_u16(&rgce[6..]));
+                    let (row, col) = match origin {
+                        Some(origin) => xlsb_rel_cell(row, col, origin),
+                        None => (row, col),
+                    };
+                    rgce = &rgce[8..];
+                    ptg_ref(ptg_cell(row, col))
+                } else {
+                    let reference = xlsb_area(&rgce[2..], origin);
+                    rgce = &rgce[14..];
+                    reference
+                };
+                Expr::Reference(Reference {
+                    sheet: Some(sheet.clone()),
+                    last_sheet: last_sheets.get(ixti).cloned().flatten(),
+                    ..reference
+                })
+            }
+            0x3c | 0x5c | 0x7c | 0x3d | 0x5d | 0x7d => {
+                // PtgRefErr3d | PtgAreaErr3d
+                rgce = &rgce[if ptg & 0x1F == 0x1C { 8 } else { 14 }..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            0x03..=0x11 => {
+                let rhs = stack.pop()?;
+                let lhs = stack.pop()?;
+                Expr::Binary(ptg_binary_op(ptg)?, Box::new(lhs), Box::new(rhs))
+            }
+            0x12..=0x14 => {
+                let op = match ptg {
+                    0x12 => UnaryOp::Plus,
+                    0x13 => UnaryOp::Minus,
+                    _ => UnaryOp::Percent,
+                };
+                Expr::Unary(op, Box::new(stack.pop()?))
+            }
+            0x15 => continue, // PtgParen
+            0x16 => Expr::Missing,
+            0x17 => {
+                // PtgStr
+                let cch = read_u16(rgce) as usize;
+                let s = UTF_16LE.decode(&rgce[2..2 + 2 * cch]).0.into_owned();
+                rgce = &rgce[2 + 2 * cch..];
+                Expr::String(s)
+            }
+            0x19 => {
+                let eptg = rgce[0];
+                rgce = &rgce[1..];
+                match eptg {
+                    0x01 | 0x02 | 0x08 | 0x20 | 0x21 | 0x40 | 0x41 | 0x80 => {
+                        rgce = &rgce[2..];
+                        continue;
+                    }
+                    0x04 => {
+                        // PtgAttrChoose
+                        let n = read_u16(rgce) as usize + 1;
+                        rgce = &rgce[2 + 2 * n..];
+                        continue;
+                    }
+                    0x10 => {
+                        // PtgAttrSum
+                        rgce = &rgce[2..];
+                        let args = vec![stack.pop()?];
+                        Expr::Function {
+                            name: "SUM".to_string(),
+                            args,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            0x1C => {
+                let err = ptg_error(rgce[0])?;
+                rgce = &rgce[1..];
+                Expr::Error(err)
+            }
+            0x1D => {
+                let b = rgce[0] != 0;
+                rgce = &rgce[1..];
+                Expr::Bool(b)
+            }
+            0x1E => {
+                let n = read_u16(rgce);
+                rgce = &rgce[2..];
+                Expr::Number(n as f64)
+            }
+            0x1F => {
+                let n = read_f64(rgce);
+                rgce = &rgce[8..];
+                Expr::Number(n)
+            }
+            0x21 | 0x41 | 0x61 => {
+                // PtgFunc
+                let iftab = read_u16(rgce) as usize;
+                rgce = &rgce[2..];
+                let argc = *crate::utils::FTAB_ARGC.get(iftab)? as usize;
+                ptg_function(&mut stack, iftab, argc)?
+            }
+            0x22 | 0x42 | 0x62 => {
+                // PtgFuncVar
+                let argc = rgce[0] as usize;
+                let iftab = (read_u16(&rgce[1..]) & 0x7FFF) as usize;
+                rgce = &rgce[3..];
+                ptg_function(&mut stack, iftab, argc)?
+            }
+            0x23 | 0x43 | 0x63 => {
+                let iname = (read_u32(rgce) as usize).checked_sub(1)?; // one-based
+                rgce = &rgce[4..];
+                Expr::Name(names.get(iname)?.0.clone())
+            }
+            0x24 | 0x44 | 0x64 => {
+                let cell = ptg_cell(read_u32(rgce), read_u16(&rgce[4..]));
+                rgce = &rgce[6..];
+                Expr::Reference(ptg_ref(cell))
+            }
+            0x25 | 0x45 | 0x65 => {
+                let reference = xlsb_area(rgce, None);
+                rgce = &rgce[12..];
+                Expr::Reference(reference)
+            }
+            0x26..=0x28 | 0x46..=0x48 | 0x66..=0x68 => {
+                // PtgMemArea | PtgMemErr | PtgMemNoMem: the sub-expression follows
+                rgce = &rgce[6..];
+                continue;
+            }
+            0x29 | 0x49 | 0x69 => {
+                // PtgMemFunc
+                rgce = &rgce[2..];
+                continue;
+            }
+            0x2C | 0x4C | 0x6C => {
+                // PtgRefN
+                let origin = origin.unwrap_or_default();
+                let (row, col) = xlsb_rel_cell(read_u32(rgce), read_u16(&rgce[4..]), origin);
+                rgce = &rgce[6..];
+                Expr::Reference(ptg_ref(ptg_cell(row, col)))
+            }
+            0x2D | 0x4D | 0x6D => {
+                // PtgAreaN
+                let reference = xlsb_area(rgce, Some(origin.unwrap_or_default()));
+                rgce = &rgce[12..];
+                Expr::Reference(reference)
+            }
+            0x2A | 0x4A | 0x6A => {
+                rgce = &rgce[6..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            0x2B | 0x4B | 0x6B => {
+                rgce = &rgce[12..];
+                Expr::Error(CellErrorType::Ref)
+            }
+            _ => {
+                // PtgExp, PtgArray, PtgList, PtgNameX ...
+                debug!("unsupported ptg {:x} in formula expression", ptg);
+                return None;
+            }
+        };
+        stack.push(expr);
+    }
+    match stack.len() {
+        1 => stack.pop(),
+        _ => None,
+    }
+}
+
+/// RgceArea [MS-XLSB 2.5.97.89], or RgceAreaRel with an `origin`
+fn xlsb_area(rgce: &[u8], origin: Option<(u32, u32)>) -> Reference {
+    let cell = |row: u32, col: u16| {
+        let (row, col) = match origin {
+            Some(origin) => xlsb_rel_cell(row, col, origin),
+            None => (row, col),
+        };
+        ptg_cell(row, col)
+    };
+    let start = cell(read_u32(rgce), read_u16(&rgce[8..]));
+    let end = cell(read_u32(&rgce[4..]), read_u16(&rgce[10..]));
+    ptg_area(start, end, 0xF_FFFF, 0x3FFF)
+}
+
+/// RgceLocRel [MS-XLSB 2.5.97.92]
+///
+/// Relative rows and columns are offsets from `origin`, wrapping around the worksheet.
+fn xlsb_rel_cell(row: u32, colu: u16, origin: (u32, u32)) -> (u32, u16) {
+    let row = if colu & 0x8000 != 0 {
+        (origin.0 as i64 + row as i32 as i64).rem_euclid(0x10_0000) as u32
+    } else {
+        row
+    };
+    let col = if colu & 0x4000 != 0 {
+        // 14 bits signed offset
+        let offset = ((colu << 2) as i16 >> 2) as i32;
+        (origin.1 as i32 + offset).rem_euclid(0x4000) as u16
+    } else {
+        colu & 0x3FFF
+    };
+    (row, (colu & 0xC000) | col)
+}
+
 fn cell_format<'a>(formats: &'a [CellFormat], buf: &[u8]) -> Option<&'a CellFormat> {
     // Parses a Cell (MS-XLSB 2.5.9) and determines if it references a Date format
 
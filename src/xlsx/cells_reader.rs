@@ -2,16 +2,19 @@ use quick_xml::{
     events::{attributes::Attribute, BytesStart, Event},
     name::QName,
 };
-use std::{borrow::Borrow, collections::HashMap};
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+};
 
 use super::{
-    get_attribute, get_dimension, get_row, get_row_column, read_string, replace_cell_names,
-    Dimensions, XlReader,
+    get_attribute, get_dimension, get_row, get_row_column, read_rich_string, read_string,
+    replace_cell_names, Dimensions, XlReader,
 };
 use crate::{
     datatype::DataRef,
     formats::{format_excel_f64_ref, CellFormat},
-    Cell, XlsxError,
+    Cell, FormulaInfo, FormulaKind, RichText, XlsxError,
 };
 
 /// An xlsx Cell Iterator
@@ -80,68 +83,234 @@ impl<'a> XlsxCellReader<'a> {
     }
 
     pub fn next_cell(&mut self) -> Result<Option<Cell<DataRef<'a>>>, XlsxError> {
-        loop {
-            self.buf.clear();
-            match self.xml.read_event_into(&mut self.buf) {
-                Ok(Event::Start(ref row_element))
-                    if row_element.local_name().as_ref() == b"row" =>
-                {
-                    let attribute = get_attribute(row_element.attributes(), QName(b"r"))?;
-                    if let Some(range) = attribute {
-                        let row = get_row(range)?;
-                        self.row_index = row;
+        Ok(self.next_cell_with_style()?.map(|(cell, _)| cell))
+    }
+
+    /// Gets the next cell along with its style index (the `s` attribute)
+    pub(crate) fn next_cell_with_style(
+        &mut self,
+    ) -> Result<Option<(Cell<DataRef<'a>>, usize)>, XlsxError> {
+        self.next_cell_by(|reader, pos, c| {
+            let style = get_attribute(c.attributes(), QName(b"s"))?
+                .and_then(|s| std::str::from_utf8(s).ok()?.parse().ok())
+                .unwrap_or(0);
+            let mut value = DataRef::Empty;
+            loop {
+                reader.cell_buf.clear();
+                match reader.xml.read_event_into(&mut reader.cell_buf) {
+                    Ok(Event::Start(ref e)) => {
+                        value = read_value(
+                            reader.strings,
+                            reader.formats,
+                            reader.is_1904,
+                            &mut reader.xml,
+                            e,
+                            c,
+                        )?
                     }
+                    Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
+                    Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
+                    Err(e) => return Err(XlsxError::Xml(e)),
+                    _ => (),
                 }
-                Ok(Event::End(ref row_element)) if row_element.local_name().as_ref() == b"row" => {
-                    self.row_index += 1;
-                    self.col_index = 0;
+            }
+            Ok(Some((Cell::new(pos, value), style)))
+        })
+    }
+
+    pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, XlsxError> {
+        let cell = self.next_formula_info()?;
+        Ok(cell.map(|c| Cell::new(c.pos, c.val.text)))
+    }
+
+    /// Reads the next cell formula along with its kind
+    pub fn next_formula_info(&mut self) -> Result<Option<Cell<FormulaInfo>>, XlsxError> {
+        self.next_cell_by(|reader, pos, c| {
+            // cell metadata, used by dynamic arrays
+            let has_metadata = get_attribute(c.attributes(), QName(b"cm"))?.is_some();
+            let mut value = None;
+            let mut kind = FormulaKind::Normal;
+            loop {
+                reader.cell_buf.clear();
+                match reader.xml.read_event_into(&mut reader.cell_buf) {
+                    Ok(Event::Start(ref e)) => {
+                        let formula = read_formula(&mut reader.xml, e)?;
+                        if let Some(f) = formula.borrow() {
+                            value = Some(f.clone());
+                        }
+                        match get_attribute(e.attributes(), QName(b"t")) {
+                            Ok(Some(b"array")) if formula.is_some() => {
+                                let range = match get_attribute(e.attributes(), QName(b"ref"))? {
+                                    Some(r) => get_dimension(r)?,
+                                    None => Dimensions::new(pos, pos),
+                                };
+                                kind = FormulaKind::Array {
+                                    range,
+                                    dynamic: has_metadata,
+                                };
+                            }
+                            Ok(Some(b"dataTable")) if formula.is_some() => {
+                                let range = match get_attribute(e.attributes(), QName(b"ref"))? {
+                                    Some(r) => get_dimension(r)?,
+                                    None => Dimensions::new(pos, pos),
+                                };
+                                kind = FormulaKind::DataTable { range };
+                                value = Some(data_table_formula(e)?);
+                            }
+                            Ok(Some(b"shared")) => {
+                                kind = FormulaKind::Shared;
+                                // shared formula
+                                let mut offset_map: HashMap<(u32, u32), (i64, i64)> =
+                                    HashMap::new();
+                                // shared index
+                                let shared_index =
+                                    match get_attribute(e.attributes(), QName(b"si"))? {
+                                        Some(res) => match std::str::from_utf8(res) {
+                                            Ok(res) => match usize::from_str_radix(res, 10) {
+                                                Ok(res) => res,
+                                                Err(e) => {
+                                                    return Err(XlsxError::ParseInt(e));
+                                                }
+                                            },
+                                            Err(_) => {
+                                                return Err(XlsxError::Unexpected(
+                                                    "si attribute must be a number",
+                                                ));
+                                            }
+                                        },
+                                        None => {
+                                            return Err(XlsxError::Unexpected(
+                                                "si attribute is mandatory if it is shared",
+                                            ));
+                                        }
+                                    };
+                                // shared reference
+                                match get_attribute(e.attributes(), QName(b"ref"))? {
+                                    Some(res) => {
+                                        // orignal reference formula
+                                        let reference = get_dimension(res)?;
+                                        if reference.start.0 != reference.end.0 {
+                                            for i in 0..=(reference.end.0 - reference.start.0) {
+                                                offset_map.insert(
+                                                    (reference.start.0 + i, reference.start.1),
+                                                    (
+                                                        (reference.start.0 as i64 - pos.0 as i64
+                                                            + i as i64),
+                                                        0,
+                                                    ),
+                                                );
+                                            }
+                                        } else if reference.start.1 != reference.end.1 {
+                                            for i in 0..=(reference.end.1 - reference.start.1) {
+                                                offset_map.insert(
+                                                    (reference.start.0, reference.start.1 + i),
+                                                    (
+                                                        0,
+                                                        (reference.start.1 as i64 - pos.1 as i64
+                                                            + i as i64),
+                                                    ),
+                                                );
+                                            }
+                                        }
+
+                                        if let Some(f) = formula.borrow() {
+                                            while reader.formulas.len() < shared_index {
+                                                reader.formulas.push(None);
+                                            }
+                                            reader.formulas.push(Some((f.clone(), offset_map)));
+                                        }
+                                        value = formula;
+                                    }
+                                    None => {
+                                        // calculated formula
+                                        if let Some(Some((f, offset_map))) =
+                                            reader.formulas.get(shared_index)
+                                        {
+                                            if let Some(offset) = offset_map.get(&*&pos) {
+                                                value = Some(replace_cell_names(f, *offset)?);
+                                            }
+                                        }
+                                    }
+                                };
+                            }
+                            _ => {}
+                        };
+                    }
+                    Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
+                    Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
+                    Err(e) => return Err(XlsxError::Xml(e)),
+                    _ => (),
                 }
-                Ok(Event::Start(ref c_element)) if c_element.local_name().as_ref() == b"c" => {
-                    let attribute = get_attribute(c_element.attributes(), QName(b"r"))?;
-                    let pos = if let Some(range) = attribute {
-                        let (row, col) = get_row_column(range)?;
-                        self.col_index = col;
-                        (row, col)
-                    } else {
-                        (self.row_index, self.col_index)
-                    };
-                    let mut value = DataRef::Empty;
-                    loop {
-                        self.cell_buf.clear();
-                        match self.xml.read_event_into(&mut self.cell_buf) {
-                            Ok(Event::Start(ref e)) => {
-                                value = read_value(
-                                    self.strings,
-                                    self.formats,
-                                    self.is_1904,
-                                    &mut self.xml,
-                                    e,
-                                    c_element,
-                                )?
+            }
+            let text = value.unwrap_or_default();
+            Ok(Some(Cell::new(pos, FormulaInfo { text, kind })))
+        })
+    }
+
+    /// Gets the next cell holding a formatted string, either shared or inline
+    pub(crate) fn next_rich_text(
+        &mut self,
+        rich_strings: &BTreeMap<usize, RichText>,
+    ) -> Result<Option<Cell<RichText>>, XlsxError> {
+        self.next_cell_by(|reader, pos, c| {
+            let is_shared = get_attribute(c.attributes(), QName(b"t"))? == Some(b"s");
+            let mut value = None;
+            loop {
+                reader.cell_buf.clear();
+                match reader.xml.read_event_into(&mut reader.cell_buf) {
+                    Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"is" => {
+                        value = read_rich_string(&mut reader.xml, e.name())?;
+                    }
+                    Ok(Event::Start(ref e)) if is_shared && e.local_name().as_ref() == b"v" => {
+                        let mut v = String::new();
+                        let mut v_buf = Vec::new();
+                        loop {
+                            v_buf.clear();
+                            match reader.xml.read_event_into(&mut v_buf)? {
+                                Event::Text(t) => v.push_str(&t.unescape()?),
+                                Event::End(end) if end.name() == e.name() => break,
+                                Event::Eof => return Err(XlsxError::XmlEof("v")),
+                                _ => (),
                             }
-                            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
-                            Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
-                            Err(e) => return Err(XlsxError::Xml(e)),
-                            _ => (),
                         }
+                        value = rich_strings.get(&v.parse()?).cloned();
                     }
-                    self.col_index += 1;
-                    return Ok(Some(Cell::new(pos, value)));
-                }
-                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => {
-                    return Ok(None);
+                    Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
+                    Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
+                    Err(e) => return Err(XlsxError::Xml(e)),
+                    _ => (),
                 }
-                Ok(Event::Eof) => return Err(XlsxError::XmlEof("sheetData")),
-                Err(e) => return Err(XlsxError::Xml(e)),
-                _ => (),
             }
-        }
+            Ok(value.map(|v| Cell::new(pos, v)))
+        })
     }
 
-    pub fn next_formula(&mut self) -> Result<Option<Cell<String>>, XlsxError> {
+    /// Reads the next `c` element with `read_cell`, tracking the position of the cells
+    /// without `r` attribute
+    ///
+    /// `read_cell` gets the cell position and must consume the element up to its end.
+    /// Cells for which it returns `None` are skipped.
+    fn next_cell_by<T, F>(&mut self, mut read_cell: F) -> Result<Option<T>, XlsxError>
+    where
+        F: FnMut(&mut Self, (u32, u32), &BytesStart<'_>) -> Result<Option<T>, XlsxError>,
+    {
+        let mut buf = std::mem::take(&mut self.buf);
+        let cell = self.read_cell_element(&mut buf, &mut read_cell);
+        self.buf = buf;
+        cell
+    }
+
+    fn read_cell_element<T, F>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        read_cell: &mut F,
+    ) -> Result<Option<T>, XlsxError>
+    where
+        F: FnMut(&mut Self, (u32, u32), &BytesStart<'_>) -> Result<Option<T>, XlsxError>,
+    {
         loop {
-            self.buf.clear();
-            match self.xml.read_event_into(&mut self.buf) {
+            buf.clear();
+            match self.xml.read_event_into(buf) {
                 Ok(Event::Start(ref row_element))
                     if row_element.local_name().as_ref() == b"row" =>
                 {
@@ -164,117 +333,11 @@ impl<'a> XlsxCellReader<'a> {
                     } else {
                         (self.row_index, self.col_index)
                     };
-                    let mut value = None;
-                    loop {
-                        self.cell_buf.clear();
-                        match self.xml.read_event_into(&mut self.cell_buf) {
-                            Ok(Event::Start(ref e)) => {
-                                let formula = read_formula(&mut self.xml, e)?;
-                                if let Some(f) = formula.borrow() {
-                                    value = Some(f.clone());
-                                }
-                                match get_attribute(e.attributes(), QName(b"t")) {
-                                    Ok(Some(b"shared")) => {
-                                        // shared formula
-                                        let mut offset_map: HashMap<(u32, u32), (i64, i64)> =
-                                            HashMap::new();
-                                        // shared index
-                                        let shared_index =
-                                            match get_attribute(e.attributes(), QName(b"si"))? {
-                                                Some(res) => match std::str::from_utf8(res) {
-                                                    Ok(res) => match usize::from_str_radix(res, 10)
-                                                    {
-                                                        Ok(res) => res,
-                                                        Err(e) => {
-                                                            return Err(XlsxError::ParseInt(e));
-                                                        }
-                                                    },
-                                                    Err(_) => {
-                                                        return Err(XlsxError::Unexpected(
-                                                            "si attribute must be a number",
-                                                        ));
-                                                    }
-                                                },
-                                                None => {
-                                                    return Err(XlsxError::Unexpected(
-                                                        "si attribute is mandatory if it is shared",
-                                                    ));
-                                                }
-                                            };
-                                        // shared reference
-                                        match get_attribute(e.attributes(), QName(b"ref"))? {
-                                            Some(res) => {
-                                                // orignal reference formula
-                                                let reference = get_dimension(res)?;
-                                                if reference.start.0 != reference.end.0 {
-                                                    for i in
-                                                        0..=(reference.end.0 - reference.start.0)
-                                                    {
-                                                        offset_map.insert(
-                                                            (
-                                                                reference.start.0 + i,
-                                                                reference.start.1,
-                                                            ),
-                                                            (
-                                                                (reference.start.0 as i64
-                                                                    - pos.0 as i64
-                                                                    + i as i64),
-                                                                0,
-                                                            ),
-                                                        );
-                                                    }
-                                                } else if reference.start.1 != reference.end.1 {
-                                                    for i in
-                                                        0..=(reference.end.1 - reference.start.1)
-                                                    {
-                                                        offset_map.insert(
-                                                            (
-                                                                reference.start.0,
-                                                                reference.start.1 + i,
-                                                            ),
-                                                            (
-                                                                0,
-                                                                (reference.start.1 as i64
-                                                                    - pos.1 as i64
-                                                                    + i as i64),
-                                                            ),
-                                                        );
-                                                    }
-                                                }
-
-                                                if let Some(f) = formula.borrow() {
-                                                    while self.formulas.len() < shared_index {
-                                                        self.formulas.push(None);
-                                                    }
-                                                    self.formulas
-                                                        .push(Some((f.clone(), offset_map)));
-                                                }
-                                                value = formula;
-                                            }
-                                            None => {
-                                                // calculated formula
-                                                if let Some(Some((f, offset_map))) =
-                                                    self.formulas.get(shared_index)
-                                                {
-                                                    if let Some(offset) = offset_map.get(&*&pos) {
-                                                        value =
-                                                            Some(replace_cell_names(f, *offset)?);
-                                                    }
-                                                }
-                                            }
-                                        };
-                                    }
-                                    _ => {}
-                                };
-                            }
-                            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"c" => break,
-                            Ok(Event::Eof) => return Err(XlsxError::XmlEof("c")),
-                            Err(e) => return Err(XlsxError::Xml(e)),
-                            _ => (),
-                        }
-                    }
+                    let value = read_cell(self, pos, c_element)?;
                     self.col_index += 1;
-                    return Ok(Some(Cell::new(pos, value.unwrap_or_default())));
+                    if let Some(value) = value {
+                        return Ok(Some(value));
+                    }
                 }
                 Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => {
                     return Ok(None);
@@ -403,6 +466,24 @@ fn read_v<'s>(
     }
 }
 
+/// `TABLE(row_input,column_input)` formula of a data table, like Excel displays it
+fn data_table_formula(e: &BytesStart<'_>) -> Result<String, XlsxError> {
+    let attribute = |name: &[u8]| -> Result<String, XlsxError> {
+        let value = get_attribute(e.attributes(), QName(name))?.unwrap_or_default();
+        Ok(String::from_utf8_lossy(value).into_owned())
+    };
+    let is_true = |value: &str| value == "1" || value == "true";
+    let (r1, r2) = (attribute(b"r1")?, attribute(b"r2")?);
+    let formula = if is_true(&attribute(b"dt2D")?) {
+        format!("TABLE({},{})", r1, r2)
+    } else if is_true(&attribute(b"dtr")?) {
+        format!("TABLE({},)", r1)
+    } else {
+        format!("TABLE(,{})", r1)
+    };
+    Ok(formula)
+}
+
 fn read_formula<'s>(
     xml: &mut XlReader<'_>,
     e: &BytesStart<'_>,
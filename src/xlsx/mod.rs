@@ -3,23 +3,40 @@ mod cells_reader;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::io::BufReader;
+#[cfg(feature = "encryption")]
+use std::io::Cursor;
 use std::io::{Read, Seek};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use log::warn;
 use quick_xml::events::attributes::{Attribute, Attributes};
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::name::QName;
 use quick_xml::Reader as XmlReader;
 use zip::read::{ZipArchive, ZipFile};
 use zip::result::ZipError;
 
 use crate::datatype::DataRef;
-use crate::formats::{builtin_format_by_id, detect_custom_number_format, CellFormat};
+use crate::formats::{
+    builtin_format_by_id, builtin_format_code, detect_custom_number_format, CellFormat,
+};
+use crate::formula::{parse_formula, resolve_structured_references, Expr};
+use crate::style::{
+    Alignment, Border, BorderStyle, Borders, Color, Fill, FillPattern, Font, HorizontalAlignment,
+    RichText, Style, StyleId, VerticalAlignment,
+};
+use crate::utils::resolve_target;
 use crate::vba::VbaProject;
+#[cfg(feature = "encryption")]
+use crate::ReaderWithPassword;
 use crate::{
-    Cell, CellErrorType, Data, Dimensions, Metadata, Range, Reader, Sheet, SheetType, SheetVisible,
-    Table,
+    cell_rows, AutoFilter, Cell, CellErrorType, ColumnProperties, Comment, Data, DataValidation,
+    DataValidationErrorStyle, DataValidationOperator, DataValidationType, DataWithFormat,
+    Dimensions, Filter, FilterColumn, FilterCondition, FilterOperator, FormulaInfo, Hyperlink,
+    Metadata, Protection, Range, Reader, RowColumnProperties, RowProperties, Sheet, SheetType,
+    SheetVisible, SortCondition, SortState, Table, TableColumn, TableMetadata, TableStyle, Tables,
+    TotalsRowFunction, WorksheetRows,
 };
 pub use cells_reader::XlsxCellReader;
 
@@ -40,6 +57,8 @@ pub enum XlsxError {
     Zip(zip::result::ZipError),
     /// Vba error
     Vba(crate::vba::VbaError),
+    /// Decryption error
+    Crypto(crate::crypto::CryptoError),
     /// Xml error
     Xml(quick_xml::Error),
     /// Xml attribute error
@@ -91,6 +110,7 @@ pub enum XlsxError {
 
 from_err!(std::io::Error, XlsxError, Io);
 from_err!(zip::result::ZipError, XlsxError, Zip);
+from_err!(crate::crypto::CryptoError, XlsxError, Crypto);
 from_err!(crate::vba::VbaError, XlsxError, Vba);
 from_err!(quick_xml::Error, XlsxError, Xml);
 from_err!(std::string::ParseError, XlsxError, Parse);
@@ -105,6 +125,7 @@ impl std::fmt::Display for XlsxError {
             XlsxError::Xml(e) => write!(f, "Xml error: {e}"),
             XlsxError::XmlAttr(e) => write!(f, "Xml attribute error: {e}"),
             XlsxError::Vba(e) => write!(f, "Vba error: {e}"),
+            XlsxError::Crypto(e) => write!(f, "Decryption error: {e}"),
             XlsxError::Parse(e) => write!(f, "Parse string error: {e}"),
             XlsxError::ParseInt(e) => write!(f, "Parse integer error: {e}"),
             XlsxError::ParseFloat(e) => write!(f, "Parse float error: {e}"),
@@ -147,6 +168,7 @@ impl std::error::Error for XlsxError {
             XlsxError::Zip(e) => Some(e),
             XlsxError::Xml(e) => Some(e),
             XlsxError::Vba(e) => Some(e),
+            XlsxError::Crypto(e) => Some(e),
             XlsxError::Parse(e) => Some(e),
             XlsxError::ParseInt(e) => Some(e),
             XlsxError::ParseFloat(e) => Some(e),
@@ -171,8 +193,6 @@ impl FromStr for CellErrorType {
     }
 }
 
-type Tables = Option<Vec<(String, String, Vec<String>, Dimensions)>>;
-
 /// A struct representing xml zipped excel file
 /// Xlsx, Xlsm, Xlam
 pub struct Xlsx<RS> {
@@ -185,6 +205,10 @@ pub struct Xlsx<RS> {
     tables: Tables,
     /// Cell (number) formats
     formats: Vec<CellFormat>,
+    /// Number format codes, by style index
+    format_codes: Vec<Option<Arc<str>>>,
+    /// Cell styles (cellXfs)
+    styles: Vec<Style>,
     /// 1904 datetime system
     is_1904: bool,
     /// Metadata
@@ -194,6 +218,10 @@ pub struct Xlsx<RS> {
     pictures: Option<Vec<(String, Vec<u8>)>>,
     /// Merged Regions: Name, Sheet, Merged Dimensions
     merged_regions: Option<Vec<(String, String, Dimensions)>>,
+    /// Threaded comments authors, by id
+    persons: Option<BTreeMap<String, String>>,
+    /// Formatted shared strings, by index
+    rich_strings: Option<BTreeMap<usize, RichText>>,
 }
 
 impl<RS: Read + Seek> Xlsx<RS> {
@@ -220,6 +248,32 @@ impl<RS: Read + Seek> Xlsx<RS> {
         Ok(())
     }
 
+    fn read_rich_strings(&mut self) -> Result<BTreeMap<usize, RichText>, XlsxError> {
+        let mut rich_strings = BTreeMap::new();
+        let mut xml = match xml_reader(&mut self.zip, "xl/sharedStrings.xml") {
+            None => return Ok(rich_strings),
+            Some(x) => x?,
+        };
+        let mut buf = Vec::with_capacity(1024);
+        let mut idx = 0;
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"si" => {
+                    if let Some(s) = read_rich_string(&mut xml, e.name())? {
+                        rich_strings.insert(idx, s);
+                    }
+                    idx += 1;
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sst" => break,
+                Ok(Event::Eof) => return Err(XlsxError::XmlEof("sst")),
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(rich_strings)
+    }
+
     fn read_styles(&mut self) -> Result<(), XlsxError> {
         let mut xml = match xml_reader(&mut self.zip, "xl/styles.xml") {
             None => return Ok(()),
@@ -227,6 +281,9 @@ impl<RS: Read + Seek> Xlsx<RS> {
         };
 
         let mut number_formats = BTreeMap::new();
+        let mut fonts = Vec::new();
+        let mut fills = Vec::new();
+        let mut borders = Vec::new();
 
         let mut buf = Vec::with_capacity(1024);
         let mut inner_buf = Vec::with_capacity(1024);
@@ -245,10 +302,10 @@ impl<RS: Read + Seek> Xlsx<RS> {
                                         key: QName(b"numFmtId"),
                                         value: v,
                                     } => id.extend_from_slice(&v),
-                                    Attribute {
+                                    a @ Attribute {
                                         key: QName(b"formatCode"),
-                                        value: v,
-                                    } => format = xml.decoder().decode(&v)?.into_owned(),
+                                        ..
+                                    } => format = a.decode_and_unescape_value(&xml)?.into_owned(),
                                     _ => (),
                                 }
                             }
@@ -262,21 +319,74 @@ impl<RS: Read + Seek> Xlsx<RS> {
                         _ => (),
                     }
                 },
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"fonts" => {
+                    fonts = read_fonts(&mut xml)?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"fills" => {
+                    fills = read_fills(&mut xml)?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"borders" => {
+                    borders = read_borders(&mut xml)?;
+                }
                 Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"cellXfs" => loop {
                     inner_buf.clear();
                     match xml.read_event_into(&mut inner_buf) {
                         Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"xf" => {
-                            self.formats.push(
-                                e.attributes()
-                                    .filter_map(|a| a.ok())
-                                    .find(|a| a.key == QName(b"numFmtId"))
-                                    .map_or(CellFormat::Other, |a| {
-                                        match number_formats.get(&*a.value) {
-                                            Some(fmt) => detect_custom_number_format(fmt),
-                                            None => builtin_format_by_id(&a.value),
-                                        }
-                                    }),
-                            );
+                            let index = |name| -> Result<usize, XlsxError> {
+                                Ok(get_attribute(e.attributes(), QName(name))?
+                                    .and_then(|v| std::str::from_utf8(v).ok()?.parse().ok())
+                                    .unwrap_or(0))
+                            };
+                            self.styles.push(Style {
+                                font: fonts.get(index(b"fontId")?).cloned().unwrap_or_default(),
+                                fill: fills.get(index(b"fillId")?).cloned().unwrap_or_default(),
+                                borders: borders
+                                    .get(index(b"borderId")?)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                                ..Default::default()
+                            });
+                            let id = e
+                                .attributes()
+                                .filter_map(|a| a.ok())
+                                .find(|a| a.key == QName(b"numFmtId"));
+                            self.formats
+                                .push(id.as_ref().map_or(CellFormat::Other, |a| {
+                                    match number_formats.get(&*a.value) {
+                                        Some(fmt) => detect_custom_number_format(fmt),
+                                        None => builtin_format_by_id(&a.value),
+                                    }
+                                }));
+                            self.format_codes.push(id.and_then(|a| {
+                                match number_formats.get(&*a.value) {
+                                    Some(fmt) => Some(fmt.as_str().into()),
+                                    None => std::str::from_utf8(&a.value)
+                                        .ok()?
+                                        .parse()
+                                        .ok()
+                                        .and_then(builtin_format_code)
+                                        .map(Arc::from),
+                                }
+                            }));
+                        }
+                        Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"alignment" => {
+                            if let Some(style) = self.styles.last_mut() {
+                                style.alignment = read_alignment(e)?;
+                            }
+                        }
+                        Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"protection" => {
+                            if let Some(style) = self.styles.last_mut() {
+                                let flag = |name| -> Result<Option<bool>, XlsxError> {
+                                    Ok(get_attribute(e.attributes(), QName(name))?
+                                        .map(|v| v == b"1" || v == b"true"))
+                                };
+                                if let Some(locked) = flag(b"locked")? {
+                                    style.protection.locked = locked;
+                                }
+                                if let Some(hidden) = flag(b"hidden")? {
+                                    style.protection.hidden = hidden;
+                                }
+                            }
                         }
                         Ok(Event::End(ref e)) if e.local_name().as_ref() == b"cellXfs" => break,
                         Ok(Event::Eof) => return Err(XlsxError::XmlEof("cellXfs")),
@@ -375,6 +485,7 @@ impl<RS: Read + Seek> Xlsx<RS> {
                         name: name.to_string(),
                         typ,
                         visible,
+                        protection: Protection::default(),
                     });
                     self.sheets.push((name, path));
                 }
@@ -388,6 +499,13 @@ impl<RS: Read + Seek> Xlsx<RS> {
                         None => false,
                     };
                 }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"workbookProtection" => {
+                    self.metadata.protection = read_protection(
+                        e,
+                        b"lockStructure",
+                        &[b"workbookPassword", b"workbookHashValue"],
+                    )?;
+                }
                 Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"definedName" => {
                     if let Some(a) = e
                         .attributes()
@@ -418,6 +536,34 @@ impl<RS: Read + Seek> Xlsx<RS> {
         Ok(())
     }
 
+    /// Reads the `sheetProtection` of each sheet, found after its data
+    fn read_sheet_protections(&mut self) -> Result<(), XlsxError> {
+        for ((_, path), sheet) in self.sheets.iter().zip(&mut self.metadata.sheets) {
+            let mut xml = match xml_reader(&mut self.zip, path) {
+                None => continue,
+                Some(x) => x?,
+            };
+            let mut buf = Vec::with_capacity(1024);
+            loop {
+                buf.clear();
+                match xml.read_event_into(&mut buf) {
+                    Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                        xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                    }
+                    Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetProtection" => {
+                        sheet.protection =
+                            read_protection(e, b"sheet", &[b"password", b"hashValue"])?;
+                        break;
+                    }
+                    Ok(Event::Eof) => break,
+                    Err(e) => return Err(XlsxError::Xml(e)),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn read_relationships(&mut self) -> Result<BTreeMap<Vec<u8>, String>, XlsxError> {
         let mut xml = match xml_reader(&mut self.zip, "xl/_rels/workbook.xml.rels") {
             None => {
@@ -530,83 +676,7 @@ impl<RS: Read + Seek> Xlsx<RS> {
                     None => continue,
                     Some(x) => x?,
                 };
-                let mut column_names = Vec::new();
-                let mut table_meta = InnerTableMetadata::new();
-                loop {
-                    buf.clear();
-                    match xml.read_event_into(&mut buf) {
-                        Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"table" => {
-                            for a in e.attributes() {
-                                match a.map_err(XlsxError::XmlAttr)? {
-                                    Attribute {
-                                        key: QName(b"displayName"),
-                                        value: v,
-                                    } => {
-                                        table_meta.display_name =
-                                            xml.decoder().decode(&v)?.into_owned()
-                                    }
-                                    Attribute {
-                                        key: QName(b"ref"),
-                                        value: v,
-                                    } => {
-                                        table_meta.ref_cells =
-                                            xml.decoder().decode(&v)?.into_owned()
-                                    }
-                                    Attribute {
-                                        key: QName(b"headerRowCount"),
-                                        value: v,
-                                    } => {
-                                        table_meta.header_row_count =
-                                            xml.decoder().decode(&v)?.parse()?
-                                    }
-                                    Attribute {
-                                        key: QName(b"insertRow"),
-                                        value: v,
-                                    } => table_meta.insert_row = *v != b"0"[..],
-                                    Attribute {
-                                        key: QName(b"totalsRowCount"),
-                                        value: v,
-                                    } => {
-                                        table_meta.totals_row_count =
-                                            xml.decoder().decode(&v)?.parse()?
-                                    }
-                                    _ => (),
-                                }
-                            }
-                        }
-                        Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"tableColumn" => {
-                            for a in e.attributes().flatten() {
-                                if let Attribute {
-                                    key: QName(b"name"),
-                                    value: v,
-                                } = a
-                                {
-                                    column_names.push(xml.decoder().decode(&v)?.into_owned())
-                                }
-                            }
-                        }
-                        Ok(Event::End(ref e)) if e.local_name().as_ref() == b"table" => break,
-                        Ok(Event::Eof) => return Err(XlsxError::XmlEof("Table")),
-                        Err(e) => return Err(XlsxError::Xml(e)),
-                        _ => (),
-                    }
-                }
-                let mut dims = get_dimension(table_meta.ref_cells.as_bytes())?;
-                if table_meta.header_row_count != 0 {
-                    dims.start.0 += table_meta.header_row_count;
-                }
-                if table_meta.totals_row_count != 0 {
-                    dims.end.0 -= table_meta.header_row_count;
-                }
-                if table_meta.insert_row {
-                    dims.end.0 -= 1;
-                }
-                new_tables.push((
-                    table_meta.display_name,
-                    sheet_name.clone(),
-                    column_names,
-                    dims,
-                ));
+                new_tables.push(read_table(&mut xml, sheet_name)?);
             }
         }
         self.tables = Some(new_tables);
@@ -718,7 +788,7 @@ impl<RS: Read + Seek> Xlsx<RS> {
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .map(|(name, ..)| name)
+            .map(|t| &t.name)
             .collect()
     }
 
@@ -728,34 +798,24 @@ impl<RS: Read + Seek> Xlsx<RS> {
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .filter(|(_, sheet, ..)| sheet == sheet_name)
-            .map(|(name, ..)| name)
+            .filter(|t| t.sheet_name == sheet_name)
+            .map(|t| &t.name)
             .collect()
     }
 
     /// Get the table by name
     // TODO: If retrieving multiple tables from a single sheet, get tables by sheet will be more efficient
     pub fn table_by_name(&mut self, table_name: &str) -> Result<Table<Data>, XlsxError> {
-        let match_table_meta = self
+        let metadata = self
             .tables
             .as_ref()
             .expect("Tables must be loaded before they are referenced")
             .iter()
-            .find(|(table, ..)| table == table_name)
+            .find(|t| t.name == table_name)
+            .cloned()
             .ok_or_else(|| XlsxError::TableNotFound(table_name.into()))?;
-        let name = match_table_meta.0.to_owned();
-        let sheet_name = match_table_meta.1.clone();
-        let columns = match_table_meta.2.clone();
-        let start_dim = match_table_meta.3.start;
-        let end_dim = match_table_meta.3.end;
-        let range = self.worksheet_range(&sheet_name)?;
-        let tbl_rng = range.range(start_dim, end_dim);
-        Ok(Table {
-            name,
-            sheet_name,
-            columns,
-            data: tbl_rng,
-        })
+        let range = self.worksheet_range(&metadata.sheet_name)?;
+        Ok(Table::new(metadata, &range))
     }
 
     /// Gets the worksheet merge cell dimensions
@@ -808,26 +868,6 @@ impl<RS: Read + Seek> Xlsx<RS> {
     }
 }
 
-struct InnerTableMetadata {
-    display_name: String,
-    ref_cells: String,
-    header_row_count: u32,
-    insert_row: bool,
-    totals_row_count: u32,
-}
-
-impl InnerTableMetadata {
-    fn new() -> Self {
-        Self {
-            display_name: String::new(),
-            ref_cells: String::new(),
-            header_row_count: 1,
-            insert_row: false,
-            totals_row_count: 0,
-        }
-    }
-}
-
 impl<RS: Read + Seek> Xlsx<RS> {
     /// Get a reader over all used cells in the given worksheet cell reader
     pub fn worksheet_cells_reader<'a>(
@@ -876,6 +916,16 @@ impl<RS: Read + Seek> Xlsx<RS> {
     }
 }
 
+#[cfg(feature = "encryption")]
+impl ReaderWithPassword for Xlsx<Cursor<Vec<u8>>> {
+    fn new_with_password<R: Read>(mut reader: R, password: &str) -> Result<Self, XlsxError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let data = crate::crypto::ooxml::decrypt(data, password)?;
+        Self::new(Cursor::new(data))
+    }
+}
+
 impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
     type Error = XlsxError;
 
@@ -886,6 +936,8 @@ impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
             zip: ZipArchive::new(reader)?,
             strings: Vec::new(),
             formats: Vec::new(),
+            format_codes: Vec::new(),
+            styles: Vec::new(),
             is_1904: false,
             sheets: Vec::new(),
             tables: None,
@@ -893,11 +945,14 @@ impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
             #[cfg(feature = "picture")]
             pictures: None,
             merged_regions: None,
+            persons: None,
+            rich_strings: None,
         };
         xlsx.read_shared_strings()?;
         xlsx.read_styles()?;
         let relationships = xlsx.read_relationships()?;
         xlsx.read_workbook(&relationships)?;
+        xlsx.read_sheet_protections()?;
         #[cfg(feature = "picture")]
         xlsx.read_pictures()?;
 
@@ -943,6 +998,318 @@ impl<RS: Read + Seek> Reader<RS> for Xlsx<RS> {
         Ok(Range::from_sparse(cells))
     }
 
+    fn worksheet_formula_info(&mut self, name: &str) -> Result<Range<FormulaInfo>, XlsxError> {
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some(cell) = cell_reader.next_formula_info()? {
+            if !cell.val.text.is_empty() {
+                cells.push(cell);
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_formula_expr(&mut self, name: &str) -> Result<Range<Expr>, XlsxError> {
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut formulas = Vec::new();
+        while let Some(cell) = cell_reader.next_formula()? {
+            if !cell.val.is_empty() {
+                formulas.push(cell);
+            }
+        }
+        drop(cell_reader);
+
+        // structured references need the tables to be turned into A1 references
+        let tables = if formulas.iter().any(|cell| cell.val.contains('[')) {
+            self.load_tables()?;
+            self.tables.clone().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let mut cells = Vec::with_capacity(formulas.len());
+        for cell in formulas {
+            let formula = if tables.is_empty() {
+                cell.val
+            } else {
+                resolve_structured_references(&cell.val, &tables, name, cell.pos)
+            };
+            match parse_formula(&formula) {
+                Ok(expr) => cells.push(Cell::new(cell.pos, expr)),
+                Err(e) => warn!("cannot parse formula '{}': {}", formula, e),
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_range_with_format(
+        &mut self,
+        name: &str,
+    ) -> Result<Range<DataWithFormat>, XlsxError> {
+        let format_codes = self.format_codes.clone();
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some((cell, style)) = cell_reader.next_cell_with_style()? {
+            if cell.val != DataRef::Empty {
+                let format = format_codes.get(style).cloned().flatten();
+                let value = DataWithFormat::new(cell.val.into(), format);
+                cells.push(Cell::new(cell.pos, value));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn worksheet_styles(&mut self, name: &str) -> Result<Range<StyleId>, XlsxError> {
+        let mut cell_reader = self.worksheet_cells_reader(name)?;
+        let mut cells = Vec::new();
+        while let Some((cell, style)) = cell_reader.next_cell_with_style()? {
+            if style != 0 {
+                cells.push(Cell::new(cell.pos, StyleId(style)));
+            }
+        }
+        Ok(Range::from_sparse(cells))
+    }
+
+    fn styles(&self) -> &[Style] {
+        &self.styles
+    }
+
+    fn worksheet_comments(&mut self, name: &str) -> Result<Vec<Comment>, XlsxError> {
+        let path = match self.sheets.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsxError::WorksheetNotFound(name.into())),
+        };
+        let rels = read_sheet_relationships(&mut self.zip, &path)?;
+        let mut comments = Vec::new();
+        for rel in rels.iter().filter(|r| r.typ.ends_with("/threadedComment")) {
+            if self.persons.is_none() {
+                self.persons = Some(read_persons(&mut self.zip)?);
+            }
+            let persons = self.persons.as_ref().expect("persons loaded");
+            if let Some(xml) = xml_reader(&mut self.zip, &resolve_target(&path, &rel.target)) {
+                comments.extend(read_threaded_comments(&mut xml?, persons)?);
+            }
+        }
+        // threaded comments are also saved as legacy comments
+        let threaded = comments.iter().map(|c| c.cell).collect::<Vec<_>>();
+        for rel in rels.iter().filter(|r| r.typ.ends_with("/comments")) {
+            if let Some(xml) = xml_reader(&mut self.zip, &resolve_target(&path, &rel.target)) {
+                let legacy = read_comments(&mut xml?)?;
+                comments.extend(legacy.into_iter().filter(|c| !threaded.contains(&c.cell)));
+            }
+        }
+        Ok(comments)
+    }
+
+    fn worksheet_hyperlinks(&mut self, name: &str) -> Result<Vec<Hyperlink>, XlsxError> {
+        let path = match self.sheets.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsxError::WorksheetNotFound(name.into())),
+        };
+        // hyperlink targets are urls, not parts of the package: they are kept as is
+        let urls = read_sheet_relationships(&mut self.zip, &path)?
+            .into_iter()
+            .filter(|r| r.typ.ends_with("/hyperlink"))
+            .map(|r| (r.id, r.target))
+            .collect::<BTreeMap<_, _>>();
+        let mut xml = match xml_reader(&mut self.zip, &path) {
+            None => return Ok(Vec::new()),
+            Some(x) => x?,
+        };
+        let mut hyperlinks = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"hyperlink" => {
+                    let mut range = None;
+                    let mut hyperlink = Hyperlink {
+                        range: Dimensions::default(),
+                        url: None,
+                        location: None,
+                        tooltip: None,
+                    };
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        match a.key.local_name().as_ref() {
+                            b"ref" => range = Some(get_dimension(&a.value)?),
+                            b"id" => {
+                                let id = xml.decoder().decode(&a.value)?;
+                                hyperlink.url = urls.get(&*id).cloned();
+                            }
+                            b"location" => {
+                                hyperlink.location =
+                                    Some(a.decode_and_unescape_value(&xml)?.into_owned());
+                            }
+                            b"tooltip" => {
+                                hyperlink.tooltip =
+                                    Some(a.decode_and_unescape_value(&xml)?.into_owned());
+                            }
+                            _ => (),
+                        }
+                    }
+                    hyperlink.range =
+                        range.ok_or(XlsxError::Unexpected("hyperlink without cell reference"))?;
+                    hyperlinks.push(hyperlink);
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(hyperlinks)
+    }
+
+    fn worksheet_rich_text(&mut self, name: &str) -> Result<Range<RichText>, XlsxError> {
+        let rich_strings = match self.rich_strings.take() {
+            Some(r) => r,
+            None => self.read_rich_strings()?,
+        };
+        let cells = self
+            .worksheet_cells_reader(name)
+            .and_then(|mut cell_reader| {
+                let mut cells = Vec::new();
+                while let Some(cell) = cell_reader.next_rich_text(&rich_strings)? {
+                    cells.push(cell);
+                }
+                Ok(cells)
+            });
+        self.rich_strings = Some(rich_strings);
+        Ok(Range::from_sparse(cells?))
+    }
+
+    fn merged_cells(&mut self, name: &str) -> Result<Vec<Dimensions>, XlsxError> {
+        self.worksheet_merge_cells(name)
+            .unwrap_or_else(|| Err(XlsxError::WorksheetNotFound(name.into())))
+    }
+
+    fn worksheet_row_column_properties(
+        &mut self,
+        name: &str,
+    ) -> Result<RowColumnProperties, XlsxError> {
+        let path = match self.sheets.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsxError::WorksheetNotFound(name.into())),
+        };
+        let mut props = RowColumnProperties::default();
+        let mut xml = match xml_reader(&mut self.zip, &path) {
+            None => return Ok(props),
+            Some(x) => x?,
+        };
+        let mut buf = Vec::with_capacity(1024);
+        let mut next_row = 0;
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetFormatPr" => {
+                    read_sheet_format(e, &mut props)?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"col" => {
+                    let (first, last, column) = read_column_properties(e)?;
+                    for col in first..=last {
+                        props.columns.insert(col, column.clone());
+                    }
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"row" => {
+                    let (row, properties) = read_row_properties(e)?;
+                    let row = row.unwrap_or(next_row);
+                    props.rows.insert(row, properties);
+                    next_row = row + 1;
+                    xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sheetData" => break,
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        props.remove_defaults();
+        Ok(props)
+    }
+
+    fn worksheet_auto_filter(&mut self, name: &str) -> Result<Option<AutoFilter>, XlsxError> {
+        let path = match self.sheets.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsxError::WorksheetNotFound(name.into())),
+        };
+        let mut xml = match xml_reader(&mut self.zip, &path) {
+            None => return Ok(None),
+            Some(x) => x?,
+        };
+        let mut filter: Option<AutoFilter> = None;
+        let mut sort = None;
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"autoFilter" => {
+                    filter = Some(read_auto_filter(&mut xml, e)?);
+                }
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sortState" => {
+                    sort = Some(read_sort_state(&mut xml, e)?);
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"worksheet" => break,
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        // the sort state is either within the autofilter or next to it
+        Ok(filter.map(|mut filter| {
+            filter.sort = filter.sort.or(sort);
+            filter
+        }))
+    }
+
+    fn worksheet_data_validations(&mut self, name: &str) -> Result<Vec<DataValidation>, XlsxError> {
+        let path = match self.sheets.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => path.clone(),
+            None => return Err(XlsxError::WorksheetNotFound(name.into())),
+        };
+        let mut xml = match xml_reader(&mut self.zip, &path) {
+            None => return Ok(Vec::new()),
+            Some(x) => x?,
+        };
+        let mut validations = Vec::new();
+        let mut buf = Vec::with_capacity(1024);
+        loop {
+            buf.clear();
+            match xml.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sheetData" => {
+                    xml.read_to_end_into(e.name(), &mut Vec::new())?;
+                }
+                // either a `dataValidation` or, for lists referencing other sheets in Excel
+                // 2010, a `x14:dataValidation` of the extensions
+                Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"dataValidation" => {
+                    validations.push(read_data_validation(&mut xml, e)?);
+                }
+                Ok(Event::End(ref e)) if e.local_name().as_ref() == b"worksheet" => break,
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(XlsxError::Xml(e)),
+                _ => (),
+            }
+        }
+        Ok(validations)
+    }
+
+    fn worksheet_rows<'a>(
+        &'a mut self,
+        name: &str,
+    ) -> Result<WorksheetRows<'a, XlsxError>, XlsxError>
+    where
+        XlsxError: 'a,
+    {
+        let mut cells_reader = self.worksheet_cells_reader(name)?;
+        Ok(cell_rows(std::iter::from_fn(move || {
+            cells_reader.next_cell().transpose()
+        })))
+    }
+
     fn worksheets(&mut self) -> Vec<(String, Range<Data>)> {
         let names = self
             .sheets
@@ -986,36 +1353,872 @@ fn xml_reader<'a, RS: Read + Seek>(
     }
 }
 
-/// search through an Element's attributes for the named one
-pub(crate) fn get_attribute<'a>(
-    atts: Attributes<'a>,
-    n: QName,
-) -> Result<Option<&'a [u8]>, XlsxError> {
-    for a in atts {
-        match a {
-            Ok(Attribute {
-                key,
-                value: Cow::Borrowed(value),
-            }) if key == n => return Ok(Some(value)),
-            Err(e) => return Err(XlsxError::XmlAttr(e)),
-            _ => {} // ignore other attributes
-        }
-    }
-    Ok(None)
-}
-
-/// converts a text representation (e.g. "A6:G67") of a dimension into integers
-/// - top left (row, column),
-/// - bottom right (row, column)
-pub(crate) fn get_dimension(dimension: &[u8]) -> Result<Dimensions, XlsxError> {
-    let parts: Vec<_> = dimension
-        .split(|c| *c == b':')
-        .map(get_row_column)
-        .collect::<Result<Vec<_>, XlsxError>>()?;
-
-    match parts.len() {
-        0 => Err(XlsxError::DimensionCount(0)),
-        1 => Ok(Dimensions {
+/// Reads a table part
+fn read_table(xml: &mut XlReader<'_>, sheet_name: &str) -> Result<TableMetadata, XlsxError> {
+    let mut table = TableMetadata {
+        sheet_name: sheet_name.to_string(),
+        header_row_count: 1,
+        totals_row_shown: true,
+        ..TableMetadata::default()
+    };
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"table" => {
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    let v = xml.decoder().decode(&a.value)?;
+                    match a.key {
+                        QName(b"displayName") => table.name = v.into_owned(),
+                        QName(b"ref") => table.range = get_dimension(v.as_bytes())?,
+                        QName(b"headerRowCount") => table.header_row_count = v.parse()?,
+                        QName(b"totalsRowCount") => table.totals_row_count = v.parse()?,
+                        QName(b"totalsRowShown") => table.totals_row_shown = is_true(&v),
+                        QName(b"insertRow") => table.insert_row = is_true(&v),
+                        _ => (),
+                    }
+                }
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"autoFilter" => {
+                if let Some(r) = get_attribute(e.attributes(), QName(b"ref"))? {
+                    table.auto_filter = Some(get_dimension(r)?);
+                }
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"tableColumn" => {
+                let mut column = TableColumn::default();
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    match a.key {
+                        QName(b"name") => {
+                            column.name = a.decode_and_unescape_value(xml)?.into_owned()
+                        }
+                        QName(b"totalsRowLabel") => {
+                            column.totals_row_label =
+                                Some(a.decode_and_unescape_value(xml)?.into_owned())
+                        }
+                        QName(b"totalsRowFunction") => {
+                            column.totals_row_function = match &*a.value {
+                                b"average" => Some(TotalsRowFunction::Average),
+                                b"count" => Some(TotalsRowFunction::Count),
+                                b"countNums" => Some(TotalsRowFunction::CountNums),
+                                b"max" => Some(TotalsRowFunction::Max),
+                                b"min" => Some(TotalsRowFunction::Min),
+                                b"stdDev" => Some(TotalsRowFunction::StdDev),
+                                b"sum" => Some(TotalsRowFunction::Sum),
+                                b"var" => Some(TotalsRowFunction::Var),
+                                // the formula is in totalsRowFormula
+                                b"custom" => Some(TotalsRowFunction::Custom(String::new())),
+                                _ => None,
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                table.columns.push(column);
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"calculatedColumnFormula" => {
+                let formula = read_text(xml, b"calculatedColumnFormula")?;
+                if let Some(column) = table.columns.last_mut() {
+                    column.calculated_formula = Some(formula);
+                }
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"totalsRowFormula" => {
+                let formula = read_text(xml, b"totalsRowFormula")?;
+                if let Some(column) = table.columns.last_mut() {
+                    column.totals_row_function = Some(TotalsRowFunction::Custom(formula));
+                }
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"tableStyleInfo" => {
+                let mut style = TableStyle::default();
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    let v = xml.decoder().decode(&a.value)?;
+                    match a.key {
+                        QName(b"name") => style.name = v.into_owned(),
+                        QName(b"showFirstColumn") => style.show_first_column = is_true(&v),
+                        QName(b"showLastColumn") => style.show_last_column = is_true(&v),
+                        QName(b"showRowStripes") => style.show_row_stripes = is_true(&v),
+                        QName(b"showColumnStripes") => style.show_column_stripes = is_true(&v),
+                        _ => (),
+                    }
+                }
+                table.style = Some(style);
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"table" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("Table")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(table)
+}
+
+/// Reads the text of an element, up to its end
+fn read_text(xml: &mut XlReader<'_>, end: &[u8]) -> Result<String, XlsxError> {
+    let mut text = String::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf)? {
+            Event::Text(t) => text.push_str(&t.unescape()?),
+            Event::End(e) if e.local_name().as_ref() == end => break,
+            Event::Eof => return Err(XlsxError::XmlEof("text")),
+            _ => (),
+        }
+    }
+    Ok(text)
+}
+
+/// Checks if a xsd:boolean attribute value is true
+fn is_true(value: &str) -> bool {
+    matches!(value, "1" | "true")
+}
+
+/// A relationship of a worksheet part
+struct Relationship {
+    id: String,
+    typ: String,
+    target: String,
+}
+
+/// Reads the relationships of a worksheet, if any
+fn read_sheet_relationships<RS: Read + Seek>(
+    zip: &mut ZipArchive<RS>,
+    sheet_path: &str,
+) -> Result<Vec<Relationship>, XlsxError> {
+    let (base_folder, file_name) = sheet_path.split_at(sheet_path.rfind('/').unwrap_or(0));
+    let rel_path = format!("{}/_rels{}.rels", base_folder, file_name);
+    let mut xml = match xml_reader(zip, &rel_path) {
+        None => return Ok(Vec::new()),
+        Some(x) => x?,
+    };
+    let mut relationships = Vec::new();
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"Relationship" => {
+                let mut rel = Relationship {
+                    id: String::new(),
+                    typ: String::new(),
+                    target: String::new(),
+                };
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    match a.key {
+                        QName(b"Id") => rel.id = xml.decoder().decode(&a.value)?.into_owned(),
+                        QName(b"Type") => rel.typ = xml.decoder().decode(&a.value)?.into_owned(),
+                        QName(b"Target") => {
+                            rel.target = a.decode_and_unescape_value(&xml)?.into_owned()
+                        }
+                        _ => (),
+                    }
+                }
+                relationships.push(rel);
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"Relationships" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("Relationships")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(relationships)
+}
+
+/// Reads the display name of the persons of threaded comments, by id
+fn read_persons<RS: Read + Seek>(
+    zip: &mut ZipArchive<RS>,
+) -> Result<BTreeMap<String, String>, XlsxError> {
+    let mut persons = BTreeMap::new();
+    let mut xml = match xml_reader(zip, "xl/persons/person.xml") {
+        None => return Ok(persons),
+        Some(x) => x?,
+    };
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"person" => {
+                let id = get_attribute(e.attributes(), QName(b"id"))?;
+                let name = e.try_get_attribute("displayName")?;
+                if let (Some(id), Some(name)) = (id, name) {
+                    let id = xml.decoder().decode(id)?.into_owned();
+                    persons.insert(id, name.decode_and_unescape_value(&xml)?.into_owned());
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(persons)
+}
+
+/// Reads a `comments` part
+fn read_comments(xml: &mut XlReader<'_>) -> Result<Vec<Comment>, XlsxError> {
+    let mut authors = Vec::new();
+    let mut comments = Vec::new();
+    let mut buf = Vec::with_capacity(1024);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"author" => {
+                let mut author = String::new();
+                let mut val_buf = Vec::new();
+                loop {
+                    val_buf.clear();
+                    match xml.read_event_into(&mut val_buf)? {
+                        Event::Text(t) => author.push_str(&t.unescape()?),
+                        Event::End(end) if end.local_name().as_ref() == b"author" => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("author")),
+                        _ => (),
+                    }
+                }
+                authors.push(author);
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"comment" => {
+                let cell = match get_attribute(e.attributes(), QName(b"ref"))? {
+                    Some(r) => get_row_column(r)?,
+                    None => return Err(XlsxError::Unexpected("comment without cell reference")),
+                };
+                let author = match get_attribute(e.attributes(), QName(b"authorId"))? {
+                    Some(id) => {
+                        let id: usize = xml.decoder().decode(id)?.parse()?;
+                        authors.get(id).cloned()
+                    }
+                    None => None,
+                };
+                let mut text = String::new();
+                let mut text_buf = Vec::new();
+                loop {
+                    text_buf.clear();
+                    match xml.read_event_into(&mut text_buf)? {
+                        Event::Start(t) if t.local_name().as_ref() == b"text" => {
+                            text = read_string(xml, QName(b"text"))?.unwrap_or_default();
+                        }
+                        Event::End(end) if end.local_name().as_ref() == b"comment" => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("comment")),
+                        _ => (),
+                    }
+                }
+                comments.push(Comment { cell, author, text });
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(comments)
+}
+
+/// Reads a `threadedComments` part
+fn read_threaded_comments(
+    xml: &mut XlReader<'_>,
+    persons: &BTreeMap<String, String>,
+) -> Result<Vec<Comment>, XlsxError> {
+    let mut comments = Vec::new();
+    let mut buf = Vec::with_capacity(1024);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"threadedComment" => {
+                let cell = match get_attribute(e.attributes(), QName(b"ref"))? {
+                    Some(r) => get_row_column(r)?,
+                    None => return Err(XlsxError::Unexpected("comment without cell reference")),
+                };
+                let author = match get_attribute(e.attributes(), QName(b"personId"))? {
+                    Some(id) => persons.get(&*xml.decoder().decode(id)?).cloned(),
+                    None => None,
+                };
+                let mut text = String::new();
+                let mut text_buf = Vec::new();
+                let mut in_text = false;
+                loop {
+                    text_buf.clear();
+                    match xml.read_event_into(&mut text_buf)? {
+                        Event::Start(t) if t.local_name().as_ref() == b"text" => in_text = true,
+                        Event::End(t) if t.local_name().as_ref() == b"text" => in_text = false,
+                        Event::Text(t) if in_text => text.push_str(&t.unescape()?),
+                        Event::End(end) if end.local_name().as_ref() == b"threadedComment" => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("threadedComment")),
+                        _ => (),
+                    }
+                }
+                comments.push(Comment { cell, author, text });
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(comments)
+}
+
+/// Reads the `font` elements of a `fonts` element
+fn read_fonts(xml: &mut XlReader<'_>) -> Result<Vec<Font>, XlsxError> {
+    let mut fonts = Vec::new();
+    let mut font = Font::default();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"font" => font = Font::default(),
+            Ok(Event::Start(ref e)) => read_font_property(xml, e, &mut font)?,
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"font" => {
+                fonts.push(std::mem::take(&mut font))
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"fonts" => return Ok(fonts),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("fonts")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Sets a font property from a child element of a `font` or a run properties (`rPr`) element
+fn read_font_property(
+    xml: &XlReader<'_>,
+    e: &BytesStart<'_>,
+    font: &mut Font,
+) -> Result<(), XlsxError> {
+    match e.local_name().as_ref() {
+        b"b" => font.bold = is_on(e)?,
+        b"i" => font.italic = is_on(e)?,
+        b"u" => font.underline = is_on(e)?,
+        b"strike" => font.strikethrough = is_on(e)?,
+        b"sz" => {
+            font.size = get_attribute(e.attributes(), QName(b"val"))?
+                .and_then(|v| std::str::from_utf8(v).ok()?.parse().ok())
+        }
+        b"name" | b"rFont" => {
+            font.name = get_attribute(e.attributes(), QName(b"val"))?
+                .map(|v| xml.decoder().decode(v))
+                .transpose()?
+                .map(|v| v.into_owned())
+        }
+        b"color" => font.color = read_color(e)?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Reads the `fill` elements of a `fills` element
+fn read_fills(xml: &mut XlReader<'_>) -> Result<Vec<Fill>, XlsxError> {
+    let mut fills = Vec::new();
+    let mut fill = Fill::default();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"fill" => fill = Fill::default(),
+                b"patternFill" => {
+                    fill.pattern = get_attribute(e.attributes(), QName(b"patternType"))?
+                        .map_or(FillPattern::None, FillPattern::from_name)
+                }
+                b"fgColor" => fill.foreground = read_color(e)?,
+                b"bgColor" => fill.background = read_color(e)?,
+                _ => (),
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"fill" => {
+                fills.push(std::mem::take(&mut fill))
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"fills" => return Ok(fills),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("fills")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the `border` elements of a `borders` element
+fn read_borders(xml: &mut XlReader<'_>) -> Result<Vec<Borders>, XlsxError> {
+    fn side<'b>(borders: &'b mut Borders, name: &[u8]) -> Option<&'b mut Border> {
+        match name {
+            b"left" | b"start" => Some(&mut borders.left),
+            b"right" | b"end" => Some(&mut borders.right),
+            b"top" => Some(&mut borders.top),
+            b"bottom" => Some(&mut borders.bottom),
+            _ => None,
+        }
+    }
+
+    let mut borders = Vec::new();
+    let mut border = Borders::default();
+    let mut current: Option<Vec<u8>> = None;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"border" => border = Borders::default(),
+                b"color" => {
+                    if let Some(side) = current.as_ref().and_then(|n| side(&mut border, n)) {
+                        side.color = read_color(e)?;
+                    }
+                }
+                name => {
+                    current = None;
+                    if let Some(side) = side(&mut border, name) {
+                        side.style = get_attribute(e.attributes(), QName(b"style"))?
+                            .map_or(BorderStyle::None, BorderStyle::from_name);
+                        current = Some(name.to_vec());
+                    }
+                }
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"border" => {
+                current = None;
+                borders.push(std::mem::take(&mut border))
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"borders" => return Ok(borders),
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("borders")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads the attributes of an `alignment` element
+fn read_alignment(e: &BytesStart<'_>) -> Result<Alignment, XlsxError> {
+    let mut alignment = Alignment::default();
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        match a.key {
+            QName(b"horizontal") => alignment.horizontal = HorizontalAlignment::from_name(&a.value),
+            QName(b"vertical") => alignment.vertical = VerticalAlignment::from_name(&a.value),
+            QName(b"wrapText") => alignment.wrap_text = &*a.value == b"1" || &*a.value == b"true",
+            QName(b"shrinkToFit") => {
+                alignment.shrink_to_fit = &*a.value == b"1" || &*a.value == b"true"
+            }
+            QName(b"indent") => {
+                alignment.indent = std::str::from_utf8(&a.value)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0)
+            }
+            _ => (),
+        }
+    }
+    Ok(alignment)
+}
+
+/// Reads the attributes of a `color`, `fgColor` or `bgColor` element
+fn read_color(e: &BytesStart<'_>) -> Result<Option<Color>, XlsxError> {
+    let parse = |v: &[u8]| std::str::from_utf8(v).ok()?.parse::<u32>().ok();
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        match a.key {
+            QName(b"rgb") => {
+                return Ok(std::str::from_utf8(&a.value).ok().and_then(Color::from_hex))
+            }
+            QName(b"indexed") => return Ok(parse(&a.value).and_then(Color::from_index)),
+            QName(b"theme") => return Ok(parse(&a.value).map(Color::Theme)),
+            _ => (),
+        }
+    }
+    Ok(None)
+}
+
+/// Checks if a boolean font property (`b`, `i`, `u`, `strike`) is set
+fn is_on(e: &BytesStart<'_>) -> Result<bool, XlsxError> {
+    Ok(get_attribute(e.attributes(), QName(b"val"))?
+        .map_or(true, |v| !matches!(v, b"0" | b"false" | b"none")))
+}
+
+/// Reads a `sheetProtection` or `workbookProtection` element
+///
+/// `flag` is the attribute locking the sheet or the workbook, `passwords` the
+/// attributes holding a password hash.
+fn read_protection(
+    e: &BytesStart<'_>,
+    flag: &[u8],
+    passwords: &[&[u8]],
+) -> Result<Protection, XlsxError> {
+    let mut protection = Protection::default();
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let key = a.key.local_name();
+        if key.as_ref() == flag {
+            protection.protected = matches!(a.value.as_ref(), b"1" | b"true");
+        } else if passwords.contains(&key.as_ref()) && !a.value.is_empty() {
+            protection.password = true;
+        }
+    }
+    Ok(protection)
+}
+
+/// Reads the default row height and column width of a `sheetFormatPr`
+fn read_sheet_format(e: &BytesStart<'_>, props: &mut RowColumnProperties) -> Result<(), XlsxError> {
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let value = std::str::from_utf8(&a.value).unwrap_or_default();
+        match a.key.local_name().as_ref() {
+            b"defaultRowHeight" => props.default_row.height = value.parse().ok(),
+            b"defaultColWidth" => props.default_column.width = value.parse().ok(),
+            b"zeroHeight" => props.default_row.hidden = is_true(value),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Reads a `row` element, returning its 0-based index, if any, and properties
+///
+/// Heights are only set for rows with a custom height.
+fn read_row_properties(e: &BytesStart<'_>) -> Result<(Option<u32>, RowProperties), XlsxError> {
+    let mut row = None;
+    let mut props = RowProperties::default();
+    let mut height = None;
+    let mut custom_height = false;
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let value = std::str::from_utf8(&a.value).unwrap_or_default();
+        match a.key.local_name().as_ref() {
+            b"r" => row = value.parse::<u32>().ok().and_then(|r| r.checked_sub(1)),
+            b"hidden" => props.hidden = is_true(value),
+            b"outlineLevel" => props.outline_level = value.parse().ok().unwrap_or(0),
+            b"collapsed" => props.collapsed = is_true(value),
+            b"ht" => height = value.parse().ok(),
+            b"customHeight" => custom_height = is_true(value),
+            _ => (),
+        }
+    }
+    if custom_height {
+        props.height = height;
+    }
+    Ok((row, props))
+}
+
+/// Reads a `col` element, returning the 0-based indexes of the first and last
+/// columns it applies to and their properties
+///
+/// Widths are only set for columns with a custom width.
+fn read_column_properties(e: &BytesStart<'_>) -> Result<(u32, u32, ColumnProperties), XlsxError> {
+    let (mut first, mut last) = (1, 1);
+    let mut props = ColumnProperties::default();
+    let mut width = None;
+    let mut custom_width = false;
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let value = std::str::from_utf8(&a.value).unwrap_or_default();
+        match a.key.local_name().as_ref() {
+            b"min" => first = value.parse().ok().unwrap_or(1),
+            b"max" => last = value.parse().ok().unwrap_or(1),
+            b"hidden" => props.hidden = is_true(value),
+            b"outlineLevel" => props.outline_level = value.parse().ok().unwrap_or(0),
+            b"collapsed" => props.collapsed = is_true(value),
+            b"width" => width = value.parse().ok(),
+            b"customWidth" => custom_width = is_true(value),
+            _ => (),
+        }
+    }
+    if custom_width {
+        props.width = width;
+    }
+    // a worksheet has at most 16,384 columns
+    let first = first.clamp(1, 16_384) - 1;
+    let last = last.clamp(1, 16_384) - 1;
+    Ok((first, last, props))
+}
+
+/// Reads an `autoFilter` element, up to its end
+fn read_auto_filter(xml: &mut XlReader<'_>, e: &BytesStart<'_>) -> Result<AutoFilter, XlsxError> {
+    let mut filter = AutoFilter::default();
+    if let Some(r) = get_attribute(e.attributes(), QName(b"ref"))? {
+        filter.range = get_dimension(r)?;
+    }
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"filterColumn" => {
+                let index = match get_attribute(e.attributes(), QName(b"colId"))? {
+                    Some(v) => std::str::from_utf8(v).unwrap_or_default().parse()?,
+                    None => 0,
+                };
+                let filter_column = read_filter_column(xml)?;
+                filter.columns.push(FilterColumn {
+                    index,
+                    filter: filter_column,
+                });
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sortState" => {
+                filter.sort = Some(read_sort_state(xml, e)?);
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"autoFilter" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("autoFilter")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(filter)
+}
+
+/// Reads the criteria of a `filterColumn` element, up to its end
+///
+/// Date groups of `filters` are ignored.
+fn read_filter_column(xml: &mut XlReader<'_>) -> Result<Filter, XlsxError> {
+    let mut filter = Filter::Other;
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"filters" => {
+                    let blank = get_attribute(e.attributes(), QName(b"blank"))?
+                        .is_some_and(|v| matches!(v, b"1" | b"true"));
+                    filter = Filter::Values {
+                        values: Vec::new(),
+                        blank,
+                    };
+                }
+                b"filter" => {
+                    if let Filter::Values { ref mut values, .. } = filter {
+                        if let Some(v) = get_attribute(e.attributes(), QName(b"val"))? {
+                            values.push(xml.decoder().decode(v)?.into_owned());
+                        }
+                    }
+                }
+                b"customFilters" => {
+                    let and = get_attribute(e.attributes(), QName(b"and"))?
+                        .is_some_and(|v| matches!(v, b"1" | b"true"));
+                    filter = Filter::Custom {
+                        conditions: Vec::new(),
+                        and,
+                    };
+                }
+                b"customFilter" => {
+                    if let Filter::Custom {
+                        ref mut conditions, ..
+                    } = filter
+                    {
+                        conditions.push(read_custom_filter(xml, e)?);
+                    }
+                }
+                b"top10" => {
+                    let mut top = true;
+                    let mut percent = false;
+                    let mut value = 10.;
+                    for a in e.attributes() {
+                        let a = a.map_err(XlsxError::XmlAttr)?;
+                        let v = std::str::from_utf8(&a.value).unwrap_or_default();
+                        match a.key.local_name().as_ref() {
+                            b"top" => top = is_true(v),
+                            b"percent" => percent = is_true(v),
+                            b"val" => value = v.parse().unwrap_or(value),
+                            _ => (),
+                        }
+                    }
+                    filter = Filter::Top10 {
+                        top,
+                        percent,
+                        value,
+                    };
+                }
+                b"dynamicFilter" => {
+                    if let Some(t) = get_attribute(e.attributes(), QName(b"type"))? {
+                        filter = Filter::Dynamic(xml.decoder().decode(t)?.into_owned());
+                    }
+                }
+                _ => (),
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"filterColumn" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("filterColumn")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(filter)
+}
+
+/// Reads a `customFilter` element
+///
+/// Numeric values are read as floats, and the `" "` value Excel uses to match
+/// (non) blank cells as [`Data::Empty`].
+fn read_custom_filter(
+    xml: &XlReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<FilterCondition, XlsxError> {
+    let mut condition = FilterCondition {
+        operator: FilterOperator::Equal,
+        value: Data::Empty,
+    };
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        match a.key.local_name().as_ref() {
+            b"operator" => {
+                condition.operator = match a.value.as_ref() {
+                    b"notEqual" => FilterOperator::NotEqual,
+                    b"lessThan" => FilterOperator::LessThan,
+                    b"lessThanOrEqual" => FilterOperator::LessThanOrEqual,
+                    b"greaterThan" => FilterOperator::GreaterThan,
+                    b"greaterThanOrEqual" => FilterOperator::GreaterThanOrEqual,
+                    _ => FilterOperator::Equal,
+                }
+            }
+            b"val" => {
+                let v = a.decode_and_unescape_value(xml)?;
+                condition.value = match v.parse() {
+                    _ if v.trim().is_empty() => Data::Empty,
+                    Ok(f) => Data::Float(f),
+                    Err(_) => Data::String(v.into_owned()),
+                };
+            }
+            _ => (),
+        }
+    }
+    Ok(condition)
+}
+
+/// Reads a `sortState` element, up to its end
+fn read_sort_state(xml: &mut XlReader<'_>, e: &BytesStart<'_>) -> Result<SortState, XlsxError> {
+    let mut sort = SortState::default();
+    if let Some(r) = get_attribute(e.attributes(), QName(b"ref"))? {
+        sort.range = get_dimension(r)?;
+    }
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"sortCondition" => {
+                let mut condition = SortCondition::default();
+                for a in e.attributes() {
+                    let a = a.map_err(XlsxError::XmlAttr)?;
+                    match a.key.local_name().as_ref() {
+                        b"ref" => condition.column = get_dimension(&a.value)?.start.1,
+                        b"descending" => {
+                            condition.descending = matches!(a.value.as_ref(), b"1" | b"true")
+                        }
+                        _ => (),
+                    }
+                }
+                sort.conditions.push(condition);
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sortState" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("sortState")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(sort)
+}
+
+/// Reads a `dataValidation` element, up to its end
+fn read_data_validation(
+    xml: &mut XlReader<'_>,
+    e: &BytesStart<'_>,
+) -> Result<DataValidation, XlsxError> {
+    let mut validation = DataValidation {
+        show_dropdown: true,
+        ..DataValidation::default()
+    };
+    for a in e.attributes() {
+        let a = a.map_err(XlsxError::XmlAttr)?;
+        let value = a.decode_and_unescape_value(xml)?;
+        let text = Some(value.to_string()).filter(|v| !v.is_empty());
+        match a.key.local_name().as_ref() {
+            b"type" => {
+                validation.typ = match &*value {
+                    "whole" => DataValidationType::Whole,
+                    "decimal" => DataValidationType::Decimal,
+                    "list" => DataValidationType::List,
+                    "date" => DataValidationType::Date,
+                    "time" => DataValidationType::Time,
+                    "textLength" => DataValidationType::TextLength,
+                    "custom" => DataValidationType::Custom,
+                    _ => DataValidationType::Any,
+                }
+            }
+            b"operator" => {
+                validation.operator = match &*value {
+                    "notBetween" => DataValidationOperator::NotBetween,
+                    "equal" => DataValidationOperator::Equal,
+                    "notEqual" => DataValidationOperator::NotEqual,
+                    "greaterThan" => DataValidationOperator::GreaterThan,
+                    "lessThan" => DataValidationOperator::LessThan,
+                    "greaterThanOrEqual" => DataValidationOperator::GreaterThanOrEqual,
+                    "lessThanOrEqual" => DataValidationOperator::LessThanOrEqual,
+                    _ => DataValidationOperator::Between,
+                }
+            }
+            b"errorStyle" => {
+                validation.error_style = match &*value {
+                    "warning" => DataValidationErrorStyle::Warning,
+                    "information" => DataValidationErrorStyle::Information,
+                    _ => DataValidationErrorStyle::Stop,
+                }
+            }
+            b"allowBlank" => validation.allow_blank = is_true(&value),
+            // despite its name, hides the dropdown
+            b"showDropDown" => validation.show_dropdown = !is_true(&value),
+            b"showInputMessage" => validation.show_prompt = is_true(&value),
+            b"showErrorMessage" => validation.show_error = is_true(&value),
+            b"promptTitle" => validation.prompt_title = text,
+            b"prompt" => validation.prompt = text,
+            b"errorTitle" => validation.error_title = text,
+            b"error" => validation.error = text,
+            b"sqref" => validation.ranges = read_sqref(&value)?,
+            _ => (),
+        }
+    }
+    let mut buf = Vec::with_capacity(64);
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"formula1" => validation.formula1 = Some(read_text(xml, b"formula1")?),
+                b"formula2" => validation.formula2 = Some(read_text(xml, b"formula2")?),
+                b"sqref" => validation.ranges = read_sqref(&read_text(xml, b"sqref")?)?,
+                _ => (),
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"dataValidation" => break,
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("dataValidation")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+    Ok(validation)
+}
+
+/// Reads a space separated list of ranges
+fn read_sqref(sqref: &str) -> Result<Vec<Dimensions>, XlsxError> {
+    sqref
+        .split_whitespace()
+        .map(|r| get_dimension(r.as_bytes()))
+        .collect()
+}
+
+/// search through an Element's attributes for the named one
+pub(crate) fn get_attribute<'a>(
+    atts: Attributes<'a>,
+    n: QName,
+) -> Result<Option<&'a [u8]>, XlsxError> {
+    for a in atts {
+        match a {
+            Ok(Attribute {
+                key,
+                value: Cow::Borrowed(value),
+            }) if key == n => return Ok(Some(value)),
+            Err(e) => return Err(XlsxError::XmlAttr(e)),
+            _ => {} // ignore other attributes
+        }
+    }
+    Ok(None)
+}
+
+/// converts a text representation (e.g. "A6:G67") of a dimension into integers
+/// - top left (row, column),
+/// - bottom right (row, column)
+pub(crate) fn get_dimension(dimension: &[u8]) -> Result<Dimensions, XlsxError> {
+    let parts: Vec<_> = dimension
+        .split(|c| *c == b':')
+        .map(get_row_column)
+        .collect::<Result<Vec<_>, XlsxError>>()?;
+
+    match parts.len() {
+        0 => Err(XlsxError::DimensionCount(0)),
+        1 => Ok(Dimensions {
             start: parts[0],
             end: parts[0],
         }),
@@ -1153,6 +2356,62 @@ pub(crate) fn read_string(
     }
 }
 
+/// Reads the runs of a richtext string, `None` if the string is not formatted
+pub(crate) fn read_rich_string(
+    xml: &mut XlReader<'_>,
+    QName(closing): QName,
+) -> Result<Option<RichText>, XlsxError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut val_buf = Vec::with_capacity(1024);
+    let mut rich = RichText::default();
+    let mut font = Font::default();
+    let mut is_rich = false;
+    let mut is_phonetic_text = false;
+    loop {
+        buf.clear();
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"r" => {
+                is_rich = true;
+                font = Font::default();
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"rPr" => loop {
+                val_buf.clear();
+                match xml.read_event_into(&mut val_buf)? {
+                    Event::Start(ref p) => read_font_property(xml, p, &mut font)?,
+                    Event::End(end) if end.local_name().as_ref() == b"rPr" => break,
+                    Event::Eof => return Err(XlsxError::XmlEof("rPr")),
+                    _ => (),
+                }
+            },
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"rPh" => {
+                is_phonetic_text = true;
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"rPh" => {
+                is_phonetic_text = false;
+            }
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"t" && !is_phonetic_text => {
+                val_buf.clear();
+                let mut value = String::new();
+                loop {
+                    match xml.read_event_into(&mut val_buf)? {
+                        Event::Text(t) => value.push_str(&t.unescape()?),
+                        Event::End(end) if end.name() == e.name() => break,
+                        Event::Eof => return Err(XlsxError::XmlEof("t")),
+                        _ => (),
+                    }
+                }
+                rich.runs.push((value, font.clone()));
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == closing => {
+                return Ok(Some(rich).filter(|_| is_rich));
+            }
+            Ok(Event::Eof) => return Err(XlsxError::XmlEof("")),
+            Err(e) => return Err(XlsxError::Xml(e)),
+            _ => (),
+        }
+    }
+}
+
 fn check_for_password_protected<RS: Read + Seek>(reader: &mut RS) -> Result<(), XlsxError> {
     let offset_end = reader.seek(std::io::SeekFrom::End(0))? as usize;
     reader.seek(std::io::SeekFrom::Start(0))?;
@@ -1,10 +1,19 @@
+use calamine::formula::{parse_formula, BinaryOp, CellRef, Expr, Reference};
 use calamine::Data::{Bool, DateTime, DateTimeIso, DurationIso, Empty, Error, Float, String};
 use calamine::{
-    open_workbook, open_workbook_auto, DataType, Dimensions, ExcelDateTime, ExcelDateTimeType, Ods,
-    Range, Reader, Sheet, SheetType, SheetVisible, Xls, Xlsb, Xlsx,
+    open_workbook, open_workbook_auto, Border, BorderStyle, Borders, Color, ColumnProperties,
+    Comment, DataRef, DataType, DataValidation, DataValidationErrorStyle, DataValidationOperator,
+    DataValidationType, DependencyGraph, Dimensions, ExcelDateTime, ExcelDateTimeType, FillPattern,
+    Filter, FilterColumn, FilterCondition, FilterOperator, FormulaInfo, FormulaKind,
+    HorizontalAlignment, Hyperlink, Ods, Protection, Range, RangeDeserializerBuilder, Reader,
+    RowProperties, Sheet, SheetArea, SheetCell, SheetType, SheetVisible, SortCondition, SortState,
+    TableColumn, TableMetadata, TableStyle, TotalsRowFunction, VerticalAlignment, Xls, Xlsb, Xlsx,
+    XlsxError,
 };
+#[cfg(feature = "encryption")]
+use calamine::{open_workbook_auto_with_password, open_workbook_with_password, CryptoError};
 use calamine::{CellErrorType::*, Data};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Cursor;
 use std::sync::Once;
 
@@ -610,6 +619,71 @@ fn formula_ods() {
 
     let formula = excel.worksheet_formula("Sheet1").unwrap();
     range_eq!(formula, [["of:=[.B1]+$$OneRange".to_string()]]);
+
+    let info = excel.worksheet_formula_info("Sheet1").unwrap();
+    range_eq!(
+        info,
+        [[FormulaInfo {
+            text: "of:=[.B1]+$$OneRange".to_string(),
+            kind: FormulaKind::Normal,
+        }]]
+    );
+}
+
+#[test]
+fn formula_ods_excel_syntax() {
+    setup();
+
+    for ext in ["ods", "xlsx"] {
+        let path = format!("{}/tests/issues.{}", env!("CARGO_MANIFEST_DIR"), ext);
+        let mut excel = open_workbook_auto(&path).unwrap();
+        let formula = excel
+            .with_excel_formulas(true)
+            .worksheet_formula("Sheet1")
+            .unwrap();
+        range_eq!(formula, [["B1+OneRange".to_string()]]);
+    }
+}
+
+#[test]
+fn formula_expr() {
+    setup();
+
+    let b1 = CellRef {
+        row: Some(0),
+        col: Some(1),
+        row_absolute: false,
+        col_absolute: false,
+    };
+    let expected = Expr::Binary(
+        BinaryOp::Add,
+        Box::new(Expr::Reference(Reference {
+            sheet: None,
+            last_sheet: None,
+            start: b1,
+            end: b1,
+        })),
+        Box::new(Expr::Name("OneRange".to_string())),
+    );
+    for ext in ["xlsx", "xlsb", "xls", "ods"] {
+        let path = format!("{}/tests/issues.{}", env!("CARGO_MANIFEST_DIR"), ext);
+        let mut excel = open_workbook_auto(&path).unwrap();
+        let formula = excel.worksheet_formula_expr("Sheet1").unwrap();
+        let formula = formula.used_cells().map(|c| c.2).collect::<Vec<_>>();
+        assert_eq!(formula, [&expected], "{}", ext);
+    }
+
+    let path = format!("{}/tests/xls_formula.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut wb: Xls<_> = open_workbook(&path).unwrap();
+    let formula = wb.worksheet_formula_expr("Sheet1").unwrap();
+    let expected = ["A1*2", "2*Sheet2!A1", "A1+Sheet2!A1"]
+        .map(|f| parse_formula(f).unwrap())
+        .to_vec();
+    let formula = formula
+        .used_cells()
+        .map(|c| c.2.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(formula, expected);
 }
 
 #[test]
@@ -740,6 +814,171 @@ fn table() {
     xls.worksheet_range_at(0).unwrap().unwrap();
 }
 
+#[test]
+fn table_all_formats() {
+    setup();
+
+    for ext in ["xlsx", "xlsb", "ods"] {
+        let path = format!(
+            "{}/tests/temperature-table.{}",
+            env!("CARGO_MANIFEST_DIR"),
+            ext
+        );
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        workbook.load_tables().unwrap();
+        assert_eq!(
+            workbook.table_names(),
+            vec!["Temperature", "OtherTable"],
+            "{}",
+            ext
+        );
+        assert_eq!(
+            workbook.table_names_in_sheet("Sheet2"),
+            vec!["OtherTable"],
+            "{}",
+            ext
+        );
+
+        let table = workbook.table_by_name("Temperature").unwrap();
+        assert_eq!(table.name(), "Temperature");
+        assert_eq!(table.sheet_name(), "Sheet1");
+        assert_eq!(table.columns(), ["label", "value"], "{}", ext);
+        range_eq!(
+            table.data(),
+            [
+                [String("celsius".to_owned()), Float(22.2222)],
+                [String("fahrenheit".to_owned()), Float(72.0)]
+            ]
+        );
+
+        let table = workbook.table_by_name("OtherTable").unwrap();
+        assert_eq!(table.sheet_name(), "Sheet2");
+        assert_eq!(table.columns(), ["label2", "value2"], "{}", ext);
+        range_eq!(
+            table.data(),
+            [
+                [String("something".to_owned()), Float(12.5)],
+                [String("else".to_owned()), Float(64.0)]
+            ]
+        );
+
+        assert!(workbook.table_by_name("Unknown").is_err());
+    }
+
+    // xls files have no tables
+    let path = format!("{}/tests/date.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut workbook = open_workbook_auto(&path).unwrap();
+    workbook.load_tables().unwrap();
+    assert!(workbook.table_names().is_empty());
+    assert!(workbook.table_by_name("Temperature").is_err());
+}
+
+#[test]
+fn table_metadata() {
+    setup();
+
+    for (ext, calculated, totals) in [
+        (
+            "xlsx",
+            "Prices[[#This Row],[price]]*1.2",
+            "SUBTOTAL(109,Prices[with_tax])",
+        ),
+        ("xlsb", "B2*1.2", "SUBTOTAL(109,C2:C3)"),
+    ] {
+        let path = format!(
+            "{}/tests/table_metadata.{}",
+            env!("CARGO_MANIFEST_DIR"),
+            ext
+        );
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        workbook.load_tables().unwrap();
+        let table = workbook.table_by_name("Prices").unwrap();
+        let expected = TableMetadata {
+            name: "Prices".to_string(),
+            sheet_name: "Sheet1".to_string(),
+            range: Dimensions::new((0, 0), (3, 2)),
+            header_row_count: 1,
+            totals_row_count: 1,
+            totals_row_shown: true,
+            insert_row: false,
+            columns: vec![
+                TableColumn {
+                    name: "item".to_string(),
+                    totals_row_label: Some("Total".to_string()),
+                    ..TableColumn::default()
+                },
+                TableColumn {
+                    name: "price".to_string(),
+                    totals_row_function: Some(TotalsRowFunction::Sum),
+                    ..TableColumn::default()
+                },
+                TableColumn {
+                    name: "with_tax".to_string(),
+                    calculated_formula: Some(calculated.to_string()),
+                    totals_row_function: Some(TotalsRowFunction::Custom(totals.to_string())),
+                    totals_row_label: None,
+                },
+            ],
+            auto_filter: Some(Dimensions::new((0, 0), (2, 2))),
+            style: Some(TableStyle {
+                name: "TableStyleLight9".to_string(),
+                show_row_stripes: true,
+                ..TableStyle::default()
+            }),
+        };
+        assert_eq!(table.metadata(), &expected, "{}", ext);
+        assert_eq!(
+            table.metadata().data_dimensions(),
+            Dimensions::new((1, 0), (2, 2))
+        );
+        assert_eq!(table.columns(), ["item", "price", "with_tax"]);
+        // the totals row is not part of the data
+        range_eq!(
+            table.data(),
+            [
+                [String("apple".to_owned()), Float(1.0), Float(1.2)],
+                [String("pear".to_owned()), Float(2.0), Float(2.4)]
+            ]
+        );
+    }
+
+    // ods database ranges only know about their header row
+    let path = format!("{}/tests/temperature-table.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    ods.load_tables().unwrap();
+    let table = ods.table_by_name("Temperature").unwrap();
+    let metadata = table.metadata();
+    assert_eq!(metadata.range, Dimensions::new((0, 0), (2, 1)));
+    assert_eq!(metadata.header_row_count, 1);
+    assert_eq!(metadata.totals_row_count, 0);
+    assert_eq!(metadata.auto_filter, None);
+    assert_eq!(metadata.style, None);
+}
+
+#[test]
+fn structured_references() {
+    setup();
+
+    let path = format!("{}/tests/table_metadata.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut workbook: Xlsx<_> = open_workbook(&path).unwrap();
+    let formulas = workbook.worksheet_formula_expr("Sheet1").unwrap();
+    let formula = |pos| formulas.get_value(pos).unwrap().to_string();
+    assert_eq!(formula((1, 2)), "$B$2*1.2");
+    assert_eq!(formula((2, 2)), "$B$3*1.2");
+    assert_eq!(formula((3, 1)), "SUBTOTAL(109,$B$2:$B$3)");
+    assert_eq!(formula((3, 2)), "SUBTOTAL(109,$C$2:$C$3)");
+
+    let graph = DependencyGraph::new(&mut workbook).unwrap();
+    assert_eq!(
+        graph.precedents("Sheet1", (3, 2)),
+        [SheetArea {
+            sheet: "Sheet1".to_string(),
+            start: (1, 2),
+            end: (2, 2),
+        }]
+    );
+}
+
 #[test]
 fn date_xls() {
     setup();
@@ -1434,6 +1673,120 @@ fn issue_305_merge_cells_xls() {
     );
 }
 
+#[test]
+fn merged_cells() {
+    setup();
+
+    let expected = [
+        Dimensions::new((0, 0), (0, 1)),
+        Dimensions::new((1, 0), (3, 0)),
+        Dimensions::new((1, 1), (3, 3)),
+    ];
+    for ext in ["xls", "xlsx", "xlsb"] {
+        let path = format!("{}/tests/merge_cells.{}", env!("CARGO_MANIFEST_DIR"), ext);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        assert_eq!(
+            workbook.merged_cells("Sheet1").unwrap(),
+            expected,
+            "{}",
+            ext
+        );
+        assert!(workbook.merged_cells("NotThere").is_err(), "{}", ext);
+    }
+
+    let path = format!("{}/tests/merged_cells.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        ods.merged_cells("Sheet1").unwrap(),
+        [Dimensions::new((1, 0), (2, 0))]
+    );
+}
+
+#[test]
+fn merged_cells_fill() {
+    setup();
+
+    let path = format!("{}/tests/merge_cells.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let merged = xlsx.merged_cells("Sheet1").unwrap();
+    let range = xlsx.worksheet_range("Sheet1").unwrap();
+
+    let mut filled = range.clone();
+    filled.fill_merged(&merged);
+    let s = |s: &str| String(s.to_string());
+    range_eq!(
+        filled,
+        [
+            [s("Row Merge"), s("Row Merge"), s("Not Merged"), Empty],
+            [
+                s("Column Merge"),
+                s("Chunk Merged"),
+                s("Chunk Merged"),
+                s("Chunk Merged")
+            ],
+            [
+                s("Column Merge"),
+                s("Chunk Merged"),
+                s("Chunk Merged"),
+                s("Chunk Merged")
+            ],
+            [
+                s("Column Merge"),
+                s("Chunk Merged"),
+                s("Chunk Merged"),
+                s("Chunk Merged")
+            ]
+        ]
+    );
+
+    // "Not Merged" is over the merged "Chunk Merged" area
+    let headers = ["Not Merged", "Row Merge"];
+    let row = ("Chunk Merged".to_string(), "Column Merge".to_string());
+    let rows: Vec<(std::string::String, std::string::String)> =
+        RangeDeserializerBuilder::with_headers(&headers)
+            .fill_merged(&merged)
+            .from_range(&range)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert_eq!(rows, [row.clone()]);
+
+    let rows: Vec<(std::string::String, std::string::String)> =
+        RangeDeserializerBuilder::with_headers(&headers)
+            .fill_merged(&merged)
+            .from_rows(xlsx.worksheet_rows("Sheet1").unwrap())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert_eq!(rows, [row.clone()]);
+
+    // the filled range includes the last rows of the merged areas
+    let rows: Vec<(std::string::String, std::string::String)> =
+        RangeDeserializerBuilder::with_headers(&headers)
+            .from_range(&filled)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert_eq!(rows, vec![row; 3]);
+
+    // without filling, merged cells are empty
+    let rows: Vec<Vec<Data>> = RangeDeserializerBuilder::new()
+        .from_range(&range)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, [[s("Column Merge"), s("Chunk Merged"), Empty]]);
+}
+
+#[test]
+fn range_set_value_grows_rows() {
+    let mut range = Range::new((0, 0), (0, 1));
+    range.set_value((2, 0), Float(1.));
+    assert_eq!(range.get_size(), (3, 2));
+    assert_eq!(range.rows().count(), 3);
+    range_eq!(range, [[Empty, Empty], [Empty, Empty], [Float(1.), Empty]]);
+}
+
 // cargo test --features picture
 #[test]
 #[cfg(feature = "picture")]
@@ -1667,22 +2020,26 @@ fn any_sheets_xlsx() {
             Sheet {
                 name: "Visible".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Hidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Hidden
+                visible: SheetVisible::Hidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "VeryHidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::VeryHidden
+                visible: SheetVisible::VeryHidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Chart".to_string(),
                 typ: SheetType::ChartSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
         ]
     );
@@ -1701,22 +2058,26 @@ fn any_sheets_xlsb() {
             Sheet {
                 name: "Visible".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Hidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Hidden
+                visible: SheetVisible::Hidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "VeryHidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::VeryHidden
+                visible: SheetVisible::VeryHidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Chart".to_string(),
                 typ: SheetType::ChartSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
         ]
     );
@@ -1735,22 +2096,26 @@ fn any_sheets_xls() {
             Sheet {
                 name: "Visible".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Hidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Hidden
+                visible: SheetVisible::Hidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "VeryHidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::VeryHidden
+                visible: SheetVisible::VeryHidden,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Chart".to_string(),
                 typ: SheetType::ChartSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
         ]
     );
@@ -1769,29 +2134,143 @@ fn any_sheets_ods() {
             Sheet {
                 name: "Visible".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
             Sheet {
                 name: "Hidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Hidden
+                visible: SheetVisible::Hidden,
+                protection: Protection::default(),
             },
             // ODS doesn't support Very Hidden
             Sheet {
                 name: "VeryHidden".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Hidden
+                visible: SheetVisible::Hidden,
+                protection: Protection::default(),
             },
             // ODS doesn't support chartsheet
             Sheet {
                 name: "Chart".to_string(),
                 typ: SheetType::WorkSheet,
-                visible: SheetVisible::Visible
+                visible: SheetVisible::Visible,
+                protection: Protection::default(),
             },
         ]
     );
 }
 
+#[test]
+fn sheet_and_workbook_protection() {
+    setup();
+
+    let locked = Protection {
+        protected: true,
+        password: true,
+    };
+    for (file, protected, open) in [
+        ("protection.xlsx", "Protected", "Open"),
+        ("protection.xlsb", "Sheet1", "Sheet2"),
+        ("protection.xls", "Protected", "Open"),
+        ("protection.ods", "Protected", "Open"),
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let workbook = open_workbook_auto(&path).unwrap();
+        assert_eq!(workbook.workbook_protection(), &locked, "{file}");
+        let sheets = workbook
+            .sheets_metadata()
+            .iter()
+            .map(|s| (s.name.as_str(), s.protection.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sheets,
+            [(protected, locked.clone()), (open, Protection::default())],
+            "{file}"
+        );
+    }
+
+    // an empty sheetProtection does not lock anything
+    let path = format!("{}/tests/issue_174.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let workbook: Xlsx<_> = open_workbook(&path).unwrap();
+    assert_eq!(workbook.workbook_protection(), &Protection::default());
+    assert!(workbook
+        .sheets_metadata()
+        .iter()
+        .all(|s| !s.protection.protected));
+}
+
+#[test]
+fn row_column_properties() {
+    setup();
+
+    let grouped_row = RowProperties {
+        hidden: true,
+        outline_level: 1,
+        ..RowProperties::default()
+    };
+    let grouped_column = ColumnProperties {
+        hidden: true,
+        outline_level: 1,
+        ..ColumnProperties::default()
+    };
+    for file in [
+        "row_column_properties.xlsx",
+        "row_column_properties.xlsb",
+        "row_column_properties.xls",
+        "row_column_properties.ods",
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let props = workbook.worksheet_row_column_properties("Sheet1").unwrap();
+
+        let mut rows = BTreeMap::new();
+        rows.insert(
+            1,
+            RowProperties {
+                height: Some(30.),
+                ..RowProperties::default()
+            },
+        );
+        rows.insert(2, grouped_row.clone());
+        rows.insert(3, grouped_row.clone());
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            1,
+            ColumnProperties {
+                width: Some(20.),
+                ..ColumnProperties::default()
+            },
+        );
+        columns.insert(2, grouped_column.clone());
+        columns.insert(3, grouped_column.clone());
+        if file.ends_with(".ods") {
+            // the default width is the one of the last columns
+            assert_eq!(props.default_column.width, Some(48. / 5.25));
+        } else {
+            assert_eq!(props.default_row.height, Some(15.), "{file}");
+            // collapsed state is set on the row and column following the group
+            rows.insert(
+                4,
+                RowProperties {
+                    collapsed: true,
+                    ..RowProperties::default()
+                },
+            );
+            columns.insert(
+                4,
+                ColumnProperties {
+                    collapsed: true,
+                    ..ColumnProperties::default()
+                },
+            );
+        }
+        assert!(!props.default_row.hidden, "{file}");
+        assert_eq!(props.rows, rows, "{file}");
+        assert_eq!(props.columns, columns, "{file}");
+    }
+}
+
 #[test]
 fn issue_102() {
     setup();
@@ -1862,6 +2341,72 @@ fn pass_protected_ods() {
     );
 }
 
+// cargo test --features encryption
+#[test]
+#[cfg(feature = "encryption")]
+fn pass_protected_xlsx_with_password() {
+    let path = format!("{}/tests/pass_protected.xlsx", env!("CARGO_MANIFEST_DIR"));
+
+    let mut xlsx: Xlsx<_> = open_workbook_with_password(&path, "123").unwrap();
+    assert_eq!(xlsx.sheet_names(), ["Sheet1"]);
+    assert!(xlsx.worksheet_range("Sheet1").unwrap().is_empty());
+
+    assert!(matches!(
+        open_workbook_with_password::<Xlsx<_>, _>(&path, "wrong"),
+        Err(XlsxError::Crypto(CryptoError::InvalidPassword))
+    ));
+
+    // unencrypted workbooks are read as usual
+    let path = format!("{}/tests/issues.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook_with_password(path, "123").unwrap();
+    assert!(xlsx.worksheet_range("issue2").is_ok());
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn pass_protected_xlsb_with_password() {
+    let path = format!("{}/tests/pass_protected.xlsb", env!("CARGO_MANIFEST_DIR"));
+
+    let mut xlsb: Xlsb<_> = open_workbook_with_password(path, "1234").unwrap();
+    let range = xlsb.worksheet_range("Sheet1").unwrap();
+    assert_eq!(range.get_value((1, 0)), Some(&String("String".to_string())));
+    assert_eq!(range.get_value((1, 2)), Some(&Float(1.1)));
+    assert_eq!(range.get_value((1, 3)), Some(&Bool(true)));
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn pass_protected_xls_with_password() {
+    let path = format!("{}/tests/issue_385.xls", env!("CARGO_MANIFEST_DIR"));
+
+    let xls: Xls<_> = open_workbook_with_password(&path, "VelvetSweatshop").unwrap();
+    assert_eq!(xls.sheet_names(), ["Sheet1"]);
+
+    assert!(matches!(
+        open_workbook_with_password::<Xls<_>, _>(&path, "wrong"),
+        Err(calamine::XlsError::Crypto(CryptoError::InvalidPassword))
+    ));
+
+    let mut sheets = open_workbook_auto_with_password(&path, "VelvetSweatshop").unwrap();
+    assert!(sheets.worksheet_range("Sheet1").unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn pass_protected_ods_with_password() {
+    let path = format!("{}/tests/pass_protected.ods", env!("CARGO_MANIFEST_DIR"));
+
+    let mut ods: Ods<_> = open_workbook_with_password(path, "1234").unwrap();
+    assert_eq!(ods.sheet_names(), ["Sheet1", "Sheet2"]);
+    let range = ods.worksheet_range("Sheet1").unwrap();
+    assert_eq!(range.get_value((1, 0)), Some(&String("String".to_string())));
+    assert_eq!(range.get_value((1, 2)), Some(&Float(1.1)));
+    assert_eq!(
+        range.get_value((1, 5)),
+        Some(&DateTimeIso("2010-10-10".to_string()))
+    );
+}
+
 #[test]
 fn issue_384_multiple_formula() {
     let path = format!("{}/tests/formula.issue.xlsx", env!("CARGO_MANIFEST_DIR"));
@@ -1952,3 +2497,816 @@ fn issue_420_empty_s_attribute() {
         ]
     );
 }
+
+#[test]
+fn number_formats() {
+    setup();
+
+    for ext in ["xls", "xlsx", "xlsb"] {
+        let path = format!("{}/tests/date.{ext}", env!("CARGO_MANIFEST_DIR"));
+        let mut excel = open_workbook_auto(&path).unwrap();
+        let range = excel.worksheet_range_with_format("Sheet1").unwrap();
+        let formats: Vec<_> = range
+            .cells()
+            .map(|(_, _, c)| c.format.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            formats,
+            [
+                r"yyyy\-mm\-dd",
+                "General",
+                r"yyyy\-mm\-dd",
+                "General",
+                "[hh]:mm:ss",
+                "General"
+            ],
+            "{ext}"
+        );
+        range_eq!(
+            range.formatted(),
+            [
+                ["2021-01-01", "15"],
+                ["2021-01-02", "16"],
+                ["255:10:10", "17"]
+            ]
+        );
+    }
+
+    let path = format!("{}/tests/number_formats.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let range = xlsx.worksheet_range_with_format("Sheet1").unwrap();
+    let formats: Vec<_> = range
+        .cells()
+        .map(|(_, _, c)| c.format.as_deref().unwrap_or_default())
+        .collect();
+    assert_eq!(formats, ["0.00%", "0%", "\"$\"#,##0.00", "\"$\"#,##0.00"]);
+    range_eq!(
+        range.formatted(),
+        [["12.34%"], ["50%"], ["$1,234.50"], ["-$1,234.50"]]
+    );
+}
+
+#[test]
+fn number_formats_ods() {
+    setup();
+
+    let path = format!("{}/tests/date.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let range = ods.worksheet_range_with_format("Sheet1").unwrap();
+    assert_eq!(
+        range.get_value((0, 0)).unwrap().format.as_deref(),
+        Some("mm/dd/yyyy")
+    );
+    assert_eq!(
+        range.get_value((3, 0)).unwrap().format.as_deref(),
+        Some("hh:mm:ss.00")
+    );
+    assert_eq!(range.get_value((0, 1)).unwrap().format, None);
+    assert!(range
+        .values()
+        .cells()
+        .eq(ods.worksheet_range("Sheet1").unwrap().cells()));
+    range_eq!(
+        range.formatted(),
+        [
+            ["01/01/2021", "15"],
+            ["01/01/2021 10:10 AM", "16"],
+            ["10:10:10", "17"],
+            ["10:10:10.12", "18"]
+        ]
+    );
+}
+
+#[test]
+fn styles_xlsx() {
+    setup();
+
+    let path = format!("{}/tests/issue_174.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let styles = xlsx.worksheet_styles("Sheet1").unwrap();
+    let style = &xlsx.styles()[styles.get_value((0, 1)).unwrap().0];
+    assert!(style.font.bold);
+    assert_eq!(style.font.name.as_deref(), Some("Calibri"));
+    assert_eq!(style.font.color, Some(Color::Rgb(255, 255, 255)));
+    assert_eq!(style.fill.pattern, FillPattern::Solid);
+    assert_eq!(style.fill.color(), Some(Color::Rgb(255, 0, 0)));
+    assert_eq!(style.borders.left.style, BorderStyle::Thin);
+    assert_eq!(style.borders.bottom.color, Some(Color::Rgb(0, 0, 0)));
+    assert!(style.protection.locked);
+}
+
+#[test]
+fn styles_xls() {
+    setup();
+
+    let path = format!("{}/tests/issue_271.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let styles = xls.worksheet_styles("sheet1").unwrap();
+    let style = &xls.styles()[styles.get_value((0, 0)).unwrap().0];
+    assert!(style.font.bold);
+    assert_eq!(style.font.size, Some(22.0));
+    assert_eq!(style.font.name.as_deref(), Some("方正小标宋简体"));
+    assert_eq!(style.alignment.horizontal, HorizontalAlignment::Center);
+    assert_eq!(style.alignment.vertical, VerticalAlignment::Center);
+    assert_eq!(style.borders, Borders::default());
+
+    // indexed colors
+    let path = format!("{}/tests/any_sheets.xls", env!("CARGO_MANIFEST_DIR"));
+    let xls: Xls<_> = open_workbook(&path).unwrap();
+    let fill = &xls.styles()[16].fill;
+    assert_eq!(fill.pattern, FillPattern::Solid);
+    assert_eq!(fill.color(), Some(Color::Indexed(27)));
+    assert_eq!(fill.color().unwrap().to_rgb(), Some((0xCC, 0xFF, 0xFF)));
+}
+
+#[test]
+fn styles_ods() {
+    setup();
+
+    let path = format!("{}/tests/styles.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    let ids = ods.worksheet_styles("Sheet1").unwrap();
+    let styles = ods.styles();
+    let style = |row, col| &styles[ids.get_value((row, col)).unwrap().0];
+
+    // bold and fill inherited from the "Highlight" style
+    let header = style(0, 0);
+    assert!(header.font.bold);
+    assert_eq!(header.font.name.as_deref(), Some("Liberation Sans"));
+    assert_eq!(header.font.size, Some(10.0));
+    assert_eq!(header.fill.color(), Some(Color::Rgb(255, 255, 0)));
+    let thin = Border {
+        style: BorderStyle::Thin,
+        color: Some(Color::Rgb(0, 0, 0)),
+    };
+    assert_eq!(header.borders.top, thin);
+    assert_eq!(header.borders.right, thin);
+
+    let late = style(0, 1);
+    assert!(late.font.italic && late.font.strikethrough && !late.font.bold);
+    assert_eq!(late.font.color, Some(Color::Rgb(255, 0, 0)));
+    assert_eq!(late.alignment.horizontal, HorizontalAlignment::Center);
+    assert_eq!(late.alignment.vertical, VerticalAlignment::Center);
+    assert!(late.alignment.wrap_text);
+    assert!(!late.protection.locked);
+
+    assert_eq!(style(1, 0).fill, header.fill);
+    assert_eq!(style(1, 0).borders, Borders::default());
+    assert_eq!(style(1, 2).borders.bottom.style, BorderStyle::Thick);
+    assert_eq!(
+        style(1, 2).borders.bottom.color,
+        Some(Color::Rgb(0, 0, 255))
+    );
+
+    // column default style, same as the workbook default
+    assert_eq!(style(0, 2), &styles[0]);
+    assert!(!styles[0].font.bold);
+}
+
+#[test]
+fn worksheet_rows() {
+    setup();
+
+    for file in ["issues.xlsx", "issues.xlsb", "issues.xls", "issues.ods"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        for sheet in workbook.sheet_names() {
+            let range = workbook.worksheet_range(&sheet).unwrap();
+            let end = range.end().unwrap_or_default();
+            let mut count = 0;
+            for row in workbook.worksheet_rows(&sheet).unwrap() {
+                let (row, cells) = row.unwrap();
+                assert!(range.start().is_some_and(|s| row >= s.0) && row <= end.0);
+                assert!(!cells.is_empty() && cells.len() as u32 <= end.1 + 1);
+                for (col, cell) in cells.into_iter().enumerate() {
+                    let expected = range.get_value((row, col as u32)).unwrap_or(&Data::Empty);
+                    assert_eq!(&Data::from(cell), expected, "{file} {sheet} ({row}, {col})");
+                }
+                count += 1;
+            }
+            let non_empty = range.rows().filter(|r| r.iter().any(|c| !c.is_empty()));
+            assert_eq!(count, non_empty.count(), "{file} {sheet}");
+        }
+    }
+
+    let path = format!("{}/tests/issues.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    assert!(matches!(
+        xlsx.worksheet_rows("missing"),
+        Err(XlsxError::WorksheetNotFound(_))
+    ));
+}
+
+/// A reader implementing only the required methods of `Reader`
+struct MinimalReader<RS>(Xlsx<RS>);
+
+impl<RS: std::io::Read + std::io::Seek> Reader<RS> for MinimalReader<RS> {
+    type Error = XlsxError;
+
+    fn new(reader: RS) -> Result<Self, XlsxError> {
+        Ok(MinimalReader(Xlsx::new(reader)?))
+    }
+
+    fn vba_project(
+        &mut self,
+    ) -> Option<Result<std::borrow::Cow<'_, calamine::vba::VbaProject>, XlsxError>> {
+        self.0.vba_project()
+    }
+
+    fn metadata(&self) -> &calamine::Metadata {
+        self.0.metadata()
+    }
+
+    fn worksheet_range(&mut self, name: &str) -> Result<Range<Data>, XlsxError> {
+        self.0.worksheet_range(name)
+    }
+
+    fn worksheets(&mut self) -> Vec<(std::string::String, Range<Data>)> {
+        self.0.worksheets()
+    }
+
+    fn worksheet_formula(&mut self, name: &str) -> Result<Range<std::string::String>, XlsxError> {
+        self.0.worksheet_formula(name)
+    }
+
+    #[cfg(feature = "picture")]
+    fn pictures(&self) -> Option<Vec<(std::string::String, Vec<u8>)>> {
+        self.0.pictures()
+    }
+}
+
+#[test]
+fn reader_default_methods() {
+    setup();
+
+    let path = format!("{}/tests/merge_cells.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut reader: MinimalReader<_> = open_workbook(&path).unwrap();
+    assert!(reader.merged_cells("Sheet1").unwrap().is_empty());
+
+    let path = format!("{}/tests/issues.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut reader: MinimalReader<_> = open_workbook(&path).unwrap();
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    // shared strings are owned by the default implementation
+    fn owned(row: Result<(u32, Vec<DataRef<'_>>), XlsxError>) -> (u32, Vec<Data>) {
+        let (row, cells) = row.unwrap();
+        (row, cells.into_iter().map(Data::from).collect())
+    }
+    for sheet in xlsx.sheet_names() {
+        let rows = reader.worksheet_rows(&sheet).unwrap().map(owned);
+        let expected = xlsx.worksheet_rows(&sheet).unwrap().map(owned);
+        assert!(rows.eq(expected), "{sheet}");
+    }
+    assert!(matches!(
+        reader.worksheet_rows("missing"),
+        Err(XlsxError::WorksheetNotFound(_))
+    ));
+}
+
+#[test]
+fn lazy_sheets() {
+    setup();
+
+    for file in [
+        "any_sheets.ods",
+        "any_sheets.xls",
+        "issues.ods",
+        "issues.xls",
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let all = workbook.worksheets();
+        assert_eq!(all.len(), workbook.sheet_names().len(), "{file}");
+
+        // sheets are parsed on demand, in any order
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        for (name, range) in all.iter().rev() {
+            let lazy = workbook.worksheet_range(name).unwrap();
+            assert!(lazy.cells().eq(range.cells()), "{file} {name}");
+        }
+        assert!(workbook.worksheet_range("missing").is_err(), "{file}");
+    }
+}
+
+#[test]
+fn worksheet_comments() {
+    setup();
+
+    let comment = |cell, author: Option<&str>, text: &str| Comment {
+        cell,
+        author: author.map(|a| a.to_string()),
+        text: text.to_string(),
+    };
+
+    for file in ["comments.xls", "comments.xlsb"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        assert_eq!(
+            workbook.worksheet_comments(&sheet).unwrap(),
+            vec![
+                comment((0, 0), Some("Alice"), "City name"),
+                comment(
+                    (2, 1),
+                    if file.ends_with("xls") {
+                        None
+                    } else {
+                        Some("Bob")
+                    },
+                    "Is this\nCelsius?"
+                ),
+            ],
+            "{file}"
+        );
+    }
+
+    // threaded comments replace their legacy copy
+    let path = format!("{}/tests/comments.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        xlsx.worksheet_comments("Sheet1").unwrap(),
+        vec![
+            comment((1, 1), Some("Bob"), "Is this Celsius?"),
+            comment((1, 1), Some("Carol"), "Yes"),
+            comment((0, 0), Some("Alice"), "Alice:\nCity name"),
+        ]
+    );
+    assert!(matches!(
+        xlsx.worksheet_comments("missing"),
+        Err(XlsxError::WorksheetNotFound(_))
+    ));
+
+    let path = format!("{}/tests/comments.ods", env!("CARGO_MANIFEST_DIR"));
+    let mut ods: Ods<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        ods.worksheet_comments("Sheet1").unwrap(),
+        vec![
+            comment((0, 0), Some("Alice"), "City name"),
+            comment((1, 1), None, "Is this\n  Celsius?"),
+            comment((4, 2), Some("Bob"), "Empty cell"),
+        ]
+    );
+}
+
+#[test]
+fn worksheet_hyperlinks() {
+    setup();
+
+    let link =
+        |start, end, url: Option<&str>, location: Option<&str>, tooltip: Option<&str>| Hyperlink {
+            range: Dimensions::new(start, end),
+            url: url.map(|u| u.to_string()),
+            location: location.map(|l| l.to_string()),
+            tooltip: tooltip.map(|t| t.to_string()),
+        };
+    let paris = Some("https://example.com/paris?a=1&b=2");
+
+    for (file, location, report) in [
+        (
+            "hyperlinks.xlsx",
+            ((2, 1), "Sheet1!A1"),
+            ((2, 0), "../docs/report.pdf"),
+        ),
+        (
+            "hyperlinks.xlsb",
+            ((2, 1), "Sheet1!A1"),
+            ((2, 0), "../docs/report.pdf"),
+        ),
+        (
+            "hyperlinks.xls",
+            ((2, 1), "Sheet1!A1"),
+            ((2, 0), "..\\docs\\report.pdf"),
+        ),
+        // ods links are set per cell
+        (
+            "hyperlinks.ods",
+            ((1, 1), "Sheet1.A1"),
+            ((2, 1), "../docs/report.pdf"),
+        ),
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        assert_eq!(
+            workbook.worksheet_hyperlinks(&sheet).unwrap(),
+            vec![
+                link((1, 0), (1, 0), paris, None, Some("Paris page")),
+                link((1, 1), location.0, None, Some(location.1), None),
+                link((2, 0), report.0, Some(report.1), None, None),
+            ],
+            "{file}"
+        );
+    }
+}
+
+#[test]
+fn worksheet_rich_text() {
+    setup();
+
+    for file in [
+        "rich_text.xlsx",
+        "rich_text.xlsb",
+        "rich_text.xls",
+        "rich_text.ods",
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        let range = workbook.worksheet_range(&sheet).unwrap();
+        let rich_text = workbook.worksheet_rich_text(&sheet).unwrap();
+
+        // (text, bold, italic, strikethrough)
+        let runs = |pos| {
+            rich_text
+                .get_value(pos)
+                .unwrap()
+                .runs
+                .iter()
+                .map(|(text, font)| (text.as_str(), font.bold, font.italic, font.strikethrough))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(runs((0, 2)), [("new", false, true, false)], "{file}");
+        assert_eq!(
+            runs((1, 2)),
+            [("cel", false, false, false), ("sius", true, false, true)],
+            "{file}"
+        );
+        for pos in [(0, 2), (1, 2)] {
+            assert_eq!(
+                range.get_value(pos),
+                Some(&Data::String(rich_text.get_value(pos).unwrap().text())),
+                "{file}"
+            );
+        }
+        // cells without formatted runs are empty
+        assert_eq!(rich_text.used_cells().count(), 2, "{file}");
+    }
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn evaluate_formulas_without_values() {
+    setup();
+
+    let path = format!("{}/tests/eval.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut workbook: Xlsx<_> = open_workbook(&path).unwrap();
+    assert_eq!(
+        workbook
+            .worksheet_range("Sheet1")
+            .unwrap()
+            .get_value((3, 1)),
+        Some(&Empty)
+    );
+
+    let mut evaluator = calamine::Evaluator::new(&mut workbook).unwrap();
+    let range = evaluator.worksheet_range("Sheet1").unwrap();
+    let column: Vec<_> = (1..10)
+        .map(|row| range.get_value((row, 1)).unwrap())
+        .collect();
+    assert_eq!(
+        column,
+        [
+            &Float(10.),
+            &Float(20.),
+            &Float(60.),
+            &Float(20.),
+            &String("ab!".to_string()),
+            &Float(45316.),
+            &Float(15.),
+            // cached values are kept
+            &Float(99.),
+            &Error(Div0),
+        ]
+    );
+    assert_eq!(
+        evaluator
+            .worksheet_range("Sheet2")
+            .unwrap()
+            .get_value((1, 0)),
+        Some(&Float(15.))
+    );
+    assert_eq!(
+        evaluator.evaluate("Sheet2", "MATCH(20, Sheet1!B:B, 0)"),
+        Some(Float(3.))
+    );
+    assert!(evaluator.worksheet_range("Sheet3").is_none());
+}
+
+#[test]
+fn dependency_graph() {
+    setup();
+
+    let path = format!("{}/tests/eval.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut workbook: Xlsx<_> = open_workbook(&path).unwrap();
+    let graph = DependencyGraph::new(&mut workbook).unwrap();
+    assert_eq!(graph.formula_cells().count(), 8);
+
+    // the `Rate` name refers to Sheet2!$A$1
+    let area = |sheet: &str, start, end| SheetArea {
+        sheet: sheet.to_string(),
+        start,
+        end,
+    };
+    assert_eq!(
+        graph.precedents("Sheet1", (3, 1)),
+        [
+            area("Sheet1", (1, 1), (2, 1)),
+            area("Sheet2", (0, 0), (0, 0))
+        ]
+    );
+
+    let cell = |sheet: &str, pos| SheetCell {
+        sheet: sheet.to_string(),
+        pos,
+    };
+    assert_eq!(
+        graph.dependents("Sheet1", (3, 1)),
+        [&cell("Sheet2", (1, 0))]
+    );
+    assert_eq!(
+        graph.all_dependents("Sheet1", (2, 1)),
+        [
+            &cell("Sheet1", (3, 1)),
+            &cell("Sheet1", (4, 1)),
+            &cell("Sheet2", (1, 0)),
+            &cell("Sheet1", (7, 1))
+        ]
+    );
+
+    let order = graph.topological_order().unwrap();
+    let position = |c: SheetCell| order.iter().position(|o| **o == c).unwrap();
+    assert!(position(cell("Sheet1", (3, 1))) < position(cell("Sheet2", (1, 0))));
+    assert!(position(cell("Sheet2", (1, 0))) < position(cell("Sheet1", (7, 1))));
+}
+
+#[test]
+fn shared_and_array_formulas() {
+    setup();
+
+    for file in ["shared_formulas.xls", "shared_formulas.xlsb"] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let formulas = workbook.worksheet_formula("Sheet1").unwrap();
+        // the array formula is only on its top left cell, like in xlsx
+        let expected = [
+            (0, 0, "A1*2"),
+            (0, 1, "A1:A3*10"),
+            (0, 2, "SUM($A$1:A1)"),
+            (1, 0, "A2*2"),
+            (1, 2, "SUM($A$1:A2)"),
+            (1, 3, "TABLE(,A1)"),
+            (2, 0, "A3*2"),
+            (2, 2, "SUM($A$1:A3)"),
+        ];
+        assert_eq!(formulas.start(), Some((0, 1)), "{}", file);
+        assert_eq!(
+            formulas
+                .used_cells()
+                .map(|(r, c, f)| (r, c, f.as_str()))
+                .collect::<Vec<_>>(),
+            expected,
+            "{}",
+            file
+        );
+
+        let exprs = workbook.worksheet_formula_expr("Sheet1").unwrap();
+        assert_eq!(exprs.start(), Some((0, 1)), "{}", file);
+        assert_eq!(
+            exprs
+                .used_cells()
+                .map(|(r, c, e)| (r, c, e.to_string()))
+                .collect::<Vec<_>>(),
+            expected
+                .iter()
+                .map(|&(r, c, f)| (r, c, f.to_string()))
+                .collect::<Vec<_>>(),
+            "{}",
+            file
+        );
+    }
+}
+
+#[test]
+fn formula_info() {
+    setup();
+
+    let kinds = |path: &str| -> Vec<(u32, u32, FormulaKind)> {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), path);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        workbook
+            .worksheet_formula_info("Sheet1")
+            .unwrap()
+            .cells()
+            .filter(|(_, _, f)| !f.text.is_empty())
+            .map(|(r, c, f)| (r as u32, c as u32, f.kind))
+            .collect()
+    };
+    let array = FormulaKind::Array {
+        range: Dimensions::new((0, 2), (2, 2)),
+        dynamic: false,
+    };
+    let table = FormulaKind::DataTable {
+        range: Dimensions::new((1, 4), (2, 4)),
+    };
+    let expected = vec![
+        (0, 0, FormulaKind::Shared),
+        (0, 1, array),
+        (0, 2, FormulaKind::Shared),
+        (1, 0, FormulaKind::Shared),
+        (1, 2, FormulaKind::Shared),
+        (1, 3, table),
+        (2, 0, FormulaKind::Shared),
+        (2, 2, FormulaKind::Shared),
+    ];
+    assert_eq!(kinds("shared_formulas.xls"), expected);
+    assert_eq!(kinds("shared_formulas.xlsb"), expected);
+
+    // xlsx also flags dynamic arrays spilling from F1
+    let mut expected = expected;
+    expected.insert(
+        3,
+        (
+            0,
+            4,
+            FormulaKind::Array {
+                range: Dimensions::new((0, 5), (2, 5)),
+                dynamic: true,
+            },
+        ),
+    );
+    assert_eq!(kinds("shared_formulas.xlsx"), expected);
+
+    let path = format!("{}/tests/shared_formulas.xlsx", env!("CARGO_MANIFEST_DIR"));
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    let info = xlsx.worksheet_formula_info("Sheet1").unwrap();
+    assert_eq!(
+        info.get_value((1, 4)),
+        Some(&FormulaInfo {
+            text: "TABLE(,A1)".to_string(),
+            kind: table,
+        })
+    );
+    assert_eq!(
+        info.get_value((0, 5)).map(|f| f.text.as_str()),
+        Some("_xlfn._xlws.SORT(A1:A3)")
+    );
+}
+
+#[test]
+fn auto_filter() {
+    setup();
+
+    let qty = FilterColumn {
+        index: 1,
+        filter: Filter::Custom {
+            conditions: vec![
+                FilterCondition {
+                    operator: FilterOperator::GreaterThanOrEqual,
+                    value: Float(1.),
+                },
+                FilterCondition {
+                    operator: FilterOperator::LessThan,
+                    value: Float(4.),
+                },
+            ],
+            and: true,
+        },
+    };
+    let rank = FilterColumn {
+        index: 2,
+        filter: Filter::Top10 {
+            top: true,
+            percent: false,
+            value: 2.,
+        },
+    };
+    for file in [
+        "auto_filter.xlsx",
+        "auto_filter.xlsb",
+        "auto_filter.xls",
+        "auto_filter.ods",
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let filter = workbook.worksheet_auto_filter("Sheet1").unwrap().unwrap();
+        assert_eq!(filter.range, Dimensions::new((0, 0), (4, 2)), "{file}");
+
+        let name = if file.ends_with(".xls") {
+            // xls stores up to two values as custom conditions
+            Filter::Custom {
+                conditions: vec![
+                    FilterCondition {
+                        operator: FilterOperator::Equal,
+                        value: String("a".to_string()),
+                    },
+                    FilterCondition {
+                        operator: FilterOperator::Equal,
+                        value: String("c".to_string()),
+                    },
+                ],
+                and: false,
+            }
+        } else {
+            Filter::Values {
+                values: vec!["a".to_string(), "c".to_string()],
+                blank: false,
+            }
+        };
+        let name = FilterColumn {
+            index: 0,
+            filter: name,
+        };
+        assert_eq!(filter.columns, [name, qty.clone(), rank.clone()], "{file}");
+
+        let sort = (file.ends_with(".xlsx") || file.ends_with(".ods")).then(|| SortState {
+            range: Dimensions::new((1, 0), (4, 2)),
+            conditions: vec![SortCondition {
+                column: 1,
+                descending: true,
+            }],
+        });
+        assert_eq!(filter.sort, sort, "{file}");
+    }
+
+    // tables autofilters are not the sheet one
+    let path = format!(
+        "{}/tests/temperature-table.xlsx",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut xlsx: Xlsx<_> = open_workbook(&path).unwrap();
+    assert_eq!(xlsx.worksheet_auto_filter("Sheet1").unwrap(), None);
+}
+
+#[test]
+fn data_validations() {
+    setup();
+
+    let list = DataValidation {
+        ranges: vec![Dimensions::new((1, 1), (9, 1))],
+        typ: DataValidationType::List,
+        formula1: Some("\"a,b,c\"".to_string()),
+        allow_blank: true,
+        show_dropdown: true,
+        show_prompt: true,
+        prompt_title: Some("Pick".to_string()),
+        prompt: Some("One of a, b or c".to_string()),
+        show_error: true,
+        error_style: DataValidationErrorStyle::Stop,
+        error_title: Some("Invalid".to_string()),
+        error: Some("Not in the list".to_string()),
+        ..Default::default()
+    };
+    let whole = DataValidation {
+        ranges: vec![Dimensions::new((1, 2), (9, 2))],
+        typ: DataValidationType::Whole,
+        operator: DataValidationOperator::Between,
+        formula1: Some("1".to_string()),
+        formula2: Some("10".to_string()),
+        show_dropdown: true,
+        show_error: true,
+        error_style: DataValidationErrorStyle::Warning,
+        ..Default::default()
+    };
+    let source = DataValidation {
+        ranges: vec![Dimensions::new((1, 3), (1, 3))],
+        typ: DataValidationType::List,
+        formula1: Some("$A$2:$A$4".to_string()),
+        allow_blank: true,
+        show_dropdown: true,
+        show_error: true,
+        ..Default::default()
+    };
+    let length = DataValidation {
+        ranges: vec![Dimensions::new((1, 4), (2, 4))],
+        typ: DataValidationType::TextLength,
+        operator: DataValidationOperator::LessThanOrEqual,
+        formula1: Some("5".to_string()),
+        allow_blank: true,
+        show_dropdown: true,
+        show_error: true,
+        ..Default::default()
+    };
+    for file in [
+        "data_validations.xlsx",
+        "data_validations.xlsb",
+        "data_validations.xls",
+        "data_validations.ods",
+    ] {
+        let path = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let mut validations = workbook.worksheet_data_validations("Sheet1").unwrap();
+        // xlsx keeps the rules referencing other cells in the extension list
+        validations.sort_by_key(|v| v.ranges[0].start.1);
+        assert_eq!(
+            validations,
+            [list.clone(), whole.clone(), source.clone(), length.clone()],
+            "{file}"
+        );
+    }
+
+    // utf-16 list literal
+    let path = format!("{}/tests/issue_271.xls", env!("CARGO_MANIFEST_DIR"));
+    let mut xls: Xls<_> = open_workbook(&path).unwrap();
+    let validations = xls.worksheet_data_validations("sheet1").unwrap();
+    assert_eq!(validations.len(), 1);
+    assert_eq!(validations[0].formula1.as_deref(), Some("\"男,女\""));
+}